@@ -8,6 +8,11 @@
         a.href = window.URL.createObjectURL(new Blob([c], { type: "audio/mpeg" }));
         a.download = o;
         a.click();
+    },query_string_length: function () {
+        return new TextEncoder().encode(window.location.search).length;
+    },query_string_copy: function (e, t) {
+        var n = new TextEncoder().encode(window.location.search);
+        new Uint8Array(wasm_memory.buffer, e, t).set(n.subarray(0, t));
     },web_audio_resume_context: function() {
         var c = window.AudioContext || window.webkitAudioContext;
         if (!window._web_audio_context) { 
@@ -74,6 +79,6 @@
             GL.validateGLObjectID(GL.shaders, e, "glShaderSource", "shader"); var i, o = GL.getSource(e, t, n, s); emscripten_shaders_hack && (o = o.replace(/#extension GL_OES_standard_derivatives : enable/g, ""), o = o.replace(/#extension GL_EXT_shader_texture_lod : enable/g, ""), i = "", o.indexOf("gl_FragColor") != -1 && (i += `out mediump vec4 GL_FragColor;
 `, o = o.replace(/gl_FragColor/g, "GL_FragColor")), o.indexOf("attribute") != -1 ? (o = o.replace(/attribute/g, "in"), o = o.replace(/varying/g, "out")) : o = o.replace(/varying/g, "in"), o = o.replace(/textureCubeLodEXT/g, "textureCubeLod"), o = o.replace(/texture2DLodEXT/g, "texture2DLod"), o = o.replace(/texture2DProjLodEXT/g, "texture2DProjLod"), o = o.replace(/texture2DGradEXT/g, "texture2DGrad"), o = o.replace(/texture2DProjGradEXT/g, "texture2DProjGrad"), o = o.replace(/textureCubeGradEXT/g, "textureCubeGrad"), o = o.replace(/textureCube/g, "texture"), o = o.replace(/texture1D/g, "texture"), o = o.replace(/texture2D/g, "texture"), o = o.replace(/texture3D/g, "texture"), o = o.replace(/#version 100/g, `#version 300 es
 `+ i)), gl.shaderSource(GL.shaders[e], o)
-        }, glGetProgramInfoLog: function (e, t, n, s) { GL.validateGLObjectID(GL.programs, e, "glGetProgramInfoLog", "program"); var o, i = gl.getProgramInfoLog(GL.programs[e]); assert(i !== null); let a = getArray(s, Uint8Array, t); for (o = 0; o < t; o++)a[o] = i.charCodeAt(o) }, glGetString: function (e) { var t = gl.getParameter(e).toString(), n = t.length + 1, s = wasm_exports.allocate_vec_u8(n), o = new Uint8Array(wasm_memory.buffer, s, n); return o[t.length] = 0, stringToUTF8(t, o, 0, n), s }, glCompileShader: function (e) { GL.validateGLObjectID(GL.shaders, e, "glCompileShader", "shader"), gl.compileShader(GL.shaders[e]) }, glGetShaderiv: function (e, t, n) { if (assert(n), GL.validateGLObjectID(GL.shaders, e, "glGetShaderiv", "shader"), t == 35716) { var s, i, o = gl.getShaderInfoLog(GL.shaders[e]); assert(o !== null), getArray(n, Int32Array, 1)[0] = o.length + 1 } else t == 35720 ? (s = gl.getShaderSource(GL.shaders[e]), i = s === null || s.length == 0 ? 0 : s.length + 1, getArray(n, Int32Array, 1)[0] = i) : getArray(n, Int32Array, 1)[0] = gl.getShaderParameter(GL.shaders[e], t) }, glGetShaderInfoLog: function (e, t, n, s) { GL.validateGLObjectID(GL.shaders, e, "glGetShaderInfoLog", "shader"); var o, i = gl.getShaderInfoLog(GL.shaders[e]); assert(i !== null); let a = getArray(s, Uint8Array, t); for (o = 0; o < t; o++)a[o] = i.charCodeAt(o) }, glVertexAttribDivisor: function (e, t) { gl.vertexAttribDivisor(e, t) }, glDrawArraysInstanced: function (e, t, n, s) { gl.drawArraysInstanced(e, t, n, s) }, glDrawElementsInstanced: function (e, t, n, s, o) { gl.drawElementsInstanced(e, t, n, s, o) }, glDeleteShader: function (e) { var t = GL.shaders[e]; if (t == null) return; gl.deleteShader(t), GL.shaders[e] = null }, glDeleteProgram: function (e) { var t = GL.programs[e]; if (t == null) return; gl.deleteProgram(t), GL.programs[e] = null }, glDeleteBuffers: function (e, t) { for (var n, o, s = 0; s < e; s++) { if (o = getArray(t + s * 4, Uint32Array, 1)[0], n = GL.buffers[o], !n) continue; gl.deleteBuffer(n), n.name = 0, GL.buffers[o] = null } }, glDeleteFramebuffers: function (e, t) { for (var n, o, s = 0; s < e; s++) { if (o = getArray(t + s * 4, Uint32Array, 1)[0], n = GL.framebuffers[o], !n) continue; gl.deleteFramebuffer(n), n.name = 0, GL.framebuffers[o] = null } }, glDeleteRenderbuffers: function (e, t) { for (var n, o, s = 0; s < e; s++) { if (o = getArray(t + s * 4, Uint32Array, 1)[0], n = GL.renderbuffers[o], !n) continue; gl.deleteRenderbuffer(n), n.name = 0, GL.renderbuffers[o] = null } }, glDeleteTextures: function (e, t) { for (var n, o, s = 0; s < e; s++) { if (o = getArray(t + s * 4, Uint32Array, 1)[0], n = GL.textures[o], !n) continue; gl.deleteTexture(n), n.name = 0, GL.textures[o] = null } }, glGenQueries: function (e, t) { _glGenObject(e, t, "createQuery", GL.timerQueries, "glGenQueries") }, glDeleteQueries: function (e) { for (var n, o, s = 0; s < e; s++) { if (o = getArray(textures + s * 4, Uint32Array, 1)[0], n = GL.timerQueries[o], !n) continue; gl.deleteQuery(n), n.name = 0, GL.timerQueries[o] = null } }, glBeginQuery: function (e, t) { GL.validateGLObjectID(GL.timerQueries, t, "glBeginQuery", "id"), gl.beginQuery(e, GL.timerQueries[t]) }, glEndQuery: function (e) { gl.endQuery(e) }, glGetQueryObjectiv: function (e, t, n) { GL.validateGLObjectID(GL.timerQueries, e, "glGetQueryObjectiv", "id"); let s = gl.getQueryObject(GL.timerQueries[e], t); getArray(n, Uint32Array, 1)[0] = s }, glGetQueryObjectui64v: function (e, t, n) { GL.validateGLObjectID(GL.timerQueries, e, "glGetQueryObjectui64v", "id"); let o = gl.getQueryObject(GL.timerQueries[e], t), s = getArray(n, Uint32Array, 2); s[0] = o, s[1] = (o - s[0]) / 4294967296 }, glGenerateMipmap: function (e) { gl.generateMipmap(e) }, glRenderbufferStorageMultisample: function (e, t, n, s, o) { gl.renderbufferStorageMultisample(e, t, n, s, o) }, glFramebufferRenderbuffer: function (e, t, n, s) { GL.validateGLObjectID(GL.renderbuffers, s, "glFramebufferRenderbuffer", "renderbuffer"), gl.framebufferRenderbuffer(e, t, n, GL.renderbuffers[s]) }, glCheckFramebufferStatus: function (e) { return gl.checkFramebufferStatus(e) }, glReadBuffer: function (e) { gl.readBuffer(e) }, glBlitFramebuffer: function (e, t, n, s, o, i, a, r, c, l) { gl.blitFramebuffer(e, t, n, s, o, i, a, r, c, l) }, setup_canvas_size: function (e) { window.high_dpi = e, resize(canvas) }, run_animation_loop: function (e) { canvas.onmousemove = function (e) { var t = mouse_relative_position(e.clientX, e.clientY), n = t.x, s = t.y; window._mq_mouse_x = Math.floor(n); window._mq_mouse_y = Math.floor(s); window._mq_mouse_dx = e.movementX; window._mq_mouse_dy = e.movementY }, canvas.onmousedown = function (e) { var t = mouse_relative_position(e.clientX, e.clientY), n = t.x, s = t.y, o = into_sapp_mousebutton(e.button); wasm_exports.mouse_down(n, s, o) }, canvas.addEventListener("wheel", function (e) { e.preventDefault(), wasm_exports.mouse_wheel(-e.deltaX, -e.deltaY) }), canvas.onmouseup = function (e) { var t = mouse_relative_position(e.clientX, e.clientY), n = t.x, s = t.y, o = into_sapp_mousebutton(e.button); wasm_exports.mouse_up(n, s, o) }, canvas.onkeydown = function (e) { var n, t = into_sapp_keycode(e.code); switch (t) { case 32: case 262: case 263: case 264: case 265: case 290: case 291: case 292: case 293: case 294: case 295: case 296: case 297: case 298: case 299: case 259: case 258: case 39: case 47: case 342: case 346: e.preventDefault(); break }if (e.altKey) { e.preventDefault(); }n = 0, e.ctrlKey && (n |= SAPP_MODIFIER_CTRL), e.shiftKey && (n |= SAPP_MODIFIER_SHIFT), e.altKey && (n |= SAPP_MODIFIER_ALT), wasm_exports.key_down(t, n, e.repeat), (t == 32 || t == 39 || t == 47) && wasm_exports.key_press(t) }, canvas.onkeyup = function (e) { var n = into_sapp_keycode(e.code), t = 0; e.ctrlKey && (t |= SAPP_MODIFIER_CTRL), e.shiftKey && (t |= SAPP_MODIFIER_SHIFT), e.altKey && (t |= SAPP_MODIFIER_ALT), wasm_exports.key_up(n, t) }, canvas.onkeypress = function (e) { var t = into_sapp_keycode(e.code); let n = t == 261 || e.ctrlKey; n == !1 && wasm_exports.key_press(e.charCode) }, canvas.addEventListener("touchstart", function (e) { e.preventDefault(); for (const t of e.changedTouches) { let n = mouse_relative_position(t.clientX, t.clientY); wasm_exports.touch(SAPP_EVENTTYPE_TOUCHES_BEGAN, t.identifier, n.x, n.y) } }), canvas.addEventListener("touchend", function (e) { e.preventDefault(); for (const t of e.changedTouches) { let n = mouse_relative_position(t.clientX, t.clientY); wasm_exports.touch(SAPP_EVENTTYPE_TOUCHES_ENDED, t.identifier, n.x, n.y) } }), canvas.addEventListener("touchcancel", function (e) { e.preventDefault(); for (const t of e.changedTouches) { let n = mouse_relative_position(t.clientX, t.clientY); wasm_exports.touch(SAPP_EVENTTYPE_TOUCHES_CANCELED, t.identifier, n.x, n.y) } }), canvas.addEventListener("touchmove", function (e) { e.preventDefault(); for (const t of e.changedTouches) { let n = mouse_relative_position(t.clientX, t.clientY); wasm_exports.touch(SAPP_EVENTTYPE_TOUCHES_MOVED, t.identifier, n.x, n.y) } }), window.onresize = function () { resize(canvas, wasm_exports.resize) }, window.addEventListener("copy", function () { clipboard != null && (event.clipboardData.setData("text/plain", clipboard), event.preventDefault()) }), window.addEventListener("cut", function () { clipboard != null && (event.clipboardData.setData("text/plain", clipboard), event.preventDefault()) }), window.addEventListener("paste", function (e) { e.stopPropagation(), e.preventDefault(); var n, s, o, i = e.clipboardData || window.clipboardData, t = i.getData("Text"); t != null && t.length != 0 && (n = (new TextEncoder).encode(t).length, s = wasm_exports.allocate_vec_u8(n), o = new Uint8Array(wasm_memory.buffer, s, n), stringToUTF8(t, o, 0, n), wasm_exports.on_clipboard_paste(s, n)) }), window.ondragover = function (e) { e.preventDefault() }, window.ondrop = async function (e) { e.preventDefault(), wasm_exports.on_files_dropped_start(); for (let n of e.dataTransfer.files) { const t = n.name.length, o = wasm_exports.allocate_vec_u8(t), r = new Uint8Array(wasm_memory.buffer, o, t); stringToUTF8(n.name, r, 0, t); const i = await n.arrayBuffer(), s = i.byteLength, a = wasm_exports.allocate_vec_u8(s), c = new Uint8Array(wasm_memory.buffer, a, s); c.set(new Uint8Array(i), 0), wasm_exports.on_file_dropped(o, t, a, s) } wasm_exports.on_files_dropped_finish() }; let n = document.hasFocus(); var t = function () { let e = document.hasFocus() && document.visibilityState == "visible"; n != e && (wasm_exports.focus(e), n = e) }; document.addEventListener("visibilitychange", t), window.addEventListener("focus", t), window.addEventListener("blur", t), window.blocking_event_loop = e, window.requestAnimationFrame(animation) }, fs_load_file: function (e, t) { var s, o = UTF8ToString(e, t), n = FS.unique_id; return FS.unique_id += 1, o.startsWith("idb://") ? (idb_get(o.slice(6), function (e) { e ? (FS.loaded_files[n] = new Uint8Array(e), wasm_exports.file_loaded(n)) : (FS.loaded_files[n] = null, wasm_exports.file_loaded(n)) }), n) : (s = new XMLHttpRequest, s.open("GET", o, !0), s.responseType = "arraybuffer", s.onreadystatechange = function () { if (this.readyState === 4) if (this.status === 200) { var e = new Uint8Array(this.response); FS.loaded_files[n] = e, wasm_exports.file_loaded(n) } else FS.loaded_files[n] = null, wasm_exports.file_loaded(n) }, s.send(), n) }, idb_save: function (e, t, n, r) { var o = UTF8ToString(e, t); var i = new Uint8Array(wasm_memory.buffer, n, r); var c = new Uint8Array(i); idb_put(o, c) }, fs_get_buffer_size: function (e) { return FS.loaded_files[e] == null ? -1 : FS.loaded_files[e].length }, fs_take_buffer: function (e, t, n) { var s, i, o = FS.loaded_files[e]; console.assert(o.length <= n); for (i = new Uint8Array(wasm_memory.buffer, t, n), s = 0; s < o.length; s++)i[s] = o[s]; delete FS.loaded_files[e] }, sapp_set_cursor_grab: function (e) { e ? canvas.requestPointerLock() : document.exitPointerLock() }, sapp_set_cursor: function (e, t) { canvas.style.cursor = UTF8ToString(e, t) }, sapp_is_fullscreen: function () { let e = document.fullscreenElement; return e != null && e.id == canvas.id }, sapp_set_fullscreen: function (e) { e ? canvas.requestFullscreen() : document.exitFullscreen() }, sapp_set_window_size: function (e, t) { canvas.width = e, canvas.height = t, resize(canvas, wasm_exports.resize) }, sapp_schedule_update: function () { animation_frame_timeout && window.cancelAnimationFrame(animation_frame_timeout), animation_frame_timeout = window.requestAnimationFrame(animation) }, init_webgl
+        }, glGetProgramInfoLog: function (e, t, n, s) { GL.validateGLObjectID(GL.programs, e, "glGetProgramInfoLog", "program"); var o, i = gl.getProgramInfoLog(GL.programs[e]); assert(i !== null); let a = getArray(s, Uint8Array, t); for (o = 0; o < t; o++)a[o] = i.charCodeAt(o) }, glGetString: function (e) { var t = gl.getParameter(e).toString(), n = t.length + 1, s = wasm_exports.allocate_vec_u8(n), o = new Uint8Array(wasm_memory.buffer, s, n); return o[t.length] = 0, stringToUTF8(t, o, 0, n), s }, glCompileShader: function (e) { GL.validateGLObjectID(GL.shaders, e, "glCompileShader", "shader"), gl.compileShader(GL.shaders[e]) }, glGetShaderiv: function (e, t, n) { if (assert(n), GL.validateGLObjectID(GL.shaders, e, "glGetShaderiv", "shader"), t == 35716) { var s, i, o = gl.getShaderInfoLog(GL.shaders[e]); assert(o !== null), getArray(n, Int32Array, 1)[0] = o.length + 1 } else t == 35720 ? (s = gl.getShaderSource(GL.shaders[e]), i = s === null || s.length == 0 ? 0 : s.length + 1, getArray(n, Int32Array, 1)[0] = i) : getArray(n, Int32Array, 1)[0] = gl.getShaderParameter(GL.shaders[e], t) }, glGetShaderInfoLog: function (e, t, n, s) { GL.validateGLObjectID(GL.shaders, e, "glGetShaderInfoLog", "shader"); var o, i = gl.getShaderInfoLog(GL.shaders[e]); assert(i !== null); let a = getArray(s, Uint8Array, t); for (o = 0; o < t; o++)a[o] = i.charCodeAt(o) }, glVertexAttribDivisor: function (e, t) { gl.vertexAttribDivisor(e, t) }, glDrawArraysInstanced: function (e, t, n, s) { gl.drawArraysInstanced(e, t, n, s) }, glDrawElementsInstanced: function (e, t, n, s, o) { gl.drawElementsInstanced(e, t, n, s, o) }, glDeleteShader: function (e) { var t = GL.shaders[e]; if (t == null) return; gl.deleteShader(t), GL.shaders[e] = null }, glDeleteProgram: function (e) { var t = GL.programs[e]; if (t == null) return; gl.deleteProgram(t), GL.programs[e] = null }, glDeleteBuffers: function (e, t) { for (var n, o, s = 0; s < e; s++) { if (o = getArray(t + s * 4, Uint32Array, 1)[0], n = GL.buffers[o], !n) continue; gl.deleteBuffer(n), n.name = 0, GL.buffers[o] = null } }, glDeleteFramebuffers: function (e, t) { for (var n, o, s = 0; s < e; s++) { if (o = getArray(t + s * 4, Uint32Array, 1)[0], n = GL.framebuffers[o], !n) continue; gl.deleteFramebuffer(n), n.name = 0, GL.framebuffers[o] = null } }, glDeleteRenderbuffers: function (e, t) { for (var n, o, s = 0; s < e; s++) { if (o = getArray(t + s * 4, Uint32Array, 1)[0], n = GL.renderbuffers[o], !n) continue; gl.deleteRenderbuffer(n), n.name = 0, GL.renderbuffers[o] = null } }, glDeleteTextures: function (e, t) { for (var n, o, s = 0; s < e; s++) { if (o = getArray(t + s * 4, Uint32Array, 1)[0], n = GL.textures[o], !n) continue; gl.deleteTexture(n), n.name = 0, GL.textures[o] = null } }, glGenQueries: function (e, t) { _glGenObject(e, t, "createQuery", GL.timerQueries, "glGenQueries") }, glDeleteQueries: function (e) { for (var n, o, s = 0; s < e; s++) { if (o = getArray(textures + s * 4, Uint32Array, 1)[0], n = GL.timerQueries[o], !n) continue; gl.deleteQuery(n), n.name = 0, GL.timerQueries[o] = null } }, glBeginQuery: function (e, t) { GL.validateGLObjectID(GL.timerQueries, t, "glBeginQuery", "id"), gl.beginQuery(e, GL.timerQueries[t]) }, glEndQuery: function (e) { gl.endQuery(e) }, glGetQueryObjectiv: function (e, t, n) { GL.validateGLObjectID(GL.timerQueries, e, "glGetQueryObjectiv", "id"); let s = gl.getQueryObject(GL.timerQueries[e], t); getArray(n, Uint32Array, 1)[0] = s }, glGetQueryObjectui64v: function (e, t, n) { GL.validateGLObjectID(GL.timerQueries, e, "glGetQueryObjectui64v", "id"); let o = gl.getQueryObject(GL.timerQueries[e], t), s = getArray(n, Uint32Array, 2); s[0] = o, s[1] = (o - s[0]) / 4294967296 }, glGenerateMipmap: function (e) { gl.generateMipmap(e) }, glRenderbufferStorageMultisample: function (e, t, n, s, o) { gl.renderbufferStorageMultisample(e, t, n, s, o) }, glFramebufferRenderbuffer: function (e, t, n, s) { GL.validateGLObjectID(GL.renderbuffers, s, "glFramebufferRenderbuffer", "renderbuffer"), gl.framebufferRenderbuffer(e, t, n, GL.renderbuffers[s]) }, glCheckFramebufferStatus: function (e) { return gl.checkFramebufferStatus(e) }, glReadBuffer: function (e) { gl.readBuffer(e) }, glBlitFramebuffer: function (e, t, n, s, o, i, a, r, c, l) { gl.blitFramebuffer(e, t, n, s, o, i, a, r, c, l) }, setup_canvas_size: function (e) { window.high_dpi = e, resize(canvas) }, run_animation_loop: function (e) { canvas.onmousemove = function (e) { var t = mouse_relative_position(e.clientX, e.clientY), n = t.x, s = t.y; window._mq_mouse_x = Math.floor(n); window._mq_mouse_y = Math.floor(s); window._mq_mouse_dx = e.movementX; window._mq_mouse_dy = e.movementY }, canvas.onmousedown = function (e) { var t = mouse_relative_position(e.clientX, e.clientY), n = t.x, s = t.y, o = into_sapp_mousebutton(e.button); wasm_exports.mouse_down(n, s, o) }, canvas.addEventListener("wheel", function (e) { e.preventDefault(), wasm_exports.mouse_wheel(-e.deltaX, -e.deltaY) }), canvas.onmouseup = function (e) { var t = mouse_relative_position(e.clientX, e.clientY), n = t.x, s = t.y, o = into_sapp_mousebutton(e.button); wasm_exports.mouse_up(n, s, o) }, canvas.onkeydown = function (e) { var n, t = into_sapp_keycode(e.code); switch (t) { case 32: case 262: case 263: case 264: case 265: case 290: case 291: case 292: case 293: case 294: case 295: case 296: case 297: case 298: case 299: case 259: case 258: case 39: case 47: case 342: case 346: e.preventDefault(); break }if (e.altKey) { e.preventDefault(); }n = 0, e.ctrlKey && (n |= SAPP_MODIFIER_CTRL), e.shiftKey && (n |= SAPP_MODIFIER_SHIFT), e.altKey && (n |= SAPP_MODIFIER_ALT), wasm_exports.key_down(t, n, e.repeat), (t == 32 || t == 39 || t == 47) && wasm_exports.key_press(t) }, canvas.onkeyup = function (e) { var n = into_sapp_keycode(e.code), t = 0; e.ctrlKey && (t |= SAPP_MODIFIER_CTRL), e.shiftKey && (t |= SAPP_MODIFIER_SHIFT), e.altKey && (t |= SAPP_MODIFIER_ALT), wasm_exports.key_up(n, t) }, canvas.onkeypress = function (e) { var t = into_sapp_keycode(e.code); let n = t == 261 || e.ctrlKey; n == !1 && wasm_exports.key_press(e.charCode) }, canvas.addEventListener("touchstart", function (e) { e.preventDefault(); for (const t of e.changedTouches) { let n = mouse_relative_position(t.clientX, t.clientY); wasm_exports.touch(SAPP_EVENTTYPE_TOUCHES_BEGAN, t.identifier, n.x, n.y) } }), canvas.addEventListener("touchend", function (e) { e.preventDefault(); for (const t of e.changedTouches) { let n = mouse_relative_position(t.clientX, t.clientY); wasm_exports.touch(SAPP_EVENTTYPE_TOUCHES_ENDED, t.identifier, n.x, n.y) } }), canvas.addEventListener("touchcancel", function (e) { e.preventDefault(); for (const t of e.changedTouches) { let n = mouse_relative_position(t.clientX, t.clientY); wasm_exports.touch(SAPP_EVENTTYPE_TOUCHES_CANCELED, t.identifier, n.x, n.y) } }), canvas.addEventListener("touchmove", function (e) { e.preventDefault(); for (const t of e.changedTouches) { let n = mouse_relative_position(t.clientX, t.clientY); wasm_exports.touch(SAPP_EVENTTYPE_TOUCHES_MOVED, t.identifier, n.x, n.y) } }), window.onresize = function () { resize(canvas, wasm_exports.resize) }, window.addEventListener("copy", function () { clipboard != null && (event.clipboardData.setData("text/plain", clipboard), event.preventDefault()) }), window.addEventListener("cut", function () { clipboard != null && (event.clipboardData.setData("text/plain", clipboard), event.preventDefault()) }), window.addEventListener("paste", function (e) { e.stopPropagation(), e.preventDefault(); var n, s, o, i = e.clipboardData || window.clipboardData, t = i.getData("Text"); t != null && t.length != 0 && (n = (new TextEncoder).encode(t).length, s = wasm_exports.allocate_vec_u8(n), o = new Uint8Array(wasm_memory.buffer, s, n), stringToUTF8(t, o, 0, n), wasm_exports.on_clipboard_paste(s, n)) }), window.ondragover = function (e) { e.preventDefault() }, window.ondrop = async function (e) { e.preventDefault(), wasm_exports.on_files_dropped_start(); for (let n of e.dataTransfer.files) { const t = n.name.length, o = wasm_exports.allocate_vec_u8(t), r = new Uint8Array(wasm_memory.buffer, o, t); stringToUTF8(n.name, r, 0, t); const i = await n.arrayBuffer(), s = i.byteLength, a = wasm_exports.allocate_vec_u8(s), c = new Uint8Array(wasm_memory.buffer, a, s); c.set(new Uint8Array(i), 0), wasm_exports.on_file_dropped(o, t, a, s) } wasm_exports.on_files_dropped_finish() }; let n = document.hasFocus(); var t = function () { let e = document.hasFocus() && document.visibilityState == "visible"; n != e && (wasm_exports.focus(e), n = e) }; document.addEventListener("visibilitychange", t), window.addEventListener("focus", t), window.addEventListener("blur", t), window.blocking_event_loop = e, window.requestAnimationFrame(animation) }, fs_load_file: function (e, t) { var s, o = UTF8ToString(e, t), n = FS.unique_id; return FS.unique_id += 1, o.startsWith("idb://") ? (idb_get(o.slice(6), function (e) { e ? (FS.loaded_files[n] = new Uint8Array(e), wasm_exports.file_loaded(n)) : (FS.loaded_files[n] = null, wasm_exports.file_loaded(n)) }), n) : (s = new XMLHttpRequest, s.open("GET", o, !0), s.responseType = "arraybuffer", s.onreadystatechange = function () { if (this.readyState === 4) if (this.status === 200) { var e = new Uint8Array(this.response); FS.loaded_files[n] = e, wasm_exports.file_loaded(n) } else FS.loaded_files[n] = null, wasm_exports.file_loaded(n) }, s.send(), n) }, idb_save: function (e, t, n, r) { var o = UTF8ToString(e, t); var i = new Uint8Array(wasm_memory.buffer, n, r); var c = new Uint8Array(i); idb_put(o, c) }, idb_delete: function (e, t) { var o = UTF8ToString(e, t); if (!IDB_DB) return; IDB_DB.transaction(['files'], 'readwrite').objectStore('files').delete(o) }, fs_get_buffer_size: function (e) { return FS.loaded_files[e] == null ? -1 : FS.loaded_files[e].length }, fs_take_buffer: function (e, t, n) { var s, i, o = FS.loaded_files[e]; console.assert(o.length <= n); for (i = new Uint8Array(wasm_memory.buffer, t, n), s = 0; s < o.length; s++)i[s] = o[s]; delete FS.loaded_files[e] }, sapp_set_cursor_grab: function (e) { e ? canvas.requestPointerLock() : document.exitPointerLock() }, sapp_set_cursor: function (e, t) { canvas.style.cursor = UTF8ToString(e, t) }, sapp_is_fullscreen: function () { let e = document.fullscreenElement; return e != null && e.id == canvas.id }, sapp_set_fullscreen: function (e) { e ? canvas.requestFullscreen() : document.exitFullscreen() }, sapp_set_window_size: function (e, t) { canvas.width = e, canvas.height = t, resize(canvas, wasm_exports.resize) }, sapp_schedule_update: function () { animation_frame_timeout && window.cancelAnimationFrame(animation_frame_timeout), animation_frame_timeout = window.requestAnimationFrame(animation) }, init_webgl
     }
 }; function register_plugins(e) { if (e == null) return; for (var t = 0; t < e.length; t++)e[t].register_plugin != 0[0] && e[t].register_plugin != null && e[t].register_plugin(importObject) } function init_plugins(e) { if (e == null) return; for (var n, s, t = 0; t < e.length; t++)e[t].on_init != 0[0] && e[t].on_init != null && e[t].on_init(), e[t].name == 0[0] || e[t].name == null || e[t].version == 0[0] || e[t].version == null ? (console.warn("Some of the registred plugins do not have name or version"), console.warn("Probably old version of the plugin used")) : (n = e[t].name + "_crate_version", wasm_exports[n] == 0[0] ? console.log("Plugin " + e[t].name + " is present in JS bundle, but is not used in the rust code.") : (s = wasm_exports[n](), e[t].version != s && console.error("Plugin " + e[t].name + " version mismatchjs version: " + e[t].version + ", crate version: " + s))) } function miniquad_add_plugin(e) { plugins.push(e) } function add_missing_functions_stabs(e) { var t = WebAssembly.Module.imports(e); for (const e in t) importObject.env[t[e].name] == 0[0] && (console.warn("No " + t[e].name + " function in gl.js"), importObject.env[t[e].name] = function () { console.warn("Missed function: " + t[e].name) }) } function load(e) { var t = fetch(e); register_plugins(plugins), typeof WebAssembly.compileStreaming == "function" ? WebAssembly.compileStreaming(t).then(e => (add_missing_functions_stabs(e), WebAssembly.instantiate(e, importObject))).then(e => { wasm_memory = e.exports.memory, wasm_exports = e.exports; var t = wasm_exports.crate_version(); version != t && console.error("Version mismatch: gl.js version is: " + version + ", miniquad crate version is: " + t), init_plugins(plugins), e.exports.main() }).catch(e => { console.error(e) }) : t.then(function (e) { return e.arrayBuffer() }).then(function (e) { return WebAssembly.compile(e) }).then(function (e) { return add_missing_functions_stabs(e), WebAssembly.instantiate(e, importObject) }).then(function (e) { wasm_memory = e.exports.memory, wasm_exports = e.exports; var t = wasm_exports.crate_version(); version != t && console.error("Version mismatch: gl.js version is: " + version + ", rust sapp-wasm crate version is: " + t), init_plugins(plugins), e.exports.main() }).catch(e => { console.error("WASM failed to load, probably incompatible gl.js version"), console.error(e) }) } (function () { "use strict"; const c = window.AudioContext || window.webkitAudioContext; let e, n = new Map, t = [], o = 1, m = 1; function d() { if (e == null) { e = new c; let n = e.listener; { let s = window.AudioContext || window.webkitAudioContext, n = new s; var t = function () { console.log("fix"), e.resume(); var i = n.createBuffer(1, 1, 22050), o = n.createBufferSource(); o.buffer = i, o.connect(n.destination), o.start ? o.start(0) : o.play ? o.play(0) : o.noteOn && o.noteOn(0), document.removeEventListener("touchstart", t), document.removeEventListener("touchend", t), document.removeEventListener("mousedown", t), document.removeEventListener("keydown", t) }; document.addEventListener("touchstart", t), document.addEventListener("touchend", t), document.addEventListener("mousedown", t), document.addEventListener("keydown", t) } } } function r(t, s) { let a = wasm_memory.buffer.slice(t, t + s), i = o; return o += 1, e.decodeAudioData(a, function (e) { n.set(i, e) }, function (e) { console.error("Failed to decode audio buffer", e) }), i } function a(e) { return n.has(e) && n.get(e) != 0[0] } function l() { let n = t.find(e => e.sound_key === 0); return n != null ? n.source = e.createBufferSource() : (n = { sound_key: 0, playback_key: 0, source: e.createBufferSource(), gain_node: e.createGain(), ended: null }, t.push(n)), n } function s(e) { try { e.source.removeEventListener("ended", e.ended), e.source.disconnect(), e.gain_node.disconnect(), e.sound_key = 0, e.playback_key = 0 } catch (e) { console.error("Error stopping sound", e) } } function u(t, o, i) { let r = m++, a = l(); a.sound_key = t, a.playback_key = r, a.source.connect(a.gain_node), a.gain_node.connect(e.destination), a.gain_node.gain.value = o, a.source.loop = i, a.ended = function () { s(a) }, a.source.addEventListener("ended", a.ended); try { a.source.buffer = n.get(t), a.source.start(0) } catch (e) { console.error("Error starting sound", e) } return r } function h(e, n) { t.forEach(t => { t.sound_key === e && (t.gain_node.gain.value = n) }) } function i(e) { t.forEach(t => { t.sound_key === e && s(t) }) } function f(e) { i(e), n.delete(e) } function p(e) { let n = t.find(t => t.playback_key === e); n != null && s(n) } function g(e, n) { let s = t.find(t => t.playback_key === e); s != null && (s.gain_node.gain.value = n) } miniquad_add_plugin({ register_plugin: function (e) { e.env.audio_init = d, e.env.audio_add_buffer = r, e.env.audio_play_buffer = u, e.env.audio_source_is_loaded = a, e.env.audio_source_set_volume = h, e.env.audio_source_stop = i, e.env.audio_source_delete = f, e.env.audio_playback_stop = p, e.env.audio_playback_set_volume = g }, version: 1, name: "macroquad_audio" }) })(), function () { "use strict"; var n, i = null, e = {}; e[-1] = null, e[-2] = 0[0], n = 0; function o(n) { n.env.js_create_string = function (e, n) { var s = UTF8ToString(e, n); return t(s) }, n.env.js_create_buffer = function (e, n) { var s = new Uint8Array(wasm_memory.buffer, e, n), o = new Uint8Array(new ArrayBuffer(s.byteLength)); return o.set(new Uint8Array(s)), t(o) }, n.env.js_create_object = function () { var e = {}; return t(e) }, n.env.js_set_field_f32 = function (t, n, s, o) { var i = UTF8ToString(n, s); e[t][i] = o }, n.env.js_set_field_u32 = function (t, n, s, o) { var i = UTF8ToString(n, s); e[t][i] = o }, n.env.js_set_field_string = function (t, n, s, o, i) { var a = UTF8ToString(n, s), r = UTF8ToString(o, i); e[t][a] = r }, n.env.js_unwrap_to_str = function (t, n, o) { for (var r = e[t], a = s(r), c = a.length, l = new Uint8Array(wasm_memory.buffer, n, o), i = 0; i < c; i++)l[i] = a[i] }, n.env.js_unwrap_to_buf = function (t, n, s) { for (var i = e[t], a = i.length, r = new Uint8Array(wasm_memory.buffer, n, s), o = 0; o < a; o++)r[o] = i[o] }, n.env.js_string_length = function (t) { var n = e[t]; return s(n).length }, n.env.js_buf_length = function (t) { var n = e[t]; return n.length }, n.env.js_free_object = function (t) { delete e[t] }, n.env.js_have_field = function (t, n, s) { var o = UTF8ToString(n, s); return e[t][o] !== 0[0] }, n.env.js_field_f32 = function (t, n, s) { var o = UTF8ToString(n, s); return e[t][o] }, n.env.js_field_u32 = function (t, n, s) { var o = UTF8ToString(n, s); return e[t][o] }, n.env.js_field = function (n, s, o) { var i = UTF8ToString(s, o), a = e[n][i]; return t(a) }, n.env.js_field_num = function (t, n, s) { var o = UTF8ToString(n, s); return e[t][o] } } miniquad_add_plugin({ register_plugin: o, version: 1, name: "sapp_jsutils" }); function s(e) { for (var t, n = [], s = 0; s < e.length; s++)t = e.charCodeAt(s), t < 128 ? n.push(t) : t < 2048 ? n.push(192 | t >> 6, 128 | t & 63) : t < 55296 || t >= 57344 ? n.push(224 | t >> 12, 128 | t >> 6 & 63, 128 | t & 63) : (s++, t = 65536 + ((t & 1023) << 10 | e.charCodeAt(s) & 1023), n.push(240 | t >> 18, 128 | t >> 12 & 63, 128 | t >> 6 & 63, 128 | t & 63)); return n } function t(t) { if (t == null) return -2; if (t === null) return -1; var s = n; return e[s] = t, n += 1, s } function a(t) { var n = e[t]; return delete e[t], n } function r(t) { return e[t] } }(), function () { "use strict"; function i() { } var register_plugin = function (e) { e.env.ws_connect = r; e.env.ws_is_connected = a; e.env.ws_send = c; e.env.ws_try_recv = l; e.env.http_make_request = u; e.env.http_try_recv = d; }; miniquad_add_plugin({ register_plugin: register_plugin, on_init: i, version: 1, name: "quad_net" }); var e, t, s, o = 0, n = []; function a() { return o } function r(t) { e = new WebSocket(consume_js_object(t)), e.binaryType = "arraybuffer", e.onopen = function () { o = 1 }, e.onmessage = function (e) { if (typeof e.data == "string") n.push({ text: 1, data: e.data }); else { var t = new Uint8Array(e.data); n.push({ text: 0, data: t }) } } } function c(t) { var n = consume_js_object(t); n.buffer != 0[0] ? e.send(n.buffer) : e.send(n) } function l() { return n.length != 0 ? js_object(n.shift()) : -1 } s = 0, t = {}; function d(e) { if (t[e] != 0[0] && t[e] != null) { var n = t[e]; return t[e] = null, js_object(n) } return -1 } function u(e, n, o, i) { var a, r, c, d, u, l = s; s += 1, e == 0 && (r = "POST"), e == 1 && (r = "PUT"), e == 2 && (r = "GET"), e == 3 && (r = "DELETE"), d = consume_js_object(n), u = consume_js_object(o), c = consume_js_object(i), a = new XMLHttpRequest, a.open(r, d, !0), a.responseType = "arraybuffer"; for (const e in c) a.setRequestHeader(e, c[e]); return a.onload = function () { if (this.status == 200) { var n = new Uint8Array(this.response); t[l] = n } }, a.onerror = function (e) { console.error("Failed to make a request"), console.error(e) }, a.send(u), l } }()
\ No newline at end of file
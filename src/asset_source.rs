@@ -0,0 +1,317 @@
+use macroquad::prelude::*;
+use futures::future::LocalBoxFuture;
+use std::sync::{Arc, RwLock};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::HashMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Mutex;
+#[cfg(not(target_arch = "wasm32"))]
+use wz_reader::{WzFile, WzNode, WzNodeArc, WzObjectType};
+
+use crate::config::config;
+use crate::flags;
+
+#[cfg(target_arch = "wasm32")]
+extern "C" {
+    fn idb_save(name_ptr: *const u8, name_len: u32, data_ptr: *const u8, data_len: u32);
+}
+
+/// A backend that game data is read from.
+///
+/// Every asset is addressed by its logical WZ path, the same path it has inside
+/// the game's data files, e.g. `UI/UIWindow2.img`, `Map/Map/Map0/000010000.img`
+/// or `Character/00002000.img`. Plain files that live next to the data
+/// (fonts) use their file name, e.g. `MaplestoryLight.ttf`.
+pub trait AssetSource: Send + Sync {
+    /// Short human readable description used in logs
+    fn describe(&self) -> String;
+
+    /// Read the raw bytes of the asset at `path`
+    fn fetch<'a>(&'a self, path: &'a str) -> LocalBoxFuture<'a, Result<Vec<u8>, String>>;
+}
+
+/// The kinds of asset source that can be selected at startup
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AssetSourceKind {
+    /// HTTP server with an IndexedDB cache (the public bucket by default)
+    Http,
+    /// Directory of extracted `.img` files
+    Directory,
+    /// Directory of packed `.wz` archives (UI.wz, Map.wz, ...)
+    WzArchive,
+}
+
+impl AssetSourceKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "http" | "https" | "web" => Some(AssetSourceKind::Http),
+            "dir" | "directory" | "img" => Some(AssetSourceKind::Directory),
+            "wz" | "archive" => Some(AssetSourceKind::WzArchive),
+            _ => None,
+        }
+    }
+}
+
+static ASSET_SOURCE: RwLock<Option<Arc<dyn AssetSource>>> = RwLock::new(None);
+
+/// Install the asset source used by `AssetManager` for the rest of the session
+pub fn set_asset_source(source: Arc<dyn AssetSource>) {
+    info!("Asset source: {}", source.describe());
+    *ASSET_SOURCE.write().unwrap() = Some(source);
+}
+
+/// Get the active asset source, creating one from the startup settings on first use
+pub fn asset_source() -> Arc<dyn AssetSource> {
+    if let Some(source) = ASSET_SOURCE.read().unwrap().as_ref() {
+        return source.clone();
+    }
+
+    let source = create_asset_source(startup_kind(), &startup_root());
+    set_asset_source(source.clone());
+    source
+}
+
+/// Build an asset source of the given kind rooted at `root`
+/// (a base URL for HTTP, a directory for the local sources)
+pub fn create_asset_source(kind: AssetSourceKind, root: &str) -> Arc<dyn AssetSource> {
+    match kind {
+        AssetSourceKind::Http => Arc::new(HttpSource::new(root)),
+        #[cfg(not(target_arch = "wasm32"))]
+        AssetSourceKind::Directory => Arc::new(LocalDirSource::new(root)),
+        #[cfg(not(target_arch = "wasm32"))]
+        AssetSourceKind::WzArchive => Arc::new(WzArchiveSource::new(root)),
+        #[cfg(target_arch = "wasm32")]
+        _ => {
+            warn!("{:?} asset source is not available on web, falling back to HTTP", kind);
+            Arc::new(HttpSource::new(flags::DEFAULT_ASSET_URL))
+        }
+    }
+}
+
+/// Asset source kind chosen in the game config
+fn startup_kind() -> AssetSourceKind {
+    let name = config().asset_source.clone();
+    AssetSourceKind::from_name(&name).unwrap_or_else(|| {
+        warn!("Unknown asset source '{}', using HTTP", name);
+        AssetSourceKind::Http
+    })
+}
+
+/// Asset root chosen in the game config
+fn startup_root() -> String {
+    config().asset_root.clone()
+}
+
+/// Fetches assets over HTTP and caches them in IndexedDB on web.
+///
+/// The public bucket splits its data over two folders: `00` holds map images,
+/// Logo.img and String/Map.img, `01` holds everything else. Self-hosted
+/// mirrors are expected to use the same layout.
+pub struct HttpSource {
+    base_url: String,
+}
+
+impl HttpSource {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn bucket_folder(path: &str) -> &'static str {
+        if path.starts_with("Map/Map/") || path == "UI/Logo.img" || path == "String/Map.img" {
+            "00"
+        } else {
+            "01"
+        }
+    }
+
+    async fn fetch_and_cache(url: &str, cache_path: &str) -> Result<Vec<u8>, String> {
+        let idb_url = format!("idb://{}", cache_path);
+
+        // Try loading from IndexedDB first
+        if let Ok(bytes) = load_file(&idb_url).await {
+            if !bytes.is_empty() {
+                return Ok(bytes);
+            }
+        }
+
+        // Fallback to HTTP
+        match load_file(url).await {
+            Ok(bytes) => {
+                #[cfg(target_arch = "wasm32")]
+                unsafe {
+                    idb_save(
+                        cache_path.as_ptr(),
+                        cache_path.len() as u32,
+                        bytes.as_ptr(),
+                        bytes.len() as u32,
+                    );
+                }
+                Ok(bytes)
+            }
+            Err(e) => {
+                error!("HTTP fetch FAILED for {}: {:?}", url, e);
+                Err(format!("Failed to load asset from {}: {:?}", url, e))
+            }
+        }
+    }
+}
+
+impl AssetSource for HttpSource {
+    fn describe(&self) -> String {
+        format!("HTTP ({})", self.base_url)
+    }
+
+    fn fetch<'a>(&'a self, path: &'a str) -> LocalBoxFuture<'a, Result<Vec<u8>, String>> {
+        Box::pin(async move {
+            let folder = Self::bucket_folder(path);
+            let url = format!("{}/{}/{}", self.base_url, folder, path);
+            let cache_path = format!("/{}/{}", folder, path);
+            Self::fetch_and_cache(&url, &cache_path).await
+        })
+    }
+}
+
+/// Reads extracted `.img` files from a local directory.
+///
+/// Both `<root>/UI/UIWindow2.img` and `<root>/UI.wz/UIWindow2.img` layouts are accepted.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct LocalDirSource {
+    root: PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl LocalDirSource {
+    pub fn new(root: &str) -> Self {
+        Self { root: PathBuf::from(root) }
+    }
+
+    fn read(&self, path: &str) -> Result<Vec<u8>, String> {
+        let direct = self.root.join(path);
+        if direct.is_file() {
+            return std::fs::read(&direct)
+                .map_err(|e| format!("Failed to read {}: {}", direct.display(), e));
+        }
+
+        if let Some((category, rest)) = path.split_once('/') {
+            let extracted = self.root.join(format!("{}.wz", category)).join(rest);
+            if extracted.is_file() {
+                return std::fs::read(&extracted)
+                    .map_err(|e| format!("Failed to read {}: {}", extracted.display(), e));
+            }
+        }
+
+        Err(format!("Asset '{}' not found under {}", path, self.root.display()))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl AssetSource for LocalDirSource {
+    fn describe(&self) -> String {
+        format!("local directory ({})", self.root.display())
+    }
+
+    fn fetch<'a>(&'a self, path: &'a str) -> LocalBoxFuture<'a, Result<Vec<u8>, String>> {
+        Box::pin(async move { self.read(path) })
+    }
+}
+
+/// Reads images out of packed `.wz` archives (`<root>/UI.wz`, `<root>/Map.wz`, ...).
+///
+/// Archives are opened on first use and kept open. The image bytes are sliced
+/// straight out of the archive, so callers parse them exactly like a standalone `.img`.
+/// Non-WZ files such as fonts are read from `<root>` directly.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct WzArchiveSource {
+    root: PathBuf,
+    archives: Mutex<HashMap<String, WzNodeArc>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl WzArchiveSource {
+    pub fn new(root: &str) -> Self {
+        Self {
+            root: PathBuf::from(root),
+            archives: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Open (or reuse) the archive for a top-level category such as "UI" or "Map"
+    fn open_archive(&self, category: &str) -> Result<WzNodeArc, String> {
+        let mut archives = self.archives.lock().unwrap();
+        if let Some(node) = archives.get(category) {
+            return Ok(node.clone());
+        }
+
+        let wz_path = self.root.join(format!("{}.wz", category));
+        info!("Opening WZ archive: {}", wz_path.display());
+
+        let wz_file = WzFile::from_file(&wz_path, None, None, None)
+            .map_err(|e| format!("Failed to open {}: {:?}", wz_path.display(), e))?;
+        let node: WzNodeArc = WzNode::new(&category.into(), wz_file, None).into();
+        node.write()
+            .unwrap()
+            .parse(&node)
+            .map_err(|e| format!("Failed to parse {}: {:?}", wz_path.display(), e))?;
+
+        archives.insert(category.to_string(), node.clone());
+        Ok(node)
+    }
+
+    fn read_image(&self, path: &str) -> Result<Vec<u8>, String> {
+        let (category, inner) = path
+            .split_once('/')
+            .ok_or_else(|| format!("Asset path '{}' has no WZ category", path))?;
+        let mut node = self.open_archive(category)?;
+
+        // Directories are parsed lazily; the image itself is left unparsed
+        // since callers only need its raw bytes
+        let segments: Vec<&str> = inner.split('/').collect();
+        for (i, segment) in segments.iter().enumerate() {
+            let child = node
+                .read()
+                .unwrap()
+                .at_path(segment)
+                .ok_or_else(|| format!("'{}' not found in {}.wz", inner, category))?;
+            node = child;
+
+            if i + 1 < segments.len() {
+                node.write()
+                    .unwrap()
+                    .parse(&node)
+                    .map_err(|e| format!("Failed to parse {}/{}: {:?}", category, segment, e))?;
+            }
+        }
+
+        let node_read = node.read().unwrap();
+        match &node_read.object_type {
+            WzObjectType::Image(image) => Ok(image
+                .reader
+                .get_slice(image.offset..image.offset + image.block_size)
+                .to_vec()),
+            _ => Err(format!("'{}' is not an image", path)),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl AssetSource for WzArchiveSource {
+    fn describe(&self) -> String {
+        format!("WZ archives ({})", self.root.display())
+    }
+
+    fn fetch<'a>(&'a self, path: &'a str) -> LocalBoxFuture<'a, Result<Vec<u8>, String>> {
+        Box::pin(async move {
+            if path.ends_with(".img") {
+                self.read_image(path)
+            } else {
+                let file = self.root.join(path);
+                std::fs::read(&file).map_err(|e| format!("Failed to read {}: {}", file.display(), e))
+            }
+        })
+    }
+}
@@ -5,6 +5,9 @@ use wz_reader::{WzImage, WzNode, WzNodeArc, WzReader, WzNodeCast};
 
 use wz_reader::WzObjectType;
 
+use crate::asset_source::asset_source;
+use crate::wz_cache::load_wz_image;
+
 #[cfg(not(target_arch = "wasm32"))]
 use memmap2::MmapOptions;
 
@@ -17,7 +20,6 @@ pub struct FrameData {
 
 #[cfg(target_arch = "wasm32")]
 extern "C" {
-    fn idb_save(name_ptr: *const u8, name_len: u32, data_ptr: *const u8, data_len: u32);
     fn console_save(
         filename_ptr: *const u8,
         filename_len: u32,
@@ -48,56 +50,22 @@ impl AssetManager {
         Ok(bytes)
     }
 
-    pub async fn fetch_and_cache(url: &str, cache_path: &str) -> Result<Vec<u8>, String> {
-        let idb_url = format!("idb://{}", cache_path);
-        // info!("fetch_and_cache() - checking cache: {}", cache_path);
-
-        // Try loading from IndexedDB first
-        if let Ok(bytes) = load_file(&idb_url).await {
-            if bytes.len() > 0 {
-                // info!("Cache HIT for {}: {} bytes", cache_path, bytes.len());
-                return Ok(bytes);
-            }
-        }
-
-        // info!("Cache MISS for {} - fetching from: {}", cache_path, url);
-
-        // Fallback to HTTP
-        match load_file(url).await {
-            Ok(bytes) => {
-                // info!("HTTP fetch success: {} bytes. Saving to IndexedDB...", bytes.len());
-                #[cfg(target_arch = "wasm32")]
-                unsafe {
-                    idb_save(
-                        cache_path.as_ptr(),
-                        cache_path.len() as u32,
-                        bytes.as_ptr(),
-                        bytes.len() as u32,
-                    );
-                }
-                // info!("Saved to IndexedDB: {}", cache_path);
-                Ok(bytes)
-            }
-            Err(e) => {
-                error!("HTTP fetch FAILED for {}: {:?}", url, e);
-                Err(format!("Failed to load asset from {}: {:?}", url, e))
-            },
-        }
+    /// Read the raw bytes of an asset from the active asset source
+    /// `path` is the logical WZ path, e.g. "UI/UIWindow2.img"
+    pub async fn fetch_and_cache(path: &str) -> Result<Vec<u8>, String> {
+        let source = asset_source();
+        source.fetch(path).await
     }
 
     /// Batch fetch multiple assets in parallel
     /// Returns a Vec of Results in the same order as the input
-    pub async fn fetch_and_cache_batch(requests: Vec<(String, String)>) -> Vec<Result<Vec<u8>, String>> {
+    pub async fn fetch_and_cache_batch(paths: Vec<String>) -> Vec<Result<Vec<u8>, String>> {
         use futures::future::join_all;
         
-        // Create futures for all requests - clone strings so each future owns them
-        let futures: Vec<_> = requests.into_iter()
-            .map(|(url, cache_path)| {
-                let url_clone = url.clone();
-                let cache_path_clone = cache_path.clone();
-                async move {
-                    Self::fetch_and_cache(&url_clone, &cache_path_clone).await
-                }
+        // Create futures for all requests - each future owns its path
+        let futures: Vec<_> = paths.into_iter()
+            .map(|path| async move {
+                Self::fetch_and_cache(&path).await
             })
             .collect();
         
@@ -105,16 +73,22 @@ impl AssetManager {
         join_all(futures).await
     }
 
-    pub async fn load_texture(url: &str, cache_path: &str) -> Result<Texture2D, String> {
-        let bytes = Self::fetch_and_cache(url, cache_path).await?;
+    /// Load a TTF font from the active asset source
+    pub async fn load_font(path: &str) -> Result<Font, String> {
+        let bytes = Self::fetch_and_cache(path).await?;
+        load_ttf_font_from_bytes(&bytes).map_err(|e| format!("Failed to load font {}: {:?}", path, e))
+    }
+
+    pub async fn load_texture(path: &str) -> Result<Texture2D, String> {
+        let bytes = Self::fetch_and_cache(path).await?;
         Ok(Texture2D::from_file_with_format(
             &bytes,
             Some(ImageFormat::Png),
         ))
     }
 
-    pub async fn load_wz_img_from_url(url: &str, cache_path: &str) -> Result<(), String> {
-        let bytes = Self::fetch_and_cache(url, cache_path).await?;
+    pub async fn load_wz_img(path: &str) -> Result<(), String> {
+        let bytes = Self::fetch_and_cache(path).await?;
 
         info!("Parsing .img file, size: {} bytes", bytes.len());
 
@@ -126,8 +100,8 @@ impl AssetManager {
         let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
 
         // Create WzImage
-        // Derive node name from cache_path
-        let name = std::path::Path::new(cache_path)
+        // Derive node name from the asset path
+        let name = std::path::Path::new(path)
             .file_name()
             .and_then(|n| n.to_str())
             .unwrap_or("unknown.img");
@@ -200,40 +174,10 @@ impl AssetManager {
     }
 
     pub async fn get_wz_child_names(
-        url: &str,
-        cache_path: &str,
+        path: &str,
         node_path: &str,
     ) -> Result<Vec<String>, String> {
-        let bytes = Self::fetch_and_cache(url, cache_path).await?;
-
-        info!(
-            "Parsing .img file to get child names, size: {} bytes",
-            bytes.len()
-        );
-
-        // Guess Version / IV
-        let wz_iv = guess_iv_from_wz_img(&bytes).ok_or("Unable to guess version from img file")?;
-
-        // Create Reader
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-
-        // Create WzImage
-        let name = std::path::Path::new(cache_path)
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("unknown.img");
-
-        let wz_image = WzImage::new(&name.into(), 0, byte_len, &reader);
-
-        // Create Root Node
-        let node: WzNodeArc = WzNode::new(&name.into(), wz_image, None).into();
-
-        // Parse the node to populate children
-        node.write()
-            .unwrap()
-            .parse(&node)
-            .map_err(|e| format!("Failed to parse WZ node: {:?}", e))?;
+        let node = load_wz_image(path).await?;
 
         // Navigate to the target path
         info!("Navigating to path: {}", node_path);
@@ -267,40 +211,10 @@ impl AssetManager {
     }
 
     pub async fn load_wz_png_texture(
-        url: &str,
-        cache_path: &str,
+        path: &str,
         node_path: &str,
     ) -> Result<Texture2D, String> {
-        let bytes = Self::fetch_and_cache(url, cache_path).await?;
-
-        info!(
-            "Parsing .img file for PNG extraction, size: {} bytes",
-            bytes.len()
-        );
-
-        // Guess Version / IV
-        let wz_iv = guess_iv_from_wz_img(&bytes).ok_or("Unable to guess version from img file")?;
-
-        // Create Reader
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-
-        // Create WzImage
-        let name = std::path::Path::new(cache_path)
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("unknown.img");
-
-        let wz_image = WzImage::new(&name.into(), 0, byte_len, &reader);
-
-        // Create Root Node
-        let node: WzNodeArc = WzNode::new(&name.into(), wz_image, None).into();
-
-        // Parse the node to populate children
-        node.write()
-            .unwrap()
-            .parse(&node)
-            .map_err(|e| format!("Failed to parse WZ node: {:?}", e))?;
+        let node = load_wz_image(path).await?;
 
         // Navigate to the target path
         info!("Navigating to path: {}", node_path);
@@ -346,35 +260,10 @@ impl AssetManager {
 
     /// Get origin coordinates from a frame path (e.g., "Wizet/6/origin")
     async fn get_frame_origin(
-        url: &str,
-        cache_path: &str,
+        path: &str,
         origin_path: &str,
     ) -> Result<Vec2, String> {
-        let bytes = Self::fetch_and_cache(url, cache_path).await?;
-
-        // Guess Version / IV
-        let wz_iv = guess_iv_from_wz_img(&bytes).ok_or("Unable to guess version from img file")?;
-
-        // Create Reader
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-
-        // Create WzImage
-        let name = std::path::Path::new(cache_path)
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("unknown.img");
-
-        let wz_image = WzImage::new(&name.into(), 0, byte_len, &reader);
-
-        // Create Root Node
-        let node: WzNodeArc = WzNode::new(&name.into(), wz_image, None).into();
-
-        // Parse the node to populate children
-        node.write()
-            .unwrap()
-            .parse(&node)
-            .map_err(|e| format!("Failed to parse WZ node: {:?}", e))?;
+        let node = load_wz_image(path).await?;
 
         // Navigate to the origin path (at_path_parsed automatically parses nodes along the path)
         let origin_node = node
@@ -396,13 +285,12 @@ impl AssetManager {
     /// Load all frames for a given animation path from a WZ file
     /// Discovers frame names, filters to numeric frames, sorts them, and loads each as a texture
     pub async fn load_animation_frames(
-        base_url: &str,
-        cache_name: &str,
+        path: &str,
         base_path: &str,
     ) -> Vec<Texture2D> {
         // First, discover all available frame names
         info!("Discovering animation frames for {}...", base_path);
-        let frame_names = match Self::get_wz_child_names(base_url, cache_name, base_path).await {
+        let frame_names = match Self::get_wz_child_names(path, base_path).await {
             Ok(names) => {
                 // Filter to only PNG nodes (exclude origin, z, etc.)
                 let mut png_frames: Vec<String> = names
@@ -426,7 +314,7 @@ impl AssetManager {
         let mut frames: Vec<Texture2D> = Vec::new();
         for frame_name in &frame_names {
             let frame_path = format!("{}/{}", base_path, frame_name);
-            match Self::load_wz_png_texture(base_url, cache_name, &frame_path).await {
+            match Self::load_wz_png_texture(path, &frame_path).await {
                 Ok(tex) => {
                     frames.push(tex);
                 }
@@ -441,7 +329,7 @@ impl AssetManager {
     }
 
     /// Load a single frame from a parsed WZ node structure (synchronous, no async needed)
-    fn load_frame_from_node(
+    pub(crate) fn load_frame_from_node(
         node: &WzNodeArc,
         frame_path: &str,
         origin_path: &str,
@@ -504,44 +392,18 @@ impl AssetManager {
     /// Load all frames for a given animation path from a WZ file with origin coordinates
     /// Optimized version: parses WZ file once, then loads all frames in parallel
     pub async fn load_animation_frames_with_origins(
-        base_url: &str,
-        cache_name: &str,
+        path: &str,
         base_path: &str,
     ) -> Vec<FrameData> {
-        // Fetch and cache the WZ file once
-        let bytes = match Self::fetch_and_cache(base_url, cache_name).await {
-            Ok(bytes) => bytes,
+        // Get the parsed WZ file once (shared with every other loader through the WZ cache)
+        let root_node = match load_wz_image(path).await {
+            Ok(node) => node,
             Err(e) => {
-                error!("Failed to fetch WZ file: {}", e);
-                return Vec::new();
-            }
-        };
-
-        // Parse WZ file once
-        let wz_iv = match guess_iv_from_wz_img(&bytes) {
-            Some(iv) => iv,
-            None => {
-                error!("Unable to guess version from img file");
+                error!("Failed to load WZ file: {}", e);
                 return Vec::new();
             }
         };
 
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        let name = std::path::Path::new(cache_name)
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("unknown.img");
-
-        let wz_image = WzImage::new(&name.into(), 0, byte_len, &reader);
-        let root_node: WzNodeArc = WzNode::new(&name.into(), wz_image, None).into();
-
-        // Parse root node once
-        if let Err(e) = root_node.write().unwrap().parse(&root_node) {
-            error!("Failed to parse WZ node: {:?}", e);
-            return Vec::new();
-        }
-
         // Discover frame names from already-parsed node (much faster than re-parsing)
         info!("Discovering animation frames with origins for {}...", base_path);
         let target_node = root_node
@@ -596,12 +458,11 @@ impl AssetManager {
     }
 
     /// Display an asset from a WZ .img file at the specified coordinates
-    /// Fetches the .img file if not in IndexedDB cache, loads the PNG texture from the specified directory path,
+    /// Fetches the .img file from the active asset source, loads the PNG texture from the specified directory path,
     /// and returns the texture so it can be drawn using draw_texture(texture, x, y, WHITE)
     /// 
     /// # Parameters
-    /// - `base_url`: The full URL to the .img file (e.g., "https://example.com/path/Logo.img")
-    /// - `img_name`: The filename to use as the cache key (e.g., "Logo.img")
+    /// - `path`: The logical WZ path of the .img file (e.g., "UI/Logo.img")
     /// - `directory`: The node path within the WZ file to the PNG (e.g., "Nexon/0" or "Nexon")
     /// - `x`: X coordinate for drawing
     /// - `y`: Y coordinate for drawing
@@ -612,8 +473,7 @@ impl AssetManager {
     /// # Example
     /// ```rust
     /// let texture = AssetManager::display_asset(
-    ///     "https://example.com/Logo.img",
-    ///     "Logo.img",
+    ///     "UI/Logo.img",
     ///     "Nexon/0",
     ///     100.0,
     ///     200.0
@@ -622,14 +482,13 @@ impl AssetManager {
     /// ```
     #[allow(unused_variables)] // x and y are part of the API contract for documentation purposes
     pub async fn display_asset(
-        base_url: &str,
-        img_name: &str,
+        path: &str,
         directory: &str,
         x: f32,
         y: f32,
     ) -> Result<Texture2D, String> {
-        // Load the PNG texture from the WZ file (fetches from cache or URL if needed)
-        let texture = Self::load_wz_png_texture(base_url, img_name, directory).await?;
+        // Load the PNG texture from the WZ file (fetched from the active asset source)
+        let texture = Self::load_wz_png_texture(path, directory).await?;
         
         // Note: Drawing must be done synchronously in the render loop
         // The caller should use: draw_texture(&texture, x, y, WHITE)
@@ -1,13 +1,14 @@
 use macroquad::{audio, prelude::*};
-use crate::assets::AssetManager;
-use std::sync::Arc;
-use wz_reader::{WzNode, WzNodeArc, WzNodeCast, WzReader, WzImage};
-use wz_reader::version::guess_iv_from_wz_img;
+use std::collections::HashMap;
+use crate::wz_cache::load_wz_image;
+use wz_reader::WzNodeCast;
 
 /// Audio manager for handling background music and sound effects
 pub struct AudioManager {
     current_bgm: Option<audio::Sound>,
     current_bgm_name: String,
+    // Sound effects by "Img/track" (None if loading failed, so it isn't retried)
+    effects: HashMap<String, Option<audio::Sound>>,
     // Alternative Web Audio API playback (for Chrome compatibility)
     #[cfg(target_arch = "wasm32")]
     web_audio_context_id: Option<u32>, // ID for Web Audio API context
@@ -23,6 +24,7 @@ impl AudioManager {
         Self {
             current_bgm: None,
             current_bgm_name: String::new(),
+            effects: HashMap::new(),
             #[cfg(target_arch = "wasm32")]
             web_audio_context_id: None,
             #[cfg(target_arch = "wasm32")]
@@ -269,6 +271,30 @@ impl AudioManager {
         // No-op for non-WASM
     }
 
+    /// Load a sound effect from `Sound/<img_name>.img/<track_name>` so `play_effect` can play it
+    /// as "<img_name>/<track_name>". Each effect is only loaded (or tried) once.
+    pub async fn load_effect(&mut self, img_name: &str, track_name: &str) {
+        let key = format!("{}/{}", img_name, track_name);
+        if self.effects.contains_key(&key) {
+            return;
+        }
+        let sound = match self.load_sound_from_wz_with_web_audio(img_name, track_name).await {
+            Ok((sound, _)) => Some(sound),
+            Err(e) => {
+                warn!("Failed to load sound effect {}: {}", key, e);
+                None
+            }
+        };
+        self.effects.insert(key, sound);
+    }
+
+    /// Play a sound effect once. Returns false if it isn't loaded.
+    pub fn play_effect(&self, key: &str) -> bool {
+        let Some(Some(sound)) = self.effects.get(key) else { return false };
+        audio::play_sound(sound, audio::PlaySoundParams { looped: false, volume: 0.5 });
+        true
+    }
+
     /// Set BGM volume (0.0 to 1.0)
     pub fn set_volume(&self, volume: f32) {
         if let Some(sound) = &self.current_bgm {
@@ -291,43 +317,13 @@ impl AudioManager {
     
     /// Load raw sound data from WZ file
     async fn load_sound_data_from_wz(&self, img_name: &str, track_name: &str) -> Result<Vec<u8>, String> {
-        // Construct the URL and cache path
-        let url = format!(
-            "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/Sound/{}.img",
-            img_name
-        );
-        let cache_path = format!("/01/Sound/{}.img", img_name);
+        // Construct the WZ path
+        let wz_path = format!("Sound/{}.img", img_name);
 
-        info!("  → Fetching sound WZ from: {}", url);
+        info!("  → Fetching sound WZ: {}", wz_path);
 
-        // Fetch and load the WZ file
-        let bytes = AssetManager::fetch_and_cache(&url, &cache_path).await?;
-        info!("  → WZ file fetched: {} bytes", bytes.len());
-
-        // Guess IV from the WZ file
-        let wz_iv = guess_iv_from_wz_img(&bytes)
-            .ok_or_else(|| "Unable to guess version from sound file".to_string())?;
-        info!("  → IV guessed successfully");
-
-        let byte_len = bytes.len();
-
-        // Create WZ reader
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        info!("  → WZ reader created");
-
-        // Create WZ image
-        let cache_name_ref: wz_reader::WzNodeName = cache_path.clone().into();
-        let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-
-        // Create root node
-        let root_node: WzNodeArc = WzNode::new(&cache_path.clone().into(), wz_image, None).into();
-
-        // Parse the root node
-        root_node
-            .write()
-            .unwrap()
-            .parse(&root_node)
-            .map_err(|e| format!("Failed to parse sound WZ: {:?}", e))?;
+        // Fetch and parse the WZ file (or reuse it from the WZ cache)
+        let root_node = load_wz_image(&wz_path).await?;
         info!("  → WZ parsed successfully");
 
         // Navigate to the sound track - ensure it's parsed first
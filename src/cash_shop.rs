@@ -1,11 +1,8 @@
 use macroquad::prelude::*;
-use crate::assets::AssetManager;
-use std::sync::Arc;
-use wz_reader::version::guess_iv_from_wz_img;
-use wz_reader::{WzImage, WzNode, WzNodeArc, WzReader, WzNodeCast};
+use crate::wz_cache::load_wz_image;
+use wz_reader::{WzNodeArc, WzNodeCast};
 
-const CASHSHOP_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/UI/CashShop.img";
-const CASHSHOP_CACHE: &str = "/01/UI/CashShop.img";
+const CASHSHOP_PATH: &str = "UI/CashShop.img";
 
 /// Texture with origin point
 struct TextureWithOrigin {
@@ -127,20 +124,8 @@ impl CashShop {
     }
 
     async fn load_from_wz() -> Result<(Option<Texture2D>, CashShopButton), String> {
-        let bytes = AssetManager::fetch_and_cache(CASHSHOP_URL, CASHSHOP_CACHE).await
-            .map_err(|e| format!("Failed to fetch CashShop.img: {}", e))?;
-
-        let wz_iv = guess_iv_from_wz_img(&bytes)
-            .ok_or_else(|| "Unable to guess version from CashShop.img".to_string())?;
-
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        let cache_name_ref: wz_reader::WzNodeName = CASHSHOP_CACHE.to_string().into();
-        let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-        let root_node: WzNodeArc = WzNode::new(&CASHSHOP_CACHE.into(), wz_image, None).into();
-
-        root_node.write().unwrap().parse(&root_node)
-            .map_err(|e| format!("Failed to parse CashShop.img: {:?}", e))?;
+        let root_node = load_wz_image(CASHSHOP_PATH).await
+            .map_err(|e| format!("Failed to load CashShop.img: {}", e))?;
 
         // Load background from Base/backgrnd
         let bg = Self::load_texture(&root_node, "Base/backgrnd").await.ok();
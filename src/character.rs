@@ -1,18 +1,31 @@
 use serde::{Deserialize, Serialize};
 use macroquad::prelude::*;
-use std::collections::HashMap;
-use std::sync::Mutex;
-use crate::flags;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use crate::config::config;
+use crate::equipment::{EquipSlot, Equipment};
+use crate::game_world::combat;
+use crate::game_world::skills::SKILL_HOTKEYS;
+use crate::item_info::EquipStats;
+use crate::key_config::ITEM_HOTKEYS;
+use crate::inventory::Inventory;
+use crate::job::Job;
+use crate::leveling::{self, ApStat};
+use crate::quest_log::QuestLog;
+use crate::skill_info::{self, SkillInfo};
+use crate::storage::{decode_versioned, encode_versioned, storage};
 
-// In-memory storage that simulates IndexedDB (works on all platforms)
-// On web, you would replace this with actual IndexedDB calls
-static STORAGE: Mutex<Option<HashMap<String, Vec<u8>>>> = Mutex::new(None);
-
-/// Character data that gets saved and loaded
+/// Character data that gets saved and loaded.
+///
+/// New fields must have a sensible `Default` (missing fields are filled from
+/// `CharacterData::default()` when an older save is loaded). Renamed or
+/// reinterpreted fields need a bump of `CHARACTER_SCHEMA_VERSION` and a step in
+/// `migrate_character`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CharacterData {
     pub name: String,
-    pub job: usize,
+    pub job: Job,
     pub level: u32,
     pub exp: u32,
     pub hp: u32,
@@ -23,60 +36,321 @@ pub struct CharacterData {
     pub dex: u32,
     pub int: u32,
     pub luk: u32,
+    /// Unspent ability points
+    pub ap: u32,
+    /// Unspent skill points
+    pub sp: u32,
+    /// Skin tone (body/head image 00002000 + skin)
+    pub skin: i32,
+    /// Hair style and color (Hair/000xxxxx.img)
+    pub hair: i32,
+    /// Face (Face/000xxxxx.img)
+    pub face: i32,
+    pub inventory: Inventory,
+    pub equipment: Equipment,
+    /// Learned skills: skill id -> level
+    pub skills: BTreeMap<i32, u32>,
+    /// Skill bound to each of `SKILL_HOTKEYS`
+    pub skill_keys: [Option<i32>; SKILL_HOTKEYS.len()],
+    /// Consumable bound to each of `ITEM_HOTKEYS`
+    pub item_keys: [Option<i32>; ITEM_HOTKEYS.len()],
+    pub meso: u32,
+    /// Started and completed quests
+    pub quests: QuestLog,
+    /// Map the character starts in next time (0 = the default starting map)
+    pub map_id: i32,
 }
 
+/// Character stats after equipment bonuses (recomputed from `CharacterData` when needed)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DerivedStats {
+    pub str: u32,
+    pub dex: u32,
+    pub int: u32,
+    pub luk: u32,
+    pub max_hp: u32,
+    pub max_mp: u32,
+    /// Weapon attack
+    pub attack: i32,
+    pub magic_attack: i32,
+    /// Weapon defense
+    pub defense: i32,
+    pub magic_defense: i32,
+    pub accuracy: i32,
+    pub avoid: i32,
+    /// Movement speed, percent
+    pub speed: i32,
+    /// Jump, percent
+    pub jump: i32,
+    /// Basic attack damage range
+    pub damage: (i32, i32),
+}
+
+/// Most meso a character can hold
+const MAX_MESO: u32 = i32::MAX as u32;
+
 /// Storage key for the character list
 const CHARACTER_LIST_KEY: &str = "character_list";
 
 /// Prefix for individual character storage keys
 const CHARACTER_KEY_PREFIX: &str = "character_";
 
-/// Initialize storage
-fn init_storage() {
-    let mut storage = STORAGE.lock().unwrap();
-    if storage.is_none() {
-        *storage = Some(HashMap::new());
-    }
-}
+/// Schema version written with every character save
+const CHARACTER_SCHEMA_VERSION: u32 = 1;
 
-/// Store data in memory (simulates IndexedDB on web)
-fn store_data(key: &str, data: &[u8]) {
-    init_storage();
-    let mut storage = STORAGE.lock().unwrap();
-    if let Some(ref mut map) = *storage {
-        map.insert(key.to_string(), data.to_vec());
+/// Upgrade a saved character from `version` to `CHARACTER_SCHEMA_VERSION`, one step at a time
+fn migrate_character(version: u32, data: Value) -> Result<Value, String> {
+    if version > CHARACTER_SCHEMA_VERSION {
+        return Err(format!(
+            "Save was written by a newer version (schema {}, this build supports {})",
+            version, CHARACTER_SCHEMA_VERSION
+        ));
     }
+
+    // Version 0 (unversioned saves) has the same layout as version 1
+    Ok(data)
 }
 
-/// Load data from memory (simulates IndexedDB on web)
-fn load_data(key: &str) -> Option<Vec<u8>> {
-    init_storage();
-    let storage = STORAGE.lock().unwrap();
-    storage.as_ref()?.get(key).cloned()
+impl Default for CharacterData {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            job: Job::Beginner,
+            level: 1,
+            exp: 0,
+            hp: 50,
+            max_hp: 50,
+            mp: 50,
+            max_mp: 50,
+            str: 4,
+            dex: 4,
+            int: 4,
+            luk: 4,
+            ap: 0,
+            sp: 0,
+            skin: 0,
+            hair: 30000,
+            face: 20000,
+            inventory: Inventory::default(),
+            equipment: Equipment::default(),
+            skills: BTreeMap::new(),
+            skill_keys: Default::default(),
+            item_keys: Default::default(),
+            meso: 0,
+            quests: QuestLog::default(),
+            map_id: 0,
+        }
+    }
 }
 
 impl CharacterData {
-    pub fn new(name: String, job: usize) -> Self {
-        let (hp, mp, str, dex, int, luk) = if flags::START_WITH_MAX_STATS {
-            (9999, 9999, 999, 999, 999, 999)
-        } else {
-            (50, 50, 4, 4, 4, 4)
-        };
-
-        Self {
+    pub fn new(name: String, job: Job) -> Self {
+        let character = Self {
             name,
             job,
-            level: 200,
-            exp: 0,
-            hp,
-            max_hp: hp,
-            mp,
-            max_mp: mp,
-            str,
-            dex,
-            int,
-            luk,
+            equipment: Self::starter_equipment(),
+            ..Default::default()
+        };
+        if !config().start_with_max_stats {
+            return character;
         }
+        Self {
+            level: leveling::MAX_LEVEL,
+            hp: 9999,
+            max_hp: 9999,
+            mp: 9999,
+            max_mp: 9999,
+            str: 999,
+            dex: 999,
+            int: 999,
+            luk: 999,
+            ..character
+        }
+    }
+
+    /// Beginner outfit every new character starts with
+    fn starter_equipment() -> Equipment {
+        let mut equipment = Equipment::new();
+        // White Undershirt, Blue Jean Shorts, Red Rubber Boots, Sword
+        for item_id in [1040002, 1060002, 1072001, 1302000] {
+            let _ = equipment.equip(item_id);
+        }
+        equipment
+    }
+
+    /// Add meso (or take it away, for negative amounts). Fails without changing anything if the
+    /// character can't afford it or would go over the cap.
+    pub fn add_meso(&mut self, amount: i32) -> Result<(), String> {
+        let meso = self.meso as i64 + amount as i64;
+        if meso < 0 {
+            return Err("You don't have enough mesos.".to_string());
+        }
+        if meso > MAX_MESO as i64 {
+            return Err("You can't hold any more mesos.".to_string());
+        }
+        self.meso = meso as u32;
+        Ok(())
+    }
+
+    /// EXP needed for the next level (0 at the level cap)
+    pub fn exp_to_next_level(&self) -> u32 {
+        leveling::exp_to_next_level(self.level)
+    }
+
+    /// Add EXP, levelling up as many times as it covers. Returns the number of levels gained.
+    pub fn gain_exp(&mut self, amount: u32) -> u32 {
+        let mut levels = 0;
+        self.exp = self.exp.saturating_add(amount);
+        while self.level < leveling::MAX_LEVEL {
+            let needed = self.exp_to_next_level();
+            if self.exp < needed {
+                break;
+            }
+            self.exp -= needed;
+            self.level_up();
+            levels += 1;
+        }
+        if self.level >= leveling::MAX_LEVEL {
+            self.exp = 0;
+        }
+        levels
+    }
+
+    /// Gain a level: AP, SP and max HP/MP, and a full heal
+    fn level_up(&mut self) {
+        self.level += 1;
+        self.ap += leveling::AP_PER_LEVEL;
+        self.sp += self.job.sp_for_level(self.level);
+
+        let (hp, mp) = self.job.level_up_hp_mp();
+        self.max_hp = (self.max_hp + hp).min(leveling::MAX_HP_MP);
+        self.max_mp = (self.max_mp + mp).min(leveling::MAX_HP_MP);
+        self.hp = self.max_hp;
+        self.mp = self.max_mp;
+    }
+
+    /// Put one ability point into a stat
+    pub fn spend_ap(&mut self, stat: ApStat) -> Result<(), String> {
+        if self.ap == 0 {
+            return Err("You have no AP left".to_string());
+        }
+
+        let (hp_per_ap, mp_per_ap) = self.job.ap_hp_mp();
+        let (value, step, max) = match stat {
+            ApStat::Str => (&mut self.str, 1, leveling::MAX_BASE_STAT),
+            ApStat::Dex => (&mut self.dex, 1, leveling::MAX_BASE_STAT),
+            ApStat::Int => (&mut self.int, 1, leveling::MAX_BASE_STAT),
+            ApStat::Luk => (&mut self.luk, 1, leveling::MAX_BASE_STAT),
+            ApStat::Hp => (&mut self.max_hp, hp_per_ap, leveling::MAX_HP_MP),
+            ApStat::Mp => (&mut self.max_mp, mp_per_ap, leveling::MAX_HP_MP),
+        };
+        if *value >= max {
+            return Err(format!("{} is already at its maximum", stat.label()));
+        }
+        *value = (*value + step).min(max);
+        self.ap -= 1;
+        Ok(())
+    }
+
+    /// Base value of STR, DEX, INT or LUK (max HP/MP for the HP and MP stats)
+    pub fn base_stat(&self, stat: ApStat) -> u32 {
+        match stat {
+            ApStat::Str => self.str,
+            ApStat::Dex => self.dex,
+            ApStat::Int => self.int,
+            ApStat::Luk => self.luk,
+            ApStat::Hp => self.max_hp,
+            ApStat::Mp => self.max_mp,
+        }
+    }
+
+    /// Check that the character can advance to `job`: it is the next step on their branch and
+    /// they meet its level and stat requirements
+    pub fn check_advancement(&self, job: Job) -> Result<(), String> {
+        if job.previous() != Some(self.job) {
+            return Err(format!("A {} can't become a {}.", self.job.name(), job.name()));
+        }
+        let requirement = job.requirement();
+        if self.level < requirement.level {
+            return Err(format!("You need to be level {} to become a {}.", requirement.level, job.name()));
+        }
+        if let Some((stat, needed)) = requirement.stat {
+            if self.base_stat(stat) < needed {
+                return Err(format!("You need {} {} to become a {}.", needed, stat.label(), job.name()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Advance to `job`, gaining its skill points and max HP/MP
+    pub fn advance_job(&mut self, job: Job) -> Result<(), String> {
+        self.check_advancement(job)?;
+        self.sp += job.advancement_sp(self.level);
+        let (hp, mp) = job.advancement_hp_mp();
+        self.max_hp = (self.max_hp + hp).min(leveling::MAX_HP_MP);
+        self.max_mp = (self.max_mp + mp).min(leveling::MAX_HP_MP);
+        self.job = job;
+        Ok(())
+    }
+
+    /// Level of a skill (0 if not learned)
+    pub fn skill_level(&self, skill_id: i32) -> u32 {
+        self.skills.get(&skill_id).copied().unwrap_or(0)
+    }
+
+    /// Put one skill point into a skill
+    pub fn spend_sp(&mut self, skill: &SkillInfo) -> Result<(), String> {
+        if self.sp == 0 {
+            return Err("You have no SP left".to_string());
+        }
+        if !skill_info::skill_books(self.job.id()).contains(&SkillInfo::book(skill.id)) {
+            return Err("Your job cannot learn this skill".to_string());
+        }
+        let level = self.skill_level(skill.id);
+        if level >= skill.max_level() {
+            return Err(format!("{} is already at its master level", skill.name));
+        }
+        for &(required_id, required_level) in &skill.required {
+            if self.skill_level(required_id) < required_level {
+                let name = skill_info::skill_info(required_id).map_or_else(|| required_id.to_string(), |info| info.name);
+                return Err(format!("Requires {} level {}", name, required_level));
+            }
+        }
+
+        self.skills.insert(skill.id, level + 1);
+        self.sp -= 1;
+        Ok(())
+    }
+
+    /// Stats with equipment and buff bonuses applied, and the values derived from them
+    pub fn derived_stats(&self, buffs: &EquipStats) -> DerivedStats {
+        let mut bonus = self.equipment.bonus_stats();
+        bonus.add_bonuses(buffs);
+        let add = |base: u32, inc: i32| (base as i32 + inc).max(0) as u32;
+
+        let mut stats = DerivedStats {
+            str: add(self.str, bonus.inc_str),
+            dex: add(self.dex, bonus.inc_dex),
+            int: add(self.int, bonus.inc_int),
+            luk: add(self.luk, bonus.inc_luk),
+            max_hp: add(self.max_hp, bonus.inc_max_hp),
+            max_mp: add(self.max_mp, bonus.inc_max_mp),
+            attack: bonus.inc_pad,
+            magic_attack: bonus.inc_mad,
+            defense: bonus.inc_pdd,
+            magic_defense: bonus.inc_mdd,
+            accuracy: 0,
+            avoid: 0,
+            speed: (100 + bonus.inc_speed).clamp(100, 140),
+            jump: (100 + bonus.inc_jump).clamp(100, 123),
+            damage: (0, 0),
+        };
+        // Pre-Big Bang accuracy and avoidability
+        stats.accuracy = (stats.dex as f32 * 0.8 + stats.luk as f32 * 0.5) as i32 + bonus.inc_acc;
+        stats.avoid = (stats.dex as f32 * 0.25 + stats.luk as f32 * 0.5) as i32 + bonus.inc_eva;
+        stats.magic_attack += stats.int as i32;
+        stats.damage = combat::damage_range(&stats, self.equipment.get(EquipSlot::Weapon));
+        stats
     }
 
     /// Get storage key for a specific character
@@ -87,12 +361,12 @@ impl CharacterData {
     /// Save character data to storage (IndexedDB on web, file on native)
     pub fn save(&self) -> Result<(), String> {
         // Serialize character data
-        let json = serde_json::to_string(self)
+        let bytes = encode_versioned(CHARACTER_SCHEMA_VERSION, self)
             .map_err(|e| format!("Failed to serialize character: {}", e))?;
 
         // Save individual character
         let character_key = Self::get_character_key(&self.name);
-        store_data(&character_key, json.as_bytes());
+        storage().store(&character_key, &bytes)?;
 
         // Update character list
         let mut character_names = Self::load_character_list();
@@ -101,13 +375,25 @@ impl CharacterData {
             Self::save_character_list(&character_names)?;
         }
 
-        if flags::VERBOSE_CHARACTER_IO {
+        if config().verbose_character_io {
             info!("Character '{}' saved to storage (key: {})", self.name, character_key);
         }
 
         Ok(())
     }
 
+    /// Whether a character with this name exists already. Names differing only in case count as
+    /// the same: save files would clash on case-insensitive file systems (macOS, Windows).
+    pub fn name_taken(name: &str) -> bool {
+        let name = name.to_lowercase();
+        Self::load_character_list().iter().any(|existing| existing.to_lowercase() == name)
+    }
+
+    /// Whether the account has room for another character (`max_characters`)
+    pub fn has_free_slot() -> bool {
+        Self::load_character_list().len() < config().max_characters
+    }
+
     /// Load all characters from storage
     pub fn load_all() -> Vec<CharacterData> {
         let character_names = Self::load_character_list();
@@ -119,7 +405,7 @@ impl CharacterData {
             }
         }
 
-        if flags::VERBOSE_CHARACTER_IO {
+        if config().verbose_character_io {
             info!("Loaded {} characters from storage", characters.len());
         }
 
@@ -130,32 +416,34 @@ impl CharacterData {
     fn load_one(name: &str) -> Option<CharacterData> {
         let character_key = Self::get_character_key(name);
 
-        if let Some(bytes) = load_data(&character_key) {
-            if let Ok(json) = String::from_utf8(bytes) {
-                if let Ok(character) = serde_json::from_str::<CharacterData>(&json) {
-                    return Some(character);
-                }
-            }
-        }
+        let result = storage().load(&character_key).and_then(|bytes| {
+            let bytes = bytes.ok_or_else(|| "not found".to_string())?;
+            let (version, data) = decode_versioned(&bytes)?;
+            let data = migrate_character(version, data)?;
+            serde_json::from_value::<CharacterData>(data).map_err(|e| format!("Invalid character data: {}", e))
+        });
 
-        if flags::VERBOSE_CHARACTER_IO {
-            warn!("Failed to load character: {}", name);
+        match result {
+            Ok(character) => Some(character),
+            Err(e) => {
+                warn!("Failed to load character {}: {}", name, e);
+                None
+            }
         }
-        None
     }
 
     /// Delete a character from storage
     pub fn delete(name: &str) -> Result<(), String> {
         // Delete individual character
         let character_key = Self::get_character_key(name);
-        store_data(&character_key, &[]); // Clear the storage
+        storage().remove(&character_key)?;
 
         // Update character list
         let mut character_names = Self::load_character_list();
         character_names.retain(|n| n != name);
         Self::save_character_list(&character_names)?;
 
-        if flags::VERBOSE_CHARACTER_IO {
+        if config().verbose_character_io {
             info!("Character '{}' deleted from storage", name);
         }
 
@@ -164,14 +452,17 @@ impl CharacterData {
 
     /// Load the list of character names
     fn load_character_list() -> Vec<String> {
-        if let Some(bytes) = load_data(CHARACTER_LIST_KEY) {
-            if let Ok(json) = String::from_utf8(bytes) {
-                if let Ok(names) = serde_json::from_str::<Vec<String>>(&json) {
-                    return names;
-                }
+        match storage().load(CHARACTER_LIST_KEY) {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                error!("Character list is corrupt: {}", e);
+                Vec::new()
+            }),
+            Ok(None) => Vec::new(),
+            Err(e) => {
+                error!("Failed to load character list: {}", e);
+                Vec::new()
             }
         }
-        Vec::new()
     }
 
     /// Save the list of character names
@@ -179,12 +470,12 @@ impl CharacterData {
         let json = serde_json::to_string(names)
             .map_err(|e| format!("Failed to serialize character list: {}", e))?;
 
-        store_data(CHARACTER_LIST_KEY, json.as_bytes());
-        Ok(())
+        storage().store(CHARACTER_LIST_KEY, json.as_bytes())
     }
 
     /// Create a test character (for debugging)
     pub fn create_test_character() -> Self {
-        Self::new(flags::TEST_CHARACTER_NAME.to_string(), flags::TEST_CHARACTER_JOB)
+        let job = Job::from(config().test_character_job);
+        Self::new(config().test_character_name.clone(), job)
     }
 }
@@ -1,19 +1,27 @@
 use macroquad::prelude::*;
-use crate::assets::AssetManager;
 use crate::character::CharacterData;
+use crate::character_renderer::{CharacterLook, CharacterRenderer, CharacterState};
 use crate::cursor::CursorManager;
-use crate::flags;
-use std::sync::Arc;
-use wz_reader::version::guess_iv_from_wz_img;
-use wz_reader::{WzImage, WzNode, WzNodeArc, WzReader, WzObjectType, WzNodeCast};
+use crate::config::config;
+use crate::job::Job;
+use crate::wz_cache::load_wz_image;
+use wz_reader::{WzNodeArc, WzObjectType, WzNodeCast};
 
 #[cfg(not(target_arch = "wasm32"))]
 use memmap2::MmapOptions;
 
-const LOGIN_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/UI/Login.img";
-const LOGIN_CACHE_NAME: &str = "/01/UI/Login.img";
-const BACKGROUND_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/Map/Back/login.img";
-const BACKGROUND_CACHE_NAME: &str = "/01/Map/Back/login.img";
+const LOGIN_PATH: &str = "UI/Login.img";
+const BACKGROUND_PATH: &str = "Map/Back/login.img";
+
+/// Appearance options offered at character creation
+const FACE_OPTIONS: [i32; 6] = [20000, 20001, 20002, 21000, 21001, 21002];
+const HAIR_OPTIONS: [i32; 6] = [30000, 30020, 30030, 31000, 31040, 31050];
+const HAIR_COLOR_COUNT: usize = 8; // last digit of the hair id
+const SKIN_OPTIONS: [i32; 4] = [0, 1, 2, 3];
+
+/// Rows of the appearance picker drawn on the job panel
+const LOOK_ROWS: [&str; 4] = ["Face", "Hair", "Hair Color", "Skin"];
+const LOOK_ROW_HEIGHT: f32 = 22.0;
 
 /// Structure to hold texture with its origin point
 #[derive(Clone)]
@@ -190,7 +198,11 @@ pub struct CharacterCreationState {
 
     // Character state
     character_name: String,
-    selected_job: usize,
+
+    // Appearance picker: chosen option per LOOK_ROWS entry, and the row the arrows change
+    look_choices: [usize; 4],
+    selected_look_row: usize,
+    preview: CharacterRenderer,
 
     // Character anchor point (where character setting assets align to)
     character_anchor: Vec2,
@@ -257,9 +269,11 @@ impl CharacterCreationState {
             right_button: Button::new(400.0, 300.0),
             check_button: Button::new(400.0, 300.0),
             character_name: String::new(),
-            selected_job: 0,
+            look_choices: [0; 4],
+            selected_look_row: 0,
+            preview: CharacterRenderer::new(),
             character_anchor: vec2(0.0, 0.0),
-            movable_mode: flags::DEFAULT_MOVABLE_MODE,
+            movable_mode: config().default_movable_mode,
             drag_state: DragState {
                 active: false,
                 element: None,
@@ -281,6 +295,55 @@ impl CharacterCreationState {
         }
     }
 
+    /// The character that would be created with the current name and appearance choices.
+    /// Every explorer starts out as a Beginner and picks a job from a job instructor.
+    fn build_character(&self) -> CharacterData {
+        let mut character = CharacterData::new(self.character_name.clone(), Job::Beginner);
+        character.face = FACE_OPTIONS[self.look_choices[0]];
+        character.hair = HAIR_OPTIONS[self.look_choices[1]] + self.look_choices[2] as i32;
+        character.skin = SKIN_OPTIONS[self.look_choices[3]];
+        character
+    }
+
+    /// Step the option of an appearance row forwards or backwards (wrapping around)
+    fn change_look(&mut self, row: usize, step: isize) {
+        let count = match row {
+            0 => FACE_OPTIONS.len(),
+            1 => HAIR_OPTIONS.len(),
+            2 => HAIR_COLOR_COUNT,
+            _ => SKIN_OPTIONS.len(),
+        };
+        let choice = &mut self.look_choices[row];
+        *choice = (*choice as isize + step).rem_euclid(count as isize) as usize;
+        info!("{} option {}", LOOK_ROWS[row], *choice);
+        self.preview.set_look(CharacterLook::from_character(&self.build_character()));
+    }
+
+    /// Value shown for an appearance row
+    fn look_value(&self, row: usize) -> String {
+        let character = self.build_character();
+        match row {
+            0 => character.face.to_string(),
+            1 => (character.hair / 10 * 10).to_string(),
+            2 => self.look_choices[2].to_string(),
+            _ => character.skin.to_string(),
+        }
+    }
+
+    /// Top-left of the first appearance row inside the job panel
+    fn look_rows_origin(&self, center_x: f32, center_y: f32) -> Option<Vec2> {
+        let panel = self.char_job_panel.as_ref()?;
+        let pos = self.char_job_pos.unwrap_or(vec2(center_x - 200.0, center_y - 100.0));
+        Some(vec2(pos.x - panel.origin.x + 20.0, pos.y - panel.origin.y + 40.0))
+    }
+
+    /// Rebuild the preview character after an appearance change (async wrapper)
+    pub async fn update_preview(&mut self) {
+        if self.preview.needs_reload() {
+            self.preview.load_assets().await;
+        }
+    }
+
     /// Check if should transition back to character selection
     pub fn should_transition_to_char_select(&self) -> bool {
         self.transition_to_char_select
@@ -296,64 +359,20 @@ impl CharacterCreationState {
             show_mouse(false);
         }
 
-        let bytes = match AssetManager::fetch_and_cache(LOGIN_URL, LOGIN_CACHE_NAME).await {
-            Ok(bytes) => bytes,
+        let root_node = match load_wz_image(LOGIN_PATH).await {
+            Ok(node) => node,
             Err(e) => {
-                error!("Failed to fetch Login.img: {}", e);
+                error!("Failed to load Login.img: {}", e);
                 return;
             }
         };
 
-        info!("Parsing Login.img (size: {} bytes)...", bytes.len());
-
-        let wz_iv = match guess_iv_from_wz_img(&bytes) {
-            Some(iv) => iv,
-            None => {
-                error!("Unable to guess version from Login.img");
-                return;
-            }
-        };
-
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        let wz_image = WzImage::new(&LOGIN_CACHE_NAME.into(), 0, byte_len, &reader);
-        let root_node: WzNodeArc = WzNode::new(&LOGIN_CACHE_NAME.into(), wz_image, None).into();
-
-        if let Err(e) = root_node.write().unwrap().parse(&root_node) {
-            error!("Failed to parse WZ root node: {:?}", e);
-            return;
-        }
-
         info!("WZ file parsed successfully");
 
         // Load background from login.img
         info!("Loading background assets...");
-        let bg_bytes = match AssetManager::fetch_and_cache(BACKGROUND_URL, BACKGROUND_CACHE_NAME).await {
-            Ok(bytes) => bytes,
-            Err(e) => {
-                error!("Failed to fetch background: {}", e);
-                Vec::new()
-            }
-        };
-
-        if !bg_bytes.is_empty() {
-            info!("Parsing background (size: {} bytes)...", bg_bytes.len());
-
-            let bg_wz_iv = match guess_iv_from_wz_img(&bg_bytes) {
-                Some(iv) => iv,
-                None => {
-                    error!("Unable to guess version from background IMG file");
-                    [0; 4]
-                }
-            };
-
-            let bg_byte_len = bg_bytes.len();
-            let bg_reader = Arc::new(WzReader::from_buff(&bg_bytes).with_iv(bg_wz_iv));
-            let bg_wz_image = WzImage::new(&BACKGROUND_CACHE_NAME.into(), 0, bg_byte_len, &bg_reader);
-            let bg_root_node: WzNodeArc = WzNode::new(&BACKGROUND_CACHE_NAME.into(), bg_wz_image, None).into();
-
-            // Parse the background node
-            if bg_root_node.write().unwrap().parse(&bg_root_node).is_ok() {
+        match load_wz_image(BACKGROUND_PATH).await {
+            Ok(bg_root_node) => {
                 // Load sky gradient (back/1)
                 match load_png_from_node(&bg_root_node, "back/1") {
                     Ok(two) => {
@@ -374,6 +393,7 @@ impl CharacterCreationState {
                     Err(e) => error!("Failed to load scene background: {}", e),
                 }
             }
+            Err(e) => error!("Failed to load background: {}", e),
         }
 
         // Load UI panels
@@ -479,6 +499,10 @@ impl CharacterCreationState {
         self.check_button.pressed = load_png_from_node(&root_node, "NewChar/BtCheck/pressed/0").ok();
         self.check_button.disabled = load_png_from_node(&root_node, "NewChar/BtCheck/disabled/0").ok();
 
+        // Preview of the character being created
+        self.preview.set_look(CharacterLook::from_character(&self.build_character()));
+        self.preview.load_assets().await;
+
         self.loaded = true;
         info!("Character creation screen assets loaded successfully");
     }
@@ -571,19 +595,23 @@ impl CharacterCreationState {
         // Check for button clicks
         if self.yes_button.is_clicked() && !self.character_created {
             // Validate character name if validation is enabled
-            let name_valid = if flags::ENABLE_CHARACTER_VALIDATION {
+            let name_valid = if config().enable_character_validation {
                 let name_len = self.character_name.len();
-                name_len >= flags::MIN_CHARACTER_NAME_LENGTH &&
-                name_len <= flags::MAX_CHARACTER_NAME_LENGTH
+                name_len >= config().min_character_name_length &&
+                name_len <= config().max_character_name_length
             } else {
                 !self.character_name.is_empty()
             };
 
-            if name_valid {
+            if !CharacterData::has_free_slot() {
+                warn!("Can't create '{}': all {} character slots are taken", self.character_name, config().max_characters);
+            } else if name_valid && CharacterData::name_taken(&self.character_name) {
+                warn!("Can't create '{}': that name is already taken", self.character_name);
+            } else if name_valid {
                 info!("Yes button clicked! Creating character: {}", self.character_name);
 
                 // Create and save character data
-                let character = CharacterData::new(self.character_name.clone(), self.selected_job);
+                let character = self.build_character();
                 match character.save() {
                     Ok(_) => {
                         info!("Character '{}' saved successfully!", self.character_name);
@@ -603,12 +631,30 @@ impl CharacterCreationState {
             info!("No button clicked! Canceling character creation");
             self.transition_to_char_select = true;
         }
-        if self.left_button.is_clicked() {
-            info!("Left button clicked!");
+        // Appearance picker: click a row (or Up/Down) to select it, arrows change it
+        if !self.movable_mode && is_mouse_button_pressed(MouseButton::Left) {
+            if let Some(origin) = self.look_rows_origin(center_x, center_y) {
+                let (mouse_x, mouse_y) = mouse_position();
+                let row = ((mouse_y - origin.y) / LOOK_ROW_HEIGHT).floor();
+                if mouse_x >= origin.x && mouse_x <= origin.x + 160.0 && row >= 0.0 && (row as usize) < LOOK_ROWS.len() {
+                    self.selected_look_row = row as usize;
+                }
+            }
+        }
+        if is_key_pressed(KeyCode::Up) {
+            self.selected_look_row = (self.selected_look_row + LOOK_ROWS.len() - 1) % LOOK_ROWS.len();
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.selected_look_row = (self.selected_look_row + 1) % LOOK_ROWS.len();
+        }
+        if self.left_button.is_clicked() || is_key_pressed(KeyCode::Left) {
+            self.change_look(self.selected_look_row, -1);
         }
-        if self.right_button.is_clicked() {
-            info!("Right button clicked!");
+        if self.right_button.is_clicked() || is_key_pressed(KeyCode::Right) {
+            self.change_look(self.selected_look_row, 1);
         }
+
+        self.preview.update(dt, CharacterState::Stand, false);
         if self.check_button.is_clicked() {
             info!("Check button clicked!");
         }
@@ -622,8 +668,8 @@ impl CharacterCreationState {
 
         // Get typed characters
         while let Some(ch) = get_char_pressed() {
-            let max_length = if flags::ENABLE_CHARACTER_VALIDATION {
-                flags::MAX_CHARACTER_NAME_LENGTH
+            let max_length = if config().enable_character_validation {
+                config().max_character_name_length
             } else {
                 12 // Default fallback
             };
@@ -768,6 +814,21 @@ impl CharacterCreationState {
             draw_texture(&panel.texture, draw_x, draw_y, color);
         }
 
+        // Appearance picker rows on the job panel
+        if let Some(origin) = self.look_rows_origin(center_x, center_y) {
+            for (row, label) in LOOK_ROWS.iter().enumerate() {
+                let row_y = origin.y + row as f32 * LOOK_ROW_HEIGHT;
+                let text_color = if row == self.selected_look_row {
+                    Color::from_rgba(200, 80, 0, alpha)
+                } else {
+                    Color::from_rgba(0, 0, 0, alpha)
+                };
+                let marker = if row == self.selected_look_row { ">" } else { " " };
+                let text = format!("{} {}: {}", marker, label, self.look_value(row));
+                draw_text(&text, origin.x, row_y + 16.0, 16.0, text_color);
+            }
+        }
+
         if let Some(panel) = &self.char_set_panel {
             // Use character anchor point to position this panel (or override position if set)
             let pos = self.char_set_pos.unwrap_or(vec2(self.character_anchor.x, center_y - 100.0));
@@ -798,6 +859,9 @@ impl CharacterCreationState {
             }
         };
 
+        // Character preview next to the panels
+        self.preview.draw(self.character_anchor.x, center_y + 60.0);
+
         // Draw all buttons with fade-in effect
         draw_button_with_fade(&self.yes_button, alpha);
         draw_button_with_fade(&self.no_button, alpha);
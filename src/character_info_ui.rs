@@ -1,14 +1,17 @@
 use macroquad::prelude::*;
-use crate::assets::AssetManager;
 use crate::character::CharacterData;
 use std::collections::HashMap;
-use std::sync::Arc;
-use wz_reader::version::guess_iv_from_wz_img;
-use wz_reader::{WzImage, WzNode, WzNodeArc, WzReader, WzNodeCast};
+use crate::wz_cache::load_wz_image;
+use crate::item_info::load_item_icon;
+use crate::skill_info::load_skill_icon;
+use crate::status_effect::{EffectSource, StatusEffects};
+use wz_reader::{WzNodeArc, WzNodeCast};
 
 // StatusBar2.img is the correct file (StatusBar3.img doesn't exist)
-const STATUSBAR_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/UI/StatusBar2.img";
-const STATUSBAR_CACHE: &str = "/01/UI/StatusBar2.img";
+const STATUSBAR_PATH: &str = "UI/StatusBar2.img";
+
+/// Size of the buff icons drawn above the status bar
+const BUFF_ICON_SIZE: f32 = 32.0;
 
 /// Structure to hold texture with its origin point
 struct TextureWithOrigin {
@@ -243,6 +246,9 @@ pub struct StatusBarUI {
 
     // Chat focus state
     chat_focused: bool,
+
+    // Icons of buffs, by what applied them
+    buff_icons: HashMap<EffectSource, Texture2D>,
 }
 
 impl StatusBarUI {
@@ -300,6 +306,7 @@ impl StatusBarUI {
             dragging_gauge: false,
             drag_start: Vec2::ZERO,
             chat_focused: false,
+            buff_icons: HashMap::new(),
         }
     }
 
@@ -357,21 +364,8 @@ impl StatusBarUI {
     /// Load status bar from WZ file - returns a temporary structure with all loaded data
     async fn load_statusbar_from_wz() -> Result<StatusBarData, String> {
         // Fetch the WZ file
-        let bytes = AssetManager::fetch_and_cache(STATUSBAR_URL, STATUSBAR_CACHE).await
-            .map_err(|e| format!("Failed to fetch StatusBar2.img: {}", e))?;
-
-        // Parse WZ file
-        let wz_iv = guess_iv_from_wz_img(&bytes)
-            .ok_or_else(|| "Unable to guess WZ version from StatusBar2.img".to_string())?;
-
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        let cache_name_ref: wz_reader::WzNodeName = STATUSBAR_CACHE.to_string().into();
-        let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-        let root_node: WzNodeArc = WzNode::new(&STATUSBAR_CACHE.to_string().into(), wz_image, None).into();
-
-        root_node.write().unwrap().parse(&root_node)
-            .map_err(|e| format!("Failed to parse StatusBar2.img: {:?}", e))?;
+        let root_node = load_wz_image(STATUSBAR_PATH).await
+            .map_err(|e| format!("Failed to load StatusBar2.img: {}", e))?;
 
         // Load all UI elements
         let mut data = StatusBarData::default();
@@ -1120,17 +1114,18 @@ impl StatusBarUI {
             // Calculate HP/MP percentages based on current/max
             let hp_pct = character.hp as f32 / character.max_hp.max(1) as f32;
             let mp_pct = character.mp as f32 / character.max_mp.max(1) as f32;
-            
+            let exp_needed = character.exp_to_next_level();
+            let exp_pct = if exp_needed > 0 { character.exp as f32 / exp_needed as f32 } else { 0.0 };
+
             self.draw_gauge("hp", hp_pct, base_x, base_y);
             self.draw_gauge("mp", mp_pct, base_x, base_y);
-            self.draw_gauge("exp", 0.5, base_x, base_y);  // 50% EXP
+            self.draw_gauge("exp", exp_pct, base_x, base_y);
 
             // Draw HP/MP/EXP numbers (current/max)
             self.draw_gauge_numbers("hp", character.hp, character.max_hp, base_x, base_y);
             self.draw_gauge_numbers("mp", character.mp, character.max_mp, base_x, base_y);
             // For EXP, show as percentage (0-100)
-            let exp_percent = 50; // Placeholder
-            self.draw_gauge_numbers("exp", exp_percent, 100, base_x, base_y);
+            self.draw_gauge_numbers("exp", (exp_pct * 100.0) as u32, 100, base_x, base_y);
         }
 
         if let Some(gauge_cov) = &self.gauge_cover {
@@ -1464,6 +1459,54 @@ impl StatusBarUI {
         }
     }
 
+    /// Load the icon shown while a buff from `source` runs
+    pub async fn load_buff_icon(&mut self, source: EffectSource) {
+        if self.buff_icons.contains_key(&source) {
+            return;
+        }
+        let icon = match source {
+            EffectSource::Skill(skill_id) => load_skill_icon(skill_id).await,
+            EffectSource::Item(item_id) => load_item_icon(item_id).await,
+        };
+        if let Some(icon) = icon {
+            self.buff_icons.insert(source, icon);
+        }
+    }
+
+    /// Draw running buffs as a row of icons above the right end of the status bar, newest
+    /// on the left. Each icon darkens from the top as its buff runs out.
+    pub fn draw_buffs(&self, effects: &StatusEffects) {
+        let bar_top = match &self.background {
+            Some(bg) if self.loaded => screen_height() - bg.origin.y,
+            _ => screen_height() - 120.0,
+        };
+        let y = bar_top - BUFF_ICON_SIZE - 18.0;
+        let mut x = screen_width() - BUFF_ICON_SIZE - 10.0;
+
+        for effect in effects.iter() {
+            match self.buff_icons.get(&effect.source) {
+                Some(icon) => draw_texture_ex(icon, x, y, WHITE, DrawTextureParams {
+                    dest_size: Some(vec2(BUFF_ICON_SIZE, BUFF_ICON_SIZE)),
+                    ..Default::default()
+                }),
+                None => draw_rectangle(x, y, BUFF_ICON_SIZE, BUFF_ICON_SIZE, Color::from_rgba(60, 60, 90, 220)),
+            }
+
+            let elapsed = 1.0 - (effect.remaining / effect.duration.max(0.001)).clamp(0.0, 1.0);
+            draw_rectangle(x, y, BUFF_ICON_SIZE, BUFF_ICON_SIZE * elapsed, Color::from_rgba(0, 0, 0, 120));
+            draw_rectangle_lines(x, y, BUFF_ICON_SIZE, BUFF_ICON_SIZE, 1.0, Color::from_rgba(255, 255, 255, 160));
+
+            let seconds = effect.remaining.ceil() as i32;
+            let label = if seconds >= 60 { format!("{}m", seconds / 60) } else { format!("{}s", seconds) };
+            let dims = measure_text(&label, None, 14, 1.0);
+            let text_x = x + (BUFF_ICON_SIZE - dims.width) / 2.0;
+            draw_text(&label, text_x + 1.0, y + BUFF_ICON_SIZE + 13.0, 14.0, BLACK);
+            draw_text(&label, text_x, y + BUFF_ICON_SIZE + 12.0, 14.0, WHITE);
+
+            x -= BUFF_ICON_SIZE + 6.0;
+        }
+    }
+
     /// Check if status bar is loaded
     pub fn is_loaded(&self) -> bool {
         self.loaded
@@ -1504,6 +1547,20 @@ impl StatusBarUI {
     pub fn take_last_sent_message(&mut self) -> Option<String> {
         self.chat_state.last_sent_message.take()
     }
+
+    /// Add a game notice (item gained, inventory full, ...) to the chat log
+    pub fn add_system_message(&mut self, text: &str) {
+        self.chat_state.messages.push(ChatMessage {
+            target: "system".to_string(),
+            sender: "System".to_string(),
+            text: text.to_string(),
+            timestamp: get_time(),
+        });
+
+        // Keep the newest message in view
+        self.chat_state.scroll_offset = self.chat_state.messages.len()
+            .saturating_sub(self.chat_state.max_visible_lines);
+    }
 }
 
 impl Default for StatusBarUI {
@@ -1,28 +1,224 @@
 use macroquad::prelude::*;
-use crate::assets::AssetManager;
-use std::sync::Arc;
 use std::collections::HashMap;
-use wz_reader::version::guess_iv_from_wz_img;
-use wz_reader::{WzImage, WzNode, WzNodeArc, WzReader, WzNodeCast};
+use crate::wz_cache::load_wz_image;
+use crate::item_info;
+use crate::character::CharacterData;
+use wz_reader::{WzNodeArc, WzNodeCast, WzObjectType};
+
+/// Face expressions loaded from Face/000xxxxx.img; F1-F7 play the ones after blink
+pub const EXPRESSIONS: [&str; 9] = ["default", "blink", "hit", "smile", "troubled", "cry", "angry", "bewildered", "stunned"];
+
+/// How long an expression picked by the player stays on (seconds)
+const EXPRESSION_DURATION: f32 = 5.0;
+
+/// How long the alert stance is held after attacking or being hit (seconds)
+const ALERT_DURATION: f32 = 3.0;
+
+/// Frame delay used when a stance frame has no `delay` (ms)
+const DEFAULT_FRAME_DELAY: u32 = 100;
+
+/// Sprite layers from back to front (after Base.wz/zmap.img).
+/// A part's `z` string picks its layer; unknown layers are drawn with the body.
+const Z_ORDER: &[&str] = &[
+    "backWeapon", "backHairBelowCapWide", "backHairBelowCapNarrow", "backHairBelowCap", "backShield",
+    "backCap", "backHair", "hairBelowBody", "capeBelowBody", "shieldBelowBody", "weaponBelowBody",
+    "gloveBelowBody", "gloveWristBelowBody", "capAccessoryBelowBody", "cape",
+    "body", "gloveOverBody", "gloveWristOverBody", "mailChestBelowPants", "pantsBelowShoes", "shoes",
+    "pants", "pantsOverShoesBelowMailChest", "mailChest", "shoesOverPants", "mailChestOverPants",
+    "pantsOverMailChest", "shoesTop", "mailChestTop", "weaponOverBody", "weaponBelowArm",
+    "armBelowHead", "mailArmBelowHead", "armBelowHeadOverMailChest", "mailArmBelowHeadOverMailChest",
+    "gloveBelowMailArm", "gloveWristBelowMailArm", "weaponOverArmBelowHead", "mailChestOverHighest", "shield",
+    "head", "accessoryFaceBelowFace", "accessoryEyeBelowFace", "face", "accessoryFaceOverFaceBelowCap",
+    "capBelowAccessory", "accessoryEar", "capAccessoryBelowAccFace", "accessoryFace", "accessoryEyeShadow",
+    "accessoryEye", "hair", "cap", "capAccessory", "accessoryEyeOverCap", "hairOverHead", "capOverHair",
+    "weaponBelowHand", "arm", "mailArm", "handBelowWeapon", "armOverHairBelowWeapon",
+    "mailArmOverHairBelowWeapon", "weapon", "weaponOverArm", "armOverHair", "mailArmOverHair",
+    "gloveWristBelowWeapon", "gloveBelowWeapon", "weaponOverHand", "handOverHair", "rHand", "lHand",
+    "gloveWrist", "glove", "weaponOverGlove", "gloveWristOverHair", "gloveOverHair", "weaponWristOverGlove",
+    "shieldOverHair", "weaponOverHair", "capeOverHead",
+];
+
+fn z_rank(z: &str) -> usize {
+    Z_ORDER
+        .iter()
+        .position(|&layer| layer == z)
+        .or_else(|| Z_ORDER.iter().position(|&layer| layer == "body"))
+        .unwrap_or(0)
+}
+
+/// Stances a character can be drawn in (Character/00002000.img top-level nodes)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CharacterState {
+    Stand,
+    Move,
+    Jump,
+    Fall,
+    Ladder,
+    Rope,
+    Prone,
+    ProneStab,
+    Sit,
+    Alert,
+    SwingO1,
+    SwingO2,
+    SwingO3,
+    StabO1,
+    StabO2,
+    Shoot1,
+    Dead,
+}
+
+impl CharacterState {
+    pub const ALL: [CharacterState; 17] = [
+        CharacterState::Stand,
+        CharacterState::Move,
+        CharacterState::Jump,
+        CharacterState::Fall,
+        CharacterState::Ladder,
+        CharacterState::Rope,
+        CharacterState::Prone,
+        CharacterState::ProneStab,
+        CharacterState::Sit,
+        CharacterState::Alert,
+        CharacterState::SwingO1,
+        CharacterState::SwingO2,
+        CharacterState::SwingO3,
+        CharacterState::StabO1,
+        CharacterState::StabO2,
+        CharacterState::Shoot1,
+        CharacterState::Dead,
+    ];
+
+    /// WZ stance node; standing and walking depend on the weapon's stance set
+    fn wz_name(self, weapon_stance: i32) -> String {
+        match self {
+            CharacterState::Stand => format!("stand{}", weapon_stance),
+            CharacterState::Move => format!("walk{}", weapon_stance),
+            CharacterState::Jump | CharacterState::Fall => "jump".to_string(),
+            CharacterState::Ladder => "ladder".to_string(),
+            CharacterState::Rope => "rope".to_string(),
+            CharacterState::Prone => "prone".to_string(),
+            CharacterState::ProneStab => "proneStab".to_string(),
+            CharacterState::Sit => "sit".to_string(),
+            CharacterState::Alert => "alert".to_string(),
+            CharacterState::SwingO1 => "swingO1".to_string(),
+            CharacterState::SwingO2 => "swingO2".to_string(),
+            CharacterState::SwingO3 => "swingO3".to_string(),
+            CharacterState::StabO1 => "stabO1".to_string(),
+            CharacterState::StabO2 => "stabO2".to_string(),
+            CharacterState::Shoot1 => "shoot1".to_string(),
+            CharacterState::Dead => "dead".to_string(),
+        }
+    }
+
+    /// Stance whose animation is drawn (falling reuses the jump frames)
+    fn animation(self) -> Self {
+        match self {
+            CharacterState::Fall => CharacterState::Jump,
+            state => state,
+        }
+    }
+
+    /// Attacks play once and then hand back to the movement stance
+    pub fn is_action(self) -> bool {
+        matches!(
+            self,
+            CharacterState::ProneStab
+                | CharacterState::SwingO1
+                | CharacterState::SwingO2
+                | CharacterState::SwingO3
+                | CharacterState::StabO1
+                | CharacterState::StabO2
+                | CharacterState::Shoot1
+        )
+    }
+
+    /// Actions and death stop on their last frame instead of looping
+    fn loops(self) -> bool {
+        !self.is_action() && self != CharacterState::Dead
+    }
+
+    /// Attack stance named by a skill's `action` (None for stances this renderer doesn't have)
+    pub fn from_wz_action(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|state| state.is_action() && state.wz_name(1) == name)
+    }
+
+    /// A random attack stance for the equipped weapon (by its 3-digit category)
+    pub fn attack_for_weapon(weapon_id: Option<i32>) -> Self {
+        let choices: &[CharacterState] = match weapon_id.map(|id| id / 10000) {
+            // Bows, crossbows, claws and guns
+            Some(145..=147) | Some(149) => &[CharacterState::Shoot1],
+            // Daggers
+            Some(133) => &[CharacterState::StabO1, CharacterState::StabO2],
+            _ => &[CharacterState::SwingO1, CharacterState::SwingO2, CharacterState::SwingO3],
+        };
+        choices[macroquad::rand::gen_range(0, choices.len())]
+    }
+}
+
+/// Everything that decides how a character is drawn
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CharacterLook {
+    pub skin: i32,
+    pub hair: i32,
+    pub face: i32,
+    pub equips: Vec<i32>,
+}
+
+impl CharacterLook {
+    pub fn from_character(character: &CharacterData) -> Self {
+        let mut equips = character.equipment.item_ids();
+        equips.sort_unstable();
+        Self {
+            skin: character.skin,
+            hair: character.hair,
+            face: character.face,
+            equips,
+        }
+    }
+}
 
-const CHARACTER_BODY_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/Character/00002000.img";
-const CHARACTER_BODY_CACHE: &str = "/01/Character/00002000.img";
-const CHARACTER_HEAD_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/Character/00012010.img";
-const CHARACTER_HEAD_CACHE: &str = "/01/Character/00012010.img";
+/// Parsed WZ images that make up a character
+struct LookNodes {
+    body: WzNodeArc,
+    head: WzNodeArc,
+    hair: Option<WzNodeArc>,
+    equips: Vec<WzNodeArc>,
+    // Hair layers covered by the equipped cap
+    hidden_hair: &'static [&'static str],
+}
 
-#[derive(Clone, Copy, PartialEq)]
-pub enum CharacterState { Stand, Move, Jump, Fall }
+/// One positioned sprite of a frame (body, arm, coat, weapon, ...)
+#[derive(Clone)]
+struct FramePart {
+    texture: Texture2D,
+    offset: Vec2, // top-left relative to the character's feet, facing left
+    z: usize,
+}
 
 #[derive(Clone)]
 struct CharacterFrame {
-    body_texture: Texture2D,
-    body_origin: Vec2,
-    head_texture: Option<Texture2D>,
-    head_origin: Vec2,
-    head_offset: Vec2, // neck position from body
+    parts: Vec<FramePart>, // sorted back to front
+    brow: Option<Vec2>, // where the face goes; None when the face isn't visible
+    delay: u32,
+}
+
+/// One frame of a face expression
+#[derive(Clone)]
+struct FaceFrame {
+    texture: Texture2D,
+    offset: Vec2, // top-left relative to the head's brow anchor, facing left
     delay: u32,
 }
 
+/// A sprite read from a stance frame, before it is positioned
+struct PartSprite {
+    texture: Texture2D,
+    origin: Vec2,
+    map: Vec<(String, Vec2)>,
+    z: String,
+}
+
 #[derive(Clone)]
 struct CharacterAnimation {
     frames: Vec<CharacterFrame>,
@@ -35,13 +231,31 @@ impl CharacterAnimation {
         Self { frames, current_frame: 0, frame_timer: 0.0 }
     }
 
-    fn update(&mut self, dt: f32) {
-        if self.frames.is_empty() { return; }
+    fn reset(&mut self) {
+        self.current_frame = 0;
+        self.frame_timer = 0.0;
+    }
+
+    /// Advance by each frame's own delay. Returns true once a non-looping animation
+    /// has played its last frame (it then holds that frame).
+    fn update(&mut self, dt: f32, looping: bool) -> bool {
+        if self.frames.is_empty() { return true; }
         self.frame_timer += dt * 1000.0;
-        let delay = self.frames[self.current_frame].delay as f32;
-        if self.frame_timer >= delay {
-            self.frame_timer = 0.0;
-            self.current_frame = (self.current_frame + 1) % self.frames.len();
+        loop {
+            let delay = self.frames[self.current_frame].delay as f32;
+            if self.frame_timer < delay {
+                return false;
+            }
+            if self.current_frame + 1 < self.frames.len() {
+                self.frame_timer -= delay;
+                self.current_frame += 1;
+            } else if looping {
+                self.frame_timer -= delay;
+                self.current_frame = 0;
+            } else {
+                self.frame_timer = delay;
+                return true;
+            }
         }
     }
 
@@ -53,18 +267,103 @@ impl CharacterAnimation {
 #[derive(Clone)]
 pub struct CharacterRenderer {
     loaded: bool,
-    animations: HashMap<String, CharacterAnimation>,
+    animations: HashMap<CharacterState, CharacterAnimation>,
     facing_right: bool,
+    // Stance being drawn, the attack overriding the movement stance, and alert time left
+    stance: CharacterState,
+    action: Option<CharacterState>,
+    alert_time_left: f32,
+    // Appearance to draw, and whether it changed since the last load
+    look: CharacterLook,
+    look_dirty: bool,
+    // Face expressions and the one currently playing
+    expressions: HashMap<String, Vec<FaceFrame>>,
+    expression: String,
+    expression_frame: usize,
+    expression_timer: f32,
+    expression_time_left: f32,
+    blink_cooldown: f32,
 }
 
 impl CharacterRenderer {
     pub fn new() -> Self {
-        Self { loaded: false, animations: HashMap::new(), facing_right: true }
+        Self {
+            loaded: false,
+            animations: HashMap::new(),
+            facing_right: true,
+            stance: CharacterState::Stand,
+            action: None,
+            alert_time_left: 0.0,
+            look: CharacterLook {
+                skin: 0,
+                hair: 30000,
+                face: 20000,
+                equips: Vec::new(),
+            },
+            look_dirty: false,
+            expressions: HashMap::new(),
+            expression: "default".to_string(),
+            expression_frame: 0,
+            expression_timer: 0.0,
+            expression_time_left: 0.0,
+            blink_cooldown: 3.0,
+        }
+    }
+
+    /// Set the appearance to draw. Takes effect on the next `load_assets`.
+    pub fn set_look(&mut self, look: CharacterLook) {
+        if look != self.look {
+            self.look = look;
+            self.look_dirty = true;
+        }
+    }
+
+    /// True if the appearance changed and the sprites need to be rebuilt
+    pub fn needs_reload(&self) -> bool {
+        self.look_dirty
+    }
+
+    /// Play a face expression (one of `EXPRESSIONS`) for a few seconds
+    pub fn set_expression(&mut self, name: &str) {
+        if self.expressions.contains_key(name) {
+            self.expression = name.to_string();
+            self.expression_frame = 0;
+            self.expression_timer = 0.0;
+            self.expression_time_left = EXPRESSION_DURATION;
+        }
+    }
+
+    /// Request an attack (played once) or the alert stance (held for a few seconds).
+    /// Returns false if an attack is already playing or the stance can't be shown.
+    pub fn request_stance(&mut self, stance: CharacterState) -> bool {
+        if self.stance == CharacterState::Dead {
+            return false;
+        }
+        if stance == CharacterState::Alert {
+            self.alert_time_left = ALERT_DURATION;
+            return true;
+        }
+        if !stance.is_action() || self.action.is_some() {
+            return false;
+        }
+        let Some(anim) = self.animations.get_mut(&stance) else { return false };
+        anim.reset();
+        self.action = Some(stance);
+        true
+    }
+
+    /// True while an attack stance is playing
+    pub fn is_attacking(&self) -> bool {
+        self.action.is_some()
     }
 
     pub async fn load_assets(&mut self) {
         info!("CharacterRenderer: Loading assets...");
-        match Self::load_from_wz().await {
+        self.look_dirty = false;
+        self.expressions = Self::load_expressions(self.look.face).await;
+        self.expression = "default".to_string();
+        self.expression_frame = 0;
+        match Self::load_from_wz(&self.look).await {
             Ok(anims) => {
                 let count = anims.len();
                 self.animations = anims;
@@ -78,36 +377,63 @@ impl CharacterRenderer {
         }
     }
 
-    async fn load_from_wz() -> Result<HashMap<String, CharacterAnimation>, String> {
-        // Load body
-        let body_bytes = AssetManager::fetch_and_cache(CHARACTER_BODY_URL, CHARACTER_BODY_CACHE).await
-            .map_err(|e| format!("Failed to fetch body: {}", e))?;
-        let body_iv = guess_iv_from_wz_img(&body_bytes).ok_or("No body IV")?;
-        let body_len = body_bytes.len();
-        let body_reader = Arc::new(WzReader::from_buff(&body_bytes).with_iv(body_iv));
-        let body_name: wz_reader::WzNodeName = CHARACTER_BODY_CACHE.to_string().into();
-        let body_img = WzImage::new(&body_name, 0, body_len, &body_reader);
-        let body_node: WzNodeArc = WzNode::new(&CHARACTER_BODY_CACHE.into(), body_img, None).into();
-        body_node.write().unwrap().parse(&body_node).map_err(|e| format!("Body parse: {:?}", e))?;
-
-        // Load head
-        let head_bytes = AssetManager::fetch_and_cache(CHARACTER_HEAD_URL, CHARACTER_HEAD_CACHE).await
-            .map_err(|e| format!("Failed to fetch head: {}", e))?;
-        let head_iv = guess_iv_from_wz_img(&head_bytes).ok_or("No head IV")?;
-        let head_len = head_bytes.len();
-        let head_reader = Arc::new(WzReader::from_buff(&head_bytes).with_iv(head_iv));
-        let head_name: wz_reader::WzNodeName = CHARACTER_HEAD_CACHE.to_string().into();
-        let head_img = WzImage::new(&head_name, 0, head_len, &head_reader);
-        let head_node: WzNodeArc = WzNode::new(&CHARACTER_HEAD_CACHE.into(), head_img, None).into();
-        head_node.write().unwrap().parse(&head_node).map_err(|e| format!("Head parse: {:?}", e))?;
+    async fn load_from_wz(look: &CharacterLook) -> Result<HashMap<CharacterState, CharacterAnimation>, String> {
+        // Load body and head in the skin tone's variant (00002000 + skin, 00012000 + skin)
+        let body_path = format!("Character/{:08}.img", 2000 + look.skin);
+        let body_node = load_wz_image(&body_path).await
+            .map_err(|e| format!("Failed to load body: {}", e))?;
+
+        let head_path = format!("Character/{:08}.img", 12000 + look.skin);
+        let head_node = load_wz_image(&head_path).await
+            .map_err(|e| format!("Failed to load head: {}", e))?;
+
+        let hair_node = match load_wz_image(&format!("Character/Hair/{:08}.img", look.hair)).await {
+            Ok(node) => Some(node),
+            Err(e) => {
+                warn!("CharacterRenderer: Failed to load hair {}: {}", look.hair, e);
+                None
+            }
+        };
+
+        // Load equips; a missing equip is skipped rather than failing the whole character
+        let mut equip_nodes = Vec::new();
+        let mut weapon_stance = 1;
+        let mut hidden_hair: &'static [&'static str] = &["backHairBelowCap", "backHairBelowCapWide", "backHairBelowCapNarrow"];
+        for &item_id in &look.equips {
+            let node = match item_info::load_item_node(item_id).await {
+                Ok(node) => node,
+                Err(e) => {
+                    warn!("CharacterRenderer: Failed to load equip {}: {}", item_id, e);
+                    continue;
+                }
+            };
+            if item_id / 10000 == 100 {
+                hidden_hair = Self::hair_hidden_by_cap(&node);
+            }
+            equip_nodes.push(node);
+
+            let stats = item_info::load_item_info(item_id).await;
+            if item_id / 10000 >= 130 && stats.equip.map_or(false, |s| s.stand == 2) {
+                weapon_stance = 2;
+            }
+        }
+
+        let nodes = LookNodes {
+            body: body_node,
+            head: head_node,
+            hair: hair_node,
+            equips: equip_nodes,
+            hidden_hair,
+        };
 
         let mut animations = HashMap::new();
 
-        for (anim_name, key) in [("stand", "stand1"), ("move", "walk1"), ("jump", "jump")] {
-            if let Ok(frames) = Self::load_animation(&body_node, &head_node, key).await {
+        for state in CharacterState::ALL.into_iter().filter(|s| s.animation() == *s) {
+            let stance = state.wz_name(weapon_stance);
+            if let Ok(frames) = Self::load_animation(&nodes, &stance).await {
                 if !frames.is_empty() {
-                    info!("Loaded {} with {} frames", anim_name, frames.len());
-                    animations.insert(anim_name.to_string(), CharacterAnimation::new(frames));
+                    info!("Loaded {} with {} frames", stance, frames.len());
+                    animations.insert(state, CharacterAnimation::new(frames));
                 }
             }
         }
@@ -115,22 +441,32 @@ impl CharacterRenderer {
         Ok(animations)
     }
 
-    async fn load_animation(body_root: &WzNodeArc, head_root: &WzNodeArc, anim_name: &str) -> Result<Vec<CharacterFrame>, String> {
-        let mut frames = Vec::new();
+    /// Hair layers a cap covers, from the cap's `info/vslot` (which slots it takes up)
+    fn hair_hidden_by_cap(cap: &WzNodeArc) -> &'static [&'static str] {
+        let vslot = cap.read().unwrap().at_path("info/vslot").and_then(|v| {
+            match &v.read().unwrap().object_type {
+                WzObjectType::Value(wz_reader::property::WzValue::String(val)) => val.get_string().ok(),
+                _ => None,
+            }
+        }).unwrap_or_default();
+
+        match vslot.as_str() {
+            // Headbands and hats that sit on top of the hair
+            "" | "Cp" | "CpH5" => &["backHairBelowCap", "backHairBelowCapWide", "backHairBelowCapNarrow"],
+            // Half cover: the hair under the cap shows, the top and back don't
+            "CpH1H5" => &["hairOverHead", "backHair"],
+            // Full cover: only the hair below the cap shows
+            _ => &["hairOverHead", "backHair", "hair"],
+        }
+    }
 
-        let body_anim = {
-            let r = body_root.read().unwrap();
-            match r.at_path(anim_name) { Some(n) => n.clone(), None => return Ok(frames) }
-        };
-        body_anim.write().unwrap().parse(&body_anim).ok();
+    async fn load_animation(nodes: &LookNodes, anim_name: &str) -> Result<Vec<CharacterFrame>, String> {
+        let mut frames = Vec::new();
 
-        let head_anim = {
-            let r = head_root.read().unwrap();
-            r.at_path(anim_name).map(|n| n.clone())
-        };
-        if let Some(ref ha) = head_anim {
-            ha.write().unwrap().parse(ha).ok();
-        }
+        let body_anim = match Self::child(&nodes.body, anim_name) { Some(n) => n, None => return Ok(frames) };
+        let head_anim = Self::child(&nodes.head, anim_name);
+        let hair_anim = nodes.hair.as_ref().and_then(|hair| Self::child(hair, anim_name));
+        let equip_anims: Vec<WzNodeArc> = nodes.equips.iter().filter_map(|root| Self::child(root, anim_name)).collect();
 
         let frame_nums: Vec<i32> = {
             let r = body_anim.read().unwrap();
@@ -139,139 +475,320 @@ impl CharacterRenderer {
             nums
         };
 
+        // Head frames usually link to the shared front/head sprite
+        let head_front = Self::child(&nodes.head, "front");
+
         for frame_num in frame_nums {
             let frame_str = frame_num.to_string();
-            
-            // Load body
-            let body_frame = {
-                let r = body_anim.read().unwrap();
-                r.at_path(&frame_str).map(|n| n.clone())
-            };
-            let body_frame = match body_frame { Some(f) => f, None => continue };
-            body_frame.write().unwrap().parse(&body_frame).ok();
-
-            let (body_tex, body_origin, neck_pos) = {
-                let fr = body_frame.read().unwrap();
-                let body_node = match fr.at_path("body") { Some(n) => n.clone(), None => continue };
-                body_node.write().unwrap().parse(&body_node).ok();
-                
-                let br = body_node.read().unwrap();
-                let png = match br.try_as_png() { Some(p) => p, None => continue };
-                let img = match png.extract_png() { Ok(i) => i, Err(_) => continue };
-                let rgba = img.to_rgba8();
-                let tex = Texture2D::from_rgba8(rgba.width() as u16, rgba.height() as u16, &rgba.into_raw());
-
-                let origin = br.children.get("origin").and_then(|o| {
-                    o.read().unwrap().try_as_vector2d().map(|v| Vec2::new(v.0 as f32, v.1 as f32))
-                }).unwrap_or(Vec2::ZERO);
-
-                info!("Frame {} body origin: ({}, {}), tex size: {}x{}", frame_num, origin.x, origin.y, tex.width(), tex.height());
-
-                // Get neck position from body/map/neck
-                let neck = br.at_path("map/neck").and_then(|n| {
-                    n.read().unwrap().try_as_vector2d().map(|v| Vec2::new(v.0 as f32, v.1 as f32))
-                }).unwrap_or(Vec2::new(0.0, -10.0));
-
-                (tex, origin, neck)
-            };
 
-            // Load head
-            let (head_tex, head_origin, head_neck) = if let Some(ref ha) = head_anim {
-                let head_frame = {
-                    let r = ha.read().unwrap();
-                    r.at_path(&frame_str).map(|n| n.clone())
-                };
-                if let Some(hf) = head_frame {
-                    hf.write().unwrap().parse(&hf).ok();
-                    let hfr = hf.read().unwrap();
-                    
-                    // Try front/head first
-                    let head_node = hfr.at_path("front/head").or_else(|| hfr.at_path("head"));
-                    if let Some(hn) = head_node {
-                        hn.write().unwrap().parse(&hn).ok();
-                        let hr = hn.read().unwrap();
-                        if let Some(png) = hr.try_as_png() {
-                            if let Ok(img) = png.extract_png() {
-                                let rgba = img.to_rgba8();
-                                let tex = Texture2D::from_rgba8(rgba.width() as u16, rgba.height() as u16, &rgba.into_raw());
-                                let origin = hr.children.get("origin").and_then(|o| {
-                                    o.read().unwrap().try_as_vector2d().map(|v| Vec2::new(v.0 as f32, v.1 as f32))
-                                }).unwrap_or(Vec2::ZERO);
-                                let neck = hr.at_path("map/neck").and_then(|n| {
-                                    n.read().unwrap().try_as_vector2d().map(|v| Vec2::new(v.0 as f32, v.1 as f32))
-                                }).unwrap_or(Vec2::ZERO);
-                                (Some(tex), origin, neck)
-                            } else { (None, Vec2::ZERO, Vec2::ZERO) }
-                        } else { (None, Vec2::ZERO, Vec2::ZERO) }
-                    } else { (None, Vec2::ZERO, Vec2::ZERO) }
-                } else { (None, Vec2::ZERO, Vec2::ZERO) }
-            } else { (None, Vec2::ZERO, Vec2::ZERO) };
-
-            // Calculate head offset: body_neck - head_neck
-            let head_offset = neck_pos - head_neck;
+            let body_frame = match Self::child(&body_anim, &frame_str) { Some(f) => f, None => continue };
+
+            // Body sprites first so the others can attach to its neck/navel/hand anchors
+            let mut sprites = Self::frame_sprites(&body_frame);
+            sprites.sort_by_key(|s| s.z != "body");
+            if sprites.is_empty() {
+                continue;
+            }
+
+            // Only the head itself; the ear variants in the head image are alternatives
+            let head = head_anim.as_ref()
+                .and_then(|ha| Self::child(ha, &format!("{}/head", frame_str)))
+                .or_else(|| head_front.as_ref().and_then(|front| Self::child(front, "head")));
+            sprites.extend(head.as_ref().and_then(Self::read_sprite));
+
+            if let Some(hair_frame) = hair_anim.as_ref().and_then(|ha| Self::child(ha, &frame_str)) {
+                sprites.extend(
+                    Self::frame_sprites(&hair_frame)
+                        .into_iter()
+                        .filter(|s| !nodes.hidden_hair.contains(&s.z.as_str())),
+                );
+            }
+
+            for equip_anim in &equip_anims {
+                if let Some(equip_frame) = Self::child(equip_anim, &frame_str) {
+                    sprites.extend(Self::frame_sprites(&equip_frame));
+                }
+            }
+
+            // Body frames say whether the face is visible (it isn't from behind)
+            let show_face = Self::get_int(&body_frame, "face").map_or(true, |v| v != 0);
+            let (parts, anchors) = Self::compose(sprites);
 
             frames.push(CharacterFrame {
-                body_texture: body_tex,
-                body_origin,
-                head_texture: head_tex,
-                head_origin,
-                head_offset,
-                delay: 200,
+                parts,
+                brow: anchors.get("brow").copied().filter(|_| show_face),
+                delay: Self::get_int(&body_frame, "delay").map_or(DEFAULT_FRAME_DELAY, |d| d.unsigned_abs().max(1)),
             });
         }
 
         Ok(frames)
     }
 
+    /// Position sprites by chaining their `map` anchors: the body sits at the character's
+    /// origin and each further sprite is placed so one of its anchors (neck, navel, hand,
+    /// brow, ...) lands on the same anchor of a sprite placed before it
+    fn compose(sprites: Vec<PartSprite>) -> (Vec<FramePart>, HashMap<String, Vec2>) {
+        let mut anchors: HashMap<String, Vec2> = HashMap::new();
+        let mut parts = Vec::new();
+
+        for sprite in sprites {
+            let position = sprite
+                .map
+                .iter()
+                .find_map(|(name, vec)| anchors.get(name).map(|anchor| *anchor - *vec))
+                .unwrap_or(Vec2::ZERO);
+
+            for (name, vec) in &sprite.map {
+                anchors.entry(name.clone()).or_insert(position + *vec);
+            }
+
+            parts.push(FramePart {
+                offset: position - sprite.origin,
+                z: z_rank(&sprite.z),
+                texture: sprite.texture,
+            });
+        }
+
+        parts.sort_by_key(|p| p.z);
+        (parts, anchors)
+    }
+
+    /// Load every expression of a face. Frames are positioned relative to the brow anchor.
+    async fn load_expressions(face_id: i32) -> HashMap<String, Vec<FaceFrame>> {
+        let mut expressions = HashMap::new();
+
+        let root = match load_wz_image(&format!("Character/Face/{:08}.img", face_id)).await {
+            Ok(root) => root,
+            Err(e) => {
+                warn!("CharacterRenderer: Failed to load face {}: {}", face_id, e);
+                return expressions;
+            }
+        };
+
+        for name in EXPRESSIONS {
+            let Some(expression) = Self::child(&root, name) else { continue };
+
+            // "default" holds the sprite directly, the others have numbered frames
+            let frame_nodes: Vec<WzNodeArc> = if Self::child(&expression, "face").is_some() {
+                vec![expression.clone()]
+            } else {
+                let mut nums: Vec<i32> = expression.read().unwrap().children.keys().filter_map(|k| k.parse().ok()).collect();
+                nums.sort();
+                nums.iter().filter_map(|n| Self::child(&expression, &n.to_string())).collect()
+            };
+
+            let frames: Vec<FaceFrame> = frame_nodes.iter().filter_map(|frame| {
+                let sprite = Self::read_sprite(&Self::child(frame, "face")?)?;
+                let brow = sprite.map.iter().find(|(n, _)| n == "brow").map(|(_, v)| *v).unwrap_or(Vec2::ZERO);
+                Some(FaceFrame {
+                    offset: -brow - sprite.origin,
+                    delay: Self::get_int(frame, "delay").unwrap_or(100).max(1) as u32,
+                    texture: sprite.texture,
+                })
+            }).collect();
+
+            if !frames.is_empty() {
+                expressions.insert(name.to_string(), frames);
+            }
+        }
+
+        expressions
+    }
+
+    fn get_int(node: &WzNodeArc, path: &str) -> Option<i32> {
+        let prop = node.read().unwrap().at_path(path)?;
+        let prop_read = prop.read().unwrap();
+        match &prop_read.object_type {
+            WzObjectType::Value(wz_reader::property::WzValue::Short(val)) => Some(*val as i32),
+            WzObjectType::Value(wz_reader::property::WzValue::Int(val)) => Some(*val),
+            WzObjectType::Value(wz_reader::property::WzValue::Long(val)) => Some(*val as i32),
+            _ => None,
+        }
+    }
+
+    /// Read every image sprite in a frame node (e.g. body, arm, lHand for a body frame)
+    fn frame_sprites(frame: &WzNodeArc) -> Vec<PartSprite> {
+        let names: Vec<String> = frame.read().unwrap().children.keys().map(|k| k.to_string()).collect();
+        names
+            .iter()
+            .filter_map(|name| Self::child(frame, name))
+            .filter_map(|node| Self::read_sprite(&node))
+            .collect()
+    }
+
+    fn read_sprite(node: &WzNodeArc) -> Option<PartSprite> {
+        let nr = node.read().unwrap();
+        let png = nr.try_as_png()?;
+        let img = png.extract_png().ok()?;
+        let rgba = img.to_rgba8();
+        let texture = Texture2D::from_rgba8(rgba.width() as u16, rgba.height() as u16, &rgba.into_raw());
+
+        let origin = nr.children.get("origin").and_then(|o| {
+            o.read().unwrap().try_as_vector2d().map(|v| Vec2::new(v.0 as f32, v.1 as f32))
+        }).unwrap_or(Vec2::ZERO);
+
+        let map = nr.children.get("map").map(|map_node| {
+            map_node.read().unwrap().children.iter().filter_map(|(name, v)| {
+                v.read().unwrap().try_as_vector2d().map(|v| (name.to_string(), Vec2::new(v.0 as f32, v.1 as f32)))
+            }).collect()
+        }).unwrap_or_default();
+
+        let z = nr.children.get("z").and_then(|z| {
+            match &z.read().unwrap().object_type {
+                WzObjectType::Value(wz_reader::property::WzValue::String(val)) => val.get_string().ok(),
+                _ => None,
+            }
+        }).unwrap_or_default();
+
+        Some(PartSprite { texture, origin, map, z })
+    }
+
+    /// Parsed child of a node, following UOL links (e.g. "../../front/head")
+    fn child(node: &WzNodeArc, name: &str) -> Option<WzNodeArc> {
+        let child = node.read().unwrap().at_path(name)?;
+        child.write().unwrap().parse(&child).ok();
+        Self::resolve_uol(child, 0)
+    }
+
+    fn resolve_uol(node: WzNodeArc, depth: u32) -> Option<WzNodeArc> {
+        let link = match &node.read().unwrap().object_type {
+            WzObjectType::Value(wz_reader::property::WzValue::UOL(path)) => path.get_string().ok()?,
+            _ => return Some(node.clone()),
+        };
+        if depth > 8 {
+            return None;
+        }
+
+        // UOL paths are relative to the node's parent
+        let mut current = node.read().unwrap().parent.upgrade()?;
+        for segment in link.split('/') {
+            current = match segment {
+                ".." => current.read().unwrap().parent.upgrade()?,
+                "" | "." => current,
+                name => current.read().unwrap().at_path(name)?,
+            };
+        }
+        current.write().unwrap().parse(&current).ok();
+        Self::resolve_uol(current, depth + 1)
+    }
+
+    /// Advance the face expression, blinking every few seconds while idle
+    fn update_expression(&mut self, dt: f32) {
+        if self.expression == "default" {
+            self.blink_cooldown -= dt;
+            if self.blink_cooldown <= 0.0 {
+                self.blink_cooldown = macroquad::rand::gen_range(2.0, 6.0);
+                if let Some(blink) = self.expressions.get("blink") {
+                    // Play the blink once
+                    self.expression_time_left = blink.iter().map(|f| f.delay as f32).sum::<f32>() / 1000.0;
+                    self.expression = "blink".to_string();
+                    self.expression_frame = 0;
+                    self.expression_timer = 0.0;
+                }
+            }
+            return;
+        }
+
+        self.expression_time_left -= dt;
+        if self.expression_time_left <= 0.0 {
+            self.expression = "default".to_string();
+            self.expression_frame = 0;
+            return;
+        }
+
+        if let Some(frames) = self.expressions.get(&self.expression) {
+            self.expression_timer += dt * 1000.0;
+            let delay = frames[self.expression_frame.min(frames.len() - 1)].delay as f32;
+            if self.expression_timer >= delay {
+                self.expression_timer = 0.0;
+                self.expression_frame = (self.expression_frame + 1) % frames.len();
+            }
+        }
+    }
+
+    fn current_face(&self) -> Option<&FaceFrame> {
+        let frames = self.expressions.get(&self.expression).or_else(|| self.expressions.get("default"))?;
+        frames.get(self.expression_frame).or_else(|| frames.first())
+    }
+
+    /// Advance the stance machine. `state` is the stance from movement; a requested attack
+    /// plays over it, and standing turns into the alert stance for a while after combat.
     pub fn update(&mut self, dt: f32, state: CharacterState, facing_right: bool) {
         self.facing_right = facing_right;
         if !self.loaded { return; }
-        let anim_name = match state {
-            CharacterState::Stand => "stand",
-            CharacterState::Move => "move",
-            CharacterState::Jump | CharacterState::Fall => "jump",
+        self.update_expression(dt);
+        self.alert_time_left = (self.alert_time_left - dt).max(0.0);
+
+        if state == CharacterState::Dead {
+            self.action = None;
+        }
+        let next = match self.action {
+            Some(action) => action,
+            None if state == CharacterState::Stand && self.alert_time_left > 0.0 => CharacterState::Alert,
+            None => state,
         };
-        if let Some(anim) = self.animations.get_mut(anim_name) {
-            anim.update(dt);
+
+        // Start a stance from its first frame
+        if next.animation() != self.stance.animation() {
+            if let Some(anim) = self.animations.get_mut(&next.animation()) {
+                anim.reset();
+            }
         }
+        self.stance = next;
+
+        let finished = self
+            .animations
+            .get_mut(&next.animation())
+            .map_or(true, |anim| anim.update(dt, next.loops()));
+        if finished && self.action.is_some() {
+            self.action = None;
+            self.alert_time_left = ALERT_DURATION;
+        }
+    }
+
+    /// Animation for a stance, falling back to standing if the stance wasn't loaded
+    fn animation_for(&self, state: CharacterState) -> Option<&CharacterAnimation> {
+        self.animations
+            .get(&state.animation())
+            .or_else(|| self.animations.get(&CharacterState::Stand))
     }
 
-    pub fn draw(&self, x: f32, y: f32, state: CharacterState) {
-        let anim_name = match state {
-            CharacterState::Stand => "stand",
-            CharacterState::Move => "move",
-            CharacterState::Jump | CharacterState::Fall => "jump",
+    /// Draw every part of a frame with the character's feet at (x, y), with the face on its layer
+    fn draw_frame(frame: &CharacterFrame, face: Option<&FaceFrame>, x: f32, y: f32, facing_right: bool) {
+        // MapleStory sprites face LEFT by default, so flip when facing right
+        let flip = facing_right;
+        let draw_sprite = |texture: &Texture2D, offset: Vec2| {
+            let sprite_x = if flip {
+                x - (offset.x + texture.width())
+            } else {
+                x + offset.x
+            };
+            draw_texture_ex(texture, sprite_x, y + offset.y, WHITE, DrawTextureParams {
+                flip_x: flip, ..Default::default()
+            });
         };
 
+        let face_z = z_rank("face");
+        let mut face = face.zip(frame.brow);
+        for part in &frame.parts {
+            if part.z > face_z {
+                if let Some((face, brow)) = face.take() {
+                    draw_sprite(&face.texture, brow + face.offset);
+                }
+            }
+            draw_sprite(&part.texture, part.offset);
+        }
+        if let Some((face, brow)) = face {
+            draw_sprite(&face.texture, brow + face.offset);
+        }
+    }
+
+    /// Draw the current stance with the character's feet at (x, y)
+    pub fn draw(&self, x: f32, y: f32) {
         if self.loaded {
-            if let Some(anim) = self.animations.get(anim_name) {
+            if let Some(anim) = self.animation_for(self.stance) {
                 if let Some(frame) = anim.get_current_frame() {
-                    // MapleStory sprites face LEFT by default, so flip when facing right
-                    let flip = self.facing_right;
-                    
-                    // Draw body - when flipping, need to adjust X origin
-                    let body_x = if flip {
-                        x - (frame.body_texture.width() - frame.body_origin.x)
-                    } else {
-                        x - frame.body_origin.x
-                    };
-                    let body_y = y - frame.body_origin.y;
-                    draw_texture_ex(&frame.body_texture, body_x, body_y, WHITE, DrawTextureParams {
-                        flip_x: flip, ..Default::default()
-                    });
-
-                    // Draw head - when flipping, need to adjust X origin
-                    if let Some(ref head_tex) = frame.head_texture {
-                        let head_x = if flip {
-                            x - (head_tex.width() - frame.head_origin.x) - frame.head_offset.x
-                        } else {
-                            x - frame.head_origin.x + frame.head_offset.x
-                        };
-                        let head_y = y - frame.head_origin.y + frame.head_offset.y;
-                        draw_texture_ex(head_tex, head_x, head_y, WHITE, DrawTextureParams {
-                            flip_x: flip, ..Default::default()
-                        });
-                    }
+                    Self::draw_frame(frame, self.current_face(), x, y, self.facing_right);
                     return;
                 }
             }
@@ -283,14 +800,8 @@ impl CharacterRenderer {
 
     /// Draw with a specific timer value (for fake players)
     pub fn draw_with_timer(&self, x: f32, y: f32, state: CharacterState, facing_right: bool, timer: f32) {
-        let anim_name = match state {
-            CharacterState::Stand => "stand",
-            CharacterState::Move => "move",
-            CharacterState::Jump | CharacterState::Fall => "jump",
-        };
-
         if self.loaded {
-            if let Some(anim) = self.animations.get(anim_name) {
+            if let Some(anim) = self.animation_for(state) {
                 if !anim.frames.is_empty() {
                     // Calculate frame index from timer
                     let total_delay: f32 = anim.frames.iter().map(|f| f.delay as f32).sum();
@@ -304,30 +815,10 @@ impl CharacterRenderer {
                             break;
                         }
                     }
-                    
+
                     if let Some(frame) = anim.frames.get(frame_idx) {
-                        let flip = facing_right;
-                        let body_x = if flip {
-                            x - (frame.body_texture.width() - frame.body_origin.x)
-                        } else {
-                            x - frame.body_origin.x
-                        };
-                        let body_y = y - frame.body_origin.y;
-                        draw_texture_ex(&frame.body_texture, body_x, body_y, WHITE, DrawTextureParams {
-                            flip_x: flip, ..Default::default()
-                        });
-
-                        if let Some(ref head_tex) = frame.head_texture {
-                            let head_x = if flip {
-                                x - (head_tex.width() - frame.head_origin.x) - frame.head_offset.x
-                            } else {
-                                x - frame.head_origin.x + frame.head_offset.x
-                            };
-                            let head_y = y - frame.head_origin.y + frame.head_offset.y;
-                            draw_texture_ex(head_tex, head_x, head_y, WHITE, DrawTextureParams {
-                                flip_x: flip, ..Default::default()
-                            });
-                        }
+                        let face = self.expressions.get("default").and_then(|frames| frames.first());
+                        Self::draw_frame(frame, face, x, y, facing_right);
                         return;
                     }
                 }
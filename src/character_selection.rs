@@ -1,18 +1,15 @@
 use macroquad::prelude::*;
-use crate::assets::AssetManager;
 use crate::character::CharacterData;
+use crate::config::config;
 use crate::cursor::CursorManager;
-use std::sync::Arc;
-use wz_reader::version::guess_iv_from_wz_img;
-use wz_reader::{WzImage, WzNode, WzNodeArc, WzReader, WzObjectType, WzNodeCast};
+use crate::wz_cache::load_wz_image;
+use wz_reader::{WzNodeArc, WzObjectType, WzNodeCast};
 
 #[cfg(not(target_arch = "wasm32"))]
 use memmap2::MmapOptions;
 
-const LOGIN_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/UI/Login.img";
-const LOGIN_CACHE_NAME: &str = "/01/UI/Login.img";
-const BACKGROUND_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/Map/Back/login.img";
-const BACKGROUND_CACHE_NAME: &str = "/01/Map/Back/login.img";
+const LOGIN_PATH: &str = "UI/Login.img";
+const BACKGROUND_PATH: &str = "Map/Back/login.img";
 
 /// Structure to hold texture with its origin point
 #[derive(Clone)]
@@ -276,64 +273,20 @@ impl CharacterSelectionState {
             show_mouse(false);
         }
 
-        let bytes = match AssetManager::fetch_and_cache(LOGIN_URL, LOGIN_CACHE_NAME).await {
-            Ok(bytes) => bytes,
+        let root_node = match load_wz_image(LOGIN_PATH).await {
+            Ok(node) => node,
             Err(e) => {
-                error!("Failed to fetch Login.img: {}", e);
+                error!("Failed to load Login.img: {}", e);
                 return;
             }
         };
 
-        info!("Parsing Login.img (size: {} bytes)...", bytes.len());
-
-        let wz_iv = match guess_iv_from_wz_img(&bytes) {
-            Some(iv) => iv,
-            None => {
-                error!("Unable to guess version from Login.img");
-                return;
-            }
-        };
-
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        let wz_image = WzImage::new(&LOGIN_CACHE_NAME.into(), 0, byte_len, &reader);
-        let root_node: WzNodeArc = WzNode::new(&LOGIN_CACHE_NAME.into(), wz_image, None).into();
-
-        if let Err(e) = root_node.write().unwrap().parse(&root_node) {
-            error!("Failed to parse WZ root node: {:?}", e);
-            return;
-        }
-
         info!("WZ file parsed successfully");
 
         // Load background from login.img (Map/Back/login.img)
         info!("Loading background assets...");
-        let bg_bytes = match AssetManager::fetch_and_cache(BACKGROUND_URL, BACKGROUND_CACHE_NAME).await {
-            Ok(bytes) => bytes,
-            Err(e) => {
-                error!("Failed to fetch background: {}", e);
-                Vec::new()
-            }
-        };
-
-        if !bg_bytes.is_empty() {
-            info!("Parsing background (size: {} bytes)...", bg_bytes.len());
-
-            let bg_wz_iv = match guess_iv_from_wz_img(&bg_bytes) {
-                Some(iv) => iv,
-                None => {
-                    error!("Unable to guess version from background IMG file");
-                    [0; 4]
-                }
-            };
-
-            let bg_byte_len = bg_bytes.len();
-            let bg_reader = Arc::new(WzReader::from_buff(&bg_bytes).with_iv(bg_wz_iv));
-            let bg_wz_image = WzImage::new(&BACKGROUND_CACHE_NAME.into(), 0, bg_byte_len, &bg_reader);
-            let bg_root_node: WzNodeArc = WzNode::new(&BACKGROUND_CACHE_NAME.into(), bg_wz_image, None).into();
-
-            // Parse the background node
-            if bg_root_node.write().unwrap().parse(&bg_root_node).is_ok() {
+        match load_wz_image(BACKGROUND_PATH).await {
+            Ok(bg_root_node) => {
                 // Load sky gradient (back/1) - sits behind everything
                 match load_png_from_node(&bg_root_node, "back/1") {
                     Ok(two) => {
@@ -354,6 +307,7 @@ impl CharacterSelectionState {
                     Err(e) => error!("Failed to load scene background: {}", e),
                 }
             }
+            Err(e) => error!("Failed to load background: {}", e),
         }
 
         // Load character info panels
@@ -489,6 +443,12 @@ impl CharacterSelectionState {
         // Update button states
         self.select_button.update();
         self.delete_button.update();
+        // No new characters once every slot is taken
+        if self.characters.len() >= config().max_characters {
+            self.new_button.state = ButtonState::Disabled;
+        } else if self.new_button.state == ButtonState::Disabled {
+            self.new_button.state = ButtonState::Normal;
+        }
         self.new_button.update();
         self.page_left_button.update();
         self.page_right_button.update();
@@ -625,8 +585,8 @@ impl CharacterSelectionState {
             let panel_x = 150.0; // Left side position
             let mut panel_y = 150.0; // Starting Y position
 
-            // Draw up to 3 character slots vertically
-            for (idx, character) in self.characters.iter().enumerate().take(3) {
+            // Draw one slot per allowed character, vertically
+            for (idx, character) in self.characters.iter().enumerate().take(config().max_characters) {
                 if let Some(panel) = self.char_info_panels.first() {
                     let draw_x = panel_x - panel.origin.x;
                     let draw_y = panel_y - panel.origin.y;
@@ -651,8 +611,8 @@ impl CharacterSelectionState {
                         text_color,
                     );
 
-                    // Draw character level
-                    let level_text = format!("Lv. {}", character.level);
+                    // Draw character level and job
+                    let level_text = format!("Lv. {}  {}", character.level, character.job.name());
                     draw_text(
                         &level_text,
                         draw_x + 10.0,
@@ -665,8 +625,8 @@ impl CharacterSelectionState {
                 }
             }
 
-            // Draw empty slots if less than 3 characters
-            for _ in self.characters.len()..3 {
+            // Draw the slots still free
+            for _ in self.characters.len()..config().max_characters {
                 if let Some(panel) = self.char_info_panels.first() {
                     let draw_x = panel_x - panel.origin.x;
                     let draw_y = panel_y - panel.origin.y;
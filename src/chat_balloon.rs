@@ -1,12 +1,10 @@
 use macroquad::prelude::*;
 use crate::assets::AssetManager;
 use std::collections::HashMap;
-use std::sync::Arc;
-use wz_reader::version::guess_iv_from_wz_img;
-use wz_reader::{WzImage, WzNode, WzNodeArc, WzReader, WzNodeCast};
+use crate::wz_cache::load_wz_image;
+use wz_reader::{WzNodeArc, WzNodeCast};
 
-const CHATBALLOON_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/UI/ChatBalloon.img";
-const CHATBALLOON_CACHE: &str = "/01/UI/ChatBalloon.img";
+const CHATBALLOON_PATH: &str = "UI/ChatBalloon.img";
 
 /// Texture with origin point
 #[derive(Clone)]
@@ -93,7 +91,7 @@ impl ChatBalloonSystem {
         }
 
         // Load font
-        match load_ttf_font("https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/MaplestoryLight.ttf").await {
+        match AssetManager::load_font("MaplestoryLight.ttf").await {
             Ok(font) => {
                 self.font = Some(font);
             }
@@ -104,20 +102,8 @@ impl ChatBalloonSystem {
     }
 
     async fn load_from_wz() -> Result<HashMap<usize, BalloonFrame>, String> {
-        let bytes = AssetManager::fetch_and_cache(CHATBALLOON_URL, CHATBALLOON_CACHE).await
-            .map_err(|e| format!("Failed to fetch ChatBalloon.img: {}", e))?;
-
-        let wz_iv = guess_iv_from_wz_img(&bytes)
-            .ok_or_else(|| "Unable to guess version from ChatBalloon.img".to_string())?;
-
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        let cache_name_ref: wz_reader::WzNodeName = CHATBALLOON_CACHE.to_string().into();
-        let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-        let root_node: WzNodeArc = WzNode::new(&CHATBALLOON_CACHE.into(), wz_image, None).into();
-
-        root_node.write().unwrap().parse(&root_node)
-            .map_err(|e| format!("Failed to parse ChatBalloon.img: {:?}", e))?;
+        let root_node = load_wz_image(CHATBALLOON_PATH).await
+            .map_err(|e| format!("Failed to load ChatBalloon.img: {}", e))?;
 
         let mut frames = HashMap::new();
 
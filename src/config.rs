@@ -0,0 +1,365 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::{OnceLock, RwLock, RwLockReadGuard};
+
+use crate::flags;
+
+/// Config file read at startup when no other path is given
+pub const DEFAULT_CONFIG_PATH: &str = "config.json";
+
+#[cfg(target_arch = "wasm32")]
+extern "C" {
+    fn query_string_length() -> u32;
+    fn query_string_copy(ptr: *mut u8, len: u32);
+}
+
+/// Runtime game configuration.
+///
+/// Starts from the defaults in flags.rs and is then overridden, in order, by:
+/// 1. a JSON config file (`config.json`, or the path given with `--config` / `?config=`)
+/// 2. `MAPLE_<KEY>` environment variables (native only, e.g. `MAPLE_GOD_MODE=true`)
+/// 3. command-line arguments on native (`--god-mode`, `--player-speed-multiplier=2`)
+///    or URL query parameters on web (`?god_mode=true&skip_to_game=false`)
+///
+/// Keys are the snake_case field names; values can be changed while the game
+/// is running from the debug console.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+    pub skip_logos: bool,
+    pub skip_login: bool,
+    pub skip_to_game: bool,
+    pub default_movable_mode: bool,
+    pub show_debug_ui: bool,
+    pub show_fps: bool,
+    pub show_map_loader: bool,
+    pub asset_source: String,
+    pub asset_root: String,
+    pub wz_cache_budget_mb: usize,
+    pub verbose_asset_loading: bool,
+    pub verbose_character_io: bool,
+    pub use_memory_storage: bool,
+    pub save_dir: String,
+    pub script_dir: String,
+    pub auto_create_test_character: bool,
+    pub test_character_name: String,
+    pub test_character_job: usize,
+    pub enable_character_validation: bool,
+    pub min_character_name_length: usize,
+    pub max_character_name_length: usize,
+    pub max_characters: usize,
+    pub enable_collision: bool,
+    pub player_speed_multiplier: f32,
+    pub camera_debug_mode: bool,
+    pub show_hitboxes: bool,
+    pub render_portals: bool,
+    pub god_mode: bool,
+    pub start_with_max_stats: bool,
+    pub verbose_map_loading: bool,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            skip_logos: flags::SKIP_LOGOS,
+            skip_login: flags::SKIP_LOGIN,
+            skip_to_game: flags::SKIP_TO_GAME,
+            default_movable_mode: flags::DEFAULT_MOVABLE_MODE,
+            show_debug_ui: flags::SHOW_DEBUG_UI,
+            show_fps: flags::SHOW_FPS,
+            show_map_loader: flags::SHOW_MAP_LOADER,
+            asset_source: flags::ASSET_SOURCE.to_string(),
+            asset_root: flags::ASSET_ROOT.to_string(),
+            wz_cache_budget_mb: flags::WZ_CACHE_BUDGET_MB,
+            verbose_asset_loading: flags::VERBOSE_ASSET_LOADING,
+            verbose_character_io: flags::VERBOSE_CHARACTER_IO,
+            use_memory_storage: flags::USE_MEMORY_STORAGE,
+            save_dir: flags::SAVE_DIRECTORY.to_string(),
+            script_dir: flags::SCRIPT_DIRECTORY.to_string(),
+            auto_create_test_character: flags::AUTO_CREATE_TEST_CHARACTER,
+            test_character_name: flags::TEST_CHARACTER_NAME.to_string(),
+            test_character_job: flags::TEST_CHARACTER_JOB,
+            enable_character_validation: flags::ENABLE_CHARACTER_VALIDATION,
+            min_character_name_length: flags::MIN_CHARACTER_NAME_LENGTH,
+            max_character_name_length: flags::MAX_CHARACTER_NAME_LENGTH,
+            max_characters: flags::MAX_CHARACTERS,
+            enable_collision: flags::ENABLE_COLLISION,
+            player_speed_multiplier: flags::PLAYER_SPEED_MULTIPLIER,
+            camera_debug_mode: flags::CAMERA_DEBUG_MODE,
+            show_hitboxes: flags::SHOW_HITBOXES,
+            render_portals: flags::RENDER_PORTALS,
+            god_mode: flags::GOD_MODE,
+            start_with_max_stats: flags::START_WITH_MAX_STATS,
+            verbose_map_loading: flags::VERBOSE_MAP_LOADING,
+        }
+    }
+}
+
+impl GameConfig {
+    /// Parse a JSON config file. Missing keys keep their defaults.
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        serde_json::from_str(text).map_err(|e| format!("Invalid config: {}", e))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// All config keys, sorted
+    pub fn keys(&self) -> Vec<String> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map.keys().cloned().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Current value of a key, formatted for display
+    pub fn get(&self, key: &str) -> Option<String> {
+        let value = serde_json::to_value(self).ok()?;
+        match value.get(normalize_key(key).as_str())? {
+            Value::String(s) => Some(s.clone()),
+            other => Some(other.to_string()),
+        }
+    }
+
+    /// Set a key from its string form. The value is parsed according to the key's type.
+    pub fn set(&mut self, key: &str, raw: &str) -> Result<(), String> {
+        let key = normalize_key(key);
+        let mut value = serde_json::to_value(&*self).map_err(|e| e.to_string())?;
+        let map = value.as_object_mut().ok_or("Config is not an object")?;
+
+        let parsed = match map.get(&key) {
+            None => return Err(format!("Unknown config key '{}'", key)),
+            Some(Value::Bool(_)) => Value::Bool(parse_bool(raw)?),
+            Some(Value::Number(n)) if n.is_f64() => raw
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number)
+                .ok_or_else(|| format!("'{}' expects a number, got '{}'", key, raw))?,
+            Some(Value::Number(_)) => raw
+                .parse::<u64>()
+                .map(Value::from)
+                .map_err(|_| format!("'{}' expects a whole number, got '{}'", key, raw))?,
+            Some(_) => Value::String(raw.to_string()),
+        };
+
+        map.insert(key, parsed);
+        *self = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Flip a boolean key, returning its new value
+    pub fn toggle(&mut self, key: &str) -> Result<bool, String> {
+        let current = self.get(key).ok_or_else(|| format!("Unknown config key '{}'", normalize_key(key)))?;
+        let new_value = !parse_bool(&current).map_err(|_| format!("'{}' is not a boolean", normalize_key(key)))?;
+        self.set(key, if new_value { "true" } else { "false" })?;
+        Ok(new_value)
+    }
+
+    /// Apply `--key=value`, `--key value`, `--flag` and `--no-flag` arguments
+    pub fn apply_args(&mut self, args: &[String]) {
+        let mut i = 0;
+        while i < args.len() {
+            let Some(arg) = args[i].strip_prefix("--") else {
+                i += 1;
+                continue;
+            };
+
+            let (key, value) = if let Some((key, value)) = arg.split_once('=') {
+                (key.to_string(), value.to_string())
+            } else if let Some(key) = arg.strip_prefix("no-") {
+                (key.to_string(), "false".to_string())
+            } else if self.is_bool_key(arg) {
+                (arg.to_string(), "true".to_string())
+            } else if i + 1 < args.len() {
+                i += 1;
+                (arg.to_string(), args[i].clone())
+            } else {
+                (arg.to_string(), String::new())
+            };
+            i += 1;
+
+            if key == "config" {
+                continue;
+            }
+            if let Err(e) = self.set(&key, &value) {
+                warn!("Ignoring argument --{}: {}", arg, e);
+            }
+        }
+    }
+
+    /// Apply URL query parameters (`?god_mode=true&show_fps`)
+    pub fn apply_query(&mut self, query: &str) {
+        for pair in query.trim_start_matches('?').split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, "true"));
+            let value = percent_decode(value);
+            if key == "config" {
+                continue;
+            }
+            if let Err(e) = self.set(key, &value) {
+                warn!("Ignoring query parameter {}: {}", key, e);
+            }
+        }
+    }
+
+    /// Apply `MAPLE_<KEY>` environment variables
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn apply_env(&mut self) {
+        for key in self.keys() {
+            let var = format!("MAPLE_{}", key.to_ascii_uppercase());
+            if let Ok(value) = std::env::var(&var) {
+                if let Err(e) = self.set(&key, &value) {
+                    warn!("Ignoring {}: {}", var, e);
+                }
+            }
+        }
+    }
+
+    fn is_bool_key(&self, key: &str) -> bool {
+        matches!(self.get(key).as_deref(), Some("true") | Some("false"))
+    }
+}
+
+static CONFIG: OnceLock<RwLock<GameConfig>> = OnceLock::new();
+
+fn config_lock() -> &'static RwLock<GameConfig> {
+    CONFIG.get_or_init(|| RwLock::new(GameConfig::default()))
+}
+
+/// The active configuration
+pub fn config() -> RwLockReadGuard<'static, GameConfig> {
+    config_lock().read().unwrap()
+}
+
+/// Modify the active configuration (used by the debug console)
+pub fn update_config<R>(f: impl FnOnce(&mut GameConfig) -> R) -> R {
+    f(&mut config_lock().write().unwrap())
+}
+
+/// Build the startup configuration from the config file, environment,
+/// command line and query string. Call once before anything reads the config.
+pub async fn init() {
+    let args = startup_args();
+    let query = startup_query();
+
+    let path = config_path(&args, &query);
+    let mut config = match load_file(&path).await {
+        Ok(bytes) => match GameConfig::from_json(&String::from_utf8_lossy(&bytes)) {
+            Ok(config) => {
+                info!("Loaded config from {}", path);
+                config
+            }
+            Err(e) => {
+                error!("Failed to parse {}: {}", path, e);
+                GameConfig::default()
+            }
+        },
+        Err(_) => {
+            info!("No config file at {}, using defaults", path);
+            GameConfig::default()
+        }
+    };
+
+    #[cfg(not(target_arch = "wasm32"))]
+    config.apply_env();
+    config.apply_args(&args);
+    config.apply_query(&query);
+
+    *config_lock().write().unwrap() = config;
+}
+
+/// Write the active configuration to the config file (native only)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save(path: &str) -> Result<(), String> {
+    std::fs::write(path, config().to_json()).map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+fn config_path(args: &[String], query: &str) -> String {
+    for (i, arg) in args.iter().enumerate() {
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return path.to_string();
+        }
+        if arg == "--config" {
+            if let Some(path) = args.get(i + 1) {
+                return path.clone();
+            }
+        }
+    }
+
+    for pair in query.trim_start_matches('?').split('&') {
+        if let Some(path) = pair.strip_prefix("config=") {
+            return percent_decode(path);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Ok(path) = std::env::var("MAPLE_CONFIG") {
+        return path;
+    }
+
+    DEFAULT_CONFIG_PATH.to_string()
+}
+
+fn startup_args() -> Vec<String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::env::args().skip(1).collect()
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        Vec::new()
+    }
+}
+
+fn startup_query() -> String {
+    #[cfg(target_arch = "wasm32")]
+    unsafe {
+        let len = query_string_length();
+        let mut buf = vec![0u8; len as usize];
+        query_string_copy(buf.as_mut_ptr(), len);
+        String::from_utf8_lossy(&buf).into_owned()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        String::new()
+    }
+}
+
+/// Accept both `god-mode` (CLI style) and `god_mode`
+fn normalize_key(key: &str) -> String {
+    key.trim().replace('-', "_").to_ascii_lowercase()
+}
+
+fn parse_bool(raw: &str) -> Result<bool, String> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Ok(true),
+        "false" | "0" | "no" | "off" => Ok(false),
+        _ => Err(format!("'{}' is not a boolean", raw)),
+    }
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(b) => {
+                        out.push(b);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
@@ -1,8 +1,7 @@
 use macroquad::prelude::*;
-use crate::assets::{AssetManager, FrameData};
-use std::sync::Arc;
-use wz_reader::{WzNode, WzNodeArc, WzNodeCast, WzReader, WzImage};
-use wz_reader::version::guess_iv_from_wz_img;
+use crate::assets::FrameData;
+use wz_reader::{WzNodeArc, WzNodeCast};
+use crate::wz_cache::load_wz_image;
 
 /// Cursor state determines which cursor animation to display
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -53,10 +52,7 @@ impl CursorManager {
 
     /// Load all cursor animations from WZ
     pub async fn load_cursors(&mut self) {
-        let url = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/UI/Basic.img";
-        let cache_path = "/01/UI/Basic.img";
-
-        match Self::load_cursor_from_wz(url, cache_path).await {
+        match Self::load_cursor_from_wz("UI/Basic.img").await {
             Ok((default, npc_hover, dragging, right_click)) => {
                 // Verify all cursors have at least one frame
                 if default.is_empty() || npc_hover.is_empty() || dragging.is_empty() || right_click.is_empty() {
@@ -80,24 +76,10 @@ impl CursorManager {
 
     /// Load cursor animations from WZ file
     async fn load_cursor_from_wz(
-        url: &str,
-        cache_path: &str,
+        wz_path: &str,
     ) -> Result<(Vec<FrameData>, Vec<FrameData>, Vec<FrameData>, Vec<FrameData>), String> {
-        // Fetch the WZ file
-        let bytes = AssetManager::fetch_and_cache(url, cache_path).await?;
-
-        // Parse WZ file
-        let wz_iv = guess_iv_from_wz_img(&bytes)
-            .ok_or_else(|| "Unable to guess version from Basic.img".to_string())?;
-
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        let cache_name_ref: wz_reader::WzNodeName = cache_path.to_string().into();
-        let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-        let root_node: WzNodeArc = WzNode::new(&cache_path.to_string().into(), wz_image, None).into();
-
-        root_node.write().unwrap().parse(&root_node)
-            .map_err(|e| format!("Failed to parse Basic.img: {:?}", e))?;
+        // Fetch and parse the WZ file
+        let root_node = load_wz_image(wz_path).await?;
 
         // Load each cursor type
         let default = Self::load_cursor_animation(&root_node, "Cursor/0").await?;
@@ -0,0 +1,182 @@
+use macroquad::prelude::*;
+use std::collections::HashMap;
+use crate::wz_cache::load_wz_image;
+use wz_reader::{WzNodeArc, WzNodeCast};
+
+const BASIC_EFF_PATH: &str = "Effect/BasicEff.img";
+
+/// How long a damage number stays on screen (seconds)
+const NUMBER_LIFETIME: f32 = 1.0;
+/// How far a damage number floats up over its lifetime (pixels)
+const NUMBER_RISE: f32 = 40.0;
+/// Digits overlap a little, like the client draws them
+const DIGIT_OVERLAP: f32 = 0.2;
+
+/// Which digit sprites a number is drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DamageStyle {
+    /// Damage dealt to mobs (orange)
+    ToMob,
+    /// Damage taken by the player (violet)
+    ToPlayer,
+}
+
+impl DamageStyle {
+    /// BasicEff digit sets: the first digit uses the larger "1" set, the rest the "0" set
+    fn sets(self) -> (&'static str, &'static str) {
+        match self {
+            DamageStyle::ToMob => ("NoRed1", "NoRed0"),
+            DamageStyle::ToPlayer => ("NoViolet1", "NoViolet0"),
+        }
+    }
+}
+
+/// Texture with origin point
+#[derive(Clone)]
+struct Digit {
+    texture: Texture2D,
+    origin: Vec2,
+}
+
+/// Digits 0-9 of one style
+#[derive(Clone, Default)]
+struct DigitSet {
+    first: Vec<Digit>,
+    rest: Vec<Digit>,
+}
+
+/// A damage number floating above a mob or the player
+struct DamageNumber {
+    value: i32,
+    x: f32,
+    y: f32,
+    age: f32,
+    style: DamageStyle,
+}
+
+/// Damage numbers drawn with the Effect.wz BasicEff digits
+pub struct DamageNumberSystem {
+    loaded: bool,
+    digit_sets: HashMap<DamageStyle, DigitSet>,
+    numbers: Vec<DamageNumber>,
+}
+
+impl DamageNumberSystem {
+    pub fn new() -> Self {
+        Self {
+            loaded: false,
+            digit_sets: HashMap::new(),
+            numbers: Vec::new(),
+        }
+    }
+
+    /// Load the BasicEff digit sprites
+    pub async fn load_assets(&mut self) {
+        info!("Loading damage number assets...");
+
+        let root_node = match load_wz_image(BASIC_EFF_PATH).await {
+            Ok(node) => node,
+            Err(e) => {
+                error!("Failed to load BasicEff.img: {}", e);
+                return;
+            }
+        };
+
+        for style in [DamageStyle::ToMob, DamageStyle::ToPlayer] {
+            let (first_set, rest_set) = style.sets();
+            let set = DigitSet {
+                first: Self::load_digits(&root_node, first_set),
+                rest: Self::load_digits(&root_node, rest_set),
+            };
+            if set.first.len() == 10 && set.rest.len() == 10 {
+                self.digit_sets.insert(style, set);
+            } else {
+                warn!("Damage digits for {:?} incomplete", style);
+            }
+        }
+
+        self.loaded = !self.digit_sets.is_empty();
+        info!("Damage number assets loaded: {} styles", self.digit_sets.len());
+    }
+
+    fn load_digits(root_node: &WzNodeArc, set: &str) -> Vec<Digit> {
+        (0..10).map_while(|digit| Self::load_digit(root_node, &format!("{}/{}", set, digit))).collect()
+    }
+
+    fn load_digit(root_node: &WzNodeArc, path: &str) -> Option<Digit> {
+        let node = root_node.read().unwrap().at_path_parsed(path).ok()?;
+        node.write().unwrap().parse(&node).ok()?;
+
+        let node_read = node.read().unwrap();
+        let png = node_read.try_as_png()?;
+        let rgba_img = png.extract_png().ok()?.to_rgba8();
+        let texture = Texture2D::from_rgba8(rgba_img.width() as u16, rgba_img.height() as u16, &rgba_img.into_raw());
+
+        let origin = node_read.children.get("origin")
+            .and_then(|o| o.read().unwrap().try_as_vector2d().map(|v| Vec2::new(v.0 as f32, v.1 as f32)))
+            .unwrap_or(Vec2::ZERO);
+
+        Some(Digit { texture, origin })
+    }
+
+    /// Show a damage number centered on (x, y) in world coordinates
+    pub fn spawn(&mut self, value: i32, x: f32, y: f32, style: DamageStyle) {
+        self.numbers.push(DamageNumber { value: value.max(0), x, y, age: 0.0, style });
+    }
+
+    /// Remove all numbers (when changing maps)
+    pub fn clear(&mut self) {
+        self.numbers.clear();
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for number in &mut self.numbers {
+            number.age += dt;
+        }
+        self.numbers.retain(|n| n.age < NUMBER_LIFETIME);
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32) {
+        for number in &self.numbers {
+            let progress = number.age / NUMBER_LIFETIME;
+            // Fade out over the second half
+            let alpha = (2.0 - progress * 2.0).min(1.0);
+            let x = number.x - camera_x;
+            let y = number.y - camera_y - NUMBER_RISE * progress;
+            let text = number.value.to_string();
+
+            let Some(set) = self.digit_sets.get(&number.style).filter(|_| self.loaded) else {
+                let color = match number.style {
+                    DamageStyle::ToMob => Color::new(1.0, 0.6, 0.1, alpha),
+                    DamageStyle::ToPlayer => Color::new(0.7, 0.4, 1.0, alpha),
+                };
+                let dims = measure_text(&text, None, 24, 1.0);
+                draw_text(&text, x - dims.width / 2.0, y, 24.0, color);
+                continue;
+            };
+
+            let digits: Vec<&Digit> = text
+                .bytes()
+                .enumerate()
+                .map(|(i, c)| {
+                    let d = (c - b'0') as usize;
+                    if i == 0 { &set.first[d] } else { &set.rest[d] }
+                })
+                .collect();
+            let advance = |digit: &Digit| digit.texture.width() * (1.0 - DIGIT_OVERLAP);
+            let total_width: f32 = digits.iter().map(|d| advance(d)).sum();
+
+            let mut pen_x = x - total_width / 2.0;
+            for digit in digits {
+                draw_texture(&digit.texture, pen_x - digit.origin.x + digit.texture.width() / 2.0, y - digit.origin.y, Color::new(1.0, 1.0, 1.0, alpha));
+                pen_x += advance(digit);
+            }
+        }
+    }
+}
+
+impl Default for DamageNumberSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,230 @@
+use macroquad::prelude::*;
+use crate::config::{self, config, update_config};
+
+/// Maximum number of output lines kept in the console
+const MAX_LINES: usize = 200;
+
+/// Number of output lines shown above the input line
+const VISIBLE_LINES: usize = 14;
+
+const LINE_HEIGHT: f32 = 16.0;
+
+/// In-game debug console (toggle with the ` key) for inspecting and changing
+/// GameConfig values while the game is running
+pub struct DebugConsole {
+    visible: bool,
+    input: String,
+    lines: Vec<(String, Color)>,
+    history: Vec<String>,
+    history_index: Option<usize>,
+    backspace_timer: f32,
+}
+
+impl DebugConsole {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            input: String::new(),
+            lines: vec![("Type 'help' for a list of commands".to_string(), GRAY)],
+            history: Vec::new(),
+            history_index: None,
+            backspace_timer: 0.0,
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        self.input.clear();
+        self.history_index = None;
+        // Drop the ` that opened the console
+        while get_char_pressed().is_some() {}
+    }
+
+    /// Handle the toggle key and, while open, keyboard input.
+    /// Returns true if the console consumed input this frame.
+    pub fn update(&mut self, dt: f32) -> bool {
+        if is_key_pressed(KeyCode::GraveAccent) {
+            self.toggle();
+            return true;
+        }
+
+        if !self.visible {
+            return false;
+        }
+
+        if is_key_pressed(KeyCode::Escape) {
+            self.toggle();
+            return true;
+        }
+
+        while let Some(c) = get_char_pressed() {
+            if c != '`' && !c.is_control() {
+                self.input.push(c);
+            }
+        }
+
+        // Backspace with hold-to-repeat
+        if is_key_down(KeyCode::Backspace) && !self.input.is_empty() {
+            if is_key_pressed(KeyCode::Backspace) {
+                self.input.pop();
+                self.backspace_timer = 0.3;
+            } else {
+                self.backspace_timer -= dt;
+                if self.backspace_timer <= 0.0 {
+                    self.input.pop();
+                    self.backspace_timer = 0.05;
+                }
+            }
+        }
+
+        if is_key_pressed(KeyCode::Up) && !self.history.is_empty() {
+            let index = match self.history_index {
+                Some(i) => i.saturating_sub(1),
+                None => self.history.len() - 1,
+            };
+            self.history_index = Some(index);
+            self.input = self.history[index].clone();
+        }
+        if is_key_pressed(KeyCode::Down) {
+            if let Some(i) = self.history_index {
+                if i + 1 < self.history.len() {
+                    self.history_index = Some(i + 1);
+                    self.input = self.history[i + 1].clone();
+                } else {
+                    self.history_index = None;
+                    self.input.clear();
+                }
+            }
+        }
+
+        if is_key_pressed(KeyCode::Tab) {
+            self.complete_key();
+        }
+
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) {
+            let command = std::mem::take(&mut self.input);
+            if !command.trim().is_empty() {
+                self.push(format!("> {}", command), WHITE);
+                self.history.push(command.clone());
+                self.history_index = None;
+                self.execute(command.trim());
+            }
+        }
+
+        true
+    }
+
+    fn push(&mut self, line: String, color: Color) {
+        self.lines.push((line, color));
+        if self.lines.len() > MAX_LINES {
+            self.lines.remove(0);
+        }
+    }
+
+    /// Complete the config key being typed after set/get/toggle
+    fn complete_key(&mut self) {
+        let Some((command, partial)) = self.input.rsplit_once(' ') else {
+            return;
+        };
+        let matches: Vec<String> = config()
+            .keys()
+            .into_iter()
+            .filter(|key| key.starts_with(partial))
+            .collect();
+
+        match matches.len() {
+            0 => {}
+            1 => self.input = format!("{} {} ", command, matches[0]),
+            _ => self.push(matches.join("  "), GRAY),
+        }
+    }
+
+    fn execute(&mut self, command: &str) {
+        let mut parts = command.splitn(3, ' ');
+        let name = parts.next().unwrap_or_default();
+        let key = parts.next().unwrap_or_default();
+        let value = parts.next().unwrap_or_default().trim();
+
+        match name {
+            "help" => {
+                self.push("list                 show all config values".to_string(), GRAY);
+                self.push("get <key>            show one value".to_string(), GRAY);
+                self.push("set <key> <value>    change a value".to_string(), GRAY);
+                self.push("toggle <key>         flip a true/false value".to_string(), GRAY);
+                self.push("reset                restore the built-in defaults".to_string(), GRAY);
+                #[cfg(not(target_arch = "wasm32"))]
+                self.push("save [path]          write the config to a file".to_string(), GRAY);
+                self.push("clear                clear the console".to_string(), GRAY);
+            }
+            "list" => {
+                let config = config().clone();
+                for key in config.keys() {
+                    let value = config.get(&key).unwrap_or_default();
+                    self.push(format!("{} = {}", key, value), LIGHTGRAY);
+                }
+            }
+            "get" => match config().get(key) {
+                Some(value) => self.push(format!("{} = {}", key, value), LIGHTGRAY),
+                None => self.push(format!("Unknown config key '{}'", key), RED),
+            },
+            "set" => match update_config(|c| c.set(key, value)) {
+                Ok(()) => {
+                    let value = config().get(key).unwrap_or_default();
+                    info!("Config: {} = {}", key, value);
+                    self.push(format!("{} = {}", key, value), GREEN);
+                }
+                Err(e) => self.push(e, RED),
+            },
+            "toggle" => match update_config(|c| c.toggle(key)) {
+                Ok(value) => {
+                    info!("Config: {} = {}", key, value);
+                    self.push(format!("{} = {}", key, value), GREEN);
+                }
+                Err(e) => self.push(e, RED),
+            },
+            "reset" => {
+                update_config(|c| *c = config::GameConfig::default());
+                self.push("Config reset to defaults".to_string(), GREEN);
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            "save" => {
+                let path = if key.is_empty() { config::DEFAULT_CONFIG_PATH } else { key };
+                match config::save(path) {
+                    Ok(()) => self.push(format!("Saved config to {}", path), GREEN),
+                    Err(e) => self.push(e, RED),
+                }
+            }
+            "clear" => self.lines.clear(),
+            _ => self.push(format!("Unknown command '{}'", name), RED),
+        }
+    }
+
+    pub fn draw(&self) {
+        if !self.visible {
+            return;
+        }
+
+        let width = screen_width();
+        let height = (VISIBLE_LINES as f32 + 1.5) * LINE_HEIGHT + 10.0;
+        draw_rectangle(0.0, 0.0, width, height, Color::from_rgba(0, 0, 0, 210));
+        draw_line(0.0, height, width, height, 1.0, GRAY);
+
+        let start = self.lines.len().saturating_sub(VISIBLE_LINES);
+        for (i, (line, color)) in self.lines[start..].iter().enumerate() {
+            draw_text(line, 10.0, 18.0 + i as f32 * LINE_HEIGHT, 16.0, *color);
+        }
+
+        let input_y = height - 10.0;
+        draw_text(&format!("] {}_", self.input), 10.0, input_y, 16.0, YELLOW);
+    }
+}
+
+impl Default for DebugConsole {
+    fn default() -> Self {
+        Self::new()
+    }
+}
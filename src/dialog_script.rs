@@ -0,0 +1,318 @@
+use macroquad::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::character::CharacterData;
+use crate::job::Job;
+use crate::npc_dialog::DialogType;
+use crate::npc_script::{NpcScriptCommand, StyleType};
+use crate::quest_log::QuestState;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::config::config;
+#[cfg(target_arch = "wasm32")]
+use crate::assets::AssetManager;
+
+/// Script used for NPCs that have no script of their own
+const DEFAULT_SCRIPT: &str = include_str!("../data/scripts/npc/default.json");
+
+/// Scripts bundled with the game, by NPC id. A script file with the same id replaces them.
+const BUNDLED_SCRIPTS: [(i32, &str); 6] = [
+    (1012009, include_str!("../data/scripts/npc/1012009.json")),
+    (1012100, include_str!("../data/scripts/npc/1012100.json")),
+    (1012103, include_str!("../data/scripts/npc/1012103.json")),
+    (1022000, include_str!("../data/scripts/npc/1022000.json")),
+    (9000000, include_str!("../data/scripts/npc/9000000.json")),
+    (9000001, include_str!("../data/scripts/npc/9000001.json")),
+];
+
+/// Page a conversation starts on unless the script names another
+fn start_page() -> String {
+    "start".to_string()
+}
+
+/// An NPC's conversation as a tree of pages, loaded from `<npc id>.json`:
+///
+/// ```json
+/// { "pages": {
+///     "start": { "text": "Need a potion?", "type": "yes_no", "next": "give", "no": "bye" },
+///     "give":  { "actions": [{ "give_item": { "item_id": 2000000, "quantity": 1 } }] },
+///     "bye":   { "text": "Take care!" } } }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct DialogScript {
+    #[serde(default = "start_page")]
+    pub start: String,
+    pub pages: HashMap<String, ScriptPage>,
+}
+
+/// One page of a conversation. When a page is entered its first matching branch is followed,
+/// then its actions run, then its text is shown. A page without text ends the conversation.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ScriptPage {
+    pub text: String,
+    #[serde(rename = "type")]
+    pub kind: PageKind,
+    /// Page after OK, Next, Yes or Accept (the conversation ends if there is none)
+    pub next: Option<String>,
+    /// Page after No or Decline
+    pub no: Option<String>,
+    /// Choices of a `selection` page
+    pub options: Vec<ScriptOption>,
+    /// What a `style` page changes (`hair`, `face` or `skin`) and the styles it offers.
+    /// The picked style is applied before going to `next`; Cancel goes to `no`.
+    pub style: StyleType,
+    pub styles: Vec<i32>,
+    /// Jumps to another page, checked in order before anything on this page happens
+    pub branches: Vec<ScriptBranch>,
+    pub actions: Vec<ScriptAction>,
+}
+
+/// Buttons a page is shown with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PageKind {
+    #[default]
+    Ok,
+    Next,
+    YesNo,
+    AcceptDecline,
+    Selection,
+    Style,
+}
+
+impl PageKind {
+    pub fn dialog_type(self) -> DialogType {
+        match self {
+            PageKind::Ok => DialogType::Ok,
+            PageKind::Next => DialogType::Next,
+            PageKind::YesNo => DialogType::YesNo,
+            PageKind::AcceptDecline => DialogType::AcceptDecline,
+            PageKind::Selection => DialogType::Selection,
+            PageKind::Style => DialogType::Style,
+        }
+    }
+}
+
+/// A choice on a selection page, only listed while its condition holds
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScriptOption {
+    pub text: String,
+    pub goto: Option<String>,
+    #[serde(rename = "if", default)]
+    pub condition: Condition,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScriptBranch {
+    #[serde(rename = "if")]
+    pub condition: Condition,
+    pub goto: String,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct ItemCount {
+    pub item_id: i32,
+    pub quantity: u32,
+}
+
+/// Map (and optionally portal) a `warp` action goes to
+#[derive(Debug, Clone, Deserialize)]
+pub struct WarpTarget {
+    pub map_id: i32,
+    #[serde(default)]
+    pub portal: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct QuestCondition {
+    pub quest_id: i32,
+    pub state: QuestState,
+}
+
+/// Checks on the player; every check that is set has to pass (an empty condition always holds)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Condition {
+    pub min_level: Option<u32>,
+    pub max_level: Option<u32>,
+    /// Any of these jobs
+    pub jobs: Vec<usize>,
+    /// All of these items, in at least these quantities
+    pub items: Vec<ItemCount>,
+    pub quests: Vec<QuestCondition>,
+    pub min_meso: Option<u32>,
+    /// The player meets the requirements to advance to this job (by job id)
+    pub can_advance_to: Option<Job>,
+}
+
+impl Condition {
+    pub fn holds(&self, character: &CharacterData) -> bool {
+        self.min_level.map_or(true, |level| character.level >= level)
+            && self.max_level.map_or(true, |level| character.level <= level)
+            && (self.jobs.is_empty() || self.jobs.contains(&character.job.id()))
+            && self.items.iter().all(|item| character.inventory.has(item.item_id, item.quantity))
+            && self.quests.iter().all(|quest| character.quests.state(quest.quest_id) == quest.state)
+            && self.min_meso.map_or(true, |meso| character.meso >= meso)
+            && self.can_advance_to.map_or(true, |job| character.check_advancement(job).is_ok())
+    }
+}
+
+/// Something a page does to the player, run as the matching `NpcScriptCommand`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptAction {
+    GiveItem(ItemCount),
+    TakeItem(ItemCount),
+    GiveMeso(i32),
+    GiveExp(i32),
+    Warp(WarpTarget),
+    StartQuest(i32),
+    CompleteQuest(i32),
+    /// Advances the player to the job with this id
+    ChangeJob(Job),
+    /// Opens the shop of the NPC with this id
+    OpenShop(i32),
+    /// Opens the account's storage
+    OpenStorage,
+}
+
+impl ScriptAction {
+    pub fn command(&self) -> NpcScriptCommand {
+        match self {
+            ScriptAction::GiveItem(item) => NpcScriptCommand::GiveItem(item.item_id, item.quantity as i32),
+            ScriptAction::TakeItem(item) => NpcScriptCommand::TakeItem(item.item_id, item.quantity as i32),
+            ScriptAction::GiveMeso(amount) => NpcScriptCommand::GiveMeso(*amount),
+            ScriptAction::GiveExp(amount) => NpcScriptCommand::GiveExp(*amount),
+            ScriptAction::Warp(target) => NpcScriptCommand::Warp(target.map_id, target.portal.clone()),
+            ScriptAction::StartQuest(quest_id) => NpcScriptCommand::StartQuest(*quest_id),
+            ScriptAction::CompleteQuest(quest_id) => NpcScriptCommand::CompleteQuest(*quest_id),
+            ScriptAction::ChangeJob(job) => NpcScriptCommand::ChangeJob(*job),
+            ScriptAction::OpenShop(npc_id) => NpcScriptCommand::OpenShop(*npc_id),
+            ScriptAction::OpenStorage => NpcScriptCommand::OpenStorage,
+        }
+    }
+}
+
+/// A script read from a file, with the file's modification time so it can be reloaded
+struct LoadedScript {
+    script: Arc<DialogScript>,
+    #[cfg(not(target_arch = "wasm32"))]
+    modified: Option<std::time::SystemTime>,
+}
+
+static SCRIPTS: Mutex<Option<HashMap<i32, LoadedScript>>> = Mutex::new(None);
+
+/// Modification time of script files that couldn't be loaded, so a broken file is only read
+/// (and warned about) again once it changes
+#[cfg(not(target_arch = "wasm32"))]
+static BROKEN_SCRIPTS: Mutex<Option<HashMap<i32, std::time::SystemTime>>> = Mutex::new(None);
+
+fn parse(name: &str, text: &str) -> Option<DialogScript> {
+    match serde_json::from_str(text) {
+        Ok(script) => Some(script),
+        Err(e) => {
+            warn!("Invalid NPC script {}: {}", name, e);
+            None
+        }
+    }
+}
+
+/// Script to run for an NPC: its own script file, else its bundled script, else the default one.
+/// On native the file is read from `<script_dir>/npc` and read again whenever it changes, so
+/// scripts can be edited while the game runs.
+pub fn script_for(npc_id: i32) -> Arc<DialogScript> {
+    #[cfg(not(target_arch = "wasm32"))]
+    reload_if_changed(npc_id);
+
+    if let Some(loaded) = SCRIPTS.lock().unwrap().as_ref().and_then(|scripts| scripts.get(&npc_id)) {
+        return loaded.script.clone();
+    }
+    let bundled = BUNDLED_SCRIPTS.iter().find(|(id, _)| *id == npc_id).map(|(_, text)| *text);
+    let script = bundled
+        .and_then(|text| parse(&npc_id.to_string(), text))
+        .unwrap_or_else(|| parse("default", DEFAULT_SCRIPT).expect("bundled default NPC script is valid"));
+    Arc::new(script)
+}
+
+/// True if an NPC has a script of its own (a file or a bundled one) rather than the default one
+pub fn has_script(npc_id: i32) -> bool {
+    #[cfg(not(target_arch = "wasm32"))]
+    reload_if_changed(npc_id);
+
+    SCRIPTS.lock().unwrap().as_ref().is_some_and(|scripts| scripts.contains_key(&npc_id))
+        || BUNDLED_SCRIPTS.iter().any(|(id, _)| *id == npc_id)
+}
+
+/// Read an NPC's script file again if it was added, changed or removed since the last read
+#[cfg(not(target_arch = "wasm32"))]
+fn reload_if_changed(npc_id: i32) {
+    let path = std::path::Path::new(&config().script_dir).join("npc").join(format!("{}.json", npc_id));
+    let modified = std::fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+
+    let mut guard = SCRIPTS.lock().unwrap();
+    let scripts = guard.get_or_insert_with(HashMap::new);
+    let cached = scripts.get(&npc_id).map(|loaded| loaded.modified);
+    if cached == Some(modified) || (cached.is_none() && modified.is_none()) {
+        return;
+    }
+    let mut broken_guard = BROKEN_SCRIPTS.lock().unwrap();
+    let broken = broken_guard.get_or_insert_with(HashMap::new);
+    let Some(modified_time) = modified else {
+        info!("NPC script {} was removed", path.display());
+        scripts.remove(&npc_id);
+        broken.remove(&npc_id);
+        return;
+    };
+    if broken.get(&npc_id) == Some(&modified_time) {
+        return;
+    }
+
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) => {
+            warn!("Failed to read {}: {}", path.display(), e);
+            broken.insert(npc_id, modified_time);
+            return;
+        }
+    };
+    // A broken edit keeps the old version running until the file changes again
+    let script = match parse(&path.display().to_string(), &text) {
+        Some(script) => {
+            broken.remove(&npc_id);
+            Arc::new(script)
+        }
+        None => match scripts.get(&npc_id) {
+            Some(loaded) => loaded.script.clone(),
+            None => {
+                broken.insert(npc_id, modified_time);
+                return;
+            }
+        },
+    };
+    info!("Loaded NPC script {}", path.display());
+    scripts.insert(npc_id, LoadedScript { script, modified });
+}
+
+/// Fetch the script files of the NPCs on a map from the asset source (`scripts/npc/<id>.json`).
+/// Scripts are read from disk when they are used on native, so this only matters on web.
+pub async fn preload_scripts(npc_ids: &[i32]) {
+    #[cfg(target_arch = "wasm32")]
+    for &npc_id in npc_ids {
+        if SCRIPTS.lock().unwrap().as_ref().is_some_and(|scripts| scripts.contains_key(&npc_id)) {
+            continue;
+        }
+        let path = format!("scripts/npc/{}.json", npc_id);
+        let Ok(bytes) = AssetManager::fetch_and_cache(&path).await else { continue };
+        let Some(script) = parse(&path, &String::from_utf8_lossy(&bytes)) else { continue };
+        SCRIPTS
+            .lock()
+            .unwrap()
+            .get_or_insert_with(HashMap::new)
+            .insert(npc_id, LoadedScript { script: Arc::new(script) });
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = npc_ids;
+}
@@ -0,0 +1,169 @@
+use macroquad::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::assets::AssetManager;
+
+/// Drop table bundled with the game
+const DEFAULT_DROP_TABLE: &str = include_str!("../data/drops.json");
+
+/// A `drops.json` next to the game data replaces the bundled table
+const DROP_TABLE_PATH: &str = "drops.json";
+
+/// Something a mob leaves behind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropContent {
+    Item { item_id: i32, quantity: u32 },
+    Meso(u32),
+}
+
+fn one() -> u32 {
+    1
+}
+
+/// An item with a chance to drop
+#[derive(Debug, Clone, Deserialize)]
+struct ItemDrop {
+    item_id: i32,
+    /// 0.0 - 1.0
+    chance: f32,
+    #[serde(default = "one")]
+    min: u32,
+    #[serde(default = "one")]
+    max: u32,
+}
+
+/// Meso every mob can drop, scaled by the mob's level
+#[derive(Debug, Clone, Deserialize)]
+struct LevelMeso {
+    chance: f32,
+    min_per_level: u32,
+    max_per_level: u32,
+}
+
+/// A mob's (or reactor's) own meso drop, used instead of the level-based one
+#[derive(Debug, Clone, Deserialize)]
+struct MesoDrop {
+    chance: f32,
+    min: u32,
+    max: u32,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct MobDrops {
+    #[serde(default)]
+    items: Vec<ItemDrop>,
+    meso: Option<MesoDrop>,
+}
+
+/// Mob drops, from JSON: level-based meso and `global` items for every mob, and each mob's
+/// own items by mob id. Broken reactors drop only their own items, by reactor id.
+#[derive(Debug, Clone, Deserialize)]
+struct DropTable {
+    meso: Option<LevelMeso>,
+    #[serde(default)]
+    global: Vec<ItemDrop>,
+    #[serde(default)]
+    mobs: HashMap<String, MobDrops>,
+    #[serde(default)]
+    reactors: HashMap<String, MobDrops>,
+}
+
+impl DropTable {
+    /// Look up a mob's drops; ids match with or without leading zeros
+    fn mob(&self, mob_id: i32) -> Option<&MobDrops> {
+        find_drops(&self.mobs, mob_id)
+    }
+
+    fn reactor(&self, reactor_id: i32) -> Option<&MobDrops> {
+        find_drops(&self.reactors, reactor_id)
+    }
+}
+
+fn find_drops(drops: &HashMap<String, MobDrops>, wanted: i32) -> Option<&MobDrops> {
+    drops.iter().find(|(id, _)| id.parse::<i32>().ok() == Some(wanted)).map(|(_, drops)| drops)
+}
+
+static DROP_TABLE: Mutex<Option<DropTable>> = Mutex::new(None);
+
+/// Load the drop table: `drops.json` from the asset source if there is one, the bundled table otherwise
+pub async fn load_drop_table() {
+    if DROP_TABLE.lock().unwrap().is_some() {
+        return;
+    }
+
+    let custom = match AssetManager::fetch_and_cache(DROP_TABLE_PATH).await {
+        Ok(bytes) => match serde_json::from_slice::<DropTable>(&bytes) {
+            Ok(table) => Some(table),
+            Err(e) => {
+                warn!("Ignoring {}: {}", DROP_TABLE_PATH, e);
+                None
+            }
+        },
+        Err(_) => None,
+    };
+    let table = match custom {
+        Some(table) => {
+            info!("Loaded drop table from {} ({} mobs)", DROP_TABLE_PATH, table.mobs.len());
+            table
+        }
+        None => serde_json::from_str(DEFAULT_DROP_TABLE).expect("bundled drop table is valid JSON"),
+    };
+    *DROP_TABLE.lock().unwrap() = Some(table);
+}
+
+/// Roll what a killed mob drops
+pub fn roll_drops(mob_id: i32, mob_level: i32) -> Vec<DropContent> {
+    let guard = DROP_TABLE.lock().unwrap();
+    let Some(table) = guard.as_ref() else { return Vec::new() };
+    let mob = table.mob(mob_id);
+
+    let mut drops = Vec::new();
+    let meso = match (mob.and_then(|m| m.meso.as_ref()), &table.meso) {
+        (Some(meso), _) => roll(meso.chance).then(|| rand::gen_range(meso.min, meso.max.max(meso.min) + 1)),
+        (None, Some(meso)) => roll(meso.chance).then(|| {
+            let level = mob_level.max(1) as u32;
+            rand::gen_range(meso.min_per_level * level, meso.max_per_level.max(meso.min_per_level) * level + 1)
+        }),
+        (None, None) => None,
+    };
+    if let Some(amount) = meso.filter(|&amount| amount > 0) {
+        drops.push(DropContent::Meso(amount));
+    }
+
+    let items = mob.map_or(&[][..], |m| &m.items).iter().chain(&table.global);
+    roll_items(items, &mut drops);
+    drops
+}
+
+/// Roll what a broken reactor drops
+pub fn roll_reactor_drops(reactor_id: i32) -> Vec<DropContent> {
+    let guard = DROP_TABLE.lock().unwrap();
+    let Some(reactor) = guard.as_ref().and_then(|table| table.reactor(reactor_id)) else {
+        return Vec::new();
+    };
+
+    let mut drops = Vec::new();
+    let meso = reactor.meso.as_ref().and_then(|meso| {
+        roll(meso.chance).then(|| rand::gen_range(meso.min, meso.max.max(meso.min) + 1))
+    });
+    if let Some(amount) = meso.filter(|&amount| amount > 0) {
+        drops.push(DropContent::Meso(amount));
+    }
+    roll_items(&reactor.items, &mut drops);
+    drops
+}
+
+fn roll_items<'a>(items: impl IntoIterator<Item = &'a ItemDrop>, drops: &mut Vec<DropContent>) {
+    for item in items {
+        if roll(item.chance) {
+            let quantity = rand::gen_range(item.min, item.max.max(item.min) + 1).max(1);
+            drops.push(DropContent::Item { item_id: item.item_id, quantity });
+        }
+    }
+}
+
+fn roll(chance: f32) -> bool {
+    rand::gen_range(0.0, 1.0) < chance
+}
@@ -0,0 +1,134 @@
+use macroquad::prelude::*;
+use std::collections::HashMap;
+use crate::assets::{AssetManager, FrameData};
+
+const BASIC_EFF_PATH: &str = "Effect/BasicEff.img";
+
+/// BasicEff animations loaded up front
+const BASIC_EFFECTS: [&str; 2] = ["LevelUp", "JobChanged"];
+
+/// Time each effect frame is shown (seconds)
+const EFFECT_FRAME_TIME: f32 = 0.1;
+
+/// Where an effect is drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EffectAnchor {
+    /// Follows the player's feet
+    Player,
+    /// Fixed point in world coordinates
+    World(f32, f32),
+}
+
+/// An effect animation that is playing
+struct ActiveEffect {
+    name: String,
+    anchor: EffectAnchor,
+    elapsed: f32,
+}
+
+/// One-shot animations (level up, skill effects and the like) drawn over the map
+pub struct EffectSystem {
+    animations: HashMap<String, Vec<FrameData>>,
+    active: Vec<ActiveEffect>,
+    player_x: f32,
+    player_y: f32,
+}
+
+impl EffectSystem {
+    pub fn new() -> Self {
+        Self {
+            animations: HashMap::new(),
+            active: Vec::new(),
+            player_x: 0.0,
+            player_y: 0.0,
+        }
+    }
+
+    /// Load the BasicEff animations
+    pub async fn load_assets(&mut self) {
+        info!("Loading effect assets...");
+        for name in BASIC_EFFECTS {
+            let frames = AssetManager::load_animation_frames_with_origins(BASIC_EFF_PATH, name).await;
+            if frames.is_empty() {
+                warn!("Effect {} has no frames", name);
+                continue;
+            }
+            self.animations.insert(name.to_string(), frames);
+        }
+        info!("Effect assets loaded: {} animations", self.animations.len());
+    }
+
+    /// Load an animation from any WZ image under `key` (skill effects and the like).
+    /// Does nothing if the key is already loaded.
+    pub async fn load_animation(&mut self, key: &str, wz_path: &str, base_path: &str) {
+        if self.animations.contains_key(key) {
+            return;
+        }
+        let frames = AssetManager::load_animation_frames_with_origins(wz_path, base_path).await;
+        if !frames.is_empty() {
+            self.animations.insert(key.to_string(), frames);
+        }
+    }
+
+    /// True if an animation is loaded under `name`
+    pub fn has(&self, name: &str) -> bool {
+        self.animations.contains_key(name)
+    }
+
+    /// Play an effect on the player
+    pub fn play_on_player(&mut self, name: &str) {
+        self.play(name, EffectAnchor::Player);
+    }
+
+    /// Play an effect at a point in world coordinates
+    pub fn play_at(&mut self, name: &str, x: f32, y: f32) {
+        self.play(name, EffectAnchor::World(x, y));
+    }
+
+    fn play(&mut self, name: &str, anchor: EffectAnchor) {
+        if !self.animations.contains_key(name) {
+            warn!("Effect {} is not loaded", name);
+            return;
+        }
+        self.active.push(ActiveEffect { name: name.to_string(), anchor, elapsed: 0.0 });
+    }
+
+    /// Remove all playing effects (when changing maps)
+    pub fn clear(&mut self) {
+        self.active.clear();
+    }
+
+    pub fn update(&mut self, dt: f32, player_x: f32, player_y: f32) {
+        self.player_x = player_x;
+        self.player_y = player_y;
+
+        let animations = &self.animations;
+        for effect in &mut self.active {
+            effect.elapsed += dt;
+        }
+        self.active.retain(|effect| {
+            let frame_count = animations.get(&effect.name).map_or(0, Vec::len);
+            effect.elapsed < frame_count as f32 * EFFECT_FRAME_TIME
+        });
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32) {
+        for effect in &self.active {
+            let Some(frames) = self.animations.get(&effect.name) else { continue };
+            let index = ((effect.elapsed / EFFECT_FRAME_TIME) as usize).min(frames.len() - 1);
+            let frame = &frames[index];
+
+            let (x, y) = match effect.anchor {
+                EffectAnchor::Player => (self.player_x, self.player_y),
+                EffectAnchor::World(x, y) => (x, y),
+            };
+            draw_texture(&frame.texture, x - frame.origin.x - camera_x, y - frame.origin.y - camera_y, WHITE);
+        }
+    }
+}
+
+impl Default for EffectSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
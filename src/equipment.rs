@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::character::CharacterData;
+use crate::item_info::{self, EquipStats};
+use crate::job::Job;
+
+/// Equipment slots shown in the equip window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum EquipSlot {
+    Cap,
+    FaceAccessory,
+    EyeAccessory,
+    Earrings,
+    Top,
+    Bottom,
+    Shoes,
+    Gloves,
+    Cape,
+    Shield,
+    Weapon,
+    Ring,
+    Pendant,
+}
+
+impl EquipSlot {
+    pub const ALL: [EquipSlot; 13] = [
+        EquipSlot::Cap,
+        EquipSlot::FaceAccessory,
+        EquipSlot::EyeAccessory,
+        EquipSlot::Earrings,
+        EquipSlot::Top,
+        EquipSlot::Bottom,
+        EquipSlot::Shoes,
+        EquipSlot::Gloves,
+        EquipSlot::Cape,
+        EquipSlot::Shield,
+        EquipSlot::Weapon,
+        EquipSlot::Ring,
+        EquipSlot::Pendant,
+    ];
+
+    /// Slot an equip goes into, from its 4-digit category (overalls use the Top slot)
+    pub fn from_item_id(item_id: i32) -> Option<Self> {
+        if !item_info::is_equip(item_id) {
+            return None;
+        }
+        match item_id / 10000 {
+            100 => Some(EquipSlot::Cap),
+            101 => Some(EquipSlot::FaceAccessory),
+            102 => Some(EquipSlot::EyeAccessory),
+            103 => Some(EquipSlot::Earrings),
+            104 | 105 => Some(EquipSlot::Top),
+            106 => Some(EquipSlot::Bottom),
+            107 => Some(EquipSlot::Shoes),
+            108 => Some(EquipSlot::Gloves),
+            109 => Some(EquipSlot::Shield),
+            110 => Some(EquipSlot::Cape),
+            111 => Some(EquipSlot::Ring),
+            112 => Some(EquipSlot::Pendant),
+            130..=170 => Some(EquipSlot::Weapon),
+            _ => None,
+        }
+    }
+}
+
+/// True for overalls (105xxxx), which cover both the Top and Bottom slots
+pub fn is_overall(item_id: i32) -> bool {
+    item_id / 10000 == 105
+}
+
+/// Job class bit used by `reqJob` for a job (0 for beginners)
+fn job_class_mask(job: Job) -> i32 {
+    match job.class() {
+        1 => 1,
+        2 => 2,
+        3 => 4,
+        4 => 8,
+        5 => 16,
+        _ => 0,
+    }
+}
+
+/// Check an equip's level, stat and job requirements against a character.
+/// The item's info must have been loaded with `item_info::load_item_info`.
+pub fn check_requirements(item_id: i32, character: &CharacterData) -> Result<(), String> {
+    let stats = item_info::equip_stats(item_id).ok_or_else(|| format!("Item {} is not equipment", item_id))?;
+
+    if (character.level as i32) < stats.req_level {
+        return Err(format!("Requires level {}", stats.req_level));
+    }
+    let checks = [
+        ("STR", character.str, stats.req_str),
+        ("DEX", character.dex, stats.req_dex),
+        ("INT", character.int, stats.req_int),
+        ("LUK", character.luk, stats.req_luk),
+    ];
+    for (name, have, need) in checks {
+        if (have as i32) < need {
+            return Err(format!("Requires {} {}", need, name));
+        }
+    }
+    if stats.req_job != 0 && stats.req_job & job_class_mask(character.job) == 0 {
+        return Err("Your job cannot use this item".to_string());
+    }
+
+    Ok(())
+}
+
+/// Items a character is wearing, one per slot
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Equipment {
+    slots: BTreeMap<EquipSlot, i32>,
+}
+
+impl Equipment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, slot: EquipSlot) -> Option<i32> {
+        self.slots.get(&slot).copied()
+    }
+
+    /// Ids of every equipped item
+    pub fn item_ids(&self) -> Vec<i32> {
+        self.slots.values().copied().collect()
+    }
+
+    /// Put on an item, returning the items that were taken off to make room
+    pub fn equip(&mut self, item_id: i32) -> Result<Vec<i32>, String> {
+        let slot = EquipSlot::from_item_id(item_id).ok_or_else(|| format!("Item {} can't be equipped", item_id))?;
+
+        let mut removed: Vec<i32> = self.slots.insert(slot, item_id).into_iter().collect();
+
+        // An overall replaces the bottom, and a bottom replaces an overall
+        if is_overall(item_id) {
+            removed.extend(self.slots.remove(&EquipSlot::Bottom));
+        } else if slot == EquipSlot::Bottom {
+            if let Some(top) = self.get(EquipSlot::Top).filter(|&id| is_overall(id)) {
+                self.slots.remove(&EquipSlot::Top);
+                removed.push(top);
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Take off the item in a slot
+    pub fn unequip(&mut self, slot: EquipSlot) -> Option<i32> {
+        self.slots.remove(&slot)
+    }
+
+    /// Combined stat bonuses of everything equipped (items whose info isn't loaded count as zero)
+    pub fn bonus_stats(&self) -> EquipStats {
+        let mut total = EquipStats::default();
+        for stats in self.slots.values().filter_map(|&id| item_info::equip_stats(id)) {
+            total.add_bonuses(&stats);
+        }
+        total
+    }
+
+    /// Stance set of the equipped weapon (1 = one-handed, 2 = two-handed)
+    pub fn weapon_stance(&self) -> i32 {
+        self.get(EquipSlot::Weapon)
+            .and_then(item_info::equip_stats)
+            .map_or(1, |stats| if stats.stand == 2 { 2 } else { 1 })
+    }
+}
@@ -1,5 +1,6 @@
 /// Centralized debug and configuration flags for the game
-/// Modify these values to control game behavior for debugging and testing
+/// These are the defaults for the runtime GameConfig (see config.rs); override them with
+/// config.json, command-line arguments or URL query parameters instead of rebuilding
 
 /// Skip logo animation and go directly to login screen
 pub const SKIP_LOGOS: bool = true;
@@ -22,6 +23,19 @@ pub const SHOW_FPS: bool = true;
 /// Show debug map loader UI (press M to toggle)
 pub const SHOW_MAP_LOADER: bool = true;
 
+/// Where game data is read from: "http" (HTTP + IndexedDB cache), "dir" (extracted .img files)
+/// or "wz" (packed .wz archives)
+pub const ASSET_SOURCE: &str = "http";
+
+/// Base URL for the "http" source, or the data directory for "dir"/"wz"
+pub const ASSET_ROOT: &str = DEFAULT_ASSET_URL;
+
+/// Public bucket the game data is served from by default
+pub const DEFAULT_ASSET_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial";
+
+/// Memory budget for parsed WZ images kept in the shared cache (see wz_cache.rs)
+pub const WZ_CACHE_BUDGET_MB: usize = 256;
+
 /// Enable verbose logging for asset loading
 pub const VERBOSE_ASSET_LOADING: bool = false;
 
@@ -31,6 +45,13 @@ pub const VERBOSE_CHARACTER_IO: bool = false;
 /// Use in-memory storage instead of persistent storage (for testing)
 pub const USE_MEMORY_STORAGE: bool = false;
 
+/// Directory character saves are written to on native builds
+pub const SAVE_DIRECTORY: &str = "saves";
+
+/// Directory scripts are read from on native builds: `npc/<npc id>.json` dialog trees and
+/// `.rhai` scripts for NPCs, portals and map entry
+pub const SCRIPT_DIRECTORY: &str = "data/scripts";
+
 /// Auto-create a test character if no characters exist
 pub const AUTO_CREATE_TEST_CHARACTER: bool = false;
 
@@ -76,14 +97,17 @@ pub const START_WITH_MAX_STATS: bool = false;
 /// Enable verbose map loading logs
 pub const VERBOSE_MAP_LOADING: bool = false;
 
+use crate::config::config;
+
 impl DebugFlags {
     /// Check if we should skip directly to a specific state
     pub fn get_initial_game_state() -> InitialGameState {
-        if SKIP_TO_GAME {
+        let config = config();
+        if config.skip_to_game {
             InitialGameState::InGame
-        } else if SKIP_LOGIN {
+        } else if config.skip_login {
             InitialGameState::CharacterSelection
-        } else if SKIP_LOGOS {
+        } else if config.skip_logos {
             InitialGameState::Login
         } else {
             InitialGameState::Logos
@@ -92,19 +116,20 @@ impl DebugFlags {
 
     /// Check if debug UI should be shown
     pub fn should_show_debug_ui() -> bool {
-        SHOW_DEBUG_UI
+        config().show_debug_ui
     }
 
     /// Get player speed with debug multiplier applied
     pub fn get_player_speed(base_speed: f32) -> f32 {
-        base_speed * PLAYER_SPEED_MULTIPLIER
+        base_speed * config().player_speed_multiplier
     }
 
     /// Check if verbose logging is enabled for a specific category
     pub fn is_verbose_logging_enabled(category: LogCategory) -> bool {
+        let config = config();
         match category {
-            LogCategory::AssetLoading => VERBOSE_ASSET_LOADING,
-            LogCategory::CharacterIO => VERBOSE_CHARACTER_IO,
+            LogCategory::AssetLoading => config.verbose_asset_loading,
+            LogCategory::CharacterIO => config.verbose_character_io,
         }
     }
 }
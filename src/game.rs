@@ -6,16 +6,17 @@ use crate::character_selection;
 use crate::character_creation;
 use crate::game_world::GameplayState;
 use crate::character::CharacterData;
-use crate::flags::{self, DebugFlags, InitialGameState};
+use crate::config::{self, config};
+use crate::storage;
+use crate::flags::{DebugFlags, InitialGameState};
 
-const LOGO_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/00/UI/Logo.img";
-const LOGO_CACHE_NAME: &str = "/00/UI/Logo.img";
+const LOGO_PATH: &str = "UI/Logo.img";
 
 /// Load logo animation frames from WZ file
 pub async fn load_logo_frames() -> (Vec<FrameData>, Vec<FrameData>) {
     info!("Loading logo animations...");
-    let nexon = AssetManager::load_animation_frames_with_origins(LOGO_URL, LOGO_CACHE_NAME, "Nexon");
-    let wizet = AssetManager::load_animation_frames_with_origins(LOGO_URL, LOGO_CACHE_NAME, "Wizet");
+    let nexon = AssetManager::load_animation_frames_with_origins(LOGO_PATH, "Nexon");
+    let wizet = AssetManager::load_animation_frames_with_origins(LOGO_PATH, "Wizet");
     // Note: While these run sequentially here, the WZ file will be cached after the first load,
     // making the second load much faster. For true parallel loading, you'd need the futures crate.
     let (nexon_frames, wizet_frames) = (nexon.await, wizet.await);
@@ -72,6 +73,10 @@ enum GameState {
 
 /// Initialize and run the game
 pub async fn run() {
+    // Load the runtime config before anything reads it
+    config::init().await;
+    storage::init().await;
+
     // Determine initial game state based on flags
     let initial_state = DebugFlags::get_initial_game_state();
     let mut game_state = match initial_state {
@@ -80,15 +85,15 @@ pub async fn run() {
             GameState::Logos
         }
         InitialGameState::Login => {
-            info!("Skipping logos (SKIP_LOGOS flag enabled)");
+            info!("Skipping logos (skip_logos enabled)");
             GameState::Login
         }
         InitialGameState::CharacterSelection => {
-            info!("Skipping to character selection (SKIP_LOGIN flag enabled)");
+            info!("Skipping to character selection (skip_login enabled)");
             GameState::CharacterSelection
         }
         InitialGameState::InGame => {
-            info!("Skipping to game (SKIP_TO_GAME flag enabled)");
+            info!("Skipping to game (skip_to_game enabled)");
             GameState::InGame
         }
     };
@@ -119,7 +124,7 @@ pub async fn run() {
 
     // Handle SKIP_TO_GAME flag - create test character or load first character
     if game_state == GameState::InGame {
-        let character = if flags::AUTO_CREATE_TEST_CHARACTER {
+        let character = if config().auto_create_test_character {
             info!("Auto-creating test character");
             let test_char = CharacterData::create_test_character();
             let _ = test_char.save();
@@ -140,7 +145,7 @@ pub async fn run() {
     }
 
     // Handle AUTO_CREATE_TEST_CHARACTER for character selection
-    if flags::AUTO_CREATE_TEST_CHARACTER && game_state == GameState::CharacterSelection {
+    if config().auto_create_test_character && game_state == GameState::CharacterSelection {
         let characters = CharacterData::load_all();
         if characters.is_empty() {
             info!("Auto-creating test character for character selection");
@@ -243,6 +248,7 @@ pub async fn run() {
 
                 // Run character creation screen
                 char_create_state.update(dt);
+                char_create_state.update_preview().await;
                 char_create_state.draw();
 
                 // Check if should transition back to character selection
@@ -276,12 +282,18 @@ pub async fn run() {
                 
                 // Handle BGM playback (async)
                 state.handle_bgm().await;
+
+                // Add items given by NPC scripts (async)
+                state.handle_pending_items().await;
+
+                // Load skill books and effects for newly learned skills (async)
+                state.handle_pending_skills().await;
                 
                 state.draw();
 
                 // Check if we should load a new map
                 if let Some(map_id) = state.should_load_new_map() {
-                    info!("Loading new map from debug input: {}", map_id);
+                    info!("Loading new map: {}", map_id);
                     state.load_map_from_input().await;
                 }
 
@@ -1,12 +1,9 @@
 use macroquad::prelude::*;
-use crate::assets::AssetManager;
-use std::sync::Arc;
-use wz_reader::version::guess_iv_from_wz_img;
-use wz_reader::{WzImage, WzNode, WzNodeArc, WzReader, WzNodeCast};
+use crate::wz_cache::load_wz_image;
+use wz_reader::{WzNodeArc, WzNodeCast};
 
 // Use StatusBar2.img for menu assets (Menu section)
-const STATUSBAR_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/UI/StatusBar2.img";
-const STATUSBAR_CACHE: &str = "/01/UI/StatusBar2.img";
+const STATUSBAR_PATH: &str = "UI/StatusBar2.img";
 
 /// Texture with origin point
 struct TextureWithOrigin {
@@ -216,20 +213,8 @@ impl GameMenu {
     }
 
     async fn load_from_wz() -> Result<GameMenuData, String> {
-        let bytes = AssetManager::fetch_and_cache(STATUSBAR_URL, STATUSBAR_CACHE).await
-            .map_err(|e| format!("Failed to fetch StatusBar2.img: {}", e))?;
-
-        let wz_iv = guess_iv_from_wz_img(&bytes)
-            .ok_or_else(|| "Unable to guess version from StatusBar2.img".to_string())?;
-
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        let cache_name_ref: wz_reader::WzNodeName = STATUSBAR_CACHE.to_string().into();
-        let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-        let root_node: WzNodeArc = WzNode::new(&STATUSBAR_CACHE.into(), wz_image, None).into();
-
-        root_node.write().unwrap().parse(&root_node)
-            .map_err(|e| format!("Failed to parse StatusBar2.img: {:?}", e))?;
+        let root_node = load_wz_image(STATUSBAR_PATH).await
+            .map_err(|e| format!("Failed to load StatusBar2.img: {}", e))?;
 
         let mut data = GameMenuData::default();
 
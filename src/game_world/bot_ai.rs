@@ -1,10 +1,22 @@
 use macroquad::prelude::*;
-use crate::map::{MapData, Life, Ladder};
+use crate::map::{MapData, Life, Ladder, MobStateType};
+use crate::status_effect::StatusEffects;
+
+/// Time each frame of a mob's hit/die animation is shown (seconds)
+pub const MOB_FRAME_TIME: f32 = 0.1;
+
+/// How long a mob is pushed back after being hit (seconds)
+const KNOCKBACK_TIME: f32 = 0.4;
+const KNOCKBACK_SPEED: f32 = 120.0;
+
+/// Respawn delay for mobs with mobTime 0 (seconds)
+const DEFAULT_RESPAWN_TIME: f32 = 7.0;
 
 /// Bot AI state for a single mob
 #[derive(Debug, Clone)]
 pub struct BotState {
     pub life_id: String,
+    pub life_index: usize, // Index into map.life (several mobs share a life_id)
     pub x: f32,
     pub y: f32,
     pub vx: f32,  // Horizontal velocity
@@ -16,6 +28,15 @@ pub struct BotState {
     pub move_direction: i32, // -1 = left, 0 = idle, 1 = right
     pub climbing: bool,
     pub current_ladder: Option<i32>, // Ladder ID if climbing
+    // Combat
+    pub hp: i32,
+    pub max_hp: i32,
+    pub state: MobStateType,
+    pub state_timer: f32,
+    pub spawn_x: f32,
+    pub spawn_y: f32,
+    /// Debuffs from the player's skills (stun, slow, poison)
+    pub effects: StatusEffects,
 }
 
 /// Fake player state for simulating other players
@@ -62,7 +83,7 @@ impl FakePlayer {
 }
 
 impl BotState {
-    pub fn new(life: &Life) -> Self {
+    pub fn new(life: &Life, life_index: usize) -> Self {
         // Adjust spawn position to account for origin offset
         // The map coordinate is the reference point, but we need to position
         // the mob so its feet are on the platform, not its anchor point
@@ -70,6 +91,7 @@ impl BotState {
 
         Self {
             life_id: life.id.clone(),
+            life_index,
             x: life.x as f32,
             y: adjusted_y,
             vx: 0.0,
@@ -81,8 +103,58 @@ impl BotState {
             move_direction: 0,
             climbing: false,
             current_ladder: None,
+            hp: life.stats.max_hp.max(1),
+            max_hp: life.stats.max_hp.max(1),
+            state: MobStateType::Idle,
+            state_timer: 0.0,
+            spawn_x: life.x as f32,
+            spawn_y: adjusted_y,
+            effects: StatusEffects::new(),
         }
     }
+
+    /// False while the mob is dying or waiting to respawn
+    pub fn is_alive(&self) -> bool {
+        !matches!(self.state, MobStateType::Dying | MobStateType::Dead)
+    }
+
+    /// Apply damage from an attacker at `from_x`. Returns true if this killed the mob.
+    pub fn take_damage(&mut self, damage: i32, from_x: f32) -> bool {
+        if !self.is_alive() {
+            return false;
+        }
+
+        self.hp = (self.hp - damage).max(0);
+        self.state_timer = 0.0;
+        self.climbing = false;
+        self.current_ladder = None;
+        if self.hp == 0 {
+            self.state = MobStateType::Dying;
+            self.vx = 0.0;
+            self.effects.clear();
+            return true;
+        }
+
+        // Turn towards the attacker and get pushed away from it
+        self.state = MobStateType::KnockedBack;
+        self.facing_right = from_x > self.x;
+        self.vx = if from_x > self.x { -KNOCKBACK_SPEED } else { KNOCKBACK_SPEED };
+        false
+    }
+
+    /// Bring a dead mob back at its spawn point with full HP
+    fn respawn(&mut self) {
+        self.x = self.spawn_x;
+        self.y = self.spawn_y;
+        self.vx = 0.0;
+        self.vy = 0.0;
+        self.hp = self.max_hp;
+        self.state = MobStateType::Idle;
+        self.state_timer = 0.0;
+        self.on_ground = true;
+        self.move_direction = 0;
+        self.move_timer = rand::gen_range(1.0, 3.0);
+    }
 }
 
 /// Bot AI manager that updates all mob AI
@@ -109,10 +181,10 @@ impl BotAI {
         self.bot_states.clear();
         self.fake_players.clear();
 
-        for life in &map.life {
+        for (index, life) in map.life.iter().enumerate() {
             // Only create AI for mobs (type "m"), not NPCs (type "n")
             if life.life_type == "m" {
-                let mut bot = BotState::new(life);
+                let mut bot = BotState::new(life, index);
 
                 // Find foothold for this mob using the specified foothold ID
                 if life.foothold != 0 {
@@ -145,6 +217,8 @@ impl BotAI {
                     bot.on_ground = true;
                 }
 
+                bot.spawn_x = bot.x;
+                bot.spawn_y = bot.y;
                 self.bot_states.push(bot);
             }
         }
@@ -266,28 +340,74 @@ impl BotAI {
     /// Update a single bot's AI
     fn update_bot(bot: &mut BotState, dt: f32, map: &MapData) {
         // Find the life data for this bot
-        let life = match map.life.iter().find(|l| l.id == bot.life_id) {
+        let life = match map.life.get(bot.life_index) {
             Some(l) => l,
             None => return,
         };
 
+        // Hit reactions, death and respawning
+        bot.state_timer += dt;
+        match bot.state {
+            MobStateType::Dying => {
+                let die_time = life.die_textures.len().max(1) as f32 * MOB_FRAME_TIME;
+                if bot.state_timer >= die_time {
+                    bot.state = MobStateType::Dead;
+                    bot.state_timer = 0.0;
+                }
+                return;
+            }
+            MobStateType::Dead => {
+                // mobTime < 0 spawns once, 0 uses the normal regen time, > 0 is the delay in seconds
+                if life.mob_time >= 0 {
+                    let delay = if life.mob_time > 0 { life.mob_time as f32 } else { DEFAULT_RESPAWN_TIME };
+                    if bot.state_timer >= delay {
+                        bot.respawn();
+                    }
+                }
+                return;
+            }
+            MobStateType::KnockedBack if bot.state_timer >= KNOCKBACK_TIME => {
+                bot.state = MobStateType::Idle;
+                bot.state_timer = 0.0;
+                bot.vx = 0.0;
+                bot.move_timer = 0.0;
+            }
+            _ => {}
+        }
+        let knocked_back = bot.state == MobStateType::KnockedBack;
+
+        // Debuffs: poison can't finish a mob off, stunned mobs freeze in place
+        let tick = bot.effects.update(dt);
+        if tick.damage > 0 {
+            bot.hp = (bot.hp - tick.damage).max(1);
+        }
+        let stunned = bot.effects.is_stunned();
+
         // If climbing, handle ladder logic
         if bot.climbing {
-            Self::update_climbing(bot, dt, map, life);
+            if !stunned {
+                Self::update_climbing(bot, dt, map, life);
+            }
             return;
         }
 
-        // Update movement timer
-        bot.move_timer -= dt;
+        if stunned && !knocked_back {
+            bot.vx = 0.0;
+        } else if !knocked_back {
+            // Update movement timer
+            bot.move_timer -= dt;
 
-        if bot.move_timer <= 0.0 {
-            // Choose a new action
-            Self::choose_new_action(bot, life);
-        }
+            if bot.move_timer <= 0.0 {
+                // Choose a new action
+                Self::choose_new_action(bot, life);
+                bot.state = if bot.move_direction == 0 { MobStateType::Idle } else { MobStateType::Patrol };
+            }
 
-        // Apply movement based on current direction
-        let base_speed = 50.0; // Slower than player for more natural mob movement
-        bot.vx = (bot.move_direction as f32) * base_speed;
+            // Apply movement based on current direction
+            // Slower than player for more natural mob movement, scaled by the mob's speed
+            let base_speed = life.stats.move_speed(50.0) * bot.effects.speed_multiplier();
+            bot.vx = (bot.move_direction as f32) * base_speed;
+        }
 
         // Apply horizontal movement
         bot.x += bot.vx * dt;
@@ -309,7 +429,7 @@ impl BotAI {
         }
 
         // Check if near a ladder and randomly decide to climb
-        if bot.on_ground && rand::gen_range(0.0, 1.0) < 0.05 * dt {
+        if !knocked_back && !stunned && bot.on_ground && rand::gen_range(0.0, 1.0) < 0.05 * dt {
             if let Some(ladder) = Self::find_nearby_ladder(bot, map) {
                 bot.climbing = true;
                 bot.current_ladder = Some(ladder.id);
@@ -345,7 +465,10 @@ impl BotAI {
         bot.x = bot.x.max(map.info.vr_left as f32).min(map.info.vr_right as f32);
         bot.y = bot.y.max(map.info.vr_top as f32).min(map.info.vr_bottom as f32);
 
-        // Update facing direction based on movement
+        // Update facing direction based on movement (knocked back mobs keep facing the attacker)
+        if knocked_back {
+            return;
+        }
         if bot.vx > 0.0 {
             bot.facing_right = true;
         } else if bot.vx < 0.0 {
@@ -443,8 +566,13 @@ impl BotAI {
         None
     }
 
-    /// Get bot state by life ID
-    pub fn get_bot_state(&self, life_id: &str) -> Option<&BotState> {
-        self.bot_states.iter().find(|b| b.life_id == life_id)
+    /// Get bot state by its index in map.life
+    pub fn get_bot_state(&self, life_index: usize) -> Option<&BotState> {
+        self.bot_states.iter().find(|b| b.life_index == life_index)
+    }
+
+    /// Mutable mob states, for combat
+    pub fn mobs_mut(&mut self) -> &mut [BotState] {
+        &mut self.bot_states
     }
 }
@@ -0,0 +1,119 @@
+use macroquad::prelude::*;
+use crate::character::DerivedStats;
+use crate::character_renderer::CharacterState;
+use crate::game_world::bot_ai::BotState;
+use crate::map::{Life, MobStats};
+use crate::skill_info::SkillLevel;
+
+/// Mastery without any mastery skill (the low end of the damage range)
+const BASE_MASTERY: f32 = 0.1;
+
+/// Area an attack stance hits, for a character with its feet at (x, y).
+/// Returns None for stances that don't attack.
+pub fn attack_hitbox(stance: CharacterState, x: f32, y: f32, facing_right: bool) -> Option<Rect> {
+    // (reach in front, height above the feet)
+    let (reach, height) = match stance {
+        CharacterState::SwingO1 | CharacterState::SwingO2 | CharacterState::SwingO3 => (80.0, 70.0),
+        CharacterState::StabO1 | CharacterState::StabO2 => (100.0, 50.0),
+        CharacterState::ProneStab => (90.0, 30.0),
+        CharacterState::Shoot1 => (400.0, 60.0),
+        _ => return None,
+    };
+    // A little behind the character too, so mobs standing on top of it are hit
+    let behind = 10.0;
+    let depth = 10.0;
+
+    let left = if facing_right { x - behind } else { x - reach };
+    Some(Rect::new(left, y - height, reach + behind, height + depth))
+}
+
+/// Area a skill hits: its `lt`/`rb` range (given facing left, so mirrored when facing right),
+/// or the reach of the stance it is cast with
+pub fn skill_hitbox(values: &SkillLevel, stance: CharacterState, x: f32, y: f32, facing_right: bool) -> Option<Rect> {
+    let Some(range) = values.range else {
+        return attack_hitbox(stance, x, y, facing_right);
+    };
+    let left = if facing_right { x - range.right() } else { x + range.left() };
+    Some(Rect::new(left, y + range.top(), range.w, range.h))
+}
+
+/// Area a mob can be hit in, from its current sprite size
+pub fn mob_hitbox(life: &Life, mob: &BotState) -> Rect {
+    let (width, height) = life
+        .texture
+        .as_ref()
+        .map_or((40.0, 40.0), |tex| (tex.width(), tex.height()));
+    Rect::new(mob.x - width / 2.0, mob.y - height, width, height)
+}
+
+/// Weapon multiplier, main stat and secondary stat for the equipped weapon
+/// (pre-Big Bang formula, by the weapon's 3-digit category)
+fn weapon_factors(stats: &DerivedStats, weapon_id: Option<i32>) -> (f32, u32, u32) {
+    let (str, dex, luk) = (stats.str, stats.dex, stats.luk);
+    match weapon_id.map(|id| id / 10000) {
+        // One-handed sword / axe / blunt weapon
+        Some(130) => (4.0, str, dex),
+        Some(131) | Some(132) => (4.4, str, dex),
+        // Dagger
+        Some(133) => (3.6, luk, str + dex),
+        // Wand / staff used as a melee weapon
+        Some(137) | Some(138) => (3.6, str, dex),
+        // Two-handed sword / axe / blunt weapon
+        Some(140) => (4.6, str, dex),
+        Some(141) | Some(142) => (4.8, str, dex),
+        // Spear / polearm
+        Some(143) | Some(144) => (5.0, str, dex),
+        // Bow / crossbow
+        Some(145) => (3.4, dex, str),
+        Some(146) => (3.6, dex, str),
+        // Claw
+        Some(147) => (3.6, luk, str + dex),
+        // Knuckle / gun
+        Some(148) => (4.8, str, dex),
+        Some(149) => (3.6, dex, str),
+        // Bare hands
+        _ => (1.43, str, dex),
+    }
+}
+
+/// Min and max damage of a basic attack, from total stats, weapon attack and the weapon type
+pub fn damage_range(stats: &DerivedStats, weapon_id: Option<i32>) -> (i32, i32) {
+    let (multiplier, primary, secondary) = weapon_factors(stats, weapon_id);
+    let attack = stats.attack.max(1) as f32;
+    let (primary, secondary) = (primary as f32, secondary as f32);
+
+    let max = (primary * multiplier + secondary) * attack / 100.0;
+    let min = (primary * 0.9 * BASE_MASTERY * multiplier + secondary) * attack / 100.0;
+    let max = (max as i32).max(1);
+    ((min as i32).clamp(1, max), max)
+}
+
+/// Min and max damage of an attack spell with the given spell attack (`mad` of the skill level)
+pub fn magic_damage_range(stats: &DerivedStats, spell_attack: i32) -> (i32, i32) {
+    let magic = stats.magic_attack.max(0) as f32;
+    let int_bonus = stats.int as f32 / 200.0;
+    let spell_attack = spell_attack.max(1) as f32;
+
+    let max = ((magic * magic / 1000.0 + magic) / 30.0 + int_bonus) * spell_attack;
+    let min = ((magic * magic / 1000.0 + magic * BASE_MASTERY * 0.9) / 30.0 + int_bonus) * spell_attack;
+    let max = (max as i32).max(1);
+    ((min as i32).clamp(1, max), max)
+}
+
+/// Pick a damage value in the range
+pub fn roll_damage((min, max): (i32, i32)) -> i32 {
+    macroquad::rand::gen_range(min, max + 1)
+}
+
+/// Reduce player damage by the mob's PDDamage; mobs above the player's level take less
+pub fn apply_mob_defense(damage: i32, stats: &MobStats, player_level: u32) -> i32 {
+    let level_gap = (stats.level - player_level as i32).max(0) as f32;
+    let reduced = damage as f32 * (1.0 - 0.01 * level_gap).max(0.5) - stats.pd_damage as f32 * 0.5;
+    (reduced as i32).max(1)
+}
+
+/// Damage a mob's body does to the player: its PADamage with some spread, less the player's defense
+pub fn touch_damage(stats: &MobStats, defense: i32) -> i32 {
+    let attack = stats.pa_damage as f32 * macroquad::rand::gen_range(0.85, 1.0);
+    ((attack - defense as f32 * 0.5) as i32).max(1)
+}
@@ -0,0 +1,246 @@
+use macroquad::prelude::*;
+use std::collections::HashMap;
+use crate::assets::{AssetManager, FrameData};
+use crate::drop_table::DropContent;
+use crate::item_info;
+use crate::map::MapData;
+use crate::physics::{MapBounds, Physics, PhysicsObject};
+
+const MESO_PATH: &str = "Item/Special/0900.img";
+
+/// Meso icons by amount: coin (1-49), gold coin (50-99), bundle (100-999), bag (1000+)
+const MESO_ICONS: [(u32, &str); 4] = [(1000, "09000003"), (100, "09000002"), (50, "09000001"), (0, "09000000")];
+
+/// `Physics::move_object` works in client ticks of 8 ms
+const PHYSICS_STEP: f32 = 0.008;
+
+/// Drops pop up this fast (pixels per tick) and spread sideways over `DROP_SPREAD` pixels each
+const DROP_JUMP_SPEED: f32 = -5.0;
+const DROP_SPREAD: f32 = 25.0;
+
+/// Only the killer can loot a drop for this long (seconds)
+const OWNERSHIP_TIME: f32 = 15.0;
+
+/// Drops disappear after this long (seconds), fading out over the last `FADE_TIME`
+const EXPIRE_TIME: f32 = 180.0;
+const FADE_TIME: f32 = 1.0;
+
+/// How long a looted drop flies to the player (seconds)
+const PICKUP_TIME: f32 = 0.4;
+
+/// Time each meso frame is shown (seconds)
+const MESO_FRAME_TIME: f32 = 0.1;
+
+/// Distance from the player's feet a drop can be looted at
+const PICKUP_RANGE_X: f32 = 30.0;
+const PICKUP_RANGE_Y: f32 = 50.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DropState {
+    /// Flying out of the mob
+    Dropping,
+    /// Lying on a foothold, bobbing up and down
+    Floating,
+    /// Being looted: flies towards the player and fades
+    PickedUp { start: Vec2, elapsed: f32 },
+}
+
+/// An item or meso lying on the map
+struct MapDrop {
+    content: DropContent,
+    body: PhysicsObject,
+    state: DropState,
+    owner: String,
+    /// Seconds until anyone can loot it
+    owner_time: f32,
+    /// Seconds until it disappears
+    expire_time: f32,
+    /// Seconds since it dropped (spin, bobbing and meso animation)
+    age: f32,
+}
+
+/// Drops on the current map
+pub struct DropSystem {
+    drops: Vec<MapDrop>,
+    bounds: MapBounds,
+    step_time: f32,
+    item_icons: HashMap<i32, Texture2D>,
+    meso_icons: Vec<Vec<FrameData>>,
+    /// Items dropped whose icons aren't loaded yet
+    missing_icons: Vec<i32>,
+}
+
+impl DropSystem {
+    pub fn new() -> Self {
+        Self {
+            drops: Vec::new(),
+            bounds: MapBounds::default(),
+            step_time: 0.0,
+            item_icons: HashMap::new(),
+            meso_icons: Vec::new(),
+            missing_icons: Vec::new(),
+        }
+    }
+
+    /// Load the meso animations
+    pub async fn load_assets(&mut self) {
+        for (_, id) in MESO_ICONS {
+            let frames = AssetManager::load_animation_frames_with_origins(MESO_PATH, &format!("{}/iconRaw", id)).await;
+            if frames.is_empty() {
+                warn!("Meso icon {} has no frames", id);
+            }
+            self.meso_icons.push(frames);
+        }
+    }
+
+    /// Start over on a new map
+    pub fn set_map(&mut self, map: &MapData) {
+        self.drops.clear();
+        self.bounds = MapBounds::from_map(map);
+        self.step_time = 0.0;
+    }
+
+    /// Throw drops out of a mob at (x, y), spread out side by side
+    pub fn spawn(&mut self, contents: Vec<DropContent>, x: f32, y: f32, owner: &str) {
+        let count = contents.len();
+        for (i, content) in contents.into_iter().enumerate() {
+            if let DropContent::Item { item_id, .. } = content {
+                if !self.item_icons.contains_key(&item_id) && !self.missing_icons.contains(&item_id) {
+                    self.missing_icons.push(item_id);
+                }
+            }
+
+            // Land DROP_SPREAD apart, centered on the mob; ~48 ticks in the air
+            let offset = (i as f32 - (count - 1) as f32 / 2.0) * DROP_SPREAD;
+            let mut body = PhysicsObject::new(x, y - 20.0);
+            body.on_ground = false;
+            body.vx = offset / 48.0;
+            body.vy = DROP_JUMP_SPEED;
+
+            self.drops.push(MapDrop {
+                content,
+                body,
+                state: DropState::Dropping,
+                owner: owner.to_string(),
+                owner_time: OWNERSHIP_TIME,
+                expire_time: EXPIRE_TIME,
+                age: 0.0,
+            });
+        }
+    }
+
+    pub fn needs_icons(&self) -> bool {
+        !self.missing_icons.is_empty()
+    }
+
+    /// Load `iconRaw` and item info of newly dropped items
+    pub async fn load_icons(&mut self) {
+        for item_id in std::mem::take(&mut self.missing_icons) {
+            item_info::load_item_info(item_id).await;
+            if let Some(icon) = item_info::load_item_icon_raw(item_id).await {
+                self.item_icons.insert(item_id, icon);
+            }
+        }
+    }
+
+    /// Move drops, count down their timers and finish pickups
+    pub fn update(&mut self, dt: f32, map: &MapData, player_x: f32, player_y: f32) {
+        self.step_time += dt;
+        let steps = (self.step_time / PHYSICS_STEP) as u32;
+        self.step_time -= steps as f32 * PHYSICS_STEP;
+
+        for drop in &mut self.drops {
+            drop.age += dt;
+            drop.owner_time -= dt;
+            drop.expire_time -= dt;
+            match drop.state {
+                DropState::Dropping => {
+                    for _ in 0..steps {
+                        Physics::move_object(&mut drop.body, map, &self.bounds, PHYSICS_STEP);
+                        if drop.body.on_ground {
+                            drop.state = DropState::Floating;
+                            break;
+                        }
+                    }
+                }
+                DropState::Floating => {}
+                DropState::PickedUp { start, elapsed } => {
+                    let elapsed = elapsed + dt;
+                    drop.state = DropState::PickedUp { start, elapsed };
+                    // Arc up and into the player
+                    let t = (elapsed / PICKUP_TIME).min(1.0);
+                    drop.body.x = start.x + (player_x - start.x) * t;
+                    drop.body.y = start.y + (player_y - 30.0 - start.y) * t - (t * std::f32::consts::PI).sin() * 30.0;
+                }
+            }
+        }
+
+        self.drops.retain(|drop| match drop.state {
+            DropState::PickedUp { elapsed, .. } => elapsed < PICKUP_TIME,
+            _ => drop.expire_time > 0.0,
+        });
+    }
+
+    /// Closest drop the looter can pick up right now
+    pub fn pickup_candidate(&self, player_x: f32, player_y: f32, looter: &str) -> Option<(usize, DropContent)> {
+        self.drops
+            .iter()
+            .enumerate()
+            .filter(|(_, drop)| drop.state == DropState::Floating)
+            .filter(|(_, drop)| drop.owner_time <= 0.0 || drop.owner == looter)
+            .filter(|(_, drop)| {
+                (drop.body.x - player_x).abs() <= PICKUP_RANGE_X && (drop.body.y - player_y).abs() <= PICKUP_RANGE_Y
+            })
+            .min_by(|(_, a), (_, b)| (a.body.x - player_x).abs().total_cmp(&(b.body.x - player_x).abs()))
+            .map(|(index, drop)| (index, drop.content))
+    }
+
+    /// Start the pickup animation of a drop (it is removed once it reaches the player)
+    pub fn pick_up(&mut self, index: usize) {
+        if let Some(drop) = self.drops.get_mut(index) {
+            drop.state = DropState::PickedUp { start: vec2(drop.body.x, drop.body.y), elapsed: 0.0 };
+        }
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32) {
+        for drop in &self.drops {
+            let mut alpha = (drop.expire_time / FADE_TIME).clamp(0.0, 1.0);
+            let mut x = drop.body.x - camera_x;
+            let mut y = drop.body.y - camera_y;
+            let mut rotation = 0.0;
+            match drop.state {
+                DropState::Dropping => rotation = drop.age * 4.0 * std::f32::consts::TAU,
+                DropState::Floating => y -= 5.0 + (drop.age * 3.0).sin() * 2.5,
+                DropState::PickedUp { elapsed, .. } => alpha = 1.0 - (elapsed / PICKUP_TIME).min(1.0),
+            }
+            let color = Color::new(1.0, 1.0, 1.0, alpha);
+
+            match drop.content {
+                DropContent::Meso(amount) => {
+                    let tier = MESO_ICONS.iter().position(|(min, _)| amount >= *min).unwrap_or(MESO_ICONS.len() - 1);
+                    let Some(frames) = self.meso_icons.get(tier).filter(|frames| !frames.is_empty()) else {
+                        draw_circle(x, y - 8.0, 7.0, Color::new(1.0, 0.8, 0.1, alpha));
+                        continue;
+                    };
+                    let frame = &frames[(drop.age / MESO_FRAME_TIME) as usize % frames.len()];
+                    x -= frame.origin.x;
+                    y -= frame.origin.y;
+                    draw_texture_ex(&frame.texture, x, y, color, DrawTextureParams { rotation, ..Default::default() });
+                }
+                DropContent::Item { item_id, .. } => match self.item_icons.get(&item_id) {
+                    Some(icon) => {
+                        let params = DrawTextureParams { rotation, ..Default::default() };
+                        draw_texture_ex(icon, x - icon.width() / 2.0, y - icon.height(), color, params);
+                    }
+                    None => draw_rectangle(x - 10.0, y - 20.0, 20.0, 20.0, Color::new(0.5, 0.5, 0.5, alpha)),
+                },
+            }
+        }
+    }
+}
+
+impl Default for DropSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
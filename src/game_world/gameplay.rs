@@ -1,6 +1,8 @@
 use macroquad::prelude::*;
-use crate::character::CharacterData;
-use crate::flags::{self, DebugFlags};
+use std::collections::HashSet;
+use crate::character::{CharacterData, DerivedStats};
+use crate::config::config;
+use crate::flags::DebugFlags;
 use crate::map::{MapData, MapLoader, MapRenderer, MobState, MobAI, Foothold};
 use crate::map::portal_loader::PortalCache;
 use crate::game_world::bot_ai::BotAI;
@@ -8,18 +10,53 @@ use crate::audio::AudioManager;
 use crate::cursor::{CursorManager, CursorState};
 use crate::character_info_ui::StatusBarUI;
 use crate::minimap::MiniMap;
-use crate::ui_windows::{InventoryWindow, EquipWindow, UserInfoWindow};
+use crate::ui_windows::{InventoryWindow, InventoryEvent, EquipWindow, UserInfoWindow, StatWindow, SkillWindow, SkillEvent, QuestWindow};
+use crate::leveling::ApStat;
 use crate::cash_shop::CashShop;
-use crate::key_config::KeyConfig;
+use crate::key_config::{KeyConfig, KeyConfigEvent, ITEM_HOTKEYS};
 use crate::chat_balloon::ChatBalloonSystem;
+use crate::damage_numbers::{DamageNumberSystem, DamageStyle};
+use crate::effects::EffectSystem;
+use crate::game_world::combat;
+use crate::game_world::drops::DropSystem;
+use crate::game_world::reactors::ReactorSystem;
+use crate::game_world::quests::QuestMarks;
+use crate::game_world::transition::MapTransition;
+use crate::quest_info::{self, QuestAct};
+use crate::dialog_script;
+use crate::script_runtime;
+use crate::drop_table::{self, DropContent};
+use crate::shop_table;
+use crate::game_world::skills::{self, SkillState, SKILL_HOTKEYS};
+use crate::skill_info::{self, SkillInfo, SkillLevel};
+use crate::status_effect::{EffectSource, StatModifiers, StatusEffect};
 use crate::game_menu::{GameMenu, MenuAction};
-use crate::character_renderer::{CharacterRenderer, CharacterState};
+use crate::character_renderer::{CharacterRenderer, CharacterLook, CharacterState, EXPRESSIONS};
 use crate::npc_dialog::{NpcDialogSystem, DialogType};
-use crate::npc_script::{NpcScriptEngine, NpcScriptCommand};
+use crate::npc_shop::{NpcShop, ShopEvent};
+use crate::npc_storage::{NpcStorage, StorageEvent};
+use crate::trunk::{self, Trunk};
+use crate::job::Job;
+use crate::npc_script::{NpcScriptEngine, NpcScriptCommand, StyleType};
 use crate::social_windows::{ChannelWindow, MegaphoneWindow, MemoWindow, MessengerWindow};
 use crate::physics::{Physics, PhysicsObject};
+use crate::wz_cache;
+use crate::item_info;
+use crate::inventory::InventoryTab;
+use crate::equipment::{self, EquipSlot};
+use crate::debug_console::DebugConsole;
 use futures;
 
+/// Seconds between two consumables, so a held quick-slot key doesn't drain a whole stack
+const ITEM_COOLDOWN: f32 = 0.5;
+
+/// Sound played for consumables without their own `Item.img/<id>/Use`
+const DEFAULT_ITEM_SOUND: &str = "Item/02000000/Use";
+
+/// Seconds a dead player lies on the ground before going back to town, and the HP they get back
+const RESPAWN_DELAY: f32 = 3.0;
+const RESPAWN_HP: u32 = 50;
+
 /// Gameplay state for when the player is in the game world
 pub struct GameplayState {
     character: CharacterData,
@@ -41,6 +78,7 @@ pub struct GameplayState {
     on_ladder: bool,
     current_ladder_id: Option<i32>,
     drop_through_platform: bool, // True when jumping down through a platform
+    sitting: bool,
     foothold_min_x: f32, // Cached foothold extent
     foothold_max_x: f32,
     // Audio manager
@@ -50,8 +88,12 @@ pub struct GameplayState {
     // Debug map loader
     map_input: String,
     map_input_active: bool,
+    // Debug console for runtime config
+    debug_console: DebugConsole,
     // Mob damage cooldown
     damage_cooldown: f32,
+    // Seconds until a dead player respawns
+    respawn_timer: Option<f32>,
     loading_new_map: bool,
     backspace_timer: f32,
     backspace_repeat_delay: f32,
@@ -73,25 +115,58 @@ pub struct GameplayState {
     inventory_window: InventoryWindow,
     equip_window: EquipWindow,
     user_info_window: UserInfoWindow,
+    stat_window: StatWindow,
+    skill_window: SkillWindow,
+    quest_window: QuestWindow,
     // New UI components
     cash_shop: CashShop,
     key_config: KeyConfig,
     chat_balloon: ChatBalloonSystem,
+    damage_numbers: DamageNumberSystem,
+    effects: EffectSystem,
     game_menu: GameMenu,
     character_renderer: CharacterRenderer,
     npc_dialog: NpcDialogSystem,
     npc_script_engine: NpcScriptEngine,
+    npc_shop: NpcShop,
+    npc_storage: NpcStorage,
+    // Account-wide storage, read from the save when a storage keeper opens it
+    trunk: Trunk,
     // Social windows
     channel_window: ChannelWindow,
     megaphone_window: MegaphoneWindow,
     memo_window: MemoWindow,
     messenger_window: MessengerWindow,
+    // Items given by scripts, added once their WZ info is loaded (item_id, quantity)
+    pending_item_grants: Vec<(i32, u32)>,
+    // Inventory or equipment changed, so item info and icons may need loading
+    items_dirty: bool,
+    // Skill cooldowns and running buffs
+    skills: SkillState,
+    // Skills learned or job changed, so skill books and effects may need loading
+    skills_dirty: bool,
+    // Items and meso lying on the map
+    drops: DropSystem,
+    // Reactor states, hits and respawns on the map
+    reactors: ReactorSystem,
+    // Seconds until another consumable can be used
+    item_cooldown: f32,
+    // Quest marks over NPC heads
+    quest_marks: QuestMarks,
+    // Quests, level or items changed, so the quest marks need refreshing
+    quests_dirty: bool,
+    // Maps entered this session, for onFirstUserEnter scripts
+    entered_maps: HashSet<String>,
+    // Fade and loading screen around map changes
+    transition: MapTransition,
 }
 
 impl GameplayState {
     /// Create a new gameplay state
     pub fn new(character: CharacterData) -> Self {
         info!("Starting game with character: {}", character.name);
+        // Map the character was last in, else the default starting map
+        let start_map = if character.map_id > 0 { format!("{:09}", character.map_id) } else { "100000000".to_string() };
         Self {
             character,
             camera_x: 0.0,
@@ -105,21 +180,24 @@ impl GameplayState {
             map_data: None,
             map_renderer: MapRenderer::new(),
             portal_cache: PortalCache::new(),
-            current_map_id: "100000000".to_string(), // Default starting map
+            current_map_id: start_map,
             target_portal_name: None, // No target portal on initial spawn
             bot_ai: BotAI::new(),
             mob_states: Vec::new(),
             on_ladder: false,
             current_ladder_id: None,
             drop_through_platform: false,
+            sitting: false,
             foothold_min_x: 0.0,
             foothold_max_x: 800.0,
             audio_manager: AudioManager::new(),
             bgm_pending: false,
             map_input: String::new(),
             map_input_active: false,
+            debug_console: DebugConsole::new(),
             loading_new_map: false,
             damage_cooldown: 0.0,
+            respawn_timer: None,
             backspace_timer: 0.0,
             backspace_repeat_delay: 0.05, // Repeat every 50ms when held
             last_npc_click_time: -1.0,
@@ -133,17 +211,36 @@ impl GameplayState {
             inventory_window: InventoryWindow::new(),
             equip_window: EquipWindow::new(),
             user_info_window: UserInfoWindow::new(),
+            stat_window: StatWindow::new(),
+            skill_window: SkillWindow::new(),
+            quest_window: QuestWindow::new(),
             cash_shop: CashShop::new(),
             key_config: KeyConfig::new(),
             chat_balloon: ChatBalloonSystem::new(),
+            damage_numbers: DamageNumberSystem::new(),
+            effects: EffectSystem::new(),
             game_menu: GameMenu::new(),
             character_renderer: CharacterRenderer::new(),
             npc_dialog: NpcDialogSystem::new(),
+            npc_shop: NpcShop::new(),
+            npc_storage: NpcStorage::new(),
+            trunk: Trunk::default(),
             npc_script_engine: NpcScriptEngine::new(),
             channel_window: ChannelWindow::new(),
             megaphone_window: MegaphoneWindow::new(),
             memo_window: MemoWindow::new(),
             messenger_window: MessengerWindow::new(),
+            pending_item_grants: Vec::new(),
+            items_dirty: false,
+            skills: SkillState::new(),
+            skills_dirty: false,
+            drops: DropSystem::new(),
+            reactors: ReactorSystem::new(),
+            item_cooldown: 0.0,
+            quest_marks: QuestMarks::new(),
+            quests_dirty: true,
+            entered_maps: HashSet::new(),
+            transition: MapTransition::new(),
         }
     }
 
@@ -159,12 +256,25 @@ impl GameplayState {
         let cash_shop_load = self.cash_shop.load_assets();
         let key_config_load = self.key_config.load_assets();
         let chat_balloon_load = self.chat_balloon.load_assets();
+        let damage_numbers_load = self.damage_numbers.load_assets();
+        let effects_load = self.effects.load_assets();
         let game_menu_load = self.game_menu.load_assets();
         let inventory_load = self.inventory_window.load_assets();
         let equip_load = self.equip_window.load_assets();
         let user_info_load = self.user_info_window.load_assets();
+        let stat_load = self.stat_window.load_assets();
+        let skill_load = self.skill_window.load_assets();
+        let drops_load = self.drops.load_assets();
+        let drop_table_load = drop_table::load_drop_table();
+        let quest_load = quest_info::load_quests();
+        let quest_window_load = self.quest_window.load_assets();
+        let quest_marks_load = self.quest_marks.load_assets();
+        self.character_renderer.set_look(CharacterLook::from_character(&self.character));
         let character_renderer_load = self.character_renderer.load_assets();
         let npc_dialog_load = self.npc_dialog.load_assets();
+        let npc_shop_load = self.npc_shop.load_assets();
+        let shop_table_load = shop_table::load_shops();
+        let npc_storage_load = self.npc_storage.load_assets();
         let channel_load = self.channel_window.load();
         let megaphone_load = self.megaphone_window.load();
         let memo_load = self.memo_window.load();
@@ -173,9 +283,14 @@ impl GameplayState {
         // info!("Waiting for UI assets to load in parallel...");
         // Wait for all UI assets to load
         let _ = futures::join!(font_load, cursor_load, status_bar_load, minimap_load, 
-                               cash_shop_load, key_config_load, chat_balloon_load, game_menu_load,
-                               inventory_load, equip_load, user_info_load, character_renderer_load, npc_dialog_load,
-                               channel_load, megaphone_load, memo_load, messenger_load);
+                               cash_shop_load, key_config_load, chat_balloon_load, damage_numbers_load, effects_load, game_menu_load,
+                               inventory_load, equip_load, user_info_load, stat_load, skill_load, character_renderer_load, npc_dialog_load,
+                               channel_load, megaphone_load, memo_load, messenger_load, drops_load, drop_table_load,
+                               quest_load, quest_window_load, quest_marks_load, npc_shop_load, shop_table_load,
+                               npc_storage_load);
+
+        self.load_inventory_items().await;
+        self.load_skills().await;
 
         // info!("UI assets loaded. Font: ok, Cursors: {}, StatusBar: {}",
         //       self.cursor_manager.is_loaded(),
@@ -199,13 +314,18 @@ impl GameplayState {
         self.inventory_window.visible = false;
         self.equip_window.visible = false;
         self.user_info_window.visible = false;
+        self.stat_window.visible = false;
+        self.skill_window.visible = false;
         self.key_config.hide();
         self.game_menu.hide();
         self.npc_dialog.close_dialog();
+        self.npc_shop.close();
+        self.npc_storage.close();
         self.cash_shop.hide();
 
         match MapLoader::load_map(map_id).await {
             Ok(map) => {
+                wz_cache::log_stats();
 
                 // Determine spawn position based on target portal or spawn point
                 let spawn_x;
@@ -257,6 +377,36 @@ impl GameplayState {
 
                 // Initialize bot AI from map data
                 self.bot_ai.initialize_from_map(&map);
+                self.drops.set_map(&map);
+                self.reactors.set_map(&map);
+                self.quests_dirty = true;
+
+                let npc_ids: Vec<i32> = map
+                    .life
+                    .iter()
+                    .filter(|life| life.life_type == "n")
+                    .filter_map(|life| life.id.parse().ok())
+                    .collect();
+                dialog_script::preload_scripts(&npc_ids).await;
+                let mut script_paths: Vec<String> = npc_ids.iter().map(|id| format!("npc/{}.rhai", id)).collect();
+                script_paths.extend(
+                    map.portals
+                        .iter()
+                        .filter(|portal| !portal.script.is_empty())
+                        .map(|portal| script_runtime::portal_script_path(&portal.script)),
+                );
+                script_paths.extend(map.reactors.iter().map(|reactor| script_runtime::reactor_script_path(reactor.id)));
+                if !map.info.on_first_user_enter.is_empty() {
+                    script_paths.push(format!("map/onFirstUserEnter/{}.rhai", map.info.on_first_user_enter));
+                }
+                if !map.info.on_user_enter.is_empty() {
+                    script_paths.push(format!("map/onUserEnter/{}.rhai", map.info.on_user_enter));
+                }
+                script_runtime::preload_scripts(&script_paths).await;
+                let first_enter_script = map.info.on_first_user_enter.clone();
+                let enter_script = map.info.on_user_enter.clone();
+                self.damage_numbers.clear();
+                self.effects.clear();
 
                 // Save viewport bounds before moving map
                 let vr_left = map.info.vr_left as f32;
@@ -278,6 +428,12 @@ impl GameplayState {
                 let bgm_name = map.info.bgm.clone();
 
                 self.current_map_id = map_id.to_string();
+                // Next session starts here, or in the map's forcedReturn map if it has one
+                self.character.map_id = match map.info.forced_return {
+                    999999999 => map_id.parse().unwrap_or(0),
+                    forced_return => forced_return,
+                };
+                let _ = self.save_character();
                 
                 // Find the lowest foothold Y to ensure platform is visible (before moving map)
                 let lowest_foothold_y = map.footholds.iter()
@@ -318,12 +474,25 @@ impl GameplayState {
 
                 // Clear target portal name after successful spawn
                 self.target_portal_name = None;
+
+                // Map entry scripts, onFirstUserEnter only the first time this session
+                let mut entry_command = None;
+                if self.entered_maps.insert(map_id.to_string()) && !first_enter_script.is_empty() {
+                    entry_command = self.npc_script_engine.start_map_script(&first_enter_script, true, &self.character);
+                }
+                if entry_command.is_none() && !enter_script.is_empty() {
+                    entry_command = self.npc_script_engine.start_map_script(&enter_script, false, &self.character);
+                }
+                if let Some(cmd) = entry_command {
+                    self.execute_script_command_with_npc(cmd, String::new(), None);
+                }
             }
             Err(e) => {
                 error!("Failed to load map {}: {}", map_id, e);
                 self.loading_new_map = false;
             }
         }
+        self.transition.finish();
     }
 
     /// Trigger loading a new map from the debug input
@@ -337,9 +506,9 @@ impl GameplayState {
         }
     }
 
-    /// Check if we should load a new map and return the map ID
+    /// Check if we should load a new map and return the map ID (once the screen has faded out)
     pub fn should_load_new_map(&mut self) -> Option<String> {
-        if self.loading_new_map && !self.map_input.is_empty() {
+        if self.loading_new_map && !self.map_input.is_empty() && self.transition.ready_to_load() {
             Some(self.map_input.clone())
         } else {
             None
@@ -385,6 +554,742 @@ impl GameplayState {
         }
     }
 
+    /// Load stack limits, equip stats and icons for everything the character carries or wears
+    async fn load_inventory_items(&mut self) {
+        for item_id in self.character.inventory.item_ids().into_iter().chain(self.character.equipment.item_ids()) {
+            item_info::load_item_info(item_id).await;
+        }
+        self.inventory_window.load_icons(&self.character.inventory).await;
+        self.equip_window.load_icons(&self.character.equipment).await;
+        self.key_config.load_item_icons(&self.character.inventory).await;
+        // Items count towards quests
+        self.quests_dirty = true;
+
+        // Use sounds and buff icons of consumables
+        let (img_name, track) = DEFAULT_ITEM_SOUND.split_once('/').unwrap();
+        self.audio_manager.load_effect(img_name, track).await;
+        for item_id in self.character.inventory.item_ids() {
+            let Some(spec) = item_info::consume_spec(item_id) else { continue };
+            self.audio_manager.load_effect("Item", &format!("{:08}/Use", item_id)).await;
+            if spec.is_buff() {
+                self.status_bar.load_buff_icon(EffectSource::Item(item_id)).await;
+            }
+        }
+    }
+
+    /// Add items given by scripts and load data for changed items (async wrapper)
+    pub async fn handle_pending_items(&mut self) {
+        if !self.pending_item_grants.is_empty() {
+            self.grant_pending_items().await;
+        }
+
+        // Rebuild the character sprite after equipment changes
+        if self.character_renderer.needs_reload() {
+            self.character_renderer.load_assets().await;
+        }
+        // and the salon preview after flipping to another style
+        self.npc_dialog.load_style_preview().await;
+
+        if self.npc_shop.needs_items(&self.character.inventory) {
+            self.npc_shop.load_items(&self.character.inventory).await;
+        }
+        if self.npc_storage.needs_items(&self.character.inventory, &self.trunk) {
+            self.npc_storage.load_items(&self.character.inventory, &self.trunk).await;
+        }
+
+        if self.items_dirty {
+            self.items_dirty = false;
+            self.load_inventory_items().await;
+        }
+
+        if self.drops.needs_icons() {
+            self.drops.load_icons().await;
+        }
+    }
+
+    /// Load skill books, icons and effects after skills were learned or the job changed (async wrapper)
+    pub async fn handle_pending_skills(&mut self) {
+        if self.skills_dirty {
+            self.skills_dirty = false;
+            self.load_skills().await;
+        }
+    }
+
+    /// Load the skill books of the character's job, and the effects (and buff icons) of every learned skill
+    async fn load_skills(&mut self) {
+        if self.skill_window.needs_books(self.character.job.id()) {
+            self.skill_window.load_books(self.character.job.id()).await;
+        }
+        for &skill_id in self.character.skills.keys() {
+            let (img_path, node_path) = SkillInfo::wz_path(skill_id);
+            self.effects.load_animation(&skills::effect_key(skill_id), &img_path, &format!("{}/effect", node_path)).await;
+            self.effects.load_animation(&skills::hit_key(skill_id), &img_path, &format!("{}/hit/0", node_path)).await;
+            if skill_info::skill_info(skill_id).is_some_and(|skill| skill.levels.iter().any(SkillLevel::is_buff)) {
+                self.status_bar.load_buff_icon(EffectSource::Skill(skill_id)).await;
+            }
+        }
+    }
+
+    /// Add items given by scripts (item info has to be loaded first for stack limits)
+    async fn grant_pending_items(&mut self) {
+        for (item_id, quantity) in std::mem::take(&mut self.pending_item_grants) {
+            item_info::load_item_info(item_id).await;
+            match self.character.inventory.add(item_id, quantity) {
+                Ok(()) => {
+                    info!("Added item {} x{} to inventory", item_id, quantity);
+                    self.status_bar.add_system_message(&format!("You have gained an item ({} x{})", item_id, quantity));
+                }
+                Err(e) => {
+                    warn!("{}", e);
+                    self.status_bar.add_system_message("Your inventory is full.");
+                }
+            }
+        }
+
+        let _ = self.save_character();
+        self.items_dirty = true;
+    }
+
+    /// Equip the item in an Equip tab slot, moving whatever it replaces back into the inventory
+    fn equip_from_inventory(&mut self, slot: usize) {
+        let Some(item) = self.character.inventory.get(InventoryTab::Equip, slot) else {
+            return;
+        };
+        if let Err(e) = equipment::check_requirements(item.item_id, &self.character) {
+            self.status_bar.add_system_message(&e);
+            return;
+        }
+
+        // Work on copies so nothing changes if the replaced items don't fit
+        let mut equipment = self.character.equipment.clone();
+        let mut inventory = self.character.inventory.clone();
+        let removed = match equipment.equip(item.item_id) {
+            Ok(removed) => removed,
+            Err(e) => {
+                warn!("{}", e);
+                return;
+            }
+        };
+        inventory.remove_at(InventoryTab::Equip, slot, 1);
+        for item_id in removed {
+            if inventory.add(item_id, 1).is_err() {
+                self.status_bar.add_system_message("Your inventory is full.");
+                return;
+            }
+        }
+
+        info!("Equipped item {}", item.item_id);
+        self.character.equipment = equipment;
+        self.character.inventory = inventory;
+        self.on_equipment_changed();
+    }
+
+    /// Take off the item in an equip slot and put it in the inventory
+    fn unequip(&mut self, slot: EquipSlot) {
+        let Some(item_id) = self.character.equipment.get(slot) else {
+            return;
+        };
+        if self.character.inventory.add(item_id, 1).is_err() {
+            self.status_bar.add_system_message("Your inventory is full.");
+            return;
+        }
+
+        info!("Unequipped item {}", item_id);
+        self.character.equipment.unequip(slot);
+        self.on_equipment_changed();
+    }
+
+    fn on_equipment_changed(&mut self) {
+        self.character_renderer.set_look(CharacterLook::from_character(&self.character));
+        self.items_dirty = true;
+        let _ = self.save_character();
+    }
+
+    /// Stats with equipment and running buffs applied
+    fn derived_stats(&self) -> DerivedStats {
+        self.character.derived_stats(&self.skills.buff_bonus())
+    }
+
+    /// Hit the closest living mob inside the attack's range, and any reactors in it
+    fn resolve_attack(&mut self, stance: CharacterState) {
+        let damage_range = self.derived_stats().damage;
+        let Some(hitbox) = combat::attack_hitbox(stance, self.player_x, self.player_y, self.facing_right) else { return };
+        self.hit_reactors(hitbox);
+        let Some(map) = &self.map_data else { return };
+
+        let player_x = self.player_x;
+        let target = self
+            .bot_ai
+            .mobs_mut()
+            .iter_mut()
+            .filter(|mob| mob.is_alive())
+            .filter_map(|mob| {
+                let life = map.life.get(mob.life_index)?;
+                let mob_box = combat::mob_hitbox(life, mob);
+                mob_box.overlaps(&hitbox).then_some((mob, mob_box, life.stats))
+            })
+            .min_by(|(a, _, _), (b, _, _)| (a.x - player_x).abs().total_cmp(&(b.x - player_x).abs()));
+        let Some((mob, mob_box, stats)) = target else { return };
+
+        let damage = combat::roll_damage(damage_range);
+        let damage = combat::apply_mob_defense(damage, &stats, self.character.level);
+
+        let killed = mob.take_damage(damage, player_x);
+        self.damage_numbers.spawn(damage, mob.x, mob_box.y, DamageStyle::ToMob);
+        if killed {
+            info!("Killed mob {} with {} damage", mob.life_id, damage);
+            let mob_id = mob.life_id.parse().unwrap_or(0);
+            let drop = drop_table::roll_drops(mob_id, stats.level);
+            self.drops.spawn(drop, mob.x, mob.y, &self.character.name);
+            self.record_quest_kill(mob_id);
+            self.gain_exp(stats.exp.max(0) as u32);
+        }
+    }
+
+    /// Hit the reactors inside an attack's range. Those that break spawn their drops and run
+    /// their script.
+    fn hit_reactors(&mut self, hitbox: Rect) {
+        let Some(map) = &self.map_data else { return };
+        let mut broken = Vec::new();
+        for index in self.reactors.hit(map, hitbox) {
+            let reactor = &map.reactors[index];
+            info!("Broke reactor {}", reactor.id);
+            let drop = drop_table::roll_reactor_drops(reactor.id);
+            self.drops.spawn(drop, reactor.x as f32, reactor.y as f32, &self.character.name);
+            broken.push(reactor.id);
+        }
+        for reactor_id in broken {
+            if let Some(cmd) = self.npc_script_engine.start_reactor_script(reactor_id, &self.character) {
+                self.execute_script_command_with_npc(cmd, String::new(), None);
+            }
+        }
+    }
+
+    /// Loot the closest drop in reach: meso goes straight to the character, items need inventory room
+    fn pick_up_drop(&mut self) {
+        let Some((index, content)) = self.drops.pickup_candidate(self.player_x, self.player_y, &self.character.name) else {
+            return;
+        };
+        match content {
+            DropContent::Meso(amount) => {
+                if let Err(e) = self.character.add_meso(amount as i32) {
+                    self.status_bar.add_system_message(&e);
+                    return;
+                }
+                self.status_bar.add_system_message(&format!("You have gained mesos (+{})", amount));
+            }
+            DropContent::Item { item_id, quantity } => {
+                if self.character.inventory.add(item_id, quantity).is_err() {
+                    self.status_bar.add_system_message("Your inventory is full.");
+                    return;
+                }
+                self.status_bar.add_system_message(&format!("You have gained an item ({} x{})", item_id, quantity));
+                self.items_dirty = true;
+            }
+        }
+        self.drops.pick_up(index);
+        let _ = self.save_character();
+    }
+
+    /// Give the player EXP, playing the level up effect for any levels gained
+    fn gain_exp(&mut self, amount: u32) {
+        if amount == 0 {
+            return;
+        }
+        self.status_bar.add_system_message(&format!("You have gained experience (+{})", amount));
+
+        let levels = self.character.gain_exp(amount);
+        if levels > 0 {
+            info!("{} reached level {}", self.character.name, self.character.level);
+            self.effects.play_on_player("LevelUp");
+            self.status_bar.add_system_message(&format!("You have reached level {}!", self.character.level));
+            self.quests_dirty = true;
+        }
+        let _ = self.save_character();
+    }
+
+    /// Count a killed mob for the started quests that need it
+    fn record_quest_kill(&mut self, mob_id: i32) {
+        let wanted = quest_info::quests_needing_mob(mob_id);
+        if wanted.is_empty() {
+            return;
+        }
+        for (quest_id, kills) in self.character.quests.record_kill(mob_id, &wanted) {
+            let Some(quest) = quest_info::quest(quest_id) else { continue };
+            let needed = wanted.iter().find(|&&(id, _)| id == quest_id).map_or(0, |&(_, count)| count);
+            self.status_bar.add_system_message(&format!("{}: {} / {}", quest.name, kills, needed));
+            self.quests_dirty = true;
+        }
+    }
+
+    /// Carry out a trade from the shop window, reporting failures in the chat log
+    fn handle_shop_event(&mut self, event: ShopEvent) {
+        let result = match event {
+            ShopEvent::Buy { index, quantity } => self.shop_buy(index, quantity),
+            ShopEvent::Sell { tab, slot, quantity } => self.shop_sell(tab, slot, quantity),
+            ShopEvent::Recharge { tab, slot } => self.shop_recharge(tab, slot),
+        };
+        match result {
+            Ok(()) => {
+                self.items_dirty = true;
+                let _ = self.save_character();
+            }
+            Err(e) => self.status_bar.add_system_message(&e),
+        }
+    }
+
+    /// Buy from the open shop. Throwing stars and bullets come as one full set per purchase,
+    /// whatever `quantity` asks for.
+    fn shop_buy(&mut self, index: usize, quantity: u32) -> Result<(), String> {
+        let npc_id = self.npc_shop.npc_id();
+        let item = shop_table::shop_items(npc_id)
+            .and_then(|items| items.get(index).copied())
+            .ok_or("That item isn't sold here.")?;
+        let (quantity, count) = if item_info::is_rechargeable(item.item_id) {
+            (1, item_info::slot_max(item.item_id))
+        } else {
+            (quantity, quantity)
+        };
+        let cost = i32::try_from(item.price as u64 * quantity as u64)
+            .ok()
+            .filter(|&cost| cost as u32 <= self.character.meso)
+            .ok_or("You don't have enough mesos.")?;
+        if self.character.inventory.space_for(item.item_id) < count {
+            return Err("Your inventory is full.".to_string());
+        }
+        shop_table::take_stock(npc_id, index, quantity)?;
+        self.character.add_meso(-cost)?;
+        self.character.inventory.add(item.item_id, count)?;
+        info!("Bought {} x{} for {} mesos", item.item_id, count, cost);
+        Ok(())
+    }
+
+    /// Sell from an inventory slot at the item's WZ price (throwing stars and bullets only as a whole stack)
+    fn shop_sell(&mut self, tab: InventoryTab, slot: usize, quantity: u32) -> Result<(), String> {
+        let item = self.character.inventory.get(tab, slot).ok_or("There is nothing to sell there.")?;
+        let rechargeable = item_info::is_rechargeable(item.item_id);
+        let quantity = if rechargeable { item.quantity } else { quantity.min(item.quantity) };
+        let price = item_info::sell_price(item.item_id).unwrap_or(0) as u64;
+        let earned = if rechargeable { price } else { price * quantity as u64 };
+        self.character.add_meso(earned.min(i32::MAX as u64) as i32)?;
+        self.character.inventory.remove_at(tab, slot, quantity);
+        info!("Sold {} x{} for {} mesos", item.item_id, quantity, earned);
+        Ok(())
+    }
+
+    /// Fill a stack of throwing stars or bullets back up, at the open shop's price per unit
+    fn shop_recharge(&mut self, tab: InventoryTab, slot: usize) -> Result<(), String> {
+        let item = self.character.inventory.get(tab, slot).ok_or("There is nothing to recharge there.")?;
+        if !item_info::is_rechargeable(item.item_id) {
+            return Err("Only throwing stars and bullets can be recharged.".to_string());
+        }
+        let unit_price = shop_table::shop_items(self.npc_shop.npc_id())
+            .and_then(|items| items.iter().find(|shop_item| shop_item.item_id == item.item_id)?.unit_price)
+            .ok_or("This shop can't recharge that item.")?;
+        let missing = item_info::slot_max(item.item_id).saturating_sub(item.quantity);
+        if missing == 0 {
+            return Err("That item is already fully charged.".to_string());
+        }
+        let cost = (missing as f64 * unit_price).ceil();
+        if cost > self.character.meso as f64 {
+            return Err("You don't have enough mesos.".to_string());
+        }
+        self.character.add_meso(-(cost as i32))?;
+        let added = self.character.inventory.recharge(tab, slot).unwrap_or(0);
+        info!("Recharged {} by {} for {} mesos", item.item_id, added, cost);
+        Ok(())
+    }
+
+    /// Carry out a move from the storage window. The trunk is saved before the character; if
+    /// either save fails, the trunk, inventory and meso go back to how they were (the old trunk
+    /// is saved again if the new one was already written), so an item never ends up in both or
+    /// in neither and no fee is charged.
+    fn handle_storage_event(&mut self, event: StorageEvent) {
+        let trunk = self.trunk.clone();
+        let inventory = self.character.inventory.clone();
+        let meso = self.character.meso;
+        let expanding = matches!(event, StorageEvent::Expand);
+        let result = match event {
+            StorageEvent::Withdraw(index) => self.storage_withdraw(index),
+            StorageEvent::Deposit { tab, slot } => self.storage_deposit(tab, slot),
+            StorageEvent::Expand => self.storage_expand(),
+        };
+        if let Err(e) = result.and_then(|()| self.trunk.save()) {
+            (self.trunk, self.character.inventory, self.character.meso) = (trunk, inventory, meso);
+            self.status_bar.add_system_message(&e);
+            return;
+        }
+        if let Err(e) = self.save_character() {
+            (self.trunk, self.character.inventory, self.character.meso) = (trunk, inventory, meso);
+            if let Err(e) = self.trunk.save() {
+                error!("Failed to restore trunk: {}", e);
+            }
+            self.status_bar.add_system_message(&e);
+            return;
+        }
+        if expanding {
+            self.status_bar.add_system_message(&format!("Your storage now has {} slots.", self.trunk.slots));
+        }
+        self.items_dirty = true;
+    }
+
+    /// Take a stack out of storage, paying the withdraw fee
+    fn storage_withdraw(&mut self, index: usize) -> Result<(), String> {
+        let item = *self.trunk.items().get(index).ok_or("There is nothing stored there.")?;
+        if self.character.meso < trunk::WITHDRAW_FEE {
+            return Err("You don't have enough mesos.".to_string());
+        }
+        if self.character.inventory.space_for(item.item_id) < item.quantity {
+            return Err("Your inventory is full.".to_string());
+        }
+        self.character.add_meso(-(trunk::WITHDRAW_FEE as i32))?;
+        self.trunk.withdraw(index);
+        self.character.inventory.add(item.item_id, item.quantity)?;
+        info!("Took {} x{} out of storage", item.item_id, item.quantity);
+        Ok(())
+    }
+
+    /// Put a whole inventory stack into storage, paying the deposit fee
+    fn storage_deposit(&mut self, tab: InventoryTab, slot: usize) -> Result<(), String> {
+        let item = self.character.inventory.get(tab, slot).ok_or("There is nothing to store there.")?;
+        if self.trunk.is_full() {
+            return Err("Your storage is full.".to_string());
+        }
+        if self.character.meso < trunk::DEPOSIT_FEE {
+            return Err("You don't have enough mesos.".to_string());
+        }
+        self.character.add_meso(-(trunk::DEPOSIT_FEE as i32))?;
+        self.character.inventory.remove_at(tab, slot, item.quantity);
+        self.trunk.deposit(item)?;
+        info!("Stored {} x{}", item.item_id, item.quantity);
+        Ok(())
+    }
+
+    /// Buy `trunk::EXPANSION_SLOTS` more storage slots
+    fn storage_expand(&mut self) -> Result<(), String> {
+        if self.trunk.slots >= trunk::MAX_SLOTS {
+            return Err("Your storage cannot be expanded any further.".to_string());
+        }
+        if self.character.meso < trunk::EXPANSION_COST {
+            return Err("You don't have enough mesos.".to_string());
+        }
+        self.character.add_meso(-(trunk::EXPANSION_COST as i32))?;
+        self.trunk.expand()
+    }
+
+    /// Start a quest the player accepted from an NPC
+    fn start_quest(&mut self, quest_id: i32) {
+        let Some(quest) = quest_info::quest(quest_id) else { return };
+        if !quest.can_start(&self.character) {
+            self.status_bar.add_system_message("You can't take this quest right now.");
+            return;
+        }
+        if let Err(e) = self.character.quests.start(quest_id) {
+            warn!("{}", e);
+            return;
+        }
+        info!("Started quest {} ({})", quest_id, quest.name);
+        self.status_bar.add_system_message(&format!("Quest started: {}", quest.name));
+        self.apply_quest_act(&quest.start_act);
+        self.quests_dirty = true;
+        let _ = self.save_character();
+    }
+
+    /// Hand a finished quest in and run its end act, which takes the collected items and gives the rewards
+    fn complete_quest(&mut self, quest_id: i32) {
+        let Some(quest) = quest_info::quest(quest_id) else { return };
+        if !quest.can_complete(&self.character) {
+            self.status_bar.add_system_message("You haven't finished this quest yet.");
+            return;
+        }
+        if let Err(e) = self.character.quests.complete(quest_id) {
+            warn!("{}", e);
+            return;
+        }
+        info!("Completed quest {} ({})", quest_id, quest.name);
+        self.status_bar.add_system_message(&format!("Quest complete: {}", quest.name));
+        // The end check only gates completion; collected items and meso are taken by the end act
+        self.apply_quest_act(&quest.end_act);
+        self.quests_dirty = true;
+        let _ = self.save_character();
+    }
+
+    /// Give (or take) the items, meso and EXP of a quest's start or completion
+    fn apply_quest_act(&mut self, act: &QuestAct) {
+        for &(item_id, count) in &act.items {
+            if count > 0 {
+                // Added in handle_pending_items once the item's slotMax is known
+                self.pending_item_grants.push((item_id, count as u32));
+            } else if count < 0 {
+                match self.character.inventory.remove(item_id, count.unsigned_abs()) {
+                    Ok(()) => self.items_dirty = true,
+                    Err(e) => warn!("Quest: {}", e),
+                }
+            }
+        }
+        if act.meso != 0 {
+            match self.character.add_meso(act.meso) {
+                Ok(()) => self.status_bar.add_system_message(&format!("You have gained mesos ({:+})", act.meso)),
+                Err(e) => self.status_bar.add_system_message(&e),
+            }
+        }
+        self.gain_exp(act.exp.max(0) as u32);
+    }
+
+    /// Put an ability point into a stat from the stat window
+    fn spend_ap(&mut self, stat: ApStat) {
+        match self.character.spend_ap(stat) {
+            Ok(()) => {
+                let _ = self.save_character();
+            }
+            Err(e) => self.status_bar.add_system_message(&e),
+        }
+    }
+
+    /// Advance the player to a new job, as an NPC asked
+    fn advance_job(&mut self, job: Job) {
+        match self.character.advance_job(job) {
+            Ok(()) => {
+                info!("{} advanced to {}", self.character.name, job.name());
+                self.effects.play_on_player("JobChanged");
+                self.status_bar.add_system_message(&format!("You are now a {}!", job.name()));
+                self.skills_dirty = true;
+                self.quests_dirty = true;
+                let _ = self.save_character();
+            }
+            Err(e) => self.status_bar.add_system_message(&e),
+        }
+    }
+
+    /// Put a skill point into a skill from the skill window
+    fn spend_sp(&mut self, skill_id: i32) {
+        let Some(skill) = skill_info::skill_info(skill_id) else { return };
+        match self.character.spend_sp(&skill) {
+            Ok(()) => {
+                self.skills_dirty = true;
+                let _ = self.save_character();
+            }
+            Err(e) => self.status_bar.add_system_message(&e),
+        }
+    }
+
+    /// Bind a skill to one of the skill keys, moving it off any key it was on
+    fn bind_skill(&mut self, skill_id: i32, slot: usize) {
+        for key in self.character.skill_keys.iter_mut().filter(|key| **key == Some(skill_id)) {
+            *key = None;
+        }
+        self.character.skill_keys[slot] = Some(skill_id);
+        let _ = self.save_character();
+    }
+
+    /// Apply a quick-slot change from the KeyConfig window
+    fn bind_item(&mut self, event: KeyConfigEvent) {
+        match event {
+            KeyConfigEvent::BindItem(slot, item_id) => self.character.item_keys[slot] = item_id,
+            KeyConfigEvent::ResetItems => self.character.item_keys = Default::default(),
+        }
+        let _ = self.save_character();
+    }
+
+    /// Change maps, fading out to the loading screen and back in. Every map change goes
+    /// through here: portals, scripts, return scrolls and the debug map loader. Map 999999999
+    /// stands for the current map's return map. Without a portal name the player appears at
+    /// the new map's spawn point.
+    fn warp_to(&mut self, map_id: i32, portal: Option<String>) {
+        let map_id = if map_id == 999999999 {
+            match self.map_data.as_ref().map(|map| map.info.return_map) {
+                Some(return_map) if return_map != 999999999 => return_map,
+                _ => {
+                    warn!("Map {} has no return map", self.current_map_id);
+                    return;
+                }
+            }
+        } else {
+            map_id
+        };
+        info!("Warping to map {:09} at portal {:?}", map_id, portal);
+        self.target_portal_name = portal.filter(|name| !name.is_empty());
+        self.map_input = format!("{:09}", map_id);
+        self.map_input_active = false;
+        self.loading_new_map = true;
+        self.transition.start();
+    }
+
+    /// Use one of a consumable: restore HP/MP, start its buff or take its return scroll
+    fn use_item(&mut self, item_id: i32) {
+        if self.character.hp == 0 || self.item_cooldown > 0.0 {
+            return;
+        }
+        let Some(spec) = item_info::consume_spec(item_id) else { return };
+        let return_map = match spec.move_to {
+            0 => None,
+            999999999 => match self.map_data.as_ref().map(|map| map.info.return_map) {
+                Some(map_id) if map_id != 999999999 => Some(map_id),
+                _ => {
+                    self.status_bar.add_system_message("You cannot use it here.");
+                    return;
+                }
+            },
+            map_id => Some(map_id),
+        };
+        if self.character.inventory.remove(item_id, 1).is_err() {
+            self.status_bar.add_system_message("You don't have that item.");
+            return;
+        }
+
+        let stats = self.derived_stats();
+        let hp = spec.hp.max(0) as u32 + stats.max_hp * spec.hp_r.max(0) as u32 / 100;
+        let mp = spec.mp.max(0) as u32 + stats.max_mp * spec.mp_r.max(0) as u32 / 100;
+        self.character.hp = (self.character.hp + hp).min(stats.max_hp);
+        self.character.mp = (self.character.mp + mp).min(stats.max_mp);
+        if spec.is_buff() {
+            let modifiers = StatModifiers { bonus: spec.buff, ..Default::default() };
+            self.skills.add_buff(StatusEffect::new(EffectSource::Item(item_id), 1, spec.time as f32 / 1000.0, modifiers));
+        }
+        if let Some(map_id) = return_map {
+            info!("Return scroll {} to map {}", item_id, map_id);
+            self.warp_to(map_id, None);
+        }
+
+        if !self.audio_manager.play_effect(&format!("Item/{:08}/Use", item_id)) {
+            self.audio_manager.play_effect(DEFAULT_ITEM_SOUND);
+        }
+        self.item_cooldown = ITEM_COOLDOWN;
+        self.items_dirty = true;
+        let _ = self.save_character();
+    }
+
+    /// Use a learned skill: pay its MP/HP cost, then attack or start its buff
+    fn cast_skill(&mut self, skill_id: i32) {
+        let Some(skill) = skill_info::skill_info(skill_id) else { return };
+        let level = self.character.skill_level(skill_id);
+        if self.character.hp == 0 || self.on_ladder {
+            return;
+        }
+        let Some(values) = skill.level(level).copied() else {
+            self.status_bar.add_system_message("You haven't learned this skill.");
+            return;
+        };
+        let debuff = skills::mob_debuff(skill_id, level, &values);
+        // Debuff skills like Slow hit mobs without doing damage
+        let hits_mobs = values.is_attack() || debuff.is_some();
+        if !hits_mobs && !values.is_buff() {
+            return;
+        }
+        if self.skills.cooldown(skill_id) > 0.0 {
+            self.status_bar.add_system_message("You cannot use this skill yet.");
+            return;
+        }
+        if self.character.mp < values.mp_con.max(0) as u32 {
+            self.status_bar.add_system_message("Not enough MP.");
+            return;
+        }
+        if self.character.hp <= values.hp_con.max(0) as u32 {
+            self.status_bar.add_system_message("Not enough HP.");
+            return;
+        }
+
+        let stance = skill
+            .action
+            .as_deref()
+            .and_then(CharacterState::from_wz_action)
+            .unwrap_or_else(|| CharacterState::attack_for_weapon(self.character.equipment.get(EquipSlot::Weapon)));
+        if hits_mobs && !self.character_renderer.request_stance(stance) {
+            return;
+        }
+
+        self.character.mp -= values.mp_con.max(0) as u32;
+        self.character.hp -= values.hp_con.max(0) as u32;
+        self.skills.start_cooldown(skill_id, values.cooltime as f32);
+        self.sitting = false;
+
+        let effect = skills::effect_key(skill_id);
+        if self.effects.has(&effect) {
+            self.effects.play_on_player(&effect);
+        }
+        if hits_mobs {
+            self.resolve_skill_attack(skill_id, &values, debuff, stance);
+        } else {
+            info!("Buff {} level {} for {}s", skill.name, level, values.time);
+            self.skills.add_buff(skills::buff_effect(skill_id, level, &values));
+        }
+    }
+
+    /// Hit up to `mobCount` living mobs in the skill's range, closest first, `attackCount` times each,
+    /// and leave the skill's debuff on them. Reactors in range are hit too.
+    fn resolve_skill_attack(&mut self, skill_id: i32, values: &SkillLevel, debuff: Option<StatusEffect>, stance: CharacterState) {
+        let stats = self.derived_stats();
+        let Some(hitbox) = combat::skill_hitbox(values, stance, self.player_x, self.player_y, self.facing_right) else { return };
+        self.hit_reactors(hitbox);
+        let Some(map) = &self.map_data else { return };
+
+        // Weapon skills scale a basic attack; spells use their own spell attack
+        let (range, percent) = if values.damage > 0 {
+            (stats.damage, values.damage)
+        } else {
+            (combat::magic_damage_range(&stats, values.mad), 100)
+        };
+
+        let player_x = self.player_x;
+        let mut targets: Vec<_> = self
+            .bot_ai
+            .mobs_mut()
+            .iter_mut()
+            .filter(|mob| mob.is_alive())
+            .filter_map(|mob| {
+                let life = map.life.get(mob.life_index)?;
+                let mob_box = combat::mob_hitbox(life, mob);
+                mob_box.overlaps(&hitbox).then_some((mob, mob_box, life.stats))
+            })
+            .collect();
+        targets.sort_by(|(a, _, _), (b, _, _)| (a.x - player_x).abs().total_cmp(&(b.x - player_x).abs()));
+
+        let hit_effect = skills::hit_key(skill_id);
+        let mut exp = 0;
+        let mut killed_mobs = Vec::new();
+        for (mob, mob_box, mob_stats) in targets.into_iter().take(values.mob_count.max(1) as usize) {
+            for hit in 0..values.attack_count.max(1) {
+                if !mob.is_alive() || (values.damage <= 0 && values.mad <= 0) {
+                    break;
+                }
+                let damage = combat::roll_damage(range) * percent / 100;
+                let damage = combat::apply_mob_defense(damage, &mob_stats, self.character.level);
+                let killed = mob.take_damage(damage, player_x);
+                // Numbers of a multi-hit skill stack upwards
+                self.damage_numbers.spawn(damage, mob.x, mob_box.y - hit as f32 * 24.0, DamageStyle::ToMob);
+                if killed {
+                    info!("Killed mob {} with skill {}", mob.life_id, skill_id);
+                    exp += mob_stats.exp.max(0) as u32;
+                    let mob_id = mob.life_id.parse().unwrap_or(0);
+                    let drop = drop_table::roll_drops(mob_id, mob_stats.level);
+                    self.drops.spawn(drop, mob.x, mob.y, &self.character.name);
+                    killed_mobs.push(mob_id);
+                }
+            }
+            if let Some(debuff) = debuff.as_ref().filter(|_| mob.is_alive()) {
+                if rand::gen_range(0, 100) < values.prop {
+                    mob.effects.apply(debuff.clone());
+                }
+            }
+            if self.effects.has(&hit_effect) {
+                self.effects.play_at(&hit_effect, mob.x, mob_box.center().y);
+            }
+        }
+        for mob_id in killed_mobs {
+            self.record_quest_kill(mob_id);
+        }
+        self.gain_exp(exp);
+    }
+
+    /// Write the character (stats, inventory, ...) to storage. Failures are logged here, so
+    /// callers that can't do anything about one may ignore the result.
+    fn save_character(&self) -> Result<(), String> {
+        self.character.save().map_err(|e| {
+            error!("Failed to save character {}: {}", self.character.name, e);
+            e
+        })
+    }
+
     /// Update game logic
     #[inline(never)]
     pub fn update(&mut self, dt: f32) {
@@ -397,6 +1302,7 @@ impl GameplayState {
             dt
         };
         self.last_dt = clamped_dt;
+        self.transition.update(clamped_dt);
         
         // Check window focus state (macroquad doesn't expose this directly, so we infer from dt)
         // If dt is reasonable, window is likely focused
@@ -428,15 +1334,18 @@ impl GameplayState {
         }
         // Portal textures are already loaded in each Portal structure during map parsing
 
+        // Debug console (` key) takes all keyboard input while open
+        if self.debug_console.update(clamped_dt) {
+            return;
+        }
+
         // Handle M key - toggle minimap OR map loader depending on flags
         if is_key_pressed(KeyCode::M) {
-            if flags::SHOW_MAP_LOADER {
+            if config().show_map_loader {
                 // Toggle map loader input
                 if self.map_input_active {
-                    if !self.map_input.is_empty() {
-                        self.loading_new_map = true;
-                        self.map_input_active = false;
-                        self.target_portal_name = None;
+                    if let Ok(map_id) = self.map_input.parse() {
+                        self.warp_to(map_id, None);
                     } else {
                         self.map_input_active = false;
                         self.map_input.clear();
@@ -548,11 +1457,31 @@ impl GameplayState {
         let free_roam = is_key_down(KeyCode::Space);
 
         // Basic player movement with debug speed multiplier
-        let base_speed = if free_roam { 350.0 } else { 200.0 };
+        let stats = self.derived_stats();
+        let base_speed = if free_roam { 350.0 } else { 200.0 * stats.speed as f32 / 100.0 };
         let move_speed = DebugFlags::get_player_speed(base_speed);
+        let jump_velocity = -400.0 * stats.jump as f32 / 100.0;
 
         // Only allow player movement when chat is not focused, menu is not open, and NPC dialog is not open
-        let can_move = !self.status_bar.is_chat_focused() && !self.game_menu.is_visible() && !self.npc_dialog.is_visible();
+        // Dead characters can't act until they respawn
+        let can_move = !self.status_bar.is_chat_focused() && !self.game_menu.is_visible() && !self.npc_dialog.is_visible()
+            && !self.npc_shop.is_visible() && !self.npc_storage.is_visible() && self.character.hp > 0;
+
+        // Lying down with Down while on the ground (Alt+Down drops through the platform instead)
+        let prone = can_move && !free_roam && self.on_ground && !self.on_ladder
+            && (is_key_down(KeyCode::Down) || is_key_down(KeyCode::S))
+            && !(is_key_down(KeyCode::LeftAlt) || is_key_down(KeyCode::RightAlt));
+
+        // Stand up from a sit on any movement key
+        if self.sitting && (!can_move || !self.on_ground || is_key_down(KeyCode::Left) || is_key_down(KeyCode::Right)
+            || is_key_down(KeyCode::A) || is_key_down(KeyCode::D) || is_key_down(KeyCode::LeftAlt) || is_key_down(KeyCode::RightAlt))
+        {
+            self.sitting = false;
+        }
+
+        // No walking while lying down, sitting or attacking on the ground
+        let stance_locked = !free_roam
+            && (prone || self.sitting || (self.on_ground && self.character_renderer.is_attacking()));
 
         // Track if movement was blocked by a wall (used to prevent auto-snapping to platforms)
         let mut movement_blocked = false;
@@ -643,7 +1572,7 @@ impl GameplayState {
             }
             
             // Apply horizontal movement - only block if a wall is detected
-            if is_key_down(KeyCode::Left) || is_key_down(KeyCode::A) {
+            if !stance_locked && (is_key_down(KeyCode::Left) || is_key_down(KeyCode::A)) {
                 let new_x = self.player_x - move_speed * clamped_dt;
                 // Only block if there's a wall on the left
                 // Account for player width (half width on each side)
@@ -664,7 +1593,7 @@ impl GameplayState {
                     self.player_x = new_x.max(self.foothold_min_x);
                 }
             }
-            if is_key_down(KeyCode::Right) || is_key_down(KeyCode::D) {
+            if !stance_locked && (is_key_down(KeyCode::Right) || is_key_down(KeyCode::D)) {
                 let new_x = self.player_x + move_speed * clamped_dt;
                 // Only block if there's a wall on the right
                 // Account for player width (half width on each side)
@@ -697,6 +1626,41 @@ impl GameplayState {
             }
         }
         
+        // Ctrl attacks (stabbing while prone); X sits down or stands up; skill keys cast their skills
+        let mut pending_attack = None;
+        let mut pending_skill = None;
+        let mut pending_pickup = false;
+        let mut pending_item = None;
+        if can_move {
+            for (key, item_id) in ITEM_HOTKEYS.iter().zip(self.character.item_keys) {
+                if is_key_pressed(*key) && item_id.is_some() {
+                    pending_item = item_id;
+                }
+            }
+        }
+        if can_move && !free_roam && !self.on_ladder {
+            for (key, skill_id) in SKILL_HOTKEYS.iter().zip(self.character.skill_keys) {
+                if is_key_pressed(*key) && skill_id.is_some() && !prone {
+                    pending_skill = skill_id;
+                }
+            }
+            if is_key_pressed(KeyCode::LeftControl) || is_key_pressed(KeyCode::RightControl) {
+                let attack = if prone {
+                    CharacterState::ProneStab
+                } else {
+                    CharacterState::attack_for_weapon(self.character.equipment.get(EquipSlot::Weapon))
+                };
+                if self.character_renderer.request_stance(attack) {
+                    self.sitting = false;
+                    pending_attack = Some(attack);
+                }
+            }
+            if is_key_pressed(KeyCode::X) && self.on_ground && !prone && !self.character_renderer.is_attacking() {
+                self.sitting = !self.sitting;
+            }
+            pending_pickup = is_key_pressed(KeyCode::Z);
+        }
+
         // Handle player double-click (show UserInfo window)
         if is_mouse_button_pressed(MouseButton::Left) {
             let (mouse_x, mouse_y) = mouse_position();
@@ -725,7 +1689,8 @@ impl GameplayState {
         
         // Handle NPC double-click interaction
         // Extract NPC interaction data first to avoid borrow conflicts
-        let npc_interaction_data = if is_mouse_button_pressed(MouseButton::Left) {
+        let npc_interaction_data = if is_mouse_button_pressed(MouseButton::Left)
+            && !self.npc_shop.is_visible() && !self.npc_storage.is_visible() {
             let (mouse_x, mouse_y) = mouse_position();
             let world_x = mouse_x + self.camera_x;
             let world_y = mouse_y + self.camera_y;
@@ -790,7 +1755,7 @@ impl GameplayState {
 
         // Store NPC interaction to execute after map borrow is released
         let pending_npc_command = if let Some((npc_id, npc_name, npc_texture)) = npc_interaction_data {
-            let cmd = self.npc_script_engine.start_npc(npc_id);
+            let cmd = self.npc_script_engine.start_npc(npc_id, &self.character);
             self.last_npc_click_time = -1.0;
             self.last_npc_click_id = None;
             Some((cmd, npc_name, npc_texture))
@@ -799,6 +1764,8 @@ impl GameplayState {
         };
 
         // Portal interaction or ladder grab - Check if player is near a portal/ladder and presses Up
+        let mut pending_portal_script = None;
+        let mut pending_warp = None;
         if can_move && is_key_pressed(KeyCode::Up) && !free_roam {
             // Find nearby portals (within 40 pixels)
             let nearby_portal = map.portals.iter().find(|portal| {
@@ -812,21 +1779,12 @@ impl GameplayState {
                 info!("Player activated portal: '{}' -> map {} portal '{}'",
                       portal.pn, portal.tm, portal.tn);
 
-                // Only teleport if target map is valid (not 999999999)
-                if portal.tm != 999999999 {
-                    let target_map_id = format!("{:09}", portal.tm);
-                    let target_portal_name = portal.tn.clone();
-                    info!("Teleporting to map: {} at portal '{}'", target_map_id, target_portal_name);
-
-                    // Set target portal name for spawning in the new map
-                    self.target_portal_name = if !target_portal_name.is_empty() {
-                        Some(target_portal_name)
-                    } else {
-                        None // Use spawn portal if no target portal specified
-                    };
-
-                    self.loading_new_map = true;
-                    self.map_input = target_map_id;
+                if !portal.script.is_empty() && script_runtime::has_script(&script_runtime::portal_script_path(&portal.script)) {
+                    // Script portals run their script instead, once the map borrow is released
+                    pending_portal_script = Some(portal.script.clone());
+                } else if portal.tm != 999999999 {
+                    // Only teleport if target map is valid (not 999999999)
+                    pending_warp = Some((portal.tm, portal.tn.clone()));
                 } else {
                     // info!("Portal has no target map (tm = 999999999)");
                 }
@@ -980,13 +1938,13 @@ impl GameplayState {
                     }
                 } else if alt_pressed && self.on_ground && !down_pressed {
                     // Normal jump (Alt without Down)
-                    self.player_vy = -400.0; // Jump velocity
+                    self.player_vy = jump_velocity;
                     self.on_ground = false;
                 }
             }
 
             // Apply gravity
-            if flags::ENABLE_COLLISION && !flags::GOD_MODE {
+            if config().enable_collision && !config().god_mode {
                 let gravity = 800.0;
                 self.player_vy += gravity * clamped_dt;
             }
@@ -996,7 +1954,7 @@ impl GameplayState {
 
             // Check collision with footholds (only for vertical positioning, not horizontal limits)
             // Don't auto-snap to platforms when movement is blocked by a wall
-            if flags::ENABLE_COLLISION {
+            if config().enable_collision {
                 if !self.drop_through_platform {
                     // Simple collision: find foothold at position or below
                     // Only snap to footholds at the player's current X position to prevent teleportation
@@ -1084,15 +2042,29 @@ impl GameplayState {
 
         // Update bot AI
         self.bot_ai.update(clamped_dt, map);
+        self.drops.update(clamped_dt, map, self.player_x, self.player_y);
+        self.reactors.update(clamped_dt, map);
+        if self.quests_dirty {
+            self.quests_dirty = false;
+            self.quest_marks.refresh(map, &self.character);
+        }
+        self.quest_marks.update(clamped_dt);
 
         // Update damage cooldown
         if self.damage_cooldown > 0.0 {
             self.damage_cooldown -= clamped_dt;
         }
 
-        // Check mob collision for damage
-        if self.damage_cooldown <= 0.0 {
-            for mob in self.bot_ai.get_mobs() {
+        // Check mob collision for damage (god mode can't be hurt)
+        if self.damage_cooldown <= 0.0 && !config().god_mode {
+            let defense = stats.defense;
+            for mob in self.bot_ai.get_mobs().iter().filter(|mob| mob.is_alive()) {
+                // Only mobs with bodyAttack hurt on touch
+                let Some(stats) = map.life.get(mob.life_index).map(|life| life.stats) else { continue };
+                if !stats.body_attack {
+                    continue;
+                }
+
                 let mob_half_width = 20.0;
                 let mob_height = 40.0;
                 let player_half_width = 15.0;
@@ -1111,11 +2083,19 @@ impl GameplayState {
                 
                 if player_right > mob_left && player_left < mob_right &&
                    player_bottom > mob_top && player_top < mob_bottom {
-                    // Collision! Take 1 damage
+                    // Collision! Take the mob's touch damage
                     if self.character.hp > 0 {
-                        self.character.hp = self.character.hp.saturating_sub(1);
+                        let damage = combat::touch_damage(&stats, defense);
+                        self.character.hp = self.character.hp.saturating_sub(damage as u32);
                         self.damage_cooldown = 1.0; // 1 second invincibility
+                        self.sitting = false;
+                        self.damage_numbers.spawn(damage, self.player_x, self.player_y - 60.0, DamageStyle::ToPlayer);
+                        self.character_renderer.request_stance(CharacterState::Alert);
+                        self.character_renderer.set_expression("hit");
                         info!("Player hit by mob! HP: {}", self.character.hp);
+                        if self.character.hp == 0 {
+                            self.skills.clear();
+                        }
                     }
                     break;
                 }
@@ -1169,7 +2149,7 @@ impl GameplayState {
         self.cursor_manager.set_state(cursor_state);
 
         // Camera follows player (unless in camera debug mode)
-        if !flags::CAMERA_DEBUG_MODE {
+        if !config().camera_debug_mode {
             // Center camera on player
             let target_camera_x = self.player_x - screen_width() / 2.0;
             let target_camera_y = self.player_y - screen_height() / 2.0;
@@ -1224,14 +2204,58 @@ impl GameplayState {
         self.minimap.update();
 
         // Update UI windows
-        self.inventory_window.update();
-        self.equip_window.update();
+        match self.inventory_window.update(&mut self.character.inventory) {
+            Some(InventoryEvent::Moved) => {
+                let _ = self.save_character();
+            }
+            Some(InventoryEvent::DoubleClicked(InventoryTab::Equip, slot)) => self.equip_from_inventory(slot),
+            Some(InventoryEvent::DoubleClicked(InventoryTab::Use, slot)) => {
+                pending_item = self.character.inventory.get(InventoryTab::Use, slot).map(|item| item.item_id);
+            }
+            _ => {}
+        }
+        if let Some(slot) = self.equip_window.update(&self.character.equipment) {
+            self.unequip(slot);
+        }
         self.user_info_window.update();
+        if let Some(stat) = self.stat_window.update(&self.character) {
+            self.spend_ap(stat);
+        }
+        match self.skill_window.update(&self.character) {
+            Some(SkillEvent::LevelUp(skill_id)) => self.spend_sp(skill_id),
+            Some(SkillEvent::Use(skill_id)) if can_move => pending_skill = Some(skill_id),
+            Some(SkillEvent::Bind(skill_id, slot)) => self.bind_skill(skill_id, slot),
+            _ => {}
+        }
+        self.quest_window.update(&self.character);
+        if let Some(event) = self.npc_shop.update(&self.character.inventory) {
+            self.handle_shop_event(event);
+        }
+        if let Some(event) = self.npc_storage.update(&self.character.inventory, &self.trunk) {
+            self.handle_storage_event(event);
+        }
+        for source in self.skills.update(clamped_dt) {
+            match source {
+                EffectSource::Skill(skill_id) => {
+                    if let Some(skill) = skill_info::skill_info(skill_id) {
+                        self.status_bar.add_system_message(&format!("{} has worn off.", skill.name));
+                    }
+                }
+                EffectSource::Item(item_id) => {
+                    self.status_bar.add_system_message(&format!("The effect of item {} has worn off.", item_id));
+                }
+            }
+        }
+        self.item_cooldown = (self.item_cooldown - clamped_dt).max(0.0);
 
         // Update new UI components
         self.cash_shop.update();
-        self.key_config.update();
+        if let Some(event) = self.key_config.update(&self.character.inventory, &self.character.item_keys) {
+            self.bind_item(event);
+        }
         self.chat_balloon.update(clamped_dt);
+        self.damage_numbers.update(clamped_dt);
+        self.effects.update(clamped_dt, self.player_x, self.player_y);
         self.game_menu.update();
         self.npc_dialog.update();
 
@@ -1254,7 +2278,7 @@ impl GameplayState {
                 (String::new(), None)
             };
 
-            let cmd = self.npc_script_engine.handle_response(response);
+            let cmd = self.npc_script_engine.handle_response(response, &self.character);
             self.execute_script_command_with_npc(cmd, npc_name, npc_texture);
         }
 
@@ -1265,20 +2289,32 @@ impl GameplayState {
         self.messenger_window.update();
 
         // Update character renderer
-        let character_state = if self.on_ladder {
-            CharacterState::Stand  // Standing on ladder
+        let on_rope = self.current_ladder_id.and_then(|id| {
+            self.map_data.as_ref()?.ladders.iter().find(|lad| lad.id == id).map(|lad| !lad.ladder)
+        }).unwrap_or(false);
+        let walking = can_move && !stance_locked && (is_key_down(KeyCode::Left) || is_key_down(KeyCode::Right) ||
+                                                     is_key_down(KeyCode::A) || is_key_down(KeyCode::D));
+        let climbing = can_move && (is_key_down(KeyCode::Up) || is_key_down(KeyCode::Down) ||
+                                    is_key_down(KeyCode::W) || is_key_down(KeyCode::S));
+        let character_state = if self.character.hp == 0 {
+            CharacterState::Dead
+        } else if self.on_ladder {
+            if on_rope { CharacterState::Rope } else { CharacterState::Ladder }
         } else if !self.on_ground && self.player_vy < 0.0 {
             CharacterState::Jump
         } else if !self.on_ground && self.player_vy > 0.0 {
             CharacterState::Fall
+        } else if prone {
+            CharacterState::Prone
+        } else if self.sitting {
+            CharacterState::Sit
+        } else if walking {
+            CharacterState::Move
         } else {
-            if can_move && (is_key_down(KeyCode::Left) || is_key_down(KeyCode::Right) || 
-                            is_key_down(KeyCode::A) || is_key_down(KeyCode::D)) {
-                CharacterState::Move
-            } else {
-                CharacterState::Stand
-            }
+            CharacterState::Stand
         };
+        // Climbing frames only advance while the character is moving on the ladder
+        let anim_dt = if self.on_ladder && !climbing { 0.0 } else { clamped_dt };
         
         // Update facing direction only when actively pressing a direction key
         if is_key_down(KeyCode::Left) || is_key_down(KeyCode::A) {
@@ -1286,12 +2322,15 @@ impl GameplayState {
         } else if is_key_down(KeyCode::Right) || is_key_down(KeyCode::D) {
             self.facing_right = true;
         }
-        self.character_renderer.update(clamped_dt, character_state, self.facing_right);
+        self.character_renderer.update(anim_dt, character_state, self.facing_right);
 
         // Handle game menu actions
         match self.game_menu.take_action() {
             MenuAction::Inventory => self.inventory_window.toggle(),
             MenuAction::Equip => self.equip_window.toggle(),
+            MenuAction::Stat => self.stat_window.toggle(),
+            MenuAction::Skill => self.skill_window.toggle(),
+            MenuAction::Quest => self.quest_window.toggle(),
             MenuAction::KeyConfig => self.key_config.toggle(),
             MenuAction::Channel => self.channel_window.toggle(),
             MenuAction::Messenger => self.messenger_window.toggle(),
@@ -1308,6 +2347,10 @@ impl GameplayState {
                 self.cash_shop.hide();
             } else if self.npc_dialog.is_visible() {
                 self.npc_dialog.close_dialog();
+            } else if self.npc_shop.is_visible() {
+                self.npc_shop.close();
+            } else if self.npc_storage.is_visible() {
+                self.npc_storage.close();
             } else if self.game_menu.is_visible() {
                 self.game_menu.hide();
             } else if self.megaphone_window.is_visible() {
@@ -1326,6 +2369,12 @@ impl GameplayState {
                 self.key_config.hide();
             } else if self.user_info_window.visible {
                 self.user_info_window.visible = false;
+            } else if self.stat_window.visible {
+                self.stat_window.visible = false;
+            } else if self.skill_window.visible {
+                self.skill_window.visible = false;
+            } else if self.quest_window.visible {
+                self.quest_window.visible = false;
             }
         }
 
@@ -1339,6 +2388,10 @@ impl GameplayState {
             if is_key_pressed(KeyCode::E) {
                 self.equip_window.toggle();
             }
+            // Q key - toggle quest log
+            if is_key_pressed(KeyCode::Q) {
+                self.quest_window.toggle();
+            }
             // K key - toggle key config
             if is_key_pressed(KeyCode::K) {
                 self.key_config.toggle();
@@ -1351,6 +2404,13 @@ impl GameplayState {
             if is_key_pressed(KeyCode::T) {
                 self.megaphone_window.toggle();
             }
+            // F1-F7 - face expressions
+            let expression_keys = [KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6, KeyCode::F7];
+            for (key, expression) in expression_keys.iter().zip(&EXPRESSIONS[2..]) {
+                if is_key_pressed(*key) {
+                    self.character_renderer.set_expression(expression);
+                }
+            }
         }
 
         // Handle status bar button clicks
@@ -1373,6 +2433,58 @@ impl GameplayState {
         if let Some((cmd, npc_name, npc_texture)) = pending_npc_command {
             self.execute_script_command_with_npc(cmd, npc_name, npc_texture);
         }
+        if let Some((map_id, portal)) = pending_warp {
+            self.warp_to(map_id, Some(portal));
+        }
+        if let Some(script) = pending_portal_script {
+            if let Some(cmd) = self.npc_script_engine.start_portal_script(&script, &self.character) {
+                self.execute_script_command_with_npc(cmd, String::new(), None);
+            }
+        }
+
+        if let Some(attack) = pending_attack {
+            self.resolve_attack(attack);
+        }
+        if let Some(skill_id) = pending_skill {
+            self.cast_skill(skill_id);
+        }
+        if pending_pickup {
+            self.pick_up_drop();
+        }
+        if let Some(item_id) = pending_item {
+            self.use_item(item_id);
+        }
+
+        // Dead players (also ones saved while dead) go back to town after a moment
+        if self.character.hp == 0 {
+            let timer = match self.respawn_timer {
+                Some(timer) => timer - clamped_dt,
+                None => {
+                    self.status_bar.add_system_message("You have died. Returning to town...");
+                    RESPAWN_DELAY
+                }
+            };
+            self.respawn_timer = Some(timer);
+            if timer <= 0.0 {
+                self.respawn();
+            }
+        }
+    }
+
+    /// Bring a dead player back with a little HP, in the map's return map (this map if it has none)
+    fn respawn(&mut self) {
+        self.respawn_timer = None;
+        self.character.hp = RESPAWN_HP.min(self.derived_stats().max_hp);
+        let return_map = self
+            .map_data
+            .as_ref()
+            .map(|map| map.info.return_map)
+            .filter(|&map_id| map_id != 999999999)
+            .or_else(|| self.current_map_id.parse().ok());
+        if let Some(map_id) = return_map {
+            self.warp_to(map_id, None);
+        }
+        let _ = self.save_character();
     }
 
     /// Draw the game
@@ -1396,32 +2508,18 @@ impl GameplayState {
         if let Some(ref map) = self.map_data {
             // Render map backgrounds (behind player)
             self.map_renderer.render(map, self.camera_x, self.camera_y, Some(&self.bot_ai), Some(&self.character_renderer));
+            self.reactors.draw(map, self.camera_x, self.camera_y);
+            self.drops.draw(self.camera_x, self.camera_y);
+            self.quest_marks.draw(map, self.camera_x, self.camera_y);
 
             // Draw player using character renderer
             let player_screen_x = self.player_x - self.camera_x;
             let player_screen_y = self.player_y - self.camera_y;
             
-            // Determine character state for rendering (same logic as update)
-            let character_state = if self.on_ladder {
-                CharacterState::Stand
-            } else if !self.on_ground && self.player_vy < 0.0 {
-                CharacterState::Jump
-            } else if !self.on_ground && self.player_vy > 0.0 {
-                CharacterState::Fall
-            } else {
-                let can_move = !self.status_bar.is_chat_focused() && !self.game_menu.is_visible();
-                if can_move && (is_key_down(KeyCode::Left) || is_key_down(KeyCode::Right) || 
-                                is_key_down(KeyCode::A) || is_key_down(KeyCode::D)) {
-                    CharacterState::Move
-                } else {
-                    CharacterState::Stand
-                }
-            };
-            
-            self.character_renderer.draw(player_screen_x, player_screen_y, character_state);
+            self.character_renderer.draw(player_screen_x, player_screen_y);
 
             // Draw player hitbox if enabled
-            if flags::SHOW_HITBOXES {
+            if config().show_hitboxes {
                 draw_rectangle_lines(
                     player_screen_x - 15.0,
                     player_screen_y - 30.0,
@@ -1464,7 +2562,9 @@ impl GameplayState {
             // Render map foregrounds (in front of player)
             self.map_renderer.render_foreground(map, self.camera_x, self.camera_y, Some(&self.bot_ai));
             
-            // Draw chat balloons (above NPCs/mobs)
+            // Draw damage numbers and chat balloons (above NPCs/mobs)
+            self.effects.draw(self.camera_x, self.camera_y);
+            self.damage_numbers.draw(self.camera_x, self.camera_y);
             self.chat_balloon.draw(self.camera_x, self.camera_y);
             
             // Draw NPC dialog window (on top of everything)
@@ -1479,6 +2579,7 @@ impl GameplayState {
 
         // Draw status bar UI
         self.status_bar.draw(&self.character);
+        self.status_bar.draw_buffs(self.skills.buffs());
 
         // Draw minimap
         if let Some(map) = &self.map_data {
@@ -1486,12 +2587,17 @@ impl GameplayState {
         }
 
         // Draw UI windows
-        self.inventory_window.draw();
-        self.equip_window.draw();
-        self.user_info_window.draw(&self.character.name, self.character.level);
+        self.inventory_window.draw(&self.character.inventory, self.character.meso);
+        self.equip_window.draw(&self.character.equipment);
+        self.user_info_window.draw(&self.character.name, self.character.level, self.character.job);
+        self.stat_window.draw(&self.character, &self.derived_stats());
+        self.skill_window.draw(&self.character);
+        self.quest_window.draw(&self.character);
+        self.npc_shop.draw(&self.character.inventory, self.character.meso);
+        self.npc_storage.draw(&self.character.inventory, &self.trunk, self.character.meso);
 
         // Draw new UI windows
-        self.key_config.draw();
+        self.key_config.draw(&self.character.inventory, &self.character.item_keys);
         self.game_menu.draw();
         
         // Draw social windows
@@ -1508,6 +2614,9 @@ impl GameplayState {
         draw_rectangle(5.0, 5.0, 200.0, 20.0, Color::from_rgba(0, 0, 0, 180));
         draw_text(&coords_text, 10.0, 20.0, 14.0, YELLOW);
 
+        // Fade between maps covers everything but the cursor
+        self.transition.draw();
+
         // Draw custom MapleStory cursor (drawn last so it's on top)
         self.cursor_manager.draw();
     }
@@ -1515,7 +2624,7 @@ impl GameplayState {
     /// Draw the game UI
     fn draw_ui(&self) {
         // Draw FPS counter in top-right corner (always visible if SHOW_FPS is enabled)
-        if flags::SHOW_FPS {
+        if config().show_fps {
             let fps = get_fps();
             let fps_text = format!("FPS: {}", fps);
             let text_width = measure_text(&fps_text, None, 14, 1.0).width;
@@ -1559,8 +2668,13 @@ impl GameplayState {
             let mp_text = format!("MP: {}/{}", self.character.mp, self.character.mp);
             draw_text(&mp_text, panel_x + 10.0, panel_y + 85.0, 16.0, BLUE);
 
+            // Equipment bonuses
+            let bonus = self.character.equipment.bonus_stats();
+            let equip_text = format!("ATT +{} MATT +{} DEF +{} MDEF +{}", bonus.inc_pad, bonus.inc_mad, bonus.inc_pdd, bonus.inc_mdd);
+            draw_text(&equip_text, panel_x + 10.0, panel_y + 105.0, 14.0, ORANGE);
+
             // Debug info
-            let mut y_offset = panel_y + 105.0;
+            let mut y_offset = panel_y + 125.0;
             let line_height = 20.0;
 
             // Map ID and name
@@ -1609,11 +2723,27 @@ impl GameplayState {
                 y_offset += line_height;
             }
 
-            if flags::GOD_MODE {
+            // WZ image cache usage
+            let cache_stats = wz_cache::stats();
+            let cache_text = format!("WZ: {} imgs {:.0}MB hit {:.0}%",
+                cache_stats.entries,
+                cache_stats.used_bytes as f32 / (1024.0 * 1024.0),
+                cache_stats.hit_rate() * 100.0);
+            draw_text(&cache_text, panel_x + 10.0, y_offset, 14.0, GRAY);
+            y_offset += line_height;
+
+            if config().god_mode {
                 draw_text("GOD MODE", panel_x + 10.0, y_offset, 14.0, RED);
                 y_offset += line_height;
             }
 
+            // Running buffs
+            for buff in self.skills.buffs().iter() {
+                let buff_text = format!("Buff {:?} Lv.{} ({:.0}s)", buff.source, buff.level, buff.remaining);
+                draw_text(&buff_text, panel_x + 10.0, y_offset, 14.0, SKYBLUE);
+                y_offset += line_height;
+            }
+
             // Movement debug
             let chat_focused = self.status_bar.is_chat_focused();
             let menu_visible = self.game_menu.is_visible();
@@ -1626,7 +2756,7 @@ impl GameplayState {
         }
 
         // Show hitboxes if enabled
-        if flags::SHOW_HITBOXES {
+        if config().show_hitboxes {
             let player_screen_x = self.player_x - self.camera_x;
             let player_screen_y = self.player_y - self.camera_y;
             draw_rectangle_lines(
@@ -1640,9 +2770,11 @@ impl GameplayState {
         }
 
         // Debug map loader UI - always show if SHOW_MAP_LOADER is enabled
-        if flags::SHOW_MAP_LOADER {
+        if config().show_map_loader {
             self.draw_map_loader_ui();
         }
+
+        self.debug_console.draw();
     }
 
     /// Draw the debug map loader UI
@@ -1726,8 +2858,20 @@ impl GameplayState {
         }
     }
 
-    /// Execute NPC script command with NPC info
-    fn execute_script_command_with_npc(&mut self, cmd: NpcScriptCommand, npc_name: String, npc_texture: Option<Texture2D>) {
+    /// The player's current look with one style swapped, for previewing it in a style dialog
+    fn styled_look(&self, style_type: StyleType, style_id: i32) -> CharacterLook {
+        let mut look = CharacterLook::from_character(&self.character);
+        match style_type {
+            StyleType::Hair => look.hair = style_id,
+            StyleType::Face => look.face = style_id,
+            StyleType::Skin => look.skin = style_id,
+        }
+        look
+    }
+
+    /// Execute NPC script command with NPC info. Returns false if the player couldn't pay for it
+    /// (missing items or mesos), in which case the rest of its sequence is skipped.
+    fn execute_script_command_with_npc(&mut self, cmd: NpcScriptCommand, npc_name: String, npc_texture: Option<Texture2D>) -> bool {
         match cmd {
             NpcScriptCommand::ShowDialog { text, dialog_type } => {
                 self.npc_dialog.show_dialog_typed(
@@ -1750,35 +2894,119 @@ impl GameplayState {
             }
             NpcScriptCommand::GiveItem(id, qty) => {
                 info!("Script: Give item {} x{}", id, qty);
-                // TODO: Implement inventory system interaction
+                // Added in handle_pending_items once the item's slotMax is known
+                self.pending_item_grants.push((id, qty.max(0) as u32));
                 self.npc_dialog.close_dialog();
             }
             NpcScriptCommand::GiveMeso(amount) => {
                 info!("Script: Give {} meso", amount);
-                // TODO: Implement meso addition
+                match self.character.add_meso(amount) {
+                    Ok(()) => {
+                        self.status_bar.add_system_message(&format!("You have gained mesos ({:+})", amount));
+                        let _ = self.save_character();
+                    }
+                    Err(e) => {
+                        warn!("Script: {}", e);
+                        self.status_bar.add_system_message(&e);
+                        self.npc_dialog.close_dialog();
+                        return false;
+                    }
+                }
                 self.npc_dialog.close_dialog();
             }
             NpcScriptCommand::GiveExp(amount) => {
                 info!("Script: Give {} exp", amount);
-                // TODO: Implement exp addition
+                self.gain_exp(amount.max(0) as u32);
                 self.npc_dialog.close_dialog();
             }
             NpcScriptCommand::TakeItem(id, qty) => {
                 info!("Script: Take item {} x{}", id, qty);
-                // TODO: Implement inventory system interaction
+                match self.character.inventory.remove(id, qty.max(0) as u32) {
+                    Ok(()) => {
+                        let _ = self.save_character();
+                    }
+                    Err(e) => {
+                        warn!("Script: {}", e);
+                        self.status_bar.add_system_message("You don't have the required items.");
+                        self.npc_dialog.close_dialog();
+                        return false;
+                    }
+                }
                 self.npc_dialog.close_dialog();
             }
-            NpcScriptCommand::Warp(map_id) => {
+            NpcScriptCommand::StartQuest(quest_id) => {
+                self.start_quest(quest_id);
+                self.npc_dialog.close_dialog();
+            }
+            NpcScriptCommand::CompleteQuest(quest_id) => {
+                self.complete_quest(quest_id);
+                self.npc_dialog.close_dialog();
+            }
+            NpcScriptCommand::Message(text) => {
+                self.status_bar.add_system_message(&text);
+            }
+            NpcScriptCommand::OpenShop(npc_id) => {
+                info!("Script: Open shop {}", npc_id);
+                self.npc_dialog.close_dialog();
+                if shop_table::has_shop(npc_id) {
+                    self.npc_shop.open(npc_id, &npc_name);
+                } else {
+                    warn!("Script: NPC {} has no shop", npc_id);
+                }
+            }
+            NpcScriptCommand::OpenStorage => {
+                info!("Script: Open storage");
+                self.npc_dialog.close_dialog();
+                // Another character of the account may have changed it since it was last read
+                match Trunk::load() {
+                    Ok(trunk) => {
+                        self.trunk = trunk;
+                        self.npc_storage.open(&npc_name);
+                    }
+                    Err(e) => {
+                        error!("Failed to load trunk: {}", e);
+                        self.status_bar.add_system_message("Your storage could not be opened.");
+                    }
+                }
+            }
+            NpcScriptCommand::Warp(map_id, portal) => {
                 info!("Script: Warp to map {}", map_id);
-                // TODO: Implement map warp
                 self.npc_dialog.close_dialog();
+                self.warp_to(map_id, portal);
             }
             NpcScriptCommand::ShowStyle { text, style_type, available_styles } => {
                 info!("Script: Show style dialog {:?} with {} options", style_type, available_styles.len());
-                // TODO: Implement style dialog in Phase 4
+                let options = available_styles
+                    .into_iter()
+                    .map(|style_id| (style_id, self.styled_look(style_type, style_id)))
+                    .collect();
+                self.npc_dialog.show_style(&text, &npc_name, npc_texture, options);
+            }
+            NpcScriptCommand::ChangeJob(job) => {
+                info!("Script: Change job to {:?}", job);
+                self.advance_job(job);
+                self.npc_dialog.close_dialog();
+            }
+            NpcScriptCommand::SetStyle(style_type, style_id) => {
+                info!("Script: Set {:?} to {}", style_type, style_id);
+                match style_type {
+                    StyleType::Hair => self.character.hair = style_id,
+                    StyleType::Face => self.character.face = style_id,
+                    StyleType::Skin => self.character.skin = style_id,
+                }
+                self.character_renderer.set_look(CharacterLook::from_character(&self.character));
+                let _ = self.save_character();
                 self.npc_dialog.close_dialog();
             }
+            NpcScriptCommand::Sequence(cmds) => {
+                for cmd in cmds {
+                    if !self.execute_script_command_with_npc(cmd, npc_name.clone(), npc_texture.clone()) {
+                        return false;
+                    }
+                }
+            }
             NpcScriptCommand::None => {}
         }
+        true
     }
 }
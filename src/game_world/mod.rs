@@ -1,5 +1,11 @@
 pub mod gameplay;
 pub mod bot_ai;
+pub mod combat;
+pub mod skills;
+pub mod drops;
+pub mod reactors;
+pub mod quests;
+pub mod transition;
 
 pub use gameplay::GameplayState;
 pub use bot_ai::BotAI;
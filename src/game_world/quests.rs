@@ -0,0 +1,108 @@
+use macroquad::prelude::*;
+use crate::assets::{AssetManager, FrameData};
+use crate::character::CharacterData;
+use crate::map::{Life, MapData};
+use crate::quest_info::{self, QuestMark};
+
+const QUEST_ICON_PATH: &str = "UI/UIWindow.img";
+
+/// Time each frame of a quest mark is shown (seconds)
+const MARK_FRAME_TIME: f32 = 0.15;
+
+/// Marks over the heads of NPCs that have a quest for the player
+pub struct QuestMarks {
+    /// `QuestIcon/0` (quest available) and `QuestIcon/2` (quest can be completed)
+    available_icon: Vec<FrameData>,
+    completable_icon: Vec<FrameData>,
+    /// Index into `MapData::life` of each marked NPC
+    marks: Vec<(usize, QuestMark)>,
+    age: f32,
+}
+
+impl QuestMarks {
+    pub fn new() -> Self {
+        Self {
+            available_icon: Vec::new(),
+            completable_icon: Vec::new(),
+            marks: Vec::new(),
+            age: 0.0,
+        }
+    }
+
+    pub async fn load_assets(&mut self) {
+        self.available_icon = AssetManager::load_animation_frames_with_origins(QUEST_ICON_PATH, "QuestIcon/0").await;
+        self.completable_icon = AssetManager::load_animation_frames_with_origins(QUEST_ICON_PATH, "QuestIcon/2").await;
+        if self.available_icon.is_empty() || self.completable_icon.is_empty() {
+            warn!("Quest icons missing, drawing plain marks");
+        }
+    }
+
+    /// Work out which NPCs on the map have quests for the character. Call after the map
+    /// changes and whenever quests, level, job or items change.
+    pub fn refresh(&mut self, map: &MapData, character: &CharacterData) {
+        self.marks.clear();
+        for (index, life) in map.life.iter().enumerate() {
+            if life.life_type != "n" || life.hide {
+                continue;
+            }
+            let Ok(npc_id) = life.id.parse::<i32>() else { continue };
+            let quests = quest_info::npc_quests(npc_id, character);
+            // A quest to hand in matters more than a new one
+            let mark = if quests.iter().any(|&(_, mark)| mark == QuestMark::Completable) {
+                QuestMark::Completable
+            } else if !quests.is_empty() {
+                QuestMark::Available
+            } else {
+                continue;
+            };
+            self.marks.push((index, mark));
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.age += dt;
+    }
+
+    pub fn draw(&self, map: &MapData, camera_x: f32, camera_y: f32) {
+        for &(index, mark) in &self.marks {
+            let Some(life) = map.life.get(index) else { continue };
+            let (x, feet_y) = npc_position(map, life);
+            let head_y = feet_y - if life.texture.is_some() { life.origin_y as f32 } else { 60.0 };
+            let x = x - camera_x;
+            let y = head_y - camera_y - 8.0;
+
+            let frames = match mark {
+                QuestMark::Available => &self.available_icon,
+                QuestMark::Completable => &self.completable_icon,
+            };
+            if frames.is_empty() {
+                let (text, color) = match mark {
+                    QuestMark::Available => ("!", YELLOW),
+                    QuestMark::Completable => ("?", Color::from_rgba(120, 220, 255, 255)),
+                };
+                draw_circle(x, y - 10.0, 10.0, Color::from_rgba(0, 0, 0, 160));
+                draw_text(text, x - 4.0, y - 3.0, 22.0, color);
+                continue;
+            }
+            let frame = &frames[(self.age / MARK_FRAME_TIME) as usize % frames.len()];
+            draw_texture(&frame.texture, x - frame.origin.x, y - frame.origin.y, WHITE);
+        }
+    }
+}
+
+impl Default for QuestMarks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Where an NPC stands: its spawn x, on its foothold if it has one
+fn npc_position(map: &MapData, life: &Life) -> (f32, f32) {
+    let x = life.x as f32;
+    let Some(fh) = map.footholds.iter().find(|fh| life.foothold != 0 && fh.id == life.foothold) else {
+        return (x, life.y as f32);
+    };
+    let dx = fh.x2 - fh.x1;
+    let y = if dx != 0 { fh.y1 + ((life.x - fh.x1) * (fh.y2 - fh.y1)) / dx } else { fh.y1 };
+    (x, y as f32)
+}
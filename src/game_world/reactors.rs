@@ -0,0 +1,171 @@
+use macroquad::prelude::*;
+use crate::map::{MapData, Reactor};
+
+/// Frame shown `time` seconds into an animation with these frame delays, staying on the last
+/// frame once it has played through unless `looping`
+fn frame_at(delays: &[f32], time: f32, looping: bool) -> usize {
+    let total: f32 = delays.iter().sum();
+    let mut time = if looping && total > 0.0 { time % total } else { time };
+    for (frame, delay) in delays.iter().enumerate() {
+        if time < *delay {
+            return frame;
+        }
+        time -= delay;
+    }
+    delays.len().saturating_sub(1)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ReactorPhase {
+    /// Playing the current state's animation on a loop
+    Idle,
+    /// Playing the current state's hit animation before moving on to `next`
+    Hit { next: usize },
+    /// Playing the end state's animation once before disappearing
+    Breaking,
+    /// Gone; comes back in `respawn` seconds if the reactor has a `reactorTime`
+    Broken { respawn: Option<f32> },
+}
+
+/// Where a reactor of the map is at
+struct ReactorInstance {
+    state: usize,
+    phase: ReactorPhase,
+    /// Seconds since the phase started
+    timer: f32,
+}
+
+impl ReactorInstance {
+    fn new() -> Self {
+        Self { state: 0, phase: ReactorPhase::Idle, timer: 0.0 }
+    }
+
+    /// Frame to draw right now, with its origin
+    fn frame<'a>(&self, reactor: &'a Reactor) -> Option<(&'a Texture2D, (i32, i32))> {
+        let state = reactor.states.get(self.state)?;
+        let (textures, origins, frame) = match self.phase {
+            ReactorPhase::Broken { .. } => return None,
+            ReactorPhase::Hit { .. } if !state.hit_textures.is_empty() => {
+                (&state.hit_textures, &state.hit_origins, frame_at(&state.hit_delays, self.timer, false))
+            }
+            ReactorPhase::Breaking => (&state.textures, &state.origins, frame_at(&state.delays, self.timer, false)),
+            _ => (&state.textures, &state.origins, frame_at(&state.delays, self.timer, true)),
+        };
+        let frame = frame.min(textures.len().checked_sub(1)?);
+        Some((&textures[frame], origins.get(frame).copied().unwrap_or_default()))
+    }
+
+    /// Where the current frame is drawn on the map
+    fn bounds(&self, reactor: &Reactor) -> Option<Rect> {
+        let (texture, (origin_x, origin_y)) = self.frame(reactor)?;
+        // Flipped frames are mirrored around the reactor's position
+        let origin_x = if reactor.flip { texture.width() - origin_x as f32 } else { origin_x as f32 };
+        Some(Rect::new(
+            reactor.x as f32 - origin_x,
+            reactor.y as f32 - origin_y as f32,
+            texture.width(),
+            texture.height(),
+        ))
+    }
+}
+
+/// Whether reaching `state` breaks the reactor
+fn breaks_at(reactor: &Reactor, state: usize) -> bool {
+    reactor.states.get(state).map_or(true, |state| state.end)
+}
+
+/// A broken reactor, coming back after the map's `reactorTime` if it has one
+fn broken(reactor: &Reactor) -> ReactorPhase {
+    let respawn = (reactor.reactor_time > 0).then_some(reactor.reactor_time as f32);
+    ReactorPhase::Broken { respawn }
+}
+
+/// Reactors on the current map: their states, hit and break animations, and respawn timers
+pub struct ReactorSystem {
+    reactors: Vec<ReactorInstance>,
+}
+
+impl ReactorSystem {
+    pub fn new() -> Self {
+        Self { reactors: Vec::new() }
+    }
+
+    /// Start over on a new map, with every reactor in its first state
+    pub fn set_map(&mut self, map: &MapData) {
+        self.reactors = map.reactors.iter().map(|_| ReactorInstance::new()).collect();
+    }
+
+    /// Play animations, finish hits and bring broken reactors back after their `reactorTime`
+    pub fn update(&mut self, dt: f32, map: &MapData) {
+        for (reactor, instance) in map.reactors.iter().zip(&mut self.reactors) {
+            instance.timer += dt;
+            let state = reactor.states.get(instance.state);
+            match instance.phase {
+                ReactorPhase::Idle => {}
+                ReactorPhase::Hit { next } => {
+                    if instance.timer >= state.map_or(0.0, |state| state.hit_delays.iter().sum()) {
+                        instance.timer = 0.0;
+                        // A hit leading to a state that wasn't loaded has nothing left to play
+                        instance.phase = match reactor.states.get(next) {
+                            Some(next_state) => {
+                                instance.state = next;
+                                if next_state.end { ReactorPhase::Breaking } else { ReactorPhase::Idle }
+                            }
+                            None => broken(reactor),
+                        };
+                    }
+                }
+                ReactorPhase::Breaking => {
+                    if instance.timer >= state.map_or(0.0, |state| state.delays.iter().sum()) {
+                        instance.phase = broken(reactor);
+                    }
+                }
+                ReactorPhase::Broken { respawn: Some(respawn) } => {
+                    let respawn = respawn - dt;
+                    instance.phase = ReactorPhase::Broken { respawn: Some(respawn) };
+                    if respawn <= 0.0 {
+                        *instance = ReactorInstance::new();
+                    }
+                }
+                ReactorPhase::Broken { respawn: None } => {}
+            }
+        }
+    }
+
+    /// Hit every idle reactor inside an attack's range. Returns the indices (into `map.reactors`)
+    /// of the reactors this broke.
+    pub fn hit(&mut self, map: &MapData, hitbox: Rect) -> Vec<usize> {
+        let mut broken = Vec::new();
+        for (index, (reactor, instance)) in map.reactors.iter().zip(&mut self.reactors).enumerate() {
+            if instance.phase != ReactorPhase::Idle {
+                continue;
+            }
+            let Some(next) = reactor.states.get(instance.state).and_then(|state| state.next) else { continue };
+            if !instance.bounds(reactor).is_some_and(|bounds| bounds.overlaps(&hitbox)) {
+                continue;
+            }
+            instance.phase = ReactorPhase::Hit { next };
+            instance.timer = 0.0;
+            if breaks_at(reactor, next) {
+                broken.push(index);
+            }
+        }
+        broken
+    }
+
+    pub fn draw(&self, map: &MapData, camera_x: f32, camera_y: f32) {
+        for (reactor, instance) in map.reactors.iter().zip(&self.reactors) {
+            let (Some((texture, _)), Some(bounds)) = (instance.frame(reactor), instance.bounds(reactor)) else {
+                continue;
+            };
+            let params = DrawTextureParams { flip_x: reactor.flip, ..Default::default() };
+            draw_texture_ex(texture, bounds.x - camera_x, bounds.y - camera_y, WHITE, params);
+        }
+    }
+}
+
+impl Default for ReactorSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,148 @@
+use macroquad::prelude::*;
+use std::collections::HashMap;
+use crate::item_info::EquipStats;
+use crate::skill_info::SkillLevel;
+use crate::status_effect::{EffectSource, Stacking, StatModifiers, StatusEffect, StatusEffects};
+
+/// The client's default skill keys
+pub const SKILL_HOTKEYS: [KeyCode; 7] = [
+    KeyCode::LeftShift,
+    KeyCode::Insert,
+    KeyCode::Home,
+    KeyCode::PageUp,
+    KeyCode::Delete,
+    KeyCode::End,
+    KeyCode::PageDown,
+];
+
+/// Names of `SKILL_HOTKEYS`, shown in the skill window
+pub const SKILL_HOTKEY_LABELS: [&str; 7] = ["Shift", "Ins", "Home", "PgUp", "Del", "End", "PgDn"];
+
+/// Effect key of a skill's cast animation (`effect`)
+pub fn effect_key(skill_id: i32) -> String {
+    format!("skill/{}/effect", skill_id)
+}
+
+/// Effect key of the animation played on each mob a skill hits (`hit/0`)
+pub fn hit_key(skill_id: i32) -> String {
+    format!("skill/{}/hit", skill_id)
+}
+
+/// Skills whose hits stun mobs (Coma, Shout, Charged Blow, Cold Beam, Assaulter)
+const STUN_SKILLS: [i32; 6] = [1111005, 1111006, 1111008, 1211002, 2201004, 4211002];
+
+/// Skills that slow mobs down (Slow for both mage branches)
+const SLOW_SKILLS: [i32; 2] = [2101003, 2201003];
+
+/// How much Slow slows if the skill has no `x`
+const DEFAULT_SLOW: i32 = 50;
+
+/// How many casts of the same poison can run on a mob at once
+const MAX_POISON_STACKS: u32 = 3;
+
+/// Effect a buff skill puts on the player
+pub fn buff_effect(skill_id: i32, level: u32, values: &SkillLevel) -> StatusEffect {
+    let bonus = EquipStats {
+        inc_pad: values.pad,
+        inc_pdd: values.pdd,
+        inc_mad: values.mad,
+        inc_mdd: values.mdd,
+        inc_acc: values.acc,
+        inc_eva: values.eva,
+        inc_speed: values.speed,
+        inc_jump: values.jump,
+        ..Default::default()
+    };
+    StatusEffect::new(
+        EffectSource::Skill(skill_id),
+        level,
+        values.time as f32,
+        StatModifiers { bonus, ..Default::default() },
+    )
+}
+
+/// Debuff a skill leaves on the mobs it hits, if any (rolled against `prop` by the caller)
+pub fn mob_debuff(skill_id: i32, level: u32, values: &SkillLevel) -> Option<StatusEffect> {
+    let source = EffectSource::Skill(skill_id);
+    if values.dot > 0 {
+        let time = if values.dot_time > 0 { values.dot_time } else { values.time };
+        let modifiers = StatModifiers { damage_per_tick: values.dot, ..Default::default() };
+        return Some(
+            StatusEffect::new(source, level, time.max(1) as f32, modifiers).with_stacking(Stacking::Stack(MAX_POISON_STACKS)),
+        );
+    }
+    if values.time <= 0 {
+        return None;
+    }
+    if STUN_SKILLS.contains(&skill_id) {
+        let modifiers = StatModifiers { stun: true, ..Default::default() };
+        return Some(StatusEffect::new(source, level, values.time as f32, modifiers).with_stacking(Stacking::Refresh));
+    }
+    if SLOW_SKILLS.contains(&skill_id) {
+        let slow = if values.x != 0 { values.x.abs() } else { DEFAULT_SLOW };
+        let modifiers = StatModifiers { slow, ..Default::default() };
+        return Some(StatusEffect::new(source, level, values.time as f32, modifiers));
+    }
+    None
+}
+
+/// Cooldowns and running buffs of the player's skills (not saved)
+pub struct SkillState {
+    cooldowns: HashMap<i32, f32>,
+    buffs: StatusEffects,
+}
+
+impl SkillState {
+    pub fn new() -> Self {
+        Self {
+            cooldowns: HashMap::new(),
+            buffs: StatusEffects::new(),
+        }
+    }
+
+    /// Count down cooldowns and buffs. Returns the sources of buffs that ran out.
+    pub fn update(&mut self, dt: f32) -> Vec<EffectSource> {
+        for remaining in self.cooldowns.values_mut() {
+            *remaining -= dt;
+        }
+        self.cooldowns.retain(|_, remaining| *remaining > 0.0);
+        self.buffs.update(dt).expired
+    }
+
+    /// Seconds until a skill can be used again
+    pub fn cooldown(&self, skill_id: i32) -> f32 {
+        self.cooldowns.get(&skill_id).copied().unwrap_or(0.0)
+    }
+
+    pub fn start_cooldown(&mut self, skill_id: i32, seconds: f32) {
+        if seconds > 0.0 {
+            self.cooldowns.insert(skill_id, seconds);
+        }
+    }
+
+    /// Start a buff, following its stacking rule if it is already running
+    pub fn add_buff(&mut self, effect: StatusEffect) {
+        self.buffs.apply(effect);
+    }
+
+    pub fn buffs(&self) -> &StatusEffects {
+        &self.buffs
+    }
+
+    /// Combined stat bonuses of every running buff
+    pub fn buff_bonus(&self) -> EquipStats {
+        self.buffs.bonus()
+    }
+
+    /// Drop all buffs and cooldowns (on death)
+    pub fn clear(&mut self) {
+        self.cooldowns.clear();
+        self.buffs.clear();
+    }
+}
+
+impl Default for SkillState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
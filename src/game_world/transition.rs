@@ -0,0 +1,76 @@
+use macroquad::prelude::*;
+
+/// Time the screen takes to fade to black before a map change, and back after it (seconds)
+const FADE_TIME: f32 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    Idle,
+    FadingOut(f32),
+    /// Fully black with the loading screen up. The map is loaded once the screen has been
+    /// shown for a whole frame, since loading blocks drawing until it is done.
+    Loading { shown: bool },
+    FadingIn(f32),
+}
+
+/// Fade out, loading screen and fade in around every map change
+pub struct MapTransition {
+    phase: Phase,
+}
+
+impl MapTransition {
+    pub fn new() -> Self {
+        Self { phase: Phase::Idle }
+    }
+
+    /// Start fading out (a change already under way keeps going)
+    pub fn start(&mut self) {
+        if matches!(self.phase, Phase::Idle | Phase::FadingIn(_)) {
+            self.phase = Phase::FadingOut(0.0);
+        }
+    }
+
+    /// The screen is black and the loading screen has been shown, so the map can load
+    pub fn ready_to_load(&self) -> bool {
+        self.phase == Phase::Loading { shown: true }
+    }
+
+    /// The new map is in place (or failed to load): fade back in
+    pub fn finish(&mut self) {
+        self.phase = Phase::FadingIn(0.0);
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.phase = match self.phase {
+            Phase::FadingOut(t) if t + dt >= FADE_TIME => Phase::Loading { shown: false },
+            Phase::FadingOut(t) => Phase::FadingOut(t + dt),
+            Phase::Loading { .. } => Phase::Loading { shown: true },
+            Phase::FadingIn(t) if t + dt >= FADE_TIME => Phase::Idle,
+            Phase::FadingIn(t) => Phase::FadingIn(t + dt),
+            Phase::Idle => Phase::Idle,
+        };
+    }
+
+    /// Draw the fade over everything else
+    pub fn draw(&self) {
+        let alpha = match self.phase {
+            Phase::Idle => return,
+            Phase::FadingOut(t) => t / FADE_TIME,
+            Phase::Loading { .. } => 1.0,
+            Phase::FadingIn(t) => 1.0 - t / FADE_TIME,
+        };
+        draw_rectangle(0.0, 0.0, screen_width(), screen_height(), Color::new(0.0, 0.0, 0.0, alpha));
+
+        if let Phase::Loading { .. } = self.phase {
+            let text = "Loading...";
+            let size = measure_text(text, None, 24, 1.0);
+            draw_text(text, (screen_width() - size.width) / 2.0, screen_height() / 2.0, 24.0, WHITE);
+        }
+    }
+}
+
+impl Default for MapTransition {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,255 @@
+use serde::{Deserialize, Serialize};
+
+use crate::item_info::slot_max;
+
+/// Number of slots per tab for a new character
+pub const DEFAULT_SLOTS_PER_TAB: usize = 24;
+
+/// Inventory tabs, in the order they are shown in the inventory window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InventoryTab {
+    Equip,
+    Use,
+    Etc,
+    Setup,
+    Cash,
+}
+
+impl InventoryTab {
+    pub const ALL: [InventoryTab; 5] = [
+        InventoryTab::Equip,
+        InventoryTab::Use,
+        InventoryTab::Etc,
+        InventoryTab::Setup,
+        InventoryTab::Cash,
+    ];
+
+    /// Tab an item goes into, from the first digit of its id
+    pub fn from_item_id(item_id: i32) -> Self {
+        match item_id / 1_000_000 {
+            1 => InventoryTab::Equip,
+            2 => InventoryTab::Use,
+            3 => InventoryTab::Setup,
+            4 => InventoryTab::Etc,
+            _ => InventoryTab::Cash,
+        }
+    }
+
+    pub fn index(self) -> usize {
+        match self {
+            InventoryTab::Equip => 0,
+            InventoryTab::Use => 1,
+            InventoryTab::Etc => 2,
+            InventoryTab::Setup => 3,
+            InventoryTab::Cash => 4,
+        }
+    }
+
+    pub fn from_index(index: usize) -> Option<Self> {
+        Self::ALL.get(index).copied()
+    }
+}
+
+/// A stack of one item in an inventory slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemSlot {
+    pub item_id: i32,
+    pub quantity: u32,
+}
+
+/// Items carried by a character: one slot grid per tab.
+///
+/// Stack limits come from `item_info::slot_max`, so call `item_info::load_item_info`
+/// for an item before adding it to get the WZ value instead of the default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Inventory {
+    tabs: [Vec<Option<ItemSlot>>; 5],
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self::new(DEFAULT_SLOTS_PER_TAB)
+    }
+}
+
+impl Inventory {
+    pub fn new(slots_per_tab: usize) -> Self {
+        Self {
+            tabs: std::array::from_fn(|_| vec![None; slots_per_tab]),
+        }
+    }
+
+    /// All slots of a tab (empty slots are None)
+    pub fn slots(&self, tab: InventoryTab) -> &[Option<ItemSlot>] {
+        &self.tabs[tab.index()]
+    }
+
+    pub fn get(&self, tab: InventoryTab, slot: usize) -> Option<ItemSlot> {
+        self.tabs[tab.index()].get(slot).copied().flatten()
+    }
+
+    /// Distinct ids of every item in the inventory
+    pub fn item_ids(&self) -> Vec<i32> {
+        let mut ids: Vec<i32> = self.tabs.iter().flatten().flatten().map(|s| s.item_id).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    pub fn free_slots(&self, tab: InventoryTab) -> usize {
+        self.tabs[tab.index()].iter().filter(|s| s.is_none()).count()
+    }
+
+    /// Total quantity of an item across all stacks
+    pub fn count(&self, item_id: i32) -> u32 {
+        self.tabs[InventoryTab::from_item_id(item_id).index()]
+            .iter()
+            .flatten()
+            .filter(|s| s.item_id == item_id)
+            .map(|s| s.quantity)
+            .sum()
+    }
+
+    pub fn has(&self, item_id: i32, quantity: u32) -> bool {
+        self.count(item_id) >= quantity
+    }
+
+    /// How many of an item still fit, topping up existing stacks before using empty slots
+    pub fn space_for(&self, item_id: i32) -> u32 {
+        let max = slot_max(item_id);
+        self.tabs[InventoryTab::from_item_id(item_id).index()]
+            .iter()
+            .map(|slot| match slot {
+                None => max,
+                Some(s) if s.item_id == item_id => max.saturating_sub(s.quantity),
+                Some(_) => 0,
+            })
+            .sum()
+    }
+
+    /// Add items, filling existing stacks first. Nothing is added if they don't all fit.
+    pub fn add(&mut self, item_id: i32, quantity: u32) -> Result<(), String> {
+        if quantity == 0 {
+            return Ok(());
+        }
+        if self.space_for(item_id) < quantity {
+            return Err(format!("Not enough inventory space for {} x{}", item_id, quantity));
+        }
+
+        let max = slot_max(item_id);
+        let slots = &mut self.tabs[InventoryTab::from_item_id(item_id).index()];
+        let mut remaining = quantity;
+
+        for slot in slots.iter_mut().flatten() {
+            if slot.item_id == item_id && slot.quantity < max {
+                let added = remaining.min(max - slot.quantity);
+                slot.quantity += added;
+                remaining -= added;
+                if remaining == 0 {
+                    return Ok(());
+                }
+            }
+        }
+
+        for slot in slots.iter_mut().filter(|s| s.is_none()) {
+            let added = remaining.min(max);
+            *slot = Some(ItemSlot { item_id, quantity: added });
+            remaining -= added;
+            if remaining == 0 {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remove items, taking from the last stacks first. Nothing is removed if there aren't enough.
+    pub fn remove(&mut self, item_id: i32, quantity: u32) -> Result<(), String> {
+        if !self.has(item_id, quantity) {
+            return Err(format!("Not enough of item {} to remove {}", item_id, quantity));
+        }
+
+        let slots = &mut self.tabs[InventoryTab::from_item_id(item_id).index()];
+        let mut remaining = quantity;
+
+        for slot in slots.iter_mut().rev() {
+            if remaining == 0 {
+                break;
+            }
+            if let Some(s) = slot {
+                if s.item_id == item_id {
+                    let taken = remaining.min(s.quantity);
+                    s.quantity -= taken;
+                    remaining -= taken;
+                    if s.quantity == 0 {
+                        *slot = None;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remove up to `quantity` from a single slot, returning what was removed
+    pub fn remove_at(&mut self, tab: InventoryTab, slot: usize, quantity: u32) -> Option<ItemSlot> {
+        let entry = self.tabs[tab.index()].get_mut(slot)?;
+        let stack = entry.as_mut()?;
+
+        let taken = quantity.min(stack.quantity);
+        let item_id = stack.item_id;
+        stack.quantity -= taken;
+        if stack.quantity == 0 {
+            *entry = None;
+        }
+
+        Some(ItemSlot { item_id, quantity: taken })
+    }
+
+    /// Fill a stack back up to its slotMax (throwing stars and bullets), returning how many were added
+    pub fn recharge(&mut self, tab: InventoryTab, slot: usize) -> Option<u32> {
+        let stack = self.tabs[tab.index()].get_mut(slot)?.as_mut()?;
+        let added = slot_max(stack.item_id).saturating_sub(stack.quantity);
+        stack.quantity += added;
+        Some(added)
+    }
+
+    /// Move the stack in `from` onto `to` within a tab: moves into an empty slot,
+    /// merges into a stack of the same item (up to its slotMax), or swaps otherwise.
+    pub fn move_item(&mut self, tab: InventoryTab, from: usize, to: usize) -> Result<(), String> {
+        let slots = &mut self.tabs[tab.index()];
+        if from >= slots.len() || to >= slots.len() {
+            return Err(format!("Slot out of range ({} -> {})", from, to));
+        }
+        if from == to {
+            return Ok(());
+        }
+
+        let Some(source) = slots[from] else {
+            return Err(format!("Slot {} is empty", from));
+        };
+
+        match slots[to] {
+            Some(mut target) if target.item_id == source.item_id => {
+                let max = slot_max(source.item_id);
+                let moved = source.quantity.min(max.saturating_sub(target.quantity));
+                if moved == 0 {
+                    // Target stack is full; swap so the drag still does something
+                    slots.swap(from, to);
+                    return Ok(());
+                }
+                target.quantity += moved;
+                slots[to] = Some(target);
+                slots[from] = if source.quantity > moved {
+                    Some(ItemSlot { item_id: source.item_id, quantity: source.quantity - moved })
+                } else {
+                    None
+                };
+            }
+            _ => slots.swap(from, to),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,372 @@
+use macroquad::prelude::*;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use wz_reader::{WzNodeArc, WzNodeCast, WzObjectType};
+
+use crate::wz_cache::load_wz_image;
+
+/// Stack size used for items whose slotMax could not be read (same as the client default)
+pub const DEFAULT_SLOT_MAX: u32 = 100;
+
+/// Item properties read from the `info` node in Item.wz / Character.wz
+#[derive(Debug, Clone, Copy)]
+pub struct ItemInfo {
+    pub slot_max: u32,
+    pub price: i32,
+    /// Requirements and bonuses, for equips only
+    pub equip: Option<EquipStats>,
+    /// What using the item does, for consumables only
+    pub spec: Option<ItemSpec>,
+}
+
+/// Effect of using a consumable, from Item.wz `spec`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ItemSpec {
+    pub hp: i32,
+    pub mp: i32,
+    /// HP recovered in percent of max HP
+    pub hp_r: i32,
+    /// MP recovered in percent of max MP
+    pub mp_r: i32,
+    /// Buff duration (milliseconds)
+    pub time: i32,
+    /// Stat bonuses while the buff lasts (`pad`, `pdd`, `acc`, `speed`, ...)
+    pub buff: EquipStats,
+    /// Map a return scroll takes the player to (999999999 = the current map's return map)
+    pub move_to: i32,
+}
+
+impl ItemSpec {
+    fn from_node(node: &WzNodeArc) -> Option<Self> {
+        node.read().unwrap().at_path("spec")?;
+        let get = |key: &str| get_int(node, &format!("spec/{}", key)).unwrap_or(0);
+        Some(Self {
+            hp: get("hp"),
+            mp: get("mp"),
+            hp_r: get("hpR"),
+            mp_r: get("mpR"),
+            time: get("time"),
+            buff: EquipStats {
+                inc_pad: get("pad"),
+                inc_pdd: get("pdd"),
+                inc_mad: get("mad"),
+                inc_mdd: get("mdd"),
+                inc_acc: get("acc"),
+                inc_eva: get("eva"),
+                inc_speed: get("speed"),
+                inc_jump: get("jump"),
+                ..Default::default()
+            },
+            move_to: get("moveTo"),
+        })
+    }
+
+    /// Gives a timed stat buff
+    pub fn is_buff(&self) -> bool {
+        self.time > 0 && self.buff != EquipStats::default()
+    }
+}
+
+/// Equip requirements (`req*`) and stat bonuses (`inc*`) from Character.wz `info`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EquipStats {
+    pub req_level: i32,
+    pub req_str: i32,
+    pub req_dex: i32,
+    pub req_int: i32,
+    pub req_luk: i32,
+    /// Job class bitmask (1 warrior, 2 magician, 4 bowman, 8 thief, 16 pirate; 0 = any)
+    pub req_job: i32,
+    pub inc_str: i32,
+    pub inc_dex: i32,
+    pub inc_int: i32,
+    pub inc_luk: i32,
+    pub inc_max_hp: i32,
+    pub inc_max_mp: i32,
+    pub inc_pad: i32,
+    pub inc_mad: i32,
+    pub inc_pdd: i32,
+    pub inc_mdd: i32,
+    pub inc_acc: i32,
+    pub inc_eva: i32,
+    pub inc_speed: i32,
+    pub inc_jump: i32,
+    /// Upgrade slots
+    pub tuc: i32,
+    /// Weapon stance set: 1 for one-handed (stand1/walk1), 2 for two-handed (stand2/walk2)
+    pub stand: i32,
+}
+
+impl EquipStats {
+    fn from_node(node: &WzNodeArc) -> Self {
+        let get = |key: &str| get_int(node, &format!("info/{}", key)).unwrap_or(0);
+        Self {
+            req_level: get("reqLevel"),
+            req_str: get("reqSTR"),
+            req_dex: get("reqDEX"),
+            req_int: get("reqINT"),
+            req_luk: get("reqLUK"),
+            req_job: get("reqJob"),
+            inc_str: get("incSTR"),
+            inc_dex: get("incDEX"),
+            inc_int: get("incINT"),
+            inc_luk: get("incLUK"),
+            inc_max_hp: get("incMHP"),
+            inc_max_mp: get("incMMP"),
+            inc_pad: get("incPAD"),
+            inc_mad: get("incMAD"),
+            inc_pdd: get("incPDD"),
+            inc_mdd: get("incMDD"),
+            inc_acc: get("incACC"),
+            inc_eva: get("incEVA"),
+            inc_speed: get("incSpeed"),
+            inc_jump: get("incJump"),
+            tuc: get("tuc"),
+            stand: get_int(node, "info/stand").unwrap_or(1),
+        }
+    }
+
+    /// Sum of the bonuses of two equips (requirements are not combined)
+    pub fn add_bonuses(&mut self, other: &EquipStats) {
+        self.inc_str += other.inc_str;
+        self.inc_dex += other.inc_dex;
+        self.inc_int += other.inc_int;
+        self.inc_luk += other.inc_luk;
+        self.inc_max_hp += other.inc_max_hp;
+        self.inc_max_mp += other.inc_max_mp;
+        self.inc_pad += other.inc_pad;
+        self.inc_mad += other.inc_mad;
+        self.inc_pdd += other.inc_pdd;
+        self.inc_mdd += other.inc_mdd;
+        self.inc_acc += other.inc_acc;
+        self.inc_eva += other.inc_eva;
+        self.inc_speed += other.inc_speed;
+        self.inc_jump += other.inc_jump;
+    }
+}
+
+static ITEM_INFO: Mutex<Option<HashMap<i32, ItemInfo>>> = Mutex::new(None);
+static ITEM_NAMES: Mutex<Option<HashMap<i32, String>>> = Mutex::new(None);
+
+/// True for equipment (1xxxxxx), which lives in Character.wz and never stacks
+pub fn is_equip(item_id: i32) -> bool {
+    item_id / 1_000_000 == 1
+}
+
+/// True for consumables (2xxxxxx), which live in the Use tab
+pub fn is_consume(item_id: i32) -> bool {
+    item_id / 1_000_000 == 2
+}
+
+/// True for throwing stars (207xxxx) and bullets (233xxxx), which are bought as a full set
+/// and recharged at shops
+pub fn is_rechargeable(item_id: i32) -> bool {
+    matches!(item_id / 10000, 207 | 233)
+}
+
+/// Character.wz folder for an equip id, based on its 4-digit category
+fn equip_category(item_id: i32) -> &'static str {
+    match item_id / 10000 {
+        100 => "Cap",
+        101..=103 | 111 | 112 => "Accessory",
+        104 => "Coat",
+        105 => "Longcoat",
+        106 => "Pants",
+        107 => "Shoes",
+        108 => "Glove",
+        109 => "Shield",
+        110 => "Cape",
+        113..=119 => "Accessory",
+        120..=129 => "Ring",
+        130..=170 => "Weapon",
+        _ => "Etc",
+    }
+}
+
+/// WZ image that holds an item, and the path of the item's node inside it.
+/// Equips are one image per item (`Character/Weapon/01302000.img`); everything else is
+/// grouped by 4-digit prefix (`Item/Consume/0200.img` -> `02000000`).
+pub fn item_wz_path(item_id: i32) -> (String, String) {
+    if is_equip(item_id) {
+        return (format!("Character/{}/{:08}.img", equip_category(item_id), item_id), String::new());
+    }
+
+    let folder = match item_id / 1_000_000 {
+        2 => "Consume",
+        3 => "Install",
+        4 => "Etc",
+        9 => "Special",
+        _ => "Cash",
+    };
+    (
+        format!("Item/{}/{:04}.img", folder, item_id / 10000),
+        format!("{:08}", item_id),
+    )
+}
+
+/// Find the item's own node (root of the image for equips, which also holds their per-stance sprites)
+pub async fn load_item_node(item_id: i32) -> Result<WzNodeArc, String> {
+    let (img_path, node_path) = item_wz_path(item_id);
+    let root = load_wz_image(&img_path).await?;
+    if node_path.is_empty() {
+        return Ok(root);
+    }
+
+    let node = root
+        .read()
+        .unwrap()
+        .at_path(&node_path)
+        .ok_or_else(|| format!("Item {} not found in {}", item_id, img_path))?;
+    Ok(node)
+}
+
+/// String.wz image holding an item's name, and the path of the item's node inside it
+fn item_name_path(item_id: i32) -> (&'static str, String) {
+    if is_equip(item_id) {
+        return ("String/Eqp.img", format!("Eqp/{}/{}", equip_category(item_id), item_id));
+    }
+    match item_id / 1_000_000 {
+        2 => ("String/Consume.img", item_id.to_string()),
+        3 => ("String/Ins.img", item_id.to_string()),
+        4 => ("String/Etc.img", format!("Etc/{}", item_id)),
+        _ => ("String/Cash.img", item_id.to_string()),
+    }
+}
+
+fn get_int(node: &WzNodeArc, path: &str) -> Option<i32> {
+    let prop = node.read().unwrap().at_path(path)?;
+    let prop_read = prop.read().unwrap();
+    match &prop_read.object_type {
+        WzObjectType::Value(wz_reader::property::WzValue::Short(val)) => Some(*val as i32),
+        WzObjectType::Value(wz_reader::property::WzValue::Int(val)) => Some(*val),
+        WzObjectType::Value(wz_reader::property::WzValue::Long(val)) => Some(*val as i32),
+        WzObjectType::Value(wz_reader::property::WzValue::String(val)) => {
+            val.get_string().ok().and_then(|s| s.parse().ok())
+        }
+        _ => None,
+    }
+}
+
+/// Read (and cache) the info of an item. Falls back to defaults if the item can't be loaded.
+pub async fn load_item_info(item_id: i32) -> ItemInfo {
+    if let Some(info) = cached_info(item_id) {
+        return info;
+    }
+
+    let info = match load_item_node(item_id).await {
+        Ok(node) => ItemInfo {
+            slot_max: if is_equip(item_id) {
+                1
+            } else {
+                get_int(&node, "info/slotMax").map_or(DEFAULT_SLOT_MAX, |v| v.max(1) as u32)
+            },
+            price: get_int(&node, "info/price").unwrap_or(0),
+            equip: is_equip(item_id).then(|| EquipStats::from_node(&node)),
+            spec: if is_consume(item_id) { ItemSpec::from_node(&node) } else { None },
+        },
+        Err(e) => {
+            warn!("Failed to load item info for {}: {}", item_id, e);
+            ItemInfo {
+                slot_max: if is_equip(item_id) { 1 } else { DEFAULT_SLOT_MAX },
+                price: 0,
+                equip: is_equip(item_id).then(EquipStats::default),
+                spec: None,
+            }
+        }
+    };
+
+    ITEM_INFO
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(item_id, info);
+    info
+}
+
+fn cached_info(item_id: i32) -> Option<ItemInfo> {
+    ITEM_INFO.lock().unwrap().as_ref()?.get(&item_id).copied()
+}
+
+/// Stack limit for an item. Uses the cached WZ value when `load_item_info` has run for it.
+pub fn slot_max(item_id: i32) -> u32 {
+    match cached_info(item_id) {
+        Some(info) => info.slot_max,
+        None if is_equip(item_id) => 1,
+        None => DEFAULT_SLOT_MAX,
+    }
+}
+
+/// Use effect of a consumable, if `load_item_info` has run for it
+pub fn consume_spec(item_id: i32) -> Option<ItemSpec> {
+    cached_info(item_id)?.spec
+}
+
+/// Equip stats of an item, if `load_item_info` has run for it
+pub fn equip_stats(item_id: i32) -> Option<EquipStats> {
+    cached_info(item_id)?.equip
+}
+
+/// Read (and cache) an item's name from String.wz. Empty if it can't be found.
+pub async fn load_item_name(item_id: i32) -> String {
+    if let Some(name) = item_name(item_id) {
+        return name;
+    }
+
+    let (img_path, node_path) = item_name_path(item_id);
+    let name = match load_wz_image(img_path).await {
+        Ok(root) => {
+            let prop = root.read().unwrap().at_path(&format!("{}/name", node_path));
+            let name = prop.and_then(|prop| {
+                let prop_read = prop.read().unwrap();
+                match &prop_read.object_type {
+                    WzObjectType::Value(wz_reader::property::WzValue::String(val)) => val.get_string().ok(),
+                    _ => None,
+                }
+            });
+            name.unwrap_or_default()
+        }
+        Err(e) => {
+            warn!("Failed to load {}: {}", img_path, e);
+            String::new()
+        }
+    };
+
+    ITEM_NAMES
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(item_id, name.clone());
+    name
+}
+
+/// Name of an item, if `load_item_name` has run for it
+pub fn item_name(item_id: i32) -> Option<String> {
+    ITEM_NAMES.lock().unwrap().as_ref()?.get(&item_id).cloned()
+}
+
+/// Meso a shop pays for one of an item, if `load_item_info` has run for it
+pub fn sell_price(item_id: i32) -> Option<u32> {
+    cached_info(item_id).map(|info| info.price.max(0) as u32)
+}
+
+/// Load the inventory icon (`info/icon`) of an item
+pub async fn load_item_icon(item_id: i32) -> Option<Texture2D> {
+    load_icon(item_id, "info/icon").await
+}
+
+/// Load the icon an item has while lying on the ground (`info/iconRaw`, no shadow or border)
+pub async fn load_item_icon_raw(item_id: i32) -> Option<Texture2D> {
+    load_icon(item_id, "info/iconRaw").await
+}
+
+async fn load_icon(item_id: i32, path: &str) -> Option<Texture2D> {
+    let node = load_item_node(item_id).await.ok()?;
+    let icon = node.read().unwrap().at_path(path)?;
+    icon.write().unwrap().parse(&icon).ok()?;
+
+    let icon_read = icon.read().unwrap();
+    let png = icon_read.try_as_png()?;
+    let img = png.extract_png().ok()?;
+    let rgba = img.to_rgba8();
+    Some(Texture2D::from_rgba8(rgba.width() as u16, rgba.height() as u16, &rgba.into_raw()))
+}
@@ -0,0 +1,242 @@
+use serde::{Deserialize, Serialize};
+use macroquad::prelude::*;
+use crate::leveling::ApStat;
+
+/// Explorer jobs, by their WZ job id. The hundreds digit is the class (1 Warrior, 2 Magician,
+/// 3 Bowman, 4 Thief, 5 Pirate), the tens digit the 2nd job branch and the ones digit the
+/// advancements after it. Saved as the job id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(from = "usize", into = "usize")]
+pub enum Job {
+    #[default]
+    Beginner = 0,
+    Warrior = 100,
+    Fighter = 110,
+    Crusader = 111,
+    Hero = 112,
+    Page = 120,
+    WhiteKnight = 121,
+    Paladin = 122,
+    Spearman = 130,
+    DragonKnight = 131,
+    DarkKnight = 132,
+    Magician = 200,
+    FirePoisonWizard = 210,
+    FirePoisonMage = 211,
+    FirePoisonArchMage = 212,
+    IceLightningWizard = 220,
+    IceLightningMage = 221,
+    IceLightningArchMage = 222,
+    Cleric = 230,
+    Priest = 231,
+    Bishop = 232,
+    Bowman = 300,
+    Hunter = 310,
+    Ranger = 311,
+    Bowmaster = 312,
+    Crossbowman = 320,
+    Sniper = 321,
+    Marksman = 322,
+    Thief = 400,
+    Assassin = 410,
+    Hermit = 411,
+    NightLord = 412,
+    Bandit = 420,
+    ChiefBandit = 421,
+    Shadower = 422,
+    Pirate = 500,
+    Brawler = 510,
+    Marauder = 511,
+    Buccaneer = 512,
+    Gunslinger = 520,
+    Outlaw = 521,
+    Corsair = 522,
+}
+
+/// What a character needs to advance to a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Requirement {
+    pub level: u32,
+    /// Base stat needed (1st job only)
+    pub stat: Option<(ApStat, u32)>,
+}
+
+impl Job {
+    pub const ALL: [Job; 42] = [
+        Job::Beginner,
+        Job::Warrior, Job::Fighter, Job::Crusader, Job::Hero, Job::Page, Job::WhiteKnight, Job::Paladin,
+        Job::Spearman, Job::DragonKnight, Job::DarkKnight,
+        Job::Magician, Job::FirePoisonWizard, Job::FirePoisonMage, Job::FirePoisonArchMage,
+        Job::IceLightningWizard, Job::IceLightningMage, Job::IceLightningArchMage, Job::Cleric, Job::Priest, Job::Bishop,
+        Job::Bowman, Job::Hunter, Job::Ranger, Job::Bowmaster, Job::Crossbowman, Job::Sniper, Job::Marksman,
+        Job::Thief, Job::Assassin, Job::Hermit, Job::NightLord, Job::Bandit, Job::ChiefBandit, Job::Shadower,
+        Job::Pirate, Job::Brawler, Job::Marauder, Job::Buccaneer, Job::Gunslinger, Job::Outlaw, Job::Corsair,
+    ];
+
+    pub fn from_id(id: usize) -> Option<Job> {
+        Self::ALL.iter().copied().find(|job| job.id() == id)
+    }
+
+    /// WZ job id (e.g. 110 for Fighter)
+    pub fn id(self) -> usize {
+        self as usize
+    }
+
+    /// Job class: 0 Beginner, 1 Warrior, 2 Magician, 3 Bowman, 4 Thief, 5 Pirate
+    pub fn class(self) -> usize {
+        self.id() / 100
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Job::Beginner => "Beginner",
+            Job::Warrior => "Warrior",
+            Job::Fighter => "Fighter",
+            Job::Crusader => "Crusader",
+            Job::Hero => "Hero",
+            Job::Page => "Page",
+            Job::WhiteKnight => "White Knight",
+            Job::Paladin => "Paladin",
+            Job::Spearman => "Spearman",
+            Job::DragonKnight => "Dragon Knight",
+            Job::DarkKnight => "Dark Knight",
+            Job::Magician => "Magician",
+            Job::FirePoisonWizard => "Wizard (Fire, Poison)",
+            Job::FirePoisonMage => "Mage (Fire, Poison)",
+            Job::FirePoisonArchMage => "Arch Mage (Fire, Poison)",
+            Job::IceLightningWizard => "Wizard (Ice, Lightning)",
+            Job::IceLightningMage => "Mage (Ice, Lightning)",
+            Job::IceLightningArchMage => "Arch Mage (Ice, Lightning)",
+            Job::Cleric => "Cleric",
+            Job::Priest => "Priest",
+            Job::Bishop => "Bishop",
+            Job::Bowman => "Bowman",
+            Job::Hunter => "Hunter",
+            Job::Ranger => "Ranger",
+            Job::Bowmaster => "Bowmaster",
+            Job::Crossbowman => "Crossbowman",
+            Job::Sniper => "Sniper",
+            Job::Marksman => "Marksman",
+            Job::Thief => "Thief",
+            Job::Assassin => "Assassin",
+            Job::Hermit => "Hermit",
+            Job::NightLord => "Night Lord",
+            Job::Bandit => "Bandit",
+            Job::ChiefBandit => "Chief Bandit",
+            Job::Shadower => "Shadower",
+            Job::Pirate => "Pirate",
+            Job::Brawler => "Brawler",
+            Job::Marauder => "Marauder",
+            Job::Buccaneer => "Buccaneer",
+            Job::Gunslinger => "Gunslinger",
+            Job::Outlaw => "Outlaw",
+            Job::Corsair => "Corsair",
+        }
+    }
+
+    /// How many advancements it takes to get here: 0 for Beginner, 1 to 4 after that
+    pub fn advancement(self) -> u32 {
+        let id = self.id() as u32;
+        match (id / 100, id % 100, id % 10) {
+            (0, _, _) => 0,
+            (_, 0, _) => 1,
+            (_, _, 0) => 2,
+            (_, _, step) => step + 2,
+        }
+    }
+
+    /// The job this one is advanced from (None for Beginner)
+    pub fn previous(self) -> Option<Job> {
+        let id = self.id();
+        match self.advancement() {
+            0 => None,
+            1 => Some(Job::Beginner),
+            2 => Job::from_id(id / 100 * 100),
+            _ => Job::from_id(id - 1),
+        }
+    }
+
+    /// Level (and for 1st job, base stat) needed to advance to this job
+    pub fn requirement(self) -> Requirement {
+        let (level, stat) = match (self.advancement(), self.class()) {
+            (0, _) => (1, None),
+            (1, 1) => (10, Some((ApStat::Str, 35))),
+            (1, 2) => (8, Some((ApStat::Int, 20))),
+            (1, 3) | (1, 4) => (10, Some((ApStat::Dex, 25))),
+            (1, _) => (10, Some((ApStat::Dex, 20))),
+            (2, _) => (30, None),
+            (3, _) => (70, None),
+            _ => (120, None),
+        };
+        Requirement { level, stat }
+    }
+
+    /// Skill points granted on advancing to this job at `level`. 1st job also makes up for the
+    /// levels gained as a beginner past the requirement, which gave no SP.
+    pub fn advancement_sp(self, level: u32) -> u32 {
+        match self.advancement() {
+            0 => 0,
+            1 => 1 + 3 * level.saturating_sub(self.requirement().level),
+            _ => 1,
+        }
+    }
+
+    /// Max HP and MP granted on advancing to this job
+    pub fn advancement_hp_mp(self) -> (u32, u32) {
+        match (self.advancement(), self.class()) {
+            (1, 1) => (200, 0),
+            (1, 2) => (0, 100),
+            (1, _) => (100, 25),
+            (2, 1) => (300, 0),
+            (2, 2) => (0, 450),
+            (2, _) => (300, 150),
+            _ => (0, 0),
+        }
+    }
+
+    /// Max HP and MP gained on level up
+    pub fn level_up_hp_mp(self) -> (u32, u32) {
+        match self.class() {
+            1 => (26, 5),  // Warrior
+            2 => (12, 23), // Magician
+            3 | 4 => (22, 15), // Bowman, Thief
+            5 => (24, 20), // Pirate
+            _ => (14, 11), // Beginner
+        }
+    }
+
+    /// Max HP and MP gained per ability point put into HP or MP
+    pub fn ap_hp_mp(self) -> (u32, u32) {
+        match self.class() {
+            1 => (22, 3),
+            2 => (8, 19),
+            3 | 4 | 5 => (18, 11),
+            _ => (10, 7),
+        }
+    }
+
+    /// Skill points gained on reaching `level`: beginners only get their six for the beginner skills
+    pub fn sp_for_level(self, level: u32) -> u32 {
+        if self == Job::Beginner {
+            u32::from((2..=7).contains(&level))
+        } else {
+            3
+        }
+    }
+}
+
+impl From<usize> for Job {
+    /// Unknown job ids (from edited or newer saves) fall back to Beginner
+    fn from(id: usize) -> Self {
+        Job::from_id(id).unwrap_or_else(|| {
+            warn!("Unknown job {}, using Beginner", id);
+            Job::Beginner
+        })
+    }
+}
+
+impl From<Job> for usize {
+    fn from(job: Job) -> Self {
+        job.id()
+    }
+}
@@ -1,11 +1,38 @@
 use macroquad::prelude::*;
-use crate::assets::AssetManager;
-use std::sync::Arc;
-use wz_reader::version::guess_iv_from_wz_img;
-use wz_reader::{WzImage, WzNode, WzNodeArc, WzReader, WzNodeCast};
-
-const UIWINDOW2_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/UI/UIWindow2.img";
-const UIWINDOW2_CACHE: &str = "/01/UI/UIWindow2.img";
+use std::collections::HashMap;
+use crate::wz_cache::load_wz_image;
+use crate::inventory::{Inventory, InventoryTab};
+use crate::item_info;
+use wz_reader::{WzNodeArc, WzNodeCast};
+
+const UIWINDOW2_PATH: &str = "UI/UIWindow2.img";
+
+/// Quick-slot keys that use a consumable, bound in the KeyConfig window
+pub const ITEM_HOTKEYS: [KeyCode; 6] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+];
+
+/// Names of `ITEM_HOTKEYS`
+const ITEM_HOTKEY_LABELS: [&str; 6] = ["1", "2", "3", "4", "5", "6"];
+
+// Item quick-slot row, drawn on a strip under the keyboard
+const QUICK_SLOT_SIZE: f32 = 32.0;
+const QUICK_SLOT_GAP: f32 = 8.0;
+const QUICK_SLOT_STRIP_HEIGHT: f32 = 64.0;
+
+/// A change to the item quick slots made in the KeyConfig window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyConfigEvent {
+    /// Bind a consumable to a quick slot (None clears it)
+    BindItem(usize, Option<i32>),
+    /// Clear every quick slot (Default button)
+    ResetItems,
+}
 
 /// Texture with origin point
 struct TextureWithOrigin {
@@ -105,7 +132,7 @@ pub struct KeyConfig {
     default_button: KeyConfigButton,
     ok_button: KeyConfigButton,
     // Icons for key bindings
-    icons: std::collections::HashMap<i32, Texture2D>,
+    icons: HashMap<i32, Texture2D>,
     // Window position
     x: f32,
     y: f32,
@@ -114,6 +141,8 @@ pub struct KeyConfig {
     // Dragging
     dragging: bool,
     drag_offset: Vec2,
+    // Icons of items that can be put on quick slots
+    item_icons: HashMap<i32, Texture2D>,
 }
 
 impl KeyConfig {
@@ -127,13 +156,14 @@ impl KeyConfig {
             cancel_button: KeyConfigButton::new(),
             default_button: KeyConfigButton::new(),
             ok_button: KeyConfigButton::new(),
-            icons: std::collections::HashMap::new(),
+            icons: HashMap::new(),
             x: 100.0,
             y: 100.0,
             width: 632.0,
             height: 270.0,
             dragging: false,
             drag_offset: Vec2::ZERO,
+            item_icons: HashMap::new(),
         }
     }
 
@@ -166,21 +196,9 @@ impl KeyConfig {
         }
     }
 
-    async fn load_from_wz() -> Result<(Option<TextureWithOrigin>, Option<TextureWithOrigin>, Option<TextureWithOrigin>, KeyConfigButton, KeyConfigButton, KeyConfigButton, std::collections::HashMap<i32, Texture2D>), String> {
-        let bytes = AssetManager::fetch_and_cache(UIWINDOW2_URL, UIWINDOW2_CACHE).await
-            .map_err(|e| format!("Failed to fetch UIWindow2.img: {}", e))?;
-
-        let wz_iv = guess_iv_from_wz_img(&bytes)
-            .ok_or_else(|| "Unable to guess version from UIWindow2.img".to_string())?;
-
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        let cache_name_ref: wz_reader::WzNodeName = UIWINDOW2_CACHE.to_string().into();
-        let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-        let root_node: WzNodeArc = WzNode::new(&UIWINDOW2_CACHE.into(), wz_image, None).into();
-
-        root_node.write().unwrap().parse(&root_node)
-            .map_err(|e| format!("Failed to parse UIWindow2.img: {:?}", e))?;
+    async fn load_from_wz() -> Result<(Option<TextureWithOrigin>, Option<TextureWithOrigin>, Option<TextureWithOrigin>, KeyConfigButton, KeyConfigButton, KeyConfigButton, HashMap<i32, Texture2D>), String> {
+        let root_node = load_wz_image(UIWINDOW2_PATH).await
+            .map_err(|e| format!("Failed to load UIWindow2.img: {}", e))?;
 
         // Load background layers in z-order (backgrnd, backgrnd2, backgrnd3)
         let bg = Self::load_texture(&root_node, "KeyConfig/backgrnd").await.ok();
@@ -193,7 +211,7 @@ impl KeyConfig {
         let ok_btn = Self::load_button(&root_node, "KeyConfig/BtOK").await;
 
         // Load icons
-        let mut icons = std::collections::HashMap::new();
+        let mut icons = HashMap::new();
         let icon_ids = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 
                         20, 21, 23, 24, 25, 26, 27, 28, 29, 50, 51, 52, 53, 54, 
                         100, 101, 102, 103, 104, 105, 106];
@@ -269,6 +287,44 @@ impl KeyConfig {
         btn
     }
 
+    /// Load icons for the consumables in the Use tab
+    pub async fn load_item_icons(&mut self, inventory: &Inventory) {
+        for item_id in Self::usable_items(inventory) {
+            if self.item_icons.contains_key(&item_id) {
+                continue;
+            }
+            if let Some(icon) = item_info::load_item_icon(item_id).await {
+                self.item_icons.insert(item_id, icon);
+            }
+        }
+    }
+
+    /// Consumables that do something when used, in Use tab order
+    fn usable_items(inventory: &Inventory) -> Vec<i32> {
+        let mut items = Vec::new();
+        for item in inventory.slots(InventoryTab::Use).iter().flatten() {
+            if !items.contains(&item.item_id) && item_info::consume_spec(item.item_id).is_some() {
+                items.push(item.item_id);
+            }
+        }
+        items
+    }
+
+    /// Top-left of an item quick slot
+    fn quick_slot_position(&self, slot: usize) -> (f32, f32) {
+        (
+            self.x + 20.0 + slot as f32 * (QUICK_SLOT_SIZE + QUICK_SLOT_GAP),
+            self.y + self.height + 22.0,
+        )
+    }
+
+    fn quick_slot_at(&self, mouse_x: f32, mouse_y: f32) -> Option<usize> {
+        (0..ITEM_HOTKEYS.len()).find(|&slot| {
+            let (sx, sy) = self.quick_slot_position(slot);
+            mouse_x >= sx && mouse_x < sx + QUICK_SLOT_SIZE && mouse_y >= sy && mouse_y < sy + QUICK_SLOT_SIZE
+        })
+    }
+
     /// Show the KeyConfig window
     pub fn show(&mut self) {
         self.visible = true;
@@ -296,10 +352,11 @@ impl KeyConfig {
         self.visible
     }
 
-    /// Update KeyConfig state
-    pub fn update(&mut self) {
+    /// Update KeyConfig state. Clicking a quick slot puts the next consumable from the
+    /// Use tab on it; right-clicking clears it.
+    pub fn update(&mut self, inventory: &Inventory, item_keys: &[Option<i32>]) -> Option<KeyConfigEvent> {
         if !self.visible {
-            return;
+            return None;
         }
 
         let (mouse_x, mouse_y) = mouse_position();
@@ -342,11 +399,27 @@ impl KeyConfig {
         if self.default_button.is_clicked(self.x, self.y) {
             // Reset to defaults
             info!("Reset key bindings to default");
+            return Some(KeyConfigEvent::ResetItems);
+        }
+
+        let slot = self.quick_slot_at(mouse_x, mouse_y)?;
+        if is_mouse_button_pressed(MouseButton::Right) {
+            return Some(KeyConfigEvent::BindItem(slot, None));
+        }
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let items = Self::usable_items(inventory);
+            let current = item_keys.get(slot).copied().flatten();
+            let next = match current.and_then(|id| items.iter().position(|&item| item == id)) {
+                Some(index) => items.get(index + 1).copied(),
+                None => items.first().copied(),
+            };
+            return Some(KeyConfigEvent::BindItem(slot, next));
         }
+        None
     }
 
     /// Draw the KeyConfig window
-    pub fn draw(&self) {
+    pub fn draw(&self, inventory: &Inventory, item_keys: &[Option<i32>]) {
         if !self.visible {
             return;
         }
@@ -375,6 +448,35 @@ impl KeyConfig {
         self.cancel_button.draw(self.x, self.y);
         self.default_button.draw(self.x, self.y);
         self.ok_button.draw(self.x, self.y);
+
+        self.draw_quick_slots(inventory, item_keys);
+    }
+
+    /// Item quick slots with their key, the bound item and how many are left
+    fn draw_quick_slots(&self, inventory: &Inventory, item_keys: &[Option<i32>]) {
+        let strip_y = self.y + self.height;
+        draw_rectangle(self.x, strip_y, self.width, QUICK_SLOT_STRIP_HEIGHT, Color::from_rgba(30, 30, 40, 230));
+        draw_text(
+            "Item quick slots (click: next Use item, right-click: clear)",
+            self.x + 20.0,
+            strip_y + 15.0,
+            13.0,
+            WHITE,
+        );
+
+        for (slot, label) in ITEM_HOTKEY_LABELS.iter().enumerate() {
+            let (sx, sy) = self.quick_slot_position(slot);
+            draw_rectangle(sx, sy, QUICK_SLOT_SIZE, QUICK_SLOT_SIZE, Color::from_rgba(60, 60, 75, 255));
+            if let Some(item_id) = item_keys.get(slot).copied().flatten() {
+                let color = if inventory.has(item_id, 1) { WHITE } else { Color::from_rgba(255, 255, 255, 90) };
+                if let Some(icon) = self.item_icons.get(&item_id) {
+                    draw_texture(icon, sx, sy, color);
+                }
+                draw_text(&inventory.count(item_id).to_string(), sx + 2.0, sy + QUICK_SLOT_SIZE - 2.0, 12.0, WHITE);
+            }
+            draw_rectangle_lines(sx, sy, QUICK_SLOT_SIZE, QUICK_SLOT_SIZE, 1.0, Color::from_rgba(150, 150, 170, 255));
+            draw_text(label, sx + QUICK_SLOT_SIZE - 8.0, sy + 10.0, 12.0, YELLOW);
+        }
     }
 
 }
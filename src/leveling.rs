@@ -0,0 +1,61 @@
+/// Highest level a character can reach
+pub const MAX_LEVEL: u32 = 200;
+
+/// Highest STR/DEX/INT/LUK ability points can raise
+pub const MAX_BASE_STAT: u32 = 999;
+
+/// Highest base max HP/MP
+pub const MAX_HP_MP: u32 = 30000;
+
+/// Ability points given per level
+pub const AP_PER_LEVEL: u32 = 5;
+
+/// EXP needed to go from level 1 to 41 (pre-Big Bang table)
+const EXP_TABLE: [u32; 40] = [
+    15, 34, 57, 92, 135, 372, 560, 840, 1242, 1716,
+    2360, 3216, 4200, 5460, 7050, 8840, 11040, 13716, 16680, 20216,
+    24402, 28980, 34320, 40512, 47216, 54900, 63666, 73080, 83270, 94320,
+    105342, 118500, 132480, 147420, 163080, 180000, 199152, 218835, 238710, 260460,
+];
+
+/// Growth per level after the table, which lands level 199 at about 2.2 billion like the client
+const EXP_GROWTH: f64 = 1.0585;
+
+/// EXP needed to advance from `level` to the next one (0 at the level cap)
+pub fn exp_to_next_level(level: u32) -> u32 {
+    if level == 0 || level >= MAX_LEVEL {
+        return 0;
+    }
+    if let Some(&exp) = EXP_TABLE.get(level as usize - 1) {
+        return exp;
+    }
+    let last = EXP_TABLE[EXP_TABLE.len() - 1] as f64;
+    let steps = (level as usize - EXP_TABLE.len()) as i32;
+    (last * EXP_GROWTH.powi(steps)).min(u32::MAX as f64) as u32
+}
+
+/// Stats that ability points can be put into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApStat {
+    Str,
+    Dex,
+    Int,
+    Luk,
+    Hp,
+    Mp,
+}
+
+impl ApStat {
+    pub const ALL: [ApStat; 6] = [ApStat::Hp, ApStat::Mp, ApStat::Str, ApStat::Dex, ApStat::Int, ApStat::Luk];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ApStat::Str => "STR",
+            ApStat::Dex => "DEX",
+            ApStat::Int => "INT",
+            ApStat::Luk => "LUK",
+            ApStat::Hp => "HP",
+            ApStat::Mp => "MP",
+        }
+    }
+}
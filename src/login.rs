@@ -1,17 +1,13 @@
 use macroquad::prelude::*;
-use crate::assets::AssetManager;
 use crate::cursor::CursorManager;
-use std::sync::Arc;
-use wz_reader::version::guess_iv_from_wz_img;
-use wz_reader::{WzImage, WzNode, WzNodeArc, WzReader, WzObjectType, WzNodeCast};
+use crate::wz_cache::load_wz_image;
+use wz_reader::{WzNodeArc, WzObjectType, WzNodeCast};
 
 #[cfg(not(target_arch = "wasm32"))]
 use memmap2::MmapOptions;
 
-const LOGIN_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/UI/Login.img";
-const LOGIN_CACHE_NAME: &str = "/01/UI/Login.img";
-const BACKGROUND_URL: &str = "https://scribbles-public.s3.amazonaws.com/tutorial/01/Map/Back/login.img";
-const BACKGROUND_CACHE_NAME: &str = "/01/Map/Back/login.img";
+const LOGIN_PATH: &str = "UI/Login.img";
+const BACKGROUND_PATH: &str = "Map/Back/login.img";
 
 /// Structure to hold texture with its origin point
 struct TextureWithOrigin {
@@ -307,37 +303,14 @@ impl LoginState {
         }
 
         // Fetch and parse the WZ file once
-        let bytes = match AssetManager::fetch_and_cache(LOGIN_URL, LOGIN_CACHE_NAME).await {
-            Ok(bytes) => bytes,
+        let root_node = match load_wz_image(LOGIN_PATH).await {
+            Ok(node) => node,
             Err(e) => {
-                error!("Failed to fetch Login.img: {}", e);
+                error!("Failed to load Login.img: {}", e);
                 return;
             }
         };
 
-        info!("Parsing Login.img (size: {} bytes)...", bytes.len());
-
-        // Guess Version / IV
-        let wz_iv = match guess_iv_from_wz_img(&bytes) {
-            Some(iv) => iv,
-            None => {
-                error!("Unable to guess version from Login.img");
-                return;
-            }
-        };
-
-        // Create Reader and WZ structure
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        let wz_image = WzImage::new(&LOGIN_CACHE_NAME.into(), 0, byte_len, &reader);
-        let root_node: WzNodeArc = WzNode::new(&LOGIN_CACHE_NAME.into(), wz_image, None).into();
-
-        // Parse root node once
-        if let Err(e) = root_node.write().unwrap().parse(&root_node) {
-            error!("Failed to parse WZ root node: {:?}", e);
-            return;
-        }
-
         info!("WZ file parsed successfully");
 
         // Load frame asset
@@ -486,37 +459,15 @@ impl LoginState {
 
         // Load background from login.img (Map/Back/login.img)
         info!("Loading background assets...");
-        let bg_bytes = match AssetManager::fetch_and_cache(BACKGROUND_URL, BACKGROUND_CACHE_NAME).await {
-            Ok(bytes) => bytes,
+        let bg_root_node = match load_wz_image(BACKGROUND_PATH).await {
+            Ok(node) => node,
             Err(e) => {
-                error!("Failed to fetch background: {}", e);
+                error!("Failed to load background: {}", e);
                 self.loaded = true;
                 return;
             }
         };
 
-        info!("Parsing background (size: {} bytes)...", bg_bytes.len());
-
-        let bg_wz_iv = match guess_iv_from_wz_img(&bg_bytes) {
-            Some(iv) => iv,
-            None => {
-                error!("Unable to guess version from background IMG file");
-                self.loaded = true;
-                return;
-            }
-        };
-
-        let bg_byte_len = bg_bytes.len();
-        let bg_reader = Arc::new(WzReader::from_buff(&bg_bytes).with_iv(bg_wz_iv));
-        let bg_wz_image = WzImage::new(&BACKGROUND_CACHE_NAME.into(), 0, bg_byte_len, &bg_reader);
-        let bg_root_node: WzNodeArc = WzNode::new(&BACKGROUND_CACHE_NAME.into(), bg_wz_image, None).into();
-
-        if let Err(e) = bg_root_node.write().unwrap().parse(&bg_root_node) {
-            error!("Failed to parse background WZ root node: {:?}", e);
-            self.loaded = true;
-            return;
-        }
-
         // Load background asset at back/11
         match load_png_from_node(&bg_root_node, "back/11") {
             Ok(two) => {
@@ -1,12 +1,28 @@
 mod assets;
+mod asset_source;
 mod game;
 mod logo;
 mod login;
 mod character_selection;
 mod character_creation;
 mod character;
+mod storage;
+mod trunk;
+mod inventory;
+mod equipment;
+mod leveling;
+mod job;
+mod item_info;
+mod drop_table;
+mod shop_table;
+mod skill_info;
+mod status_effect;
+mod quest_info;
+mod quest_log;
 mod game_world;
 mod flags;
+mod config;
+mod debug_console;
 mod map;
 mod audio;
 mod cursor;
@@ -16,12 +32,19 @@ mod ui_windows;
 mod cash_shop;
 mod key_config;
 mod chat_balloon;
+mod damage_numbers;
+mod effects;
 mod game_menu;
 mod character_renderer;
 mod physics;
 mod npc_dialog;
+mod npc_shop;
+mod npc_storage;
 mod npc_script;
+mod dialog_script;
+mod script_runtime;
 mod social_windows;
+mod wz_cache;
 
 #[macroquad::main("RustMaple")]
 async fn main() {
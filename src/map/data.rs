@@ -1,5 +1,6 @@
 #[allow(non_snake_case)]
 use macroquad::prelude::*;
+use crate::map::mob_loader::MobStats;
 
 /// Complete map data structure
 #[derive(Debug, Clone)]
@@ -12,6 +13,7 @@ pub struct MapData {
     pub portals: Vec<Portal>,
     pub life: Vec<Life>,
     pub ladders: Vec<Ladder>,
+    pub reactors: Vec<Reactor>,
     pub minimap: Option<MiniMapInfo>,
 }
 
@@ -57,7 +59,7 @@ pub struct BackgroundLayer {
     pub front: bool, // Draw in front of everything
     pub flip_x: bool,
     pub flip_y: bool,
-    pub texture: Option<Texture2D>,
+    pub animation: MapAnimation, // Map.wz/Back `back/{no}`, or `ani/{no}` if animated
 }
 
 /// Tile (ground texture) data
@@ -91,9 +93,60 @@ pub struct MapObject {
     pub z_m: i32,          // Z-depth multiplier
     pub f: bool,           // Flip
     pub r: i32,            // Rotation
-    pub origin_x: i32,     // Origin offset X
-    pub origin_y: i32,     // Origin offset Y
-    pub texture: Option<Texture2D>,
+    pub animation: MapAnimation,
+}
+
+/// Frame of a map object or background
+#[derive(Debug, Clone)]
+pub struct MapFrame {
+    pub texture: Texture2D,
+    pub origin_x: i32,
+    pub origin_y: i32,
+    pub delay: f32, // Seconds the frame is shown
+    pub a0: f32,    // Alpha (0-255) at the start of the frame, fading to `a1` by its end
+    pub a1: f32,
+}
+
+/// Frames and motion of a map object or background (a single frame if it isn't animated)
+#[derive(Debug, Clone, Default)]
+pub struct MapAnimation {
+    pub frames: Vec<MapFrame>,
+    pub move_type: i32, // 1 swings sideways, 2 up and down, 3 around in an ellipse
+    pub move_w: i32,    // Horizontal swing (pixels)
+    pub move_h: i32,    // Vertical swing (pixels)
+    pub move_p: f32,    // Seconds per swing
+}
+
+impl MapAnimation {
+    /// Frame shown `time` seconds in (looping), with its alpha (0-1)
+    pub fn frame_at(&self, time: f32) -> Option<(&MapFrame, f32)> {
+        let total: f32 = self.frames.iter().map(|frame| frame.delay).sum();
+        let mut time = if total > 0.0 { time % total } else { 0.0 };
+        for frame in &self.frames {
+            if time < frame.delay {
+                let alpha = frame.a0 + (frame.a1 - frame.a0) * time / frame.delay;
+                return Some((frame, (alpha / 255.0).clamp(0.0, 1.0)));
+            }
+            time -= frame.delay;
+        }
+        let frame = self.frames.last()?;
+        Some((frame, (frame.a1 / 255.0).clamp(0.0, 1.0)))
+    }
+
+    /// How far the motion has moved it `time` seconds in
+    pub fn offset_at(&self, time: f32) -> Vec2 {
+        if self.move_p <= 0.0 {
+            return Vec2::ZERO;
+        }
+        let angle = time / self.move_p * std::f32::consts::TAU;
+        let (w, h) = (self.move_w as f32, self.move_h as f32);
+        match self.move_type {
+            1 => vec2(w * angle.sin(), 0.0),
+            2 => vec2(0.0, h * angle.sin()),
+            3 => vec2(w * angle.cos(), h * angle.sin()),
+            _ => Vec2::ZERO,
+        }
+    }
 }
 
 /// Foothold platform data
@@ -149,6 +202,11 @@ pub struct Life {
     pub texture: Option<Texture2D>, // Single texture (for NPCs or fallback)
     pub textures: Vec<Texture2D>, // Animation frames (for mobs)
     pub origins: Vec<(i32, i32)>,  // Origin offset for each frame (x, y)
+    pub hit_textures: Vec<Texture2D>, // hit1 frames (mobs)
+    pub hit_origins: Vec<(i32, i32)>,
+    pub die_textures: Vec<Texture2D>, // die1 frames (mobs)
+    pub die_origins: Vec<(i32, i32)>,
+    pub stats: MobStats,   // Mob.wz info (default for NPCs)
 }
 
 /// Ladder or rope data
@@ -162,6 +220,30 @@ pub struct Ladder {
     pub page: i32,
 }
 
+/// Reactor (breakable box, switch, quest trigger) placed on the map
+#[derive(Debug, Clone)]
+pub struct Reactor {
+    pub id: i32,               // Reactor.wz id
+    pub x: i32,
+    pub y: i32,
+    pub reactor_time: i32,     // Seconds until it comes back once broken (never if 0)
+    pub flip: bool,
+    pub states: Vec<ReactorState>,
+}
+
+/// One state of a reactor from Reactor.wz
+#[derive(Debug, Clone, Default)]
+pub struct ReactorState {
+    pub textures: Vec<Texture2D>,     // Idle animation frames
+    pub origins: Vec<(i32, i32)>,
+    pub delays: Vec<f32>,             // How long each frame is shown (seconds)
+    pub hit_textures: Vec<Texture2D>, // Played when a hit moves it on to `next`
+    pub hit_origins: Vec<(i32, i32)>,
+    pub hit_delays: Vec<f32>,
+    pub next: Option<usize>,          // State an attack moves it to (None if attacks do nothing)
+    pub end: bool,                    // No event leads out of it: reaching it breaks the reactor
+}
+
 /// Minimap information
 #[derive(Debug, Clone)]
 pub struct MiniMapInfo {
@@ -182,6 +264,7 @@ impl MapData {
             portals: Vec::new(),
             life: Vec::new(),
             ladders: Vec::new(),
+            reactors: Vec::new(),
             minimap: None,
         }
     }
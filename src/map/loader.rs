@@ -1,14 +1,14 @@
 #[allow(non_snake_case)]
 use macroquad::prelude::*;
-use crate::assets::AssetManager;
 use crate::map::data::*;
+use crate::config::config;
 use crate::map::tile_loader::TileCache;
-use crate::map::object_loader::ObjectCache;
+use crate::map::object_loader::{self, ObjectCache};
 use crate::map::npc_loader::NpcCache;
-use crate::map::mob_loader::MobCache;
-use std::sync::Arc;
-use wz_reader::version::guess_iv_from_wz_img;
-use wz_reader::{WzImage, WzNode, WzNodeArc, WzReader, WzObjectType};
+use crate::map::mob_loader::{MobCache, MobStats};
+use crate::map::reactor_loader::{self, ReactorCache};
+use crate::wz_cache::{load_wz_image, load_wz_images};
+use wz_reader::{WzNodeArc, WzObjectType};
 
 #[cfg(not(target_arch = "wasm32"))]
 use memmap2::MmapOptions;
@@ -68,45 +68,28 @@ impl MapLoader {
             info!("  Non-standard map ID length: {} characters", map_id.len());
         }
 
-        // Build URL for the map file
-        let url = format!(
-            "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/00/Map/Map/{}/{}.img",
-            map_category, map_id
-        );
-        let cache_name = format!("/00/Map/Map/{}/{}.img", map_category, map_id);
+        // Build the WZ path for the map file
+        let wz_path = format!("Map/Map/{}/{}.img", map_category, map_id);
 
-        info!("  Loading from: Map/Map/{}/{}.img", map_category, map_id);
-        info!("  Full URL: {}", url);
+        info!("  Loading from: {}", wz_path);
 
         // Fetch and parse the map file
         #[cfg(not(target_arch = "wasm32"))]
         let fetch_start = Instant::now();
 
-        let bytes = AssetManager::fetch_and_cache(&url, &cache_name).await
-            .map_err(|e| format!("Failed to fetch map: {}", e))?;
+        let root_node = load_wz_image(&wz_path)
+            .await
+            .map_err(|e| format!("Failed to load map: {}", e))?;
 
         #[cfg(not(target_arch = "wasm32"))]
         let fetch_time = fetch_start.elapsed();
 
-        info!("Parsing map file (size: {} bytes)...", bytes.len());
-
-        let wz_iv = guess_iv_from_wz_img(&bytes)
-            .ok_or_else(|| "Unable to guess version from map file".to_string())?;
-
-        let byte_len = bytes.len();
-
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        let cache_name_ref: wz_reader::WzNodeName = cache_name.clone().into();
-        let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-        let root_node: WzNodeArc = WzNode::new(&cache_name.into(), wz_image, None).into();
-
-        root_node.write().unwrap().parse(&root_node)
-            .map_err(|e| format!("Failed to parse map WZ: {:?}", e))?;
-
         info!("Map WZ file parsed successfully");
 
         // Console log the raw map structure for debugging
-        // Self::debug_log_map_structure(&root_node);
+        if config().verbose_map_loading {
+            Self::debug_log_map_structure(&root_node);
+        }
 
         // Parse map data
         let mut map_data = MapData::new();
@@ -165,6 +148,11 @@ impl MapLoader {
             Self::parse_ladders(&ladder_node, &mut map_data)?;
         }
 
+        // Parse reactors (breakable boxes, switches, quest triggers)
+        if let Ok(reactor_node) = root_node.read().unwrap().at_path_parsed("reactor") {
+            Self::parse_reactors(&reactor_node, &mut map_data).await?;
+        }
+
         // Parse tiles from numbered layers
         #[cfg(not(target_arch = "wasm32"))]
         let tiles_start = Instant::now();
@@ -229,7 +217,9 @@ impl MapLoader {
                 other => other,
             }
         });
-        // info!("Sorted {} tiles and {} objects", map_data.tiles.len(), map_data.objects.len());
+        if config().verbose_map_loading {
+            info!("Sorted {} tiles and {} objects", map_data.tiles.len(), map_data.objects.len());
+        }
 
         Ok(map_data)
     }
@@ -297,10 +287,10 @@ impl MapLoader {
         Ok(())
     }
 
-    /// Parse background layers (parallax scrolling images, NOT tiles)
+    /// Parse background layers (parallax scrolling images, NOT tiles) and load their frames
+    /// from Map.wz/Back
     async fn parse_backgrounds(node: &WzNodeArc, map_data: &mut MapData) -> Result<(), String> {
-        let node_read = node.read().unwrap();
-        let children = node_read.children.clone();
+        let children = node.read().unwrap().children.clone();
 
         for (_name, child) in children.iter() {
             let child_read = child.read().unwrap();
@@ -323,10 +313,6 @@ impl MapLoader {
                 info!("Background layer {}: bS='{}', no={}, pos=({},{}), scroll=({},{}), repeat=({},{}), front={}",
                     layer_num, bS, no, x, y, rx, ry, cx, cy, front);
 
-                // NOTE: Background textures should be loaded from Map.wz/Back/[bS]
-                // For now we're not loading them, just storing the metadata
-                // TODO: Implement background texture loading from Map.wz/Back/
-
                 let bg = BackgroundLayer {
                     layer_num,
                     bS,
@@ -342,13 +328,50 @@ impl MapLoader {
                     front,
                     flip_x: Self::get_int_property_from_node(child, "f").unwrap_or(0) == 1,
                     flip_y: false,
-                    texture: None, // TODO: Load from Map.wz/Back/
+                    animation: MapAnimation::default(),
                 };
 
                 map_data.backgrounds.push(bg);
             }
         }
 
+        // Batch fetch the background sets, then load each layer's frames
+        let mut back_sets: Vec<String> = map_data
+            .backgrounds
+            .iter()
+            .filter(|bg| !bg.bS.is_empty())
+            .map(|bg| bg.bS.clone())
+            .collect();
+        back_sets.sort();
+        back_sets.dedup();
+        let fetch_requests = back_sets.iter().map(|set| format!("Map/Back/{}.img", set)).collect();
+        let load_results = load_wz_images(fetch_requests).await;
+        let mut back_nodes = std::collections::HashMap::new();
+        for (set, result) in back_sets.into_iter().zip(load_results) {
+            match result {
+                Ok(node) => {
+                    back_nodes.insert(set, node);
+                }
+                Err(e) => warn!("Failed to load background set {}: {}", set, e),
+            }
+        }
+
+        for bg in &mut map_data.backgrounds {
+            let Some(back_node) = back_nodes.get(&bg.bS) else { continue };
+            // Still images are under back/{no}, animated ones under ani/{no}
+            let path = format!("{}/{}", if bg.ani { "ani" } else { "back" }, bg.no);
+            let result = back_node
+                .read()
+                .unwrap()
+                .at_path_parsed(&path)
+                .map_err(|_| format!("no {}", path))
+                .and_then(|node| object_loader::load_map_animation(&node));
+            match result {
+                Ok(animation) => bg.animation = animation,
+                Err(e) => warn!("Failed to load background {}/{}: {}", bg.bS, path, e),
+            }
+        }
+
         // Sort backgrounds by layer number
         map_data.backgrounds.sort_by_key(|bg| bg.layer_num);
 
@@ -559,23 +582,17 @@ impl MapLoader {
             let npc_ids_vec: Vec<String> = unique_npc_ids.iter().cloned().collect();
             let mut fetch_requests = Vec::new();
             for npc_id in &npc_ids_vec {
-                let url = format!(
-                    "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/Npc/{}.img",
-                    npc_id
-                );
-                let cache_name = format!("/01/Npc/{}.img", npc_id);
-                fetch_requests.push((url, cache_name));
+                let wz_path = format!("Npc/{}.img", npc_id);
+                fetch_requests.push(wz_path);
             }
 
-            let fetch_results = AssetManager::fetch_and_cache_batch(fetch_requests).await;
+            let load_results = load_wz_images(fetch_requests).await;
 
-            // Parse all the fetched files
-            info!("Parsing {} NPC files...", npc_ids_vec.len());
+            // Register the parsed nodes with the NPC cache
             for (i, npc_id) in npc_ids_vec.iter().enumerate() {
-                if let Ok(bytes) = &fetch_results[i] {
-                    if let Err(e) = npc_cache.preload_npc_from_bytes(npc_id, bytes.clone()).await {
-                        warn!("Failed to parse NPC {}: {}", npc_id, e);
-                    }
+                match &load_results[i] {
+                    Ok(node) => npc_cache.preload_npc(npc_id, node.clone()),
+                    Err(e) => warn!("Failed to load NPC {}: {}", npc_id, e),
                 }
             }
         }
@@ -585,23 +602,17 @@ impl MapLoader {
             let mob_ids_vec: Vec<String> = unique_mob_ids.iter().cloned().collect();
             let mut fetch_requests = Vec::new();
             for mob_id in &mob_ids_vec {
-                let url = format!(
-                    "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/Mob/{}.img",
-                    mob_id
-                );
-                let cache_name = format!("/01/Mob/{}.img", mob_id);
-                fetch_requests.push((url, cache_name));
+                let wz_path = format!("Mob/{}.img", mob_id);
+                fetch_requests.push(wz_path);
             }
 
-            let fetch_results = AssetManager::fetch_and_cache_batch(fetch_requests).await;
+            let load_results = load_wz_images(fetch_requests).await;
 
-            // Parse all the fetched files
-            info!("Parsing {} Mob files...", mob_ids_vec.len());
+            // Register the parsed nodes with the Mob cache
             for (i, mob_id) in mob_ids_vec.iter().enumerate() {
-                if let Ok(bytes) = &fetch_results[i] {
-                    if let Err(e) = mob_cache.preload_mob_from_bytes(mob_id, bytes.clone()).await {
-                        warn!("Failed to parse Mob {}: {}", mob_id, e);
-                    }
+                match &load_results[i] {
+                    Ok(node) => mob_cache.preload_mob(mob_id, node.clone()),
+                    Err(e) => warn!("Failed to load Mob {}: {}", mob_id, e),
                 }
             }
         }
@@ -668,6 +679,16 @@ impl MapLoader {
                 (String::new(), None, 0, 0, Vec::new(), Vec::new())
             };
 
+            // Mobs also need their hit/death animations and HP for combat
+            let (hit_textures, hit_origins, die_textures, die_origins, stats) = if life_entry.life_type == "m" && !life_entry.id.is_empty() {
+                let (hit_textures, hit_origins) = mob_cache.get_or_load_mob_frames(&life_entry.id, "hit1").await.unwrap_or_default();
+                let (die_textures, die_origins) = mob_cache.get_or_load_mob_frames(&life_entry.id, "die1").await.unwrap_or_default();
+                let stats = mob_cache.get_or_load_mob_stats(&life_entry.id).await;
+                (hit_textures, hit_origins, die_textures, die_origins, stats)
+            } else {
+                (Vec::new(), Vec::new(), Vec::new(), Vec::new(), MobStats::default())
+            };
+
             // Adjust y position to place life entity on the nearest foothold below
             // Use the original Y position from the map data - the renderer handles origin offset
             // Only snap to foothold if the entity has a foothold reference
@@ -701,6 +722,11 @@ impl MapLoader {
                 texture,
                 textures: mob_textures,
                 origins: mob_origins,
+                hit_textures,
+                hit_origins,
+                die_textures,
+                die_origins,
+                stats,
             };
 
             map_data.life.push(life);
@@ -733,6 +759,49 @@ impl MapLoader {
         Ok(())
     }
 
+    /// Parse reactors and load their states from Reactor.wz
+    async fn parse_reactors(node: &WzNodeArc, map_data: &mut MapData) -> Result<(), String> {
+        let mut reactor_cache = ReactorCache::new();
+        let mut reactors = Vec::new();
+        {
+            let node_read = node.read().unwrap();
+            for (_name, child) in node_read.children.iter() {
+                let Some(id) = Self::get_string_property_from_node(child, "id").and_then(|id| id.parse::<i32>().ok()) else {
+                    continue;
+                };
+                reactors.push(Reactor {
+                    id,
+                    x: Self::get_int_property_from_node(child, "x").unwrap_or(0),
+                    y: Self::get_int_property_from_node(child, "y").unwrap_or(0),
+                    reactor_time: Self::get_int_property_from_node(child, "reactorTime").unwrap_or(0),
+                    flip: Self::get_int_property_from_node(child, "f").unwrap_or(0) == 1,
+                    states: Vec::new(),
+                });
+            }
+        }
+
+        // Batch fetch the Reactor WZ files before loading their states one by one
+        let mut reactor_ids: Vec<i32> = reactors.iter().map(|reactor| reactor.id).collect();
+        reactor_ids.sort_unstable();
+        reactor_ids.dedup();
+        let fetch_requests = reactor_ids.iter().map(|&id| reactor_loader::reactor_wz_path(id)).collect();
+        let load_results = load_wz_images(fetch_requests).await;
+        for (reactor_id, result) in reactor_ids.iter().zip(load_results) {
+            match result {
+                Ok(node) => reactor_cache.preload_reactor(*reactor_id, node),
+                Err(e) => warn!("Failed to load Reactor {}: {}", reactor_id, e),
+            }
+        }
+
+        for mut reactor in reactors {
+            reactor.states = reactor_cache.get_or_load_states(reactor.id).await;
+            map_data.reactors.push(reactor);
+        }
+        info!("Parsed {} reactors", map_data.reactors.len());
+
+        Ok(())
+    }
+
     /// Parse tiles from numbered layers
     async fn parse_tiles(root_node: &WzNodeArc, map_data: &mut MapData) -> Result<(), String> {
         let root_read = root_node.read().unwrap();
@@ -789,30 +858,23 @@ impl MapLoader {
         }
 
         // SECOND PASS: Batch preload all unique tileset WZ files
-        // First, batch fetch all the WZ files
+        // First, batch fetch and parse all the WZ files
         info!("Batch fetching {} unique tileset files...", unique_tilesets.len());
         let tilesets_vec: Vec<String> = unique_tilesets.iter().cloned().collect();
         let mut fetch_requests = Vec::new();
         for tileset in &tilesets_vec {
-            let url = format!(
-                "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/Map/Tile/{}.img",
-                tileset
-            );
-            let cache_name = format!("/01/Map/Tile/{}.img", tileset);
-            fetch_requests.push((url, cache_name));
+            let wz_path = format!("Map/Tile/{}.img", tileset);
+            fetch_requests.push(wz_path);
         }
         
-        // Fetch all in parallel
-        let fetch_results = AssetManager::fetch_and_cache_batch(fetch_requests).await;
+        // Fetch all in parallel (already-parsed images come from the WZ cache)
+        let load_results = load_wz_images(fetch_requests).await;
 
-        // Now parse all the fetched files (sequentially but from cache)
-        info!("Parsing {} tileset files...", tilesets_vec.len());
+        // Register the parsed nodes with the tile cache
         for (i, tileset) in tilesets_vec.iter().enumerate() {
-            if let Ok(bytes) = &fetch_results[i] {
-                // Parse and cache the WZ node
-                if let Err(e) = tile_cache.preload_tileset_from_bytes(tileset, bytes.clone()).await {
-                    warn!("Failed to parse tileset {}: {}", tileset, e);
-                }
+            match &load_results[i] {
+                Ok(node) => tile_cache.preload_tileset(tileset, node.clone()),
+                Err(e) => warn!("Failed to load tileset {}: {}", tileset, e),
             }
         }
 
@@ -925,48 +987,43 @@ impl MapLoader {
             let object_sets_vec: Vec<String> = unique_object_sets.iter().cloned().collect();
             let mut fetch_requests = Vec::new();
             for oS in &object_sets_vec {
-                let url = format!(
-                    "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/Map/Obj/{}.img",
-                    oS
-                );
-                let cache_name = format!("/01/Map/Obj/{}.img", oS);
-                fetch_requests.push((url, cache_name));
+                let wz_path = format!("Map/Obj/{}.img", oS);
+                fetch_requests.push(wz_path);
             }
             
-            let fetch_results = AssetManager::fetch_and_cache_batch(fetch_requests).await;
+            let load_results = load_wz_images(fetch_requests).await;
             
             // Parse all the fetched files
             info!("Parsing {} object set files...", object_sets_vec.len());
             for (i, oS) in object_sets_vec.iter().enumerate() {
-                if let Ok(bytes) = &fetch_results[i] {
-                    if let Err(e) = object_cache.preload_object_set_from_bytes(oS, bytes.clone()).await {
-                        warn!("Failed to parse object set {}: {}", oS, e);
-                    }
+                match &load_results[i] {
+                    Ok(node) => object_cache.preload_object_set(oS, node.clone()),
+                    Err(e) => warn!("Failed to load object set {}: {}", oS, e),
                 }
             }
         }
 
         // THIRD PASS: Now load textures (WZ files should be cached) and create objects
-        info!("Loading {} individual object textures...", object_data.len());
+        info!("Loading {} individual object animations...", object_data.len());
         let mut failed_objects = 0;
         let mut successful_objects = 0;
 
         for (obj_id, layer_num, oS, l0, l1, l2, x, y, z, z_m, f, r) in object_data {
-            // Load object texture if object set is specified
-            let (texture, origin_x, origin_y) = if !oS.is_empty() && !l0.is_empty() {
+            // Load object frames if object set is specified
+            let animation = if !oS.is_empty() && !l0.is_empty() {
                 match object_cache.get_or_load_object(&oS, &l0, &l1, &l2).await {
-                    Some((tex, ox, oy)) => {
+                    Some(animation) => {
                         successful_objects += 1;
-                        (Some(tex), ox, oy)
+                        animation
                     }
                     None => {
                         failed_objects += 1;
                         warn!("    Failed to load object: {}/{}/{}/{} (object ID: {})", oS, l0, l1, l2, obj_id);
-                        (None, 0, 0)
+                        MapAnimation::default()
                     }
                 }
             } else {
-                (None, 0, 0)
+                MapAnimation::default()
             };
 
             let object = MapObject {
@@ -982,9 +1039,7 @@ impl MapLoader {
                 z_m,
                 f,
                 r,
-                origin_x,
-                origin_y,
-                texture,
+                animation,
             };
 
             map_data.objects.push(object);
@@ -1047,32 +1102,14 @@ impl MapLoader {
 
     /// Get map name from String/Map.img by recursively searching for the map ID
     pub async fn get_map_name(map_id: &str) -> Result<String, String> {
-        const MAP_STRING_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/00/String/Map.img";
-        const MAP_STRING_CACHE: &str = "/01/String/Map.img";
+        const MAP_STRING_PATH: &str = "String/Map.img";
 
         info!("Fetching String/Map.img to lookup map name for ID: {}", map_id);
 
         // Fetch and cache the Map.img file
-        let bytes = AssetManager::fetch_and_cache(MAP_STRING_URL, MAP_STRING_CACHE).await
-            .map_err(|e| format!("Failed to fetch String/Map.img: {}", e))?;
-
-        info!("Parsing String/Map.img (size: {} bytes)...", bytes.len());
-
-        // Guess IV and create reader
-        let wz_iv = guess_iv_from_wz_img(&bytes)
-            .ok_or_else(|| "Unable to guess version from String/Map.img".to_string())?;
-
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        
-        // Create root node
-        let name: wz_reader::WzNodeName = "Map.img".into();
-        let wz_image = WzImage::new(&name, 0, byte_len, &reader);
-        let root_node: WzNodeArc = WzNode::new(&name, wz_image, None).into();
-
-        // Parse the root node
-        root_node.write().unwrap().parse(&root_node)
-            .map_err(|e| format!("Failed to parse String/Map.img: {:?}", e))?;
+        let root_node = load_wz_image(MAP_STRING_PATH)
+            .await
+            .map_err(|e| format!("Failed to load String/Map.img: {}", e))?;
 
         info!("String/Map.img parsed, searching for map ID: {}", map_id);
 
@@ -1,4 +1,14 @@
 use crate::map::data::{Life, MapData};
+use crate::status_effect::StatusEffects;
+
+/// Walk speed of a mob with a speed modifier of 0 (pixels per second)
+const BASE_MOB_SPEED: f32 = 60.0;
+
+/// Mob.wz has no jump force; the client uses the same one for every mob
+const MOB_JUMP_VELOCITY: f32 = -350.0;
+
+/// Distance at which aggressive (firstAttack) mobs notice the player
+const FIRST_ATTACK_RANGE: f32 = 200.0;
 
 /// Mob movement states
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -9,6 +19,8 @@ pub enum MobStateType {
     Jump,
     Attack,
     KnockedBack,
+    Dying,  // Playing die1
+    Dead,   // Waiting to respawn
 }
 
 /// Runtime state for a mob (separate from Life which is static spawn data)
@@ -58,6 +70,9 @@ pub struct MobState {
     // Natural movement
     pub hesitation_timer: f32,        // Timer before jumping at edge
     pub speed_multiplier: f32,        // Current speed variation (0.9-1.1)
+
+    // Debuffs (stun, slow, poison)
+    pub effects: StatusEffects,
 }
 
 impl MobState {
@@ -117,10 +132,10 @@ impl MobState {
         // Start moving in a direction based on initial flip
         let direction = if life.flip { -1.0 } else { 1.0 };
         
-        // Mob type parameters (can be customized per mob ID later)
-        let base_speed = 60.0;
+        // Movement from Mob.wz info; only firstAttack mobs go after the player unprovoked
+        let base_speed = life.stats.move_speed(BASE_MOB_SPEED);
         let chase_speed = base_speed * 1.4;
-        let aggro_range = 200.0;
+        let aggro_range = if life.stats.first_attack { FIRST_ATTACK_RANGE } else { 0.0 };
         let max_leash_distance = 800.0;
         
         Self {
@@ -150,10 +165,11 @@ impl MobState {
             max_leash_distance,
             last_player_x: 0.0,
             last_player_y: 0.0,
-            jump_velocity: -350.0,
+            jump_velocity: MOB_JUMP_VELOCITY,
             on_ground: true,
             hesitation_timer: 0.0,
             speed_multiplier: 1.0,
+            effects: StatusEffects::new(),
         }
     }
     
@@ -200,6 +216,15 @@ impl MobAI {
         mob_state.detection_timer += dt;
         mob_state.speed_variation_timer += dt;
         
+        // Stunned mobs stay where they are and don't think; MobState has no HP, so poison
+        // damage is left to whoever tracks it
+        mob_state.effects.update(dt);
+        if mob_state.effects.is_stunned() {
+            mob_state.velocity_x = 0.0;
+            Self::apply_physics(mob_state, map, dt);
+            return;
+        }
+        
         // Update speed variation every 2-3 seconds
         if mob_state.speed_variation_timer >= 2.5 {
             mob_state.speed_multiplier = 0.9 + (mob_state.x as f32 % 20.0) / 100.0; // Pseudo-random 0.9-1.1
@@ -225,6 +250,12 @@ impl MobAI {
             MobStateType::Jump => Self::handle_jump(mob_state, map, dt),
             MobStateType::Attack => Self::handle_attack(mob_state, dt),
             MobStateType::KnockedBack => Self::handle_knocked_back(mob_state, map, dt),
+            MobStateType::Dying | MobStateType::Dead => mob_state.velocity_x = 0.0,
+        }
+        
+        // Slows scale whatever speed the state picked (knockback isn't slowed)
+        if mob_state.state != MobStateType::KnockedBack {
+            mob_state.velocity_x *= mob_state.effects.speed_multiplier();
         }
         
         // Apply physics
@@ -1,13 +1,45 @@
 use macroquad::prelude::*;
 use crate::assets::AssetManager;
 use std::collections::HashMap;
-use std::sync::Arc;
-use wz_reader::version::guess_iv_from_wz_img;
-use wz_reader::{WzImage, WzNode, WzNodeArc, WzObjectType, WzReader};
+use crate::wz_cache::load_wz_image;
+use wz_reader::{WzNodeArc, WzObjectType};
+
+/// Mob properties from Mob.wz `info`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MobStats {
+    pub level: i32,
+    pub max_hp: i32,
+    pub max_mp: i32,
+    /// PADamage: physical attack, used for touch damage
+    pub pa_damage: i32,
+    /// PDDamage: physical defense
+    pub pd_damage: i32,
+    /// Movement speed modifier in percent (-100 = doesn't move)
+    pub speed: i32,
+    /// Touching the mob hurts
+    pub body_attack: bool,
+    /// Attacks the player on sight instead of only after being hit
+    pub first_attack: bool,
+    pub undead: bool,
+    pub boss: bool,
+    /// `fs` value (movement force) as stored in the WZ
+    pub fs: f32,
+    /// EXP given when killed
+    pub exp: i32,
+}
+
+impl MobStats {
+    /// Movement speed in pixels per second, from a base speed and the speed modifier
+    pub fn move_speed(&self, base_speed: f32) -> f32 {
+        base_speed * (100 + self.speed).max(0) as f32 / 100.0
+    }
+}
 
 /// Cache for loaded Mob textures and parsed WZ nodes
 pub struct MobCache {
     mobs: HashMap<String, (Texture2D, i32, i32)>, // (texture, origin_x, origin_y)
+    stats: HashMap<String, MobStats>,
+    frames: HashMap<String, (Vec<Texture2D>, Vec<(i32, i32)>)>, // "mobId/anim" -> frames
     wz_nodes: HashMap<String, WzNodeArc>, // Cache parsed WZ IMG files
 }
 
@@ -15,34 +47,15 @@ impl MobCache {
     pub fn new() -> Self {
         Self {
             mobs: HashMap::new(),
+            stats: HashMap::new(),
+            frames: HashMap::new(),
             wz_nodes: HashMap::new(),
         }
     }
 
-    /// Preload a Mob WZ file from already-fetched bytes
-    pub async fn preload_mob_from_bytes(&mut self, mob_id: &str, bytes: Vec<u8>) -> Result<(), String> {
-        // Skip if already loaded
-        if self.wz_nodes.contains_key(mob_id) {
-            return Ok(());
-        }
-
-        let wz_iv = guess_iv_from_wz_img(&bytes)
-            .ok_or_else(|| "Unable to guess version from Mob file".to_string())?;
-
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        let cache_name = format!("/01/Mob/{}.img", mob_id);
-        let cache_name_ref: wz_reader::WzNodeName = cache_name.clone().into();
-        let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-        let node: WzNodeArc = WzNode::new(&cache_name.into(), wz_image, None).into();
-
-        node.write()
-            .unwrap()
-            .parse(&node)
-            .map_err(|e| format!("Failed to parse Mob WZ: {:?}", e))?;
-
-        self.wz_nodes.insert(mob_id.to_string(), node);
-        Ok(())
+    /// Register an already-parsed Mob WZ node
+    pub fn preload_mob(&mut self, mob_id: &str, node: WzNodeArc) {
+        self.wz_nodes.entry(mob_id.to_string()).or_insert(node);
     }
 
     /// Get or load a Mob texture
@@ -77,27 +90,37 @@ impl MobCache {
         &mut self,
         mob_id: &str,
     ) -> Option<(Vec<Texture2D>, Vec<(i32, i32)>)> {
-        info!("Loading move frames for Mob: {}", mob_id);
+        self.get_or_load_mob_frames(mob_id, "move").await
+    }
+
+    /// Get or load the frames of any Mob animation (move, hit1, die1, ...)
+    /// Returns: (textures, origins) - vectors of textures and their origin offsets
+    pub async fn get_or_load_mob_frames(
+        &mut self,
+        mob_id: &str,
+        anim: &str,
+    ) -> Option<(Vec<Texture2D>, Vec<(i32, i32)>)> {
+        let key = format!("{}/{}", mob_id, anim);
+        if let Some(frames) = self.frames.get(&key) {
+            return Some(frames.clone());
+        }
+
+        info!("Loading {} frames for Mob: {}", anim, mob_id);
 
         // Reuse generic WZ animation loader that also handles origins.
         // This is the same mechanism used for logo and portal animations,
         // so it's well-tested and safe in WASM.
-        let url = format!(
-            "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/Mob/{}.img",
-            mob_id
-        );
-        let cache_name = format!("/01/Mob/{}.img", mob_id);
+        let wz_path = format!("Mob/{}.img", mob_id);
 
-        // Mob structure: root -> move -> [0, 1, 2, ...]
+        // Mob structure: root -> anim -> [0, 1, 2, ...]
         let frames = AssetManager::load_animation_frames_with_origins(
-            &url,
-            &cache_name,
-            "move",
+            &wz_path,
+            anim,
         )
         .await;
 
         if frames.is_empty() {
-            info!("  No move frames found for Mob {}", mob_id);
+            info!("  No {} frames found for Mob {}", anim, mob_id);
             return None;
         }
 
@@ -109,10 +132,70 @@ impl MobCache {
             origins.push((frame.origin.x as i32, frame.origin.y as i32));
         }
 
-        info!("  Loaded {} move frames for Mob {}", textures.len(), mob_id);
+        info!("  Loaded {} {} frames for Mob {}", textures.len(), anim, mob_id);
+        self.frames.insert(key, (textures.clone(), origins.clone()));
         Some((textures, origins))
     }
 
+    /// Get or load a Mob's stats from its `info` node (defaults if the Mob can't be loaded)
+    pub async fn get_or_load_mob_stats(&mut self, mob_id: &str) -> MobStats {
+        if let Some(stats) = self.stats.get(mob_id) {
+            return *stats;
+        }
+
+        let root_node = if let Some(cached_node) = self.wz_nodes.get(mob_id) {
+            cached_node.clone()
+        } else {
+            match load_wz_image(&format!("Mob/{}.img", mob_id)).await {
+                Ok(node) => {
+                    self.wz_nodes.insert(mob_id.to_string(), node.clone());
+                    node
+                }
+                Err(e) => {
+                    warn!("Failed to load Mob {} stats: {}", mob_id, e);
+                    return MobStats { max_hp: 1, ..Default::default() };
+                }
+            }
+        };
+
+        let get = |key: &str| Self::get_number(&root_node, &format!("info/{}", key));
+        let stats = MobStats {
+            level: get("level").unwrap_or(1.0) as i32,
+            max_hp: (get("maxHP").unwrap_or(1.0) as i32).max(1),
+            max_mp: get("maxMP").unwrap_or(0.0) as i32,
+            pa_damage: get("PADamage").unwrap_or(0.0) as i32,
+            pd_damage: get("PDDamage").unwrap_or(0.0) as i32,
+            speed: get("speed").unwrap_or(0.0) as i32,
+            body_attack: get("bodyAttack").unwrap_or(0.0) != 0.0,
+            first_attack: get("firstAttack").unwrap_or(0.0) != 0.0,
+            undead: get("undead").unwrap_or(0.0) != 0.0,
+            boss: get("boss").unwrap_or(0.0) != 0.0,
+            fs: get("fs").unwrap_or(0.0) as f32,
+            exp: get("exp").unwrap_or(0.0) as i32,
+        };
+        info!("Mob {} stats: level {}, HP {}, PAD {}, PDD {}, speed {}", mob_id, stats.level, stats.max_hp, stats.pa_damage, stats.pd_damage, stats.speed);
+
+        self.stats.insert(mob_id.to_string(), stats);
+        stats
+    }
+
+    /// Read a numeric property (int, float or numeric string) below a node
+    fn get_number(node: &WzNodeArc, path: &str) -> Option<f64> {
+        let prop = node.read().unwrap().at_path_parsed(path).ok()?;
+        let prop_read = prop.read().unwrap();
+        match &prop_read.object_type {
+            WzObjectType::Value(wz_reader::property::WzValue::Short(val)) => Some(*val as f64),
+            WzObjectType::Value(wz_reader::property::WzValue::Int(val)) => Some(*val as f64),
+            WzObjectType::Value(wz_reader::property::WzValue::Long(val)) => Some(*val as f64),
+            WzObjectType::Value(wz_reader::property::WzValue::Float(val)) => Some(*val as f64),
+            WzObjectType::Value(wz_reader::property::WzValue::Double(val)) => Some(*val),
+            WzObjectType::Value(wz_reader::property::WzValue::String(val)) => {
+                val.get_string().ok().and_then(|s| s.parse().ok())
+            }
+            _ => None,
+        }
+    }
+
     /// Load a specific Mob with origin (using cached WZ nodes)
     /// Returns: (texture, origin_x, origin_y)
     async fn load_mob_with_origin_cached(
@@ -126,34 +209,13 @@ impl MobCache {
             // info!("  Using cached WZ node for {}.img", mob_id);
             cached_node.clone()
         } else {
-            // Build URL for the Mob file
-            let url = format!(
-                "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/Mob/{}.img",
-                mob_id
-            );
-            let cache_name = format!("/01/Mob/{}.img", mob_id);
+            // Build the WZ path for the Mob file
+            let wz_path = format!("Mob/{}.img", mob_id);
 
             // Fetch and parse the Mob file
-            let bytes = AssetManager::fetch_and_cache(&url, &cache_name)
+            let node = load_wz_image(&wz_path)
                 .await
-                .map_err(|e| format!("Failed to fetch Mob: {}", e))?;
-
-            info!("  Parsing Mob file (size: {} bytes)...", bytes.len());
-
-            let wz_iv = guess_iv_from_wz_img(&bytes)
-                .ok_or_else(|| "Unable to guess version from Mob file".to_string())?;
-
-            let byte_len = bytes.len();
-
-            let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-            let cache_name_ref: wz_reader::WzNodeName = cache_name.clone().into();
-            let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-            let node: WzNodeArc = WzNode::new(&cache_name.into(), wz_image, None).into();
-
-            node.write()
-                .unwrap()
-                .parse(&node)
-                .map_err(|e| format!("Failed to parse Mob WZ: {:?}", e))?;
+                .map_err(|e| format!("Failed to load Mob: {}", e))?;
 
             info!("  Mob WZ file parsed successfully");
 
@@ -232,32 +294,14 @@ impl MobCache {
 
     /// Get Mob name from String/Mob.img
     pub async fn get_mob_name(mob_id: &str) -> Result<String, String> {
-        const MOB_STRING_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/String/Mob.img";
-        const MOB_STRING_CACHE: &str = "/01/String/Mob.img";
+        const MOB_STRING_PATH: &str = "String/Mob.img";
 
         info!("Fetching String/Mob.img to lookup Mob name for ID: {}", mob_id);
 
         // Fetch and cache the Mob.img file
-        let bytes = AssetManager::fetch_and_cache(MOB_STRING_URL, MOB_STRING_CACHE).await
-            .map_err(|e| format!("Failed to fetch String/Mob.img: {}", e))?;
-
-        info!("Parsing String/Mob.img (size: {} bytes)...", bytes.len());
-
-        // Guess IV and create reader
-        let wz_iv = guess_iv_from_wz_img(&bytes)
-            .ok_or_else(|| "Unable to guess version from String/Mob.img".to_string())?;
-
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-
-        // Create root node
-        let name: wz_reader::WzNodeName = "Mob.img".into();
-        let wz_image = WzImage::new(&name, 0, byte_len, &reader);
-        let root_node: WzNodeArc = WzNode::new(&name, wz_image, None).into();
-
-        // Parse the root node
-        root_node.write().unwrap().parse(&root_node)
-            .map_err(|e| format!("Failed to parse String/Mob.img: {:?}", e))?;
+        let root_node = load_wz_image(MOB_STRING_PATH)
+            .await
+            .map_err(|e| format!("Failed to load String/Mob.img: {}", e))?;
 
         info!("String/Mob.img parsed, searching for Mob ID: {}", mob_id);
 
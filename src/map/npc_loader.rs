@@ -1,9 +1,7 @@
 use macroquad::prelude::*;
-use crate::assets::AssetManager;
 use std::collections::HashMap;
-use std::sync::Arc;
-use wz_reader::version::guess_iv_from_wz_img;
-use wz_reader::{WzImage, WzNode, WzNodeArc, WzObjectType, WzReader};
+use crate::wz_cache::load_wz_image;
+use wz_reader::{WzNodeArc, WzObjectType};
 
 /// Cache for loaded NPC textures and parsed WZ nodes
 pub struct NpcCache {
@@ -19,30 +17,9 @@ impl NpcCache {
         }
     }
 
-    /// Preload an NPC WZ file from already-fetched bytes
-    pub async fn preload_npc_from_bytes(&mut self, npc_id: &str, bytes: Vec<u8>) -> Result<(), String> {
-        // Skip if already loaded
-        if self.wz_nodes.contains_key(npc_id) {
-            return Ok(());
-        }
-
-        let wz_iv = guess_iv_from_wz_img(&bytes)
-            .ok_or_else(|| "Unable to guess version from NPC file".to_string())?;
-
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        let cache_name = format!("/01/Npc/{}.img", npc_id);
-        let cache_name_ref: wz_reader::WzNodeName = cache_name.clone().into();
-        let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-        let node: WzNodeArc = WzNode::new(&cache_name.into(), wz_image, None).into();
-
-        node.write()
-            .unwrap()
-            .parse(&node)
-            .map_err(|e| format!("Failed to parse NPC WZ: {:?}", e))?;
-
-        self.wz_nodes.insert(npc_id.to_string(), node);
-        Ok(())
+    /// Register an already-parsed NPC WZ node
+    pub fn preload_npc(&mut self, npc_id: &str, node: WzNodeArc) {
+        self.wz_nodes.entry(npc_id.to_string()).or_insert(node);
     }
 
     /// Get or load an NPC texture
@@ -84,34 +61,13 @@ impl NpcCache {
             info!("  Using cached WZ node for {}.img", npc_id);
             cached_node.clone()
         } else {
-            // Build URL for the NPC file
-            let url = format!(
-                "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/Npc/{}.img",
-                npc_id
-            );
-            let cache_name = format!("/01/Npc/{}.img", npc_id);
+            // Build the WZ path for the NPC file
+            let wz_path = format!("Npc/{}.img", npc_id);
 
             // Fetch and parse the NPC file
-            let bytes = AssetManager::fetch_and_cache(&url, &cache_name)
+            let node = load_wz_image(&wz_path)
                 .await
-                .map_err(|e| format!("Failed to fetch NPC: {}", e))?;
-
-            info!("  Parsing NPC file (size: {} bytes)...", bytes.len());
-
-            let wz_iv = guess_iv_from_wz_img(&bytes)
-                .ok_or_else(|| "Unable to guess version from NPC file".to_string())?;
-
-            let byte_len = bytes.len();
-
-            let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-            let cache_name_ref: wz_reader::WzNodeName = cache_name.clone().into();
-            let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-            let node: WzNodeArc = WzNode::new(&cache_name.into(), wz_image, None).into();
-
-            node.write()
-                .unwrap()
-                .parse(&node)
-                .map_err(|e| format!("Failed to parse NPC WZ: {:?}", e))?;
+                .map_err(|e| format!("Failed to load NPC: {}", e))?;
 
             info!("  NPC WZ file parsed successfully");
 
@@ -217,32 +173,14 @@ impl NpcCache {
 
     /// Get NPC name from String/NPC.img
     pub async fn get_npc_name(npc_id: &str) -> Result<String, String> {
-        const NPC_STRING_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/String/Npc.img";
-        const NPC_STRING_CACHE: &str = "/01/String/Npc.img";
+        const NPC_STRING_PATH: &str = "String/Npc.img";
 
         info!("Fetching String/Npc.img to lookup NPC name for ID: {}", npc_id);
 
         // Fetch and cache the Npc.img file
-        let bytes = AssetManager::fetch_and_cache(NPC_STRING_URL, NPC_STRING_CACHE).await
-            .map_err(|e| format!("Failed to fetch String/Npc.img: {}", e))?;
-
-        info!("Parsing String/Npc.img (size: {} bytes)...", bytes.len());
-
-        // Guess IV and create reader
-        let wz_iv = guess_iv_from_wz_img(&bytes)
-            .ok_or_else(|| "Unable to guess version from String/Npc.img".to_string())?;
-
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-
-        // Create root node
-        let name: wz_reader::WzNodeName = "Npc.img".into();
-        let wz_image = WzImage::new(&name, 0, byte_len, &reader);
-        let root_node: WzNodeArc = WzNode::new(&name, wz_image, None).into();
-
-        // Parse the root node
-        root_node.write().unwrap().parse(&root_node)
-            .map_err(|e| format!("Failed to parse String/Npc.img: {:?}", e))?;
+        let root_node = load_wz_image(NPC_STRING_PATH)
+            .await
+            .map_err(|e| format!("Failed to load String/Npc.img: {}", e))?;
 
         info!("String/Npc.img parsed, searching for NPC ID: {}", npc_id);
 
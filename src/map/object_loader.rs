@@ -1,14 +1,19 @@
 #[allow(non_snake_case)]
 use macroquad::prelude::*;
-use crate::assets::AssetManager;
 use std::collections::HashMap;
-use std::sync::Arc;
-use wz_reader::version::guess_iv_from_wz_img;
-use wz_reader::{WzImage, WzNode, WzNodeArc, WzObjectType, WzReader};
+use crate::map::data::{MapAnimation, MapFrame};
+use crate::wz_cache::load_wz_image;
+use wz_reader::{WzNodeArc, WzObjectType};
 
-/// Cache for loaded object textures and parsed WZ nodes
+/// Frame delay when a frame has none (milliseconds)
+const DEFAULT_FRAME_DELAY: i32 = 100;
+
+/// Swing period when a moving object has no `moveP` (milliseconds)
+const DEFAULT_MOVE_PERIOD: i32 = 2000;
+
+/// Cache for loaded object animations and parsed WZ nodes
 pub struct ObjectCache {
-    objects: HashMap<String, (Texture2D, i32, i32)>, // (texture, origin_x, origin_y)
+    objects: HashMap<String, MapAnimation>,
     wz_nodes: HashMap<String, WzNodeArc>, // Cache parsed WZ IMG files
 }
 
@@ -20,54 +25,32 @@ impl ObjectCache {
         }
     }
 
-    /// Preload an object set WZ file from already-fetched bytes
-    pub async fn preload_object_set_from_bytes(&mut self, oS: &str, bytes: Vec<u8>) -> Result<(), String> {
-        // Skip if already loaded
-        if self.wz_nodes.contains_key(oS) {
-            return Ok(());
-        }
-
-        let wz_iv = guess_iv_from_wz_img(&bytes)
-            .ok_or_else(|| "Unable to guess version from object file".to_string())?;
-
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        let cache_name = format!("/01/Map/Obj/{}.img", oS);
-        let cache_name_ref: wz_reader::WzNodeName = cache_name.clone().into();
-        let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-        let node: WzNodeArc = WzNode::new(&cache_name.into(), wz_image, None).into();
-
-        node.write()
-            .unwrap()
-            .parse(&node)
-            .map_err(|e| format!("Failed to parse object WZ: {:?}", e))?;
-
-        self.wz_nodes.insert(oS.to_string(), node);
-        Ok(())
+    /// Register an already-parsed object set WZ node
+    pub fn preload_object_set(&mut self, oS: &str, node: WzNodeArc) {
+        self.wz_nodes.entry(oS.to_string()).or_insert(node);
     }
 
-    /// Get or load an object texture
+    /// Get or load an object's frames
     /// Key format: "oS/l0/l1/l2" (e.g., "acc1/grassySoil/artificiality/26")
-    /// Returns: (texture, origin_x, origin_y)
     pub async fn get_or_load_object(
         &mut self,
         oS: &str,
         l0: &str,
         l1: &str,
         l2: &str,
-    ) -> Option<(Texture2D, i32, i32)> {
+    ) -> Option<MapAnimation> {
         let key = format!("{}/{}/{}/{}", oS, l0, l1, l2);
 
-        // Return cached texture+origin if available
-        if let Some((texture, ox, oy)) = self.objects.get(&key).cloned() {
-            return Some((texture, ox, oy));
+        // Return cached animation if available
+        if let Some(animation) = self.objects.get(&key) {
+            return Some(animation.clone());
         }
 
         // Load the object if not already loaded
-        match self.load_object_with_origin_cached(oS, l0, l1, l2).await {
-            Ok((texture, ox, oy)) => {
-                self.objects.insert(key.clone(), (texture.clone(), ox, oy));
-                Some((texture, ox, oy))
+        match self.load_object_cached(oS, l0, l1, l2).await {
+            Ok(animation) => {
+                self.objects.insert(key, animation.clone());
+                Some(animation)
             }
             Err(e) => {
                 warn!("Failed to load object {}: {}", key, e);
@@ -76,61 +59,33 @@ impl ObjectCache {
         }
     }
 
-    /// Load a specific object from an object set with origin (using cached WZ nodes)
-    /// Returns: (texture, origin_x, origin_y)
-    async fn load_object_with_origin_cached(
+    /// Load a specific object from an object set (using cached WZ nodes)
+    async fn load_object_cached(
         &mut self,
         oS: &str,
         l0: &str,
         l1: &str,
         l2: &str,
-    ) -> Result<(Texture2D, i32, i32), String> {
-        // info!("Loading object: {}/{}/{}/{}", oS, l0, l1, l2);
-
+    ) -> Result<MapAnimation, String> {
         // Check if we already have this WZ node cached
         let root_node = if let Some(cached_node) = self.wz_nodes.get(oS) {
-            // info!("  Using cached WZ node for {}.img", oS);
             cached_node.clone()
         } else {
-            // Build URL for the object file
-            let url = format!(
-                "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/Map/Obj/{}.img",
-                oS
-            );
-            let cache_name = format!("/01/Map/Obj/{}.img", oS);
+            // Build the WZ path for the object file
+            let wz_path = format!("Map/Obj/{}.img", oS);
 
             // Fetch and parse the object file
-            let bytes = AssetManager::fetch_and_cache(&url, &cache_name)
+            let node = load_wz_image(&wz_path)
                 .await
-                .map_err(|e| format!("Failed to fetch object: {}", e))?;
-
-            // info!("  Parsing object file (size: {} bytes)...", bytes.len());
-
-            let wz_iv = guess_iv_from_wz_img(&bytes)
-                .ok_or_else(|| "Unable to guess version from object file".to_string())?;
-
-            let byte_len = bytes.len();
-
-            let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-            let cache_name_ref: wz_reader::WzNodeName = cache_name.clone().into();
-            let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-            let node: WzNodeArc = WzNode::new(&cache_name.into(), wz_image, None).into();
-
-            node.write()
-                .unwrap()
-                .parse(&node)
-                .map_err(|e| format!("Failed to parse object WZ: {:?}", e))?;
-
-            // info!("  Object WZ file parsed successfully");
+                .map_err(|e| format!("Failed to load object: {}", e))?;
 
             // Cache the parsed node
             self.wz_nodes.insert(oS.to_string(), node.clone());
             node
         };
 
-        // Navigate to the specific object: l0/l1/l2/0
+        // Navigate to the specific object: l0/l1/l2, which holds frames 0, 1, ...
         // Build path based on which layers are present
-        // The PNG is typically at index 0 under the final layer
         let base_path = if !l2.is_empty() {
             format!("{}/{}/{}", l0, l1, l2)
         } else if !l1.is_empty() {
@@ -141,68 +96,137 @@ impl ObjectCache {
             return Err("No valid path components".to_string());
         };
 
-        // Try with /0 suffix first (most common case)
-        let path_with_zero = format!("{}/0", base_path);
-        // info!("Navigating to object path: {}", path_with_zero);
-
-        let obj_node = match root_node.read().unwrap().at_path_parsed(&path_with_zero) {
-            Ok(node) => node,
-            Err(_) => {
-                // Fallback: try without the /0 suffix
-                // info!("Failed with /0, trying base path: {}", base_path);
-                root_node
-                    .read()
-                    .unwrap()
-                    .at_path_parsed(&base_path)
-                    .map_err(|_| format!("Object not found at path: {} or {}", path_with_zero, base_path))?
-            }
-        };
+        let obj_node = root_node
+            .read()
+            .unwrap()
+            .at_path_parsed(&base_path)
+            .map_err(|_| format!("Object not found at path: {}", base_path))?;
+        load_map_animation(&obj_node)
+    }
+}
 
-        // Extract origin if available
-        let obj_read = obj_node.read().unwrap();
-        let (origin_x, origin_y) = if let Ok(origin_node) = obj_read.at_path_parsed("origin") {
+/// Load the frames of a map object or background. `node` holds frames `0`, `1`, ... or is a
+/// single canvas itself. Each frame has an `origin`, a `delay` (ms) and an alpha ramp `a0`/`a1`;
+/// `moveType`, `moveW`, `moveH` and `moveP` (ms) are read from `node` or else its first frame.
+pub fn load_map_animation(node: &WzNodeArc) -> Result<MapAnimation, String> {
+    let mut frame_nodes: Vec<(i32, WzNodeArc)> = node
+        .read()
+        .unwrap()
+        .children
+        .iter()
+        .filter_map(|(name, child)| Some((name.parse::<i32>().ok()?, child.clone())))
+        .collect();
+    frame_nodes.sort_by_key(|(index, _)| *index);
+    let frame_nodes: Vec<WzNodeArc> = if frame_nodes.is_empty() {
+        vec![node.clone()]
+    } else {
+        frame_nodes.into_iter().map(|(_, frame_node)| frame_node).collect()
+    };
+
+    // Frames may link to another frame (UOL); one that still can't be loaded is left out
+    let mut frames = Vec::with_capacity(frame_nodes.len());
+    for frame_node in &frame_nodes {
+        let frame = resolve_uol(frame_node.clone(), 0)
+            .ok_or_else(|| "Broken UOL link".to_string())
+            .and_then(|frame_node| load_map_frame(&frame_node));
+        match frame {
+            Ok(frame) => frames.push(frame),
+            Err(e) => warn!("Skipping frame {}: {}", frame_node.read().unwrap().name.as_str(), e),
+        }
+    }
+    if frames.is_empty() {
+        return Err("No frame could be loaded".to_string());
+    }
+
+    let motion = |key: &str| get_int(node, key).or_else(|| get_int(&frame_nodes[0], key));
+    Ok(MapAnimation {
+        frames,
+        move_type: motion("moveType").unwrap_or(0),
+        move_w: motion("moveW").unwrap_or(0),
+        move_h: motion("moveH").unwrap_or(0),
+        move_p: motion("moveP").filter(|&period| period > 0).unwrap_or(DEFAULT_MOVE_PERIOD) as f32 / 1000.0,
+    })
+}
+
+fn load_map_frame(node: &WzNodeArc) -> Result<MapFrame, String> {
+    let node_read = node.read().unwrap();
+
+    // Extract PNG data
+    let texture = match &node_read.object_type {
+        WzObjectType::Property(wz_reader::property::WzSubProperty::PNG(png_prop)) => {
+            let dynamic_img = png_prop.extract_png()
+                .map_err(|e| format!("Failed to extract PNG: {:?}", e))?;
+
+            // Convert to RGBA8 format
+            let rgba_img = dynamic_img.to_rgba8();
+            let width = rgba_img.width() as u16;
+            let height = rgba_img.height() as u16;
+            let bytes = rgba_img.into_raw();
+
+            let texture = Texture2D::from_rgba8(width, height, &bytes);
+            texture.set_filter(FilterMode::Linear);
+            texture
+        }
+        _ => return Err(format!("Frame {} is not a PNG", node_read.name.as_str())),
+    };
+
+    let (origin_x, origin_y) = node_read
+        .at_path_parsed("origin")
+        .ok()
+        .and_then(|origin_node| {
             let origin_read = origin_node.read().unwrap();
             match &origin_read.object_type {
-                WzObjectType::Value(wz_reader::property::WzValue::Vector(vec)) => {
-                    // info!("  Found origin: ({}, {})", vec.0, vec.1);
-                    (vec.0, vec.1)
-                }
-                _ => {
-                    // info!("  Origin found but not a Vector, using default (0, 0)");
-                    (0, 0)
-                }
+                WzObjectType::Value(wz_reader::property::WzValue::Vector(vec)) => Some((vec.0, vec.1)),
+                _ => None,
             }
-        } else {
-            // info!("  No origin found, using default (0, 0)");
-            (0, 0)
+        })
+        .unwrap_or((0, 0));
+    drop(node_read);
+
+    let a0 = get_int(node, "a0").unwrap_or(255);
+    Ok(MapFrame {
+        texture,
+        origin_x,
+        origin_y,
+        delay: get_int(node, "delay").filter(|&delay| delay > 0).unwrap_or(DEFAULT_FRAME_DELAY) as f32 / 1000.0,
+        a0: a0 as f32,
+        a1: get_int(node, "a1").unwrap_or(a0) as f32,
+    })
+}
+
+/// Follow a UOL link (e.g. "../0") to the node it points at; other nodes are returned as they are
+fn resolve_uol(node: WzNodeArc, depth: u32) -> Option<WzNodeArc> {
+    let link = match &node.read().unwrap().object_type {
+        WzObjectType::Value(wz_reader::property::WzValue::UOL(path)) => path.get_string().ok()?,
+        _ => return Some(node.clone()),
+    };
+    if depth > 8 {
+        return None;
+    }
+
+    // UOL paths are relative to the node's parent
+    let mut current = node.read().unwrap().parent.upgrade()?;
+    for segment in link.split('/') {
+        current = match segment {
+            ".." => current.read().unwrap().parent.upgrade()?,
+            "" | "." => current,
+            name => current.read().unwrap().at_path(name)?,
         };
+    }
+    current.write().unwrap().parse(&current).ok();
+    resolve_uol(current, depth + 1)
+}
 
-        // Extract PNG data
-        match &obj_read.object_type {
-            WzObjectType::Property(wz_reader::property::WzSubProperty::PNG(png_prop)) => {
-                // Get PNG data
-                let dynamic_img = png_prop.extract_png()
-                    .map_err(|e| format!("Failed to extract PNG: {:?}", e))?;
-
-                // Convert to RGBA8 format
-                let rgba_img = dynamic_img.to_rgba8();
-                let width = rgba_img.width() as u16;
-                let height = rgba_img.height() as u16;
-                let bytes = rgba_img.into_raw();
-
-                // Load texture from PNG data
-                let texture = Texture2D::from_rgba8(width, height, &bytes);
-                texture.set_filter(FilterMode::Linear);
-
-                // info!(
-                //     "Loaded object {}/{}/{}/{} ({}x{}) with origin ({}, {})",
-                //     oS, l0, l1, l2, width, height, origin_x, origin_y
-                // );
-
-                Ok((texture, origin_x, origin_y))
-            }
-            _ => Err(format!("Object node is not a PNG: {}", path_with_zero)),
+fn get_int(node: &WzNodeArc, key: &str) -> Option<i32> {
+    let prop = node.read().unwrap().at_path_parsed(key).ok()?;
+    let prop_read = prop.read().unwrap();
+    match &prop_read.object_type {
+        WzObjectType::Value(wz_reader::property::WzValue::Short(val)) => Some(*val as i32),
+        WzObjectType::Value(wz_reader::property::WzValue::Int(val)) => Some(*val),
+        WzObjectType::Value(wz_reader::property::WzValue::Long(val)) => Some(*val as i32),
+        WzObjectType::Value(wz_reader::property::WzValue::String(val)) => {
+            val.get_string().ok().and_then(|s| s.parse().ok())
         }
+        _ => None,
     }
-
 }
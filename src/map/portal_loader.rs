@@ -1,9 +1,7 @@
 use macroquad::prelude::*;
-use crate::assets::AssetManager;
 use std::collections::HashMap;
-use std::sync::Arc;
-use wz_reader::version::guess_iv_from_wz_img;
-use wz_reader::{WzImage, WzNode, WzNodeArc, WzObjectType, WzReader};
+use crate::wz_cache::load_wz_image;
+use wz_reader::{WzNodeArc, WzObjectType};
 
 /// Cache for loaded portal textures and parsed WZ nodes
 pub struct PortalCache {
@@ -59,30 +57,12 @@ impl PortalCache {
             info!("  Using cached MapHelper.img node");
             cached_node.clone()
         } else {
-            // Build URL for MapHelper.img
-            let url = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/Map/MapHelper.img";
-            let cache_name = "/01/Map/MapHelper.img";
+            let wz_path = "Map/MapHelper.img";
 
             // Fetch and parse MapHelper.img
-            let bytes = AssetManager::fetch_and_cache(url, cache_name)
+            let node = load_wz_image(wz_path)
                 .await
-                .map_err(|e| format!("Failed to fetch MapHelper.img: {}", e))?;
-
-            info!("  Parsing MapHelper.img (size: {} bytes)...", bytes.len());
-
-            let wz_iv = guess_iv_from_wz_img(&bytes)
-                .ok_or_else(|| "Unable to guess version from MapHelper.img".to_string())?;
-
-            let byte_len = bytes.len();
-            let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-            let cache_name_ref: wz_reader::WzNodeName = cache_name.to_string().into();
-            let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-            let node: WzNodeArc = WzNode::new(&cache_name.into(), wz_image, None).into();
-
-            node.write()
-                .unwrap()
-                .parse(&node)
-                .map_err(|e| format!("Failed to parse MapHelper.img: {:?}", e))?;
+                .map_err(|e| format!("Failed to load MapHelper.img: {}", e))?;
 
             info!("  MapHelper.img parsed successfully");
 
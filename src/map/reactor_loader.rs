@@ -0,0 +1,159 @@
+use macroquad::prelude::*;
+use crate::assets::AssetManager;
+use crate::map::data::ReactorState;
+use crate::wz_cache::load_wz_image;
+use std::collections::HashMap;
+use wz_reader::{WzNodeArc, WzObjectType};
+
+/// Frame delay when a frame has none (milliseconds)
+const DEFAULT_FRAME_DELAY: f64 = 100.0;
+
+/// Event types fired by attacks: plain hits (0) and hits from one side (1, 2)
+const HIT_EVENT_TYPES: [i32; 3] = [0, 1, 2];
+
+/// WZ image of a reactor (Reactor.wz ids are padded to 7 digits)
+pub fn reactor_wz_path(reactor_id: i32) -> String {
+    format!("Reactor/{:07}.img", reactor_id)
+}
+
+/// Cache for reactor states loaded from Reactor.wz
+pub struct ReactorCache {
+    states: HashMap<i32, Vec<ReactorState>>,
+    wz_nodes: HashMap<i32, WzNodeArc>, // Cache parsed WZ IMG files
+}
+
+impl ReactorCache {
+    pub fn new() -> Self {
+        Self {
+            states: HashMap::new(),
+            wz_nodes: HashMap::new(),
+        }
+    }
+
+    /// Register an already-parsed Reactor WZ node
+    pub fn preload_reactor(&mut self, reactor_id: i32, node: WzNodeArc) {
+        self.wz_nodes.entry(reactor_id).or_insert(node);
+    }
+
+    /// Get or load the states of a reactor: `0`, `1`, ... with their frames, `hit` frames and
+    /// `event`s. A reactor with `info/link` uses the states of the linked one.
+    pub async fn get_or_load_states(&mut self, reactor_id: i32) -> Vec<ReactorState> {
+        if let Some(states) = self.states.get(&reactor_id) {
+            return states.clone();
+        }
+
+        let link = match self.get_or_load_node(reactor_id).await {
+            Some(node) => Self::get_number(&node, "info/link").map(|link| link as i32),
+            None => return Vec::new(),
+        };
+        let source_id = link.unwrap_or(reactor_id);
+        let Some(root_node) = self.get_or_load_node(source_id).await else {
+            return Vec::new();
+        };
+
+        let mut states = Vec::new();
+        for index in 0.. {
+            let state_path = index.to_string();
+            let Ok(state_node) = root_node.read().unwrap().at_path_parsed(&state_path) else {
+                break;
+            };
+
+            let (textures, origins, delays) = Self::load_frames(&root_node, &state_path);
+            let (hit_textures, hit_origins, hit_delays) = Self::load_frames(&root_node, &format!("{}/hit", state_path));
+
+            // The first attack event decides where a hit goes; any event at all means more can happen
+            let mut next = None;
+            let mut end = true;
+            for event in 0.. {
+                let Some(event_type) = Self::get_number(&state_node, &format!("event/{}/type", event)) else {
+                    break;
+                };
+                end = false;
+                if next.is_none() && HIT_EVENT_TYPES.contains(&(event_type as i32)) {
+                    next = Self::get_number(&state_node, &format!("event/{}/state", event)).map(|state| state as usize);
+                }
+            }
+
+            states.push(ReactorState {
+                textures,
+                origins,
+                delays,
+                hit_textures,
+                hit_origins,
+                hit_delays,
+                next,
+                end,
+            });
+        }
+
+        info!("Loaded {} states for Reactor {}", states.len(), reactor_id);
+        self.states.insert(reactor_id, states.clone());
+        states
+    }
+
+    async fn get_or_load_node(&mut self, reactor_id: i32) -> Option<WzNodeArc> {
+        if let Some(node) = self.wz_nodes.get(&reactor_id) {
+            return Some(node.clone());
+        }
+        match load_wz_image(&reactor_wz_path(reactor_id)).await {
+            Ok(node) => {
+                self.wz_nodes.insert(reactor_id, node.clone());
+                Some(node)
+            }
+            Err(e) => {
+                warn!("Failed to load Reactor {}: {}", reactor_id, e);
+                None
+            }
+        }
+    }
+
+    /// Frames `0`, `1`, ... below a node, as textures, their origin offsets and their delays
+    /// (seconds). Frames that can't be loaded are left out; a missing node has no frames.
+    fn load_frames(root_node: &WzNodeArc, base_path: &str) -> (Vec<Texture2D>, Vec<(i32, i32)>, Vec<f32>) {
+        let (mut textures, mut origins, mut delays) = (Vec::new(), Vec::new(), Vec::new());
+        let Ok(node) = root_node.read().unwrap().at_path_parsed(base_path) else {
+            return (textures, origins, delays);
+        };
+        let mut frame_names: Vec<i32> = node.read().unwrap().children.keys().filter_map(|name| name.parse().ok()).collect();
+        frame_names.sort();
+
+        for frame in frame_names {
+            let frame_path = format!("{}/{}", base_path, frame);
+            match AssetManager::load_frame_from_node(root_node, &frame_path, &format!("{}/origin", frame_path)) {
+                Ok(frame_data) => {
+                    let delay = Self::get_number(root_node, &format!("{}/delay", frame_path))
+                        .filter(|&delay| delay > 0.0)
+                        .unwrap_or(DEFAULT_FRAME_DELAY);
+                    textures.push(frame_data.texture);
+                    origins.push((frame_data.origin.x as i32, frame_data.origin.y as i32));
+                    delays.push(delay as f32 / 1000.0);
+                }
+                Err(e) => warn!("Failed to load reactor frame {}: {}", frame_path, e),
+            }
+        }
+        (textures, origins, delays)
+    }
+
+    /// Read a numeric property (int, float or numeric string) below a node
+    fn get_number(node: &WzNodeArc, path: &str) -> Option<f64> {
+        let prop = node.read().unwrap().at_path_parsed(path).ok()?;
+        let prop_read = prop.read().unwrap();
+        match &prop_read.object_type {
+            WzObjectType::Value(wz_reader::property::WzValue::Short(val)) => Some(*val as f64),
+            WzObjectType::Value(wz_reader::property::WzValue::Int(val)) => Some(*val as f64),
+            WzObjectType::Value(wz_reader::property::WzValue::Long(val)) => Some(*val as f64),
+            WzObjectType::Value(wz_reader::property::WzValue::Float(val)) => Some(*val as f64),
+            WzObjectType::Value(wz_reader::property::WzValue::Double(val)) => Some(*val),
+            WzObjectType::Value(wz_reader::property::WzValue::String(val)) => {
+                val.get_string().ok().and_then(|s| s.parse().ok())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Default for ReactorCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,11 +1,13 @@
 use macroquad::prelude::*;
 use crate::map::data::*;
-use crate::flags;
-use crate::game_world::bot_ai::BotAI;
+use crate::map::MobStateType;
+use crate::config::config;
+use crate::flags::DebugFlags;
+use crate::assets::AssetManager;
+use crate::game_world::bot_ai::{BotAI, MOB_FRAME_TIME};
 use crate::character_renderer::{CharacterRenderer, CharacterState};
 
 pub struct MapRenderer {
-    debug_footholds: bool,
     debug_portals: bool,
     debug_bounds: bool,
     npc_font: Option<Font>,
@@ -14,7 +16,6 @@ pub struct MapRenderer {
 impl MapRenderer {
     pub fn new() -> Self {
         Self {
-            debug_footholds: flags::SHOW_HITBOXES,
             debug_portals: true, // Show portal circles for debugging
             debug_bounds: false,
             npc_font: None,
@@ -27,7 +28,7 @@ impl MapRenderer {
         // Liberation Sans is an open-source font that looks identical to Arial
         info!("Loading Arial Bold-compatible font for NPC names...");
 
-        match load_ttf_font("https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/MaplestoryLight.ttf").await {
+        match AssetManager::load_font("MaplestoryLight.ttf").await {
             Ok(font) => {
                 info!("Loaded Arial Bold-compatible font successfully");
                 self.npc_font = Some(font);
@@ -56,7 +57,7 @@ impl MapRenderer {
         }
 
         // Draw footholds (platforms) for debugging
-        if self.debug_footholds {
+        if config().show_hitboxes {
             self.render_footholds(map, camera_x, camera_y);
         }
 
@@ -75,8 +76,9 @@ impl MapRenderer {
         self.render_backgrounds(map, camera_x, camera_y, true);
     }
 
-    /// Render background layers with parallax scrolling
+    /// Render background layers with parallax scrolling, animation and motion
     fn render_backgrounds(&self, map: &MapData, camera_x: f32, camera_y: f32, front_only: bool) {
+        let time = get_time() as f32;
         for bg in &map.backgrounds {
             // Skip if we're rendering front layers only and this isn't front
             // Or if we're rendering back layers and this is front
@@ -98,17 +100,19 @@ impl MapRenderer {
                 0.0
             };
 
-            // Calculate screen position
-            // Note: parallax already includes camera adjustment, so we don't subtract camera again
-            let screen_x = bg.x as f32 + parallax_x;
-            let screen_y = bg.y as f32 + parallax_y;
+            // Draw the current frame if the background is loaded
+            if let Some((frame, frame_alpha)) = bg.animation.frame_at(time) {
+                let texture = &frame.texture;
+
+                // Calculate screen position
+                // Note: parallax already includes camera adjustment, so we don't subtract camera again
+                let motion = bg.animation.offset_at(time);
+                let screen_x = bg.x as f32 + parallax_x + motion.x - frame.origin_x as f32;
+                let screen_y = bg.y as f32 + parallax_y + motion.y - frame.origin_y as f32;
 
-            // Calculate alpha
-            let alpha = (bg.a as f32 / 255.0 * 255.0) as u8;
-            let color = Color::from_rgba(255, 255, 255, alpha);
+                // Layer alpha times the frame's fade
+                let color = Color::new(1.0, 1.0, 1.0, bg.a as f32 / 255.0 * frame_alpha);
 
-            // Draw the actual background texture if loaded
-            if let Some(texture) = &bg.texture {
                 // Calculate texture dimensions
                 let tex_width = texture.width();
                 let tex_height = texture.height();
@@ -167,7 +171,7 @@ impl MapRenderer {
                 }
 
                 // Draw layer info for debugging
-                if flags::SHOW_DEBUG_UI {
+                if DebugFlags::should_show_debug_ui() {
                     let info = format!("BG Layer {}: {}/{} @({},{})",
                         bg.layer_num, bg.bS, bg.no, bg.x, bg.y);
                     draw_text(&info, screen_x + 10.0, screen_y + 20.0, 16.0, WHITE);
@@ -181,6 +185,7 @@ impl MapRenderer {
     fn render_tiles_and_objects(&self, map: &MapData, camera_x: f32, camera_y: f32) {
         let screen_w = screen_width();
         let screen_h = screen_height();
+        let time = get_time() as f32;
 
         // Iterate through layers 0-7 (like C++ Layer::IDs)
         for layer in 0..8 {
@@ -249,20 +254,23 @@ impl MapRenderer {
                     if let Some(texture) = &tile.texture {
                         draw_texture(texture, screen_x, screen_y, WHITE);
 
-                        if flags::SHOW_DEBUG_UI {
+                        if DebugFlags::should_show_debug_ui() {
                             let info = format!("T{}:L{}z{}", tile.id, layer, tile.z_m);
                             draw_text(&info, screen_x + 5.0, screen_y + 15.0, 12.0, YELLOW);
                         }
-                    } else if flags::SHOW_DEBUG_UI {
+                    } else if DebugFlags::should_show_debug_ui() {
                         draw_rectangle(screen_x, screen_y, 90.0, 60.0, Color::from_rgba(100, 50, 0, 100));
                     }
                 } else {
                     let obj = &map.objects[item.index];
-                    let screen_x = obj.x as f32 - camera_x - obj.origin_x as f32;
-                    let screen_y = obj.y as f32 - camera_y - obj.origin_y as f32;
-
-                    let (tex_w, tex_h) = if let Some(tex) = &obj.texture {
-                        (tex.width(), tex.height())
+                    let frame = obj.animation.frame_at(time);
+                    let motion = obj.animation.offset_at(time);
+                    let (origin_x, origin_y) = frame.map_or((0, 0), |(frame, _)| (frame.origin_x, frame.origin_y));
+                    let screen_x = obj.x as f32 - camera_x - origin_x as f32 + motion.x;
+                    let screen_y = obj.y as f32 - camera_y - origin_y as f32 + motion.y;
+
+                    let (tex_w, tex_h) = if let Some((frame, _)) = frame {
+                        (frame.texture.width(), frame.texture.height())
                     } else {
                         (10.0, 10.0)
                     };
@@ -273,20 +281,20 @@ impl MapRenderer {
                         continue;
                     }
 
-                    if let Some(texture) = &obj.texture {
+                    if let Some((frame, alpha)) = frame {
                         let params = DrawTextureParams {
                             flip_x: obj.f,
                             flip_y: false,
                             rotation: (obj.r as f32).to_radians(),
                             ..Default::default()
                         };
-                        draw_texture_ex(texture, screen_x, screen_y, WHITE, params);
+                        draw_texture_ex(&frame.texture, screen_x, screen_y, Color::new(1.0, 1.0, 1.0, alpha), params);
 
-                        if flags::SHOW_DEBUG_UI {
+                        if DebugFlags::should_show_debug_ui() {
                             let info = format!("O{}:L{}z{}", obj.id, layer, obj.z);
                             draw_text(&info, screen_x + 5.0, screen_y + 15.0, 12.0, ORANGE);
                         }
-                    } else if flags::SHOW_DEBUG_UI {
+                    } else if DebugFlags::should_show_debug_ui() {
                         draw_circle(screen_x, screen_y, 5.0, Color::from_rgba(255, 165, 0, 150));
                     }
                 }
@@ -321,13 +329,13 @@ impl MapRenderer {
                 draw_texture(texture, screen_x, screen_y, WHITE);
 
                 // Draw tile info for debugging
-                if flags::SHOW_DEBUG_UI {
+                if DebugFlags::should_show_debug_ui() {
                     let info = format!("T{}: {}/{}", tile.id, tile.u, tile.no);
                     draw_text(&info, screen_x + 5.0, screen_y + 15.0, 12.0, YELLOW);
                 }
             } else {
                 // Draw placeholder for missing tile texture
-                if flags::SHOW_DEBUG_UI {
+                if DebugFlags::should_show_debug_ui() {
                     draw_rectangle(screen_x, screen_y, 90.0, 60.0, Color::from_rgba(100, 50, 0, 100));
                     let info = format!("T{}", tile.id);
                     draw_text(&info, screen_x + 5.0, screen_y + 30.0, 12.0, RED);
@@ -348,8 +356,10 @@ impl MapRenderer {
         for obj in &map.objects {
             // Calculate screen position (objects don't have parallax scrolling)
             // Apply origin offset: origin defines the anchor point of the sprite
-            let screen_x = obj.x as f32 - camera_x - obj.origin_x as f32;
-            let screen_y = obj.y as f32 - camera_y - obj.origin_y as f32;
+            let frame = obj.animation.frames.first();
+            let (origin_x, origin_y) = frame.map_or((0, 0), |frame| (frame.origin_x, frame.origin_y));
+            let screen_x = obj.x as f32 - camera_x - origin_x as f32;
+            let screen_y = obj.y as f32 - camera_y - origin_y as f32;
 
             // Screen culling - skip objects outside view (with large margin)
             // Use 500px margin to prevent pop-in for large objects
@@ -359,7 +369,7 @@ impl MapRenderer {
             }
 
             // Draw the object texture if loaded
-            if let Some(texture) = &obj.texture {
+            if let Some(texture) = frame.map(|frame| &frame.texture) {
                 let params = DrawTextureParams {
                     flip_x: obj.f,
                     flip_y: false,
@@ -369,13 +379,13 @@ impl MapRenderer {
                 draw_texture_ex(texture, screen_x, screen_y, WHITE, params);
 
                 // Draw object info for debugging
-                if flags::SHOW_DEBUG_UI {
+                if DebugFlags::should_show_debug_ui() {
                     let info = format!("O{}: {}", obj.id, obj.oS);
                     draw_text(&info, screen_x + 5.0, screen_y + 15.0, 12.0, ORANGE);
                 }
             } else {
                 // Draw placeholder for missing object texture
-                if flags::SHOW_DEBUG_UI {
+                if DebugFlags::should_show_debug_ui() {
                     draw_circle(screen_x, screen_y, 5.0, Color::from_rgba(255, 165, 0, 150));
                     let info = format!("O{}", obj.id);
                     draw_text(&info, screen_x + 10.0, screen_y + 5.0, 12.0, ORANGE);
@@ -386,16 +396,36 @@ impl MapRenderer {
 
     /// Render life (NPCs and mobs)
     fn render_life(&self, map: &MapData, camera_x: f32, camera_y: f32, bot_ai: Option<&BotAI>) {
-        for life in &map.life {
+        for (index, life) in map.life.iter().enumerate() {
             // Skip if hidden
             if life.hide {
                 continue;
             }
 
+            let bot = if life.life_type == "m" { bot_ai.and_then(|b| b.get_bot_state(index)) } else { None };
+
+            // Mobs show their hit/die frames while reacting, and nothing until they respawn
+            let mut sprite = life.texture.as_ref().map(|tex| (tex, life.origin_x, life.origin_y));
+            if let Some(bot) = bot {
+                let reaction_frame = match bot.state {
+                    MobStateType::Dead => continue,
+                    MobStateType::Dying => {
+                        let frame = (bot.state_timer / MOB_FRAME_TIME) as usize;
+                        life.die_textures.get(frame.min(life.die_textures.len().saturating_sub(1)))
+                            .zip(life.die_origins.get(frame.min(life.die_origins.len().saturating_sub(1))))
+                    }
+                    MobStateType::KnockedBack => life.hit_textures.first().zip(life.hit_origins.first()),
+                    _ => None,
+                };
+                if let Some((tex, (ox, oy))) = reaction_frame {
+                    sprite = Some((tex, *ox, *oy));
+                }
+            }
+
             // Get position from bot AI if this is a mob, otherwise use static position
             let (pos_x, pos_y, flip) = if life.life_type == "m" {
-                if let Some(bot_ai) = bot_ai {
-                    if let Some(bot) = bot_ai.get_bot_state(&life.id) {
+                if bot_ai.is_some() {
+                    if let Some(bot) = bot {
                         (bot.x, bot.y, !bot.facing_right)
                     } else {
                         // Mob not in bot AI, use spawn position snapped to foothold
@@ -464,17 +494,34 @@ impl MapRenderer {
             // Calculate screen position
             // For mobs, position so their feet are at the foothold level
             // origin_y is typically the distance from top of sprite to anchor point
-            let screen_x = pos_x - camera_x - life.origin_x as f32;
-            let screen_y = pos_y - camera_y - life.origin_y as f32;
+            let (origin_x, origin_y) = sprite.map_or((life.origin_x, life.origin_y), |(_, ox, oy)| (ox, oy));
+            let screen_x = pos_x - camera_x - origin_x as f32;
+            let screen_y = pos_y - camera_y - origin_y as f32;
 
             // Draw the NPC/mob texture if loaded
-            if let Some(texture) = &life.texture {
+            if let Some((texture, _, _)) = sprite {
                 let params = DrawTextureParams {
                     flip_x: flip,
                     flip_y: false,
                     ..Default::default()
                 };
-                draw_texture_ex(texture, screen_x, screen_y, WHITE, params);
+                // Debuffed mobs are tinted: grey while stunned, green while poisoned, blue while slowed
+                let tint = match bot {
+                    Some(bot) if bot.effects.is_stunned() => Color::from_rgba(170, 170, 170, 255),
+                    Some(bot) if bot.effects.is_poisoned() => Color::from_rgba(160, 255, 160, 255),
+                    Some(bot) if bot.effects.speed_multiplier() < 1.0 => Color::from_rgba(170, 200, 255, 255),
+                    _ => WHITE,
+                };
+                draw_texture_ex(texture, screen_x, screen_y, tint, params);
+
+                // HP bar over damaged mobs
+                if let Some(bot) = bot.filter(|b| b.is_alive() && b.hp < b.max_hp) {
+                    let bar_width = 40.0;
+                    let bar_x = screen_x + texture.width() / 2.0 - bar_width / 2.0;
+                    let bar_y = screen_y - 8.0;
+                    draw_rectangle(bar_x, bar_y, bar_width, 5.0, Color::from_rgba(0, 0, 0, 180));
+                    draw_rectangle(bar_x + 1.0, bar_y + 1.0, (bar_width - 2.0) * bot.hp as f32 / bot.max_hp as f32, 3.0, RED);
+                }
 
                 // Draw NPC name label underneath sprite (for NPCs only)
                 if life.life_type == "n" && !life.name.is_empty() {
@@ -514,17 +561,17 @@ impl MapRenderer {
                 }
 
                 // Draw life info for debugging
-                if flags::SHOW_DEBUG_UI {
+                if DebugFlags::should_show_debug_ui() {
                     let info = if life.life_type == "n" {
                         format!("NPC ID: {}", life.id)
                     } else {
-                        format!("Mob: {}", life.id)
+                        format!("Mob: {} Lv.{}", life.id, life.stats.level)
                     };
                     draw_text(&info, screen_x + 5.0, screen_y - 10.0, 10.0, YELLOW);
                 }
             } else {
                 // Draw placeholder for missing life texture
-                if flags::SHOW_DEBUG_UI {
+                if DebugFlags::should_show_debug_ui() {
                     let color = if life.life_type == "n" {
                         GREEN
                     } else {
@@ -634,7 +681,7 @@ impl MapRenderer {
     /// Render portals
     fn render_portals(&self, map: &MapData, camera_x: f32, camera_y: f32) {
         // Skip portal rendering if disabled via flag
-        if !flags::RENDER_PORTALS {
+        if !config().render_portals {
             return;
         }
 
@@ -691,7 +738,7 @@ impl MapRenderer {
                     draw_texture(texture, draw_x, draw_y, WHITE);
 
                     // Draw portal name for debugging
-                    if self.debug_portals && flags::SHOW_DEBUG_UI {
+                    if self.debug_portals && DebugFlags::should_show_debug_ui() {
                         let name = if !portal.pn.is_empty() {
                             &portal.pn
                         } else {
@@ -720,7 +767,7 @@ impl MapRenderer {
                 draw_circle_lines(screen_x, screen_y, 10.0, 2.0, WHITE);
 
                 // Draw portal name
-                if flags::SHOW_DEBUG_UI {
+                if DebugFlags::should_show_debug_ui() {
                     let name = if !portal.pn.is_empty() {
                         &portal.pn
                     } else {
@@ -1,9 +1,7 @@
 use macroquad::prelude::*;
-use crate::assets::AssetManager;
 use std::collections::HashMap;
-use std::sync::Arc;
-use wz_reader::version::guess_iv_from_wz_img;
-use wz_reader::{WzImage, WzNode, WzNodeArc, WzObjectType, WzReader};
+use crate::wz_cache::load_wz_image;
+use wz_reader::{WzNodeArc, WzObjectType};
 
 /// Cache for loaded tile textures and parsed WZ nodes
 pub struct TileCache {
@@ -19,32 +17,10 @@ impl TileCache {
         }
     }
 
-    /// Preload a tileset WZ file from already-fetched bytes
+    /// Register an already-parsed tileset WZ node
     /// This allows batching I/O operations (fetch in parallel, then parse)
-    pub async fn preload_tileset_from_bytes(&mut self, tileset_name: &str, bytes: Vec<u8>) -> Result<(), String> {
-        // Skip if already loaded
-        if self.wz_nodes.contains_key(tileset_name) {
-            return Ok(());
-        }
-
-        let wz_iv = guess_iv_from_wz_img(&bytes)
-            .ok_or_else(|| "Unable to guess version from tile file".to_string())?;
-
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        let cache_name = format!("/01/Map/Tile/{}.img", tileset_name);
-        let cache_name_ref: wz_reader::WzNodeName = cache_name.clone().into();
-        let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-        let node: WzNodeArc = WzNode::new(&cache_name.into(), wz_image, None).into();
-
-        node.write()
-            .unwrap()
-            .parse(&node)
-            .map_err(|e| format!("Failed to parse tile WZ: {:?}", e))?;
-
-        // Cache the parsed node
-        self.wz_nodes.insert(tileset_name.to_string(), node);
-        Ok(())
+    pub fn preload_tileset(&mut self, tileset_name: &str, node: WzNodeArc) {
+        self.wz_nodes.entry(tileset_name.to_string()).or_insert(node);
     }
 
     /// Get or load a tile texture
@@ -91,34 +67,13 @@ impl TileCache {
             // info!("  Using cached WZ node for {}.img", tileset_name);
             cached_node.clone()
         } else {
-            // Build URL for the tile file
-            let url = format!(
-                "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/Map/Tile/{}.img",
-                tileset_name
-            );
-            let cache_name = format!("/01/Map/Tile/{}.img", tileset_name);
+            // Build the WZ path for the tile file
+            let wz_path = format!("Map/Tile/{}.img", tileset_name);
 
             // Fetch and parse the tile file
-            let bytes = AssetManager::fetch_and_cache(&url, &cache_name)
+            let node = load_wz_image(&wz_path)
                 .await
-                .map_err(|e| format!("Failed to fetch tile: {}", e))?;
-
-            info!("  Parsing tile file (size: {} bytes)...", bytes.len());
-
-            let wz_iv = guess_iv_from_wz_img(&bytes)
-                .ok_or_else(|| "Unable to guess version from tile file".to_string())?;
-
-            let byte_len = bytes.len();
-
-            let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-            let cache_name_ref: wz_reader::WzNodeName = cache_name.clone().into();
-            let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-            let node: WzNodeArc = WzNode::new(&cache_name.into(), wz_image, None).into();
-
-            node.write()
-                .unwrap()
-                .parse(&node)
-                .map_err(|e| format!("Failed to parse tile WZ: {:?}", e))?;
+                .map_err(|e| format!("Failed to load tile: {}", e))?;
 
             info!("  Tile WZ file parsed successfully");
 
@@ -188,31 +143,11 @@ impl TileCache {
         category: &str,
         tile_number: i32,
     ) -> Result<(i32, i32), String> {
-        let url = format!(
-            "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/Map/Tile/{}.img",
-            tileset_name
-        );
-        let cache_name = format!("/01/Map/Tile/{}.img", tileset_name);
+        let wz_path = format!("Map/Tile/{}.img", tileset_name);
 
-        let bytes = AssetManager::fetch_and_cache(&url, &cache_name)
+        let root_node = load_wz_image(&wz_path)
             .await
-            .map_err(|e| format!("Failed to fetch tile: {}", e))?;
-
-        let wz_iv = guess_iv_from_wz_img(&bytes)
-            .ok_or_else(|| "Unable to guess version from tile file".to_string())?;
-
-        let byte_len = bytes.len();
-
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        let cache_name_ref: wz_reader::WzNodeName = cache_name.clone().into();
-        let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-        let root_node: WzNodeArc = WzNode::new(&cache_name.into(), wz_image, None).into();
-
-        root_node
-            .write()
-            .unwrap()
-            .parse(&root_node)
-            .map_err(|e| format!("Failed to parse tile WZ: {:?}", e))?;
+            .map_err(|e| format!("Failed to load tile: {}", e))?;
 
         // Navigate to origin: tileset_name/category/tile_number/origin
         let origin_path = format!("{}/{}/{}/origin", tileset_name, category, tile_number);
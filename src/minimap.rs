@@ -1,12 +1,9 @@
 use macroquad::prelude::*;
-use crate::assets::AssetManager;
 use crate::map::data::MapData;
-use std::sync::Arc;
-use wz_reader::version::guess_iv_from_wz_img;
-use wz_reader::{WzImage, WzNode, WzNodeArc, WzReader, WzNodeCast};
+use crate::wz_cache::load_wz_image;
+use wz_reader::{WzNodeArc, WzNodeCast};
 
-const UIWINDOW2_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/UI/UIWindow2.img";
-const UIWINDOW2_CACHE: &str = "/01/UI/UIWindow2.img";
+const UIWINDOW2_PATH: &str = "UI/UIWindow2.img";
 
 /// Texture with origin point
 struct TextureWithOrigin {
@@ -194,20 +191,8 @@ impl MiniMap {
     }
 
     async fn load_from_wz() -> Result<MiniMapData, String> {
-        let bytes = AssetManager::fetch_and_cache(UIWINDOW2_URL, UIWINDOW2_CACHE).await
-            .map_err(|e| format!("Failed to fetch UIWindow2.img: {}", e))?;
-
-        let wz_iv = guess_iv_from_wz_img(&bytes)
-            .ok_or_else(|| "Unable to guess WZ version from UIWindow2.img".to_string())?;
-
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        let cache_name_ref: wz_reader::WzNodeName = UIWINDOW2_CACHE.to_string().into();
-        let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-        let root_node: WzNodeArc = WzNode::new(&UIWINDOW2_CACHE.to_string().into(), wz_image, None).into();
-
-        root_node.write().unwrap().parse(&root_node)
-            .map_err(|e| format!("Failed to parse UIWindow2.img: {:?}", e))?;
+        let root_node = load_wz_image(UIWINDOW2_PATH).await
+            .map_err(|e| format!("Failed to load UIWindow2.img: {}", e))?;
 
         let mut data = MiniMapData::default();
 
@@ -1,11 +1,13 @@
 use macroquad::prelude::*;
 use crate::assets::AssetManager;
-use std::sync::Arc;
-use wz_reader::version::guess_iv_from_wz_img;
-use wz_reader::{WzImage, WzNode, WzNodeArc, WzReader, WzNodeCast};
+use crate::character_renderer::{CharacterLook, CharacterRenderer, CharacterState};
+use crate::wz_cache::load_wz_image;
+use wz_reader::{WzNodeArc, WzNodeCast};
 
-const UIWINDOW2_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/UI/UIWindow2.img";
-const UIWINDOW2_CACHE: &str = "/01/UI/UIWindow2.img";
+const UIWINDOW2_PATH: &str = "UI/UIWindow2.img";
+
+/// Height of one line of a selection list
+const OPTION_HEIGHT: f32 = 16.0;
 
 /// Dialog type determines UI layout and available buttons (matches C++ TalkType enum)
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -105,6 +107,11 @@ pub struct NpcDialogSystem {
     selection_options: Vec<String>,
     selected_index: usize,
     selection_scroll_offset: usize,
+    // Style dialog: candidate (style id, look) pairs and the avatar previewing the current one
+    style_options: Vec<(i32, CharacterLook)>,
+    style_index: usize,
+    style_preview: CharacterRenderer,
+    style_timer: f32,
     // Button hover tracking
     btn_hovered: i32,  // -1 = none, 0+ = button index
     btn_pressed: i32,
@@ -146,6 +153,10 @@ impl NpcDialogSystem {
             selection_options: Vec::new(),
             selected_index: 0,
             selection_scroll_offset: 0,
+            style_options: Vec::new(),
+            style_index: 0,
+            style_preview: CharacterRenderer::new(),
+            style_timer: 0.0,
             btn_hovered: -1,
             btn_pressed: -1,
             is_dragging: false,
@@ -157,46 +168,36 @@ impl NpcDialogSystem {
     pub async fn load_assets(&mut self) {
         info!("Loading NPC dialog assets...");
         
-        if let Ok(bytes) = AssetManager::fetch_and_cache(UIWINDOW2_URL, UIWINDOW2_CACHE).await {
-            if let Some(wz_iv) = guess_iv_from_wz_img(&bytes) {
-                let byte_len = bytes.len();
-                let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-                let cache_name_ref: wz_reader::WzNodeName = UIWINDOW2_CACHE.to_string().into();
-                let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-                let root_node: WzNodeArc = WzNode::new(&UIWINDOW2_CACHE.to_string().into(), wz_image, None).into();
-
-                if root_node.write().unwrap().parse(&root_node).is_ok() {
-                    self.tex_t = Self::load_tex(&root_node, "UtilDlgEx/t").await;
-                    self.tex_c = Self::load_tex(&root_node, "UtilDlgEx/c").await;
-                    self.tex_s = Self::load_tex(&root_node, "UtilDlgEx/s").await;
-                    self.tex_bar = Self::load_tex(&root_node, "UtilDlgEx/bar").await;
-
-                    // Store dimensions for positioning calculations
-                    self.t_height = self.tex_t.as_ref().map(|t| t.height()).unwrap_or(26.0);
-                    self.c_height = self.tex_c.as_ref().map(|t| t.height()).unwrap_or(14.0);
-                    self.s_height = self.tex_s.as_ref().map(|t| t.height()).unwrap_or(26.0);
-                    self.t_width = self.tex_t.as_ref().map(|t| t.width()).unwrap_or(510.0);
-                    self.bar_width = self.tex_bar.as_ref().map(|t| t.width()).unwrap_or(100.0);
-
-                    self.btn_ok = Self::load_button(&root_node, "UtilDlgEx/BtOK").await;
-                    self.btn_prev = Self::load_button(&root_node, "UtilDlgEx/BtPrev").await;
-                    self.btn_next = Self::load_button(&root_node, "UtilDlgEx/BtNext").await;
-                    self.btn_close = Self::load_button(&root_node, "UtilDlgEx/BtClose").await;
-                    self.btn_yes = Self::load_button(&root_node, "UtilDlgEx/BtYes").await;
-                    self.btn_no = Self::load_button(&root_node, "UtilDlgEx/BtNo").await;
-                    // Quest-specific buttons
-                    self.btn_qyes = Self::load_button(&root_node, "UtilDlgEx/BtQYes").await;
-                    self.btn_qno = Self::load_button(&root_node, "UtilDlgEx/BtQNo").await;
-
-                    self.loaded = true;
-                    info!("NPC dialog loaded: t={}, c={}, s={}, bar={}, dims=({},{},{},{})",
-                        self.tex_t.is_some(), self.tex_c.is_some(), self.tex_s.is_some(), self.tex_bar.is_some(),
-                        self.t_width, self.t_height, self.c_height, self.s_height);
-                }
-            }
+        if let Ok(root_node) = load_wz_image(UIWINDOW2_PATH).await {
+            self.tex_t = Self::load_tex(&root_node, "UtilDlgEx/t").await;
+            self.tex_c = Self::load_tex(&root_node, "UtilDlgEx/c").await;
+            self.tex_s = Self::load_tex(&root_node, "UtilDlgEx/s").await;
+            self.tex_bar = Self::load_tex(&root_node, "UtilDlgEx/bar").await;
+
+            // Store dimensions for positioning calculations
+            self.t_height = self.tex_t.as_ref().map(|t| t.height()).unwrap_or(26.0);
+            self.c_height = self.tex_c.as_ref().map(|t| t.height()).unwrap_or(14.0);
+            self.s_height = self.tex_s.as_ref().map(|t| t.height()).unwrap_or(26.0);
+            self.t_width = self.tex_t.as_ref().map(|t| t.width()).unwrap_or(510.0);
+            self.bar_width = self.tex_bar.as_ref().map(|t| t.width()).unwrap_or(100.0);
+
+            self.btn_ok = Self::load_button(&root_node, "UtilDlgEx/BtOK").await;
+            self.btn_prev = Self::load_button(&root_node, "UtilDlgEx/BtPrev").await;
+            self.btn_next = Self::load_button(&root_node, "UtilDlgEx/BtNext").await;
+            self.btn_close = Self::load_button(&root_node, "UtilDlgEx/BtClose").await;
+            self.btn_yes = Self::load_button(&root_node, "UtilDlgEx/BtYes").await;
+            self.btn_no = Self::load_button(&root_node, "UtilDlgEx/BtNo").await;
+            // Quest-specific buttons
+            self.btn_qyes = Self::load_button(&root_node, "UtilDlgEx/BtQYes").await;
+            self.btn_qno = Self::load_button(&root_node, "UtilDlgEx/BtQNo").await;
+
+            self.loaded = true;
+            info!("NPC dialog loaded: t={}, c={}, s={}, bar={}, dims=({},{},{},{})",
+                self.tex_t.is_some(), self.tex_c.is_some(), self.tex_s.is_some(), self.tex_bar.is_some(),
+                self.t_width, self.t_height, self.c_height, self.s_height);
         }
 
-        if let Ok(font) = load_ttf_font("https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/MaplestoryLight.ttf").await {
+        if let Ok(font) = AssetManager::load_font("MaplestoryLight.ttf").await {
             self.font = Some(font);
         }
     }
@@ -250,11 +251,120 @@ impl NpcDialogSystem {
     pub fn show_selection(&mut self, text: &str, npc_name: &str,
                           npc_tex: Option<Texture2D>, options: Vec<String>) {
         self.show_dialog_typed(text, npc_name, npc_tex, DialogType::Selection);
+        // Make room for the options below the text
+        let lines = self.wrap_text(text, 320.0, 12.0).len() + options.len();
+        self.vtile = (lines + 2).clamp(8, 16);
+        self.center_on_screen();
         self.selection_options = options;
         self.selected_index = 0;
         self.selection_scroll_offset = 0;
     }
 
+    /// Show a style dialog previewing each candidate look on the player's avatar.
+    /// Left/Right cycle through them and OK answers with the shown style's id.
+    pub fn show_style(&mut self, text: &str, npc_name: &str,
+                      npc_tex: Option<Texture2D>, options: Vec<(i32, CharacterLook)>) {
+        self.show_dialog_typed(text, npc_name, npc_tex, DialogType::Style);
+        self.style_options = options;
+        self.style_timer = 0.0;
+        self.show_style_option(0);
+    }
+
+    fn show_style_option(&mut self, index: usize) {
+        if let Some((_, look)) = self.style_options.get(index) {
+            self.style_index = index;
+            self.style_preview.set_look(look.clone());
+        }
+    }
+
+    /// Rebuild the preview avatar after the shown style changed
+    pub async fn load_style_preview(&mut self) {
+        if self.style_preview.needs_reload() {
+            self.style_preview.load_assets().await;
+        }
+    }
+
+    /// Response for confirming the style dialog
+    fn chosen_style(&self) -> DialogResponse {
+        self.style_options.get(self.style_index)
+            .map_or(DialogResponse::None, |&(id, _)| DialogResponse::Style(id))
+    }
+
+    /// Feet position of the style preview, where the speaker is normally drawn
+    fn style_preview_pos(&self) -> (f32, f32) {
+        if self.tex_t.is_some() && self.tex_c.is_some() && self.tex_s.is_some() {
+            (self.x + 22.0 + self.bar_width / 2.0, self.y + 11.0 + self.get_total_height() / 2.0)
+        } else {
+            (self.x + 50.0, self.y + 100.0)
+        }
+    }
+
+    /// Hit boxes of the previous/next style arrows on either side of the preview
+    fn style_arrow_rects(&self) -> [Rect; 2] {
+        let (x, y) = self.style_preview_pos();
+        [Rect::new(x - 48.0, y - 45.0, 16.0, 20.0), Rect::new(x + 32.0, y - 45.0, 16.0, 20.0)]
+    }
+
+    /// Avatar wearing the shown style, with arrows and its position in the list
+    fn draw_style_preview(&self) {
+        let (x, y) = self.style_preview_pos();
+        self.style_preview.draw_with_timer(x, y, CharacterState::Stand, true, self.style_timer);
+        if self.style_options.len() < 2 {
+            return;
+        }
+
+        let (mx, my) = mouse_position();
+        for (i, rect) in self.style_arrow_rects().iter().enumerate() {
+            let color = if rect.contains(vec2(mx, my)) {
+                Color::from_rgba(255, 200, 80, 255)
+            } else {
+                Color::from_rgba(200, 160, 100, 255)
+            };
+            let (tip, back) = if i == 0 { (rect.x, rect.x + rect.w) } else { (rect.x + rect.w, rect.x) };
+            draw_triangle(vec2(tip, rect.y + rect.h / 2.0), vec2(back, rect.y), vec2(back, rect.y + rect.h), color);
+        }
+
+        let label = format!("{} / {}", self.style_index + 1, self.style_options.len());
+        let width = measure_text(&label, None, 12, 1.0).width;
+        draw_text(&label, x - width / 2.0, y + 32.0, 12.0, Color::from_rgba(80, 80, 80, 255));
+    }
+
+    /// Hit box of each selection option, below the dialog text
+    fn option_rects(&self) -> Vec<Rect> {
+        let Some(dialog) = &self.active_dialog else { return Vec::new() };
+        let (x, first_y) = if self.tex_t.is_some() && self.tex_c.is_some() && self.tex_s.is_some() {
+            let lines = self.wrap_text(&dialog.text, 320.0, 12.0).len() as f32;
+            let y_adj = self.get_content_height() - (8.0 * self.c_height + 14.0);
+            (self.x + 166.0, self.y + 48.0 - y_adj + lines * 14.0 + 4.0)
+        } else {
+            let lines = self.wrap_text(&dialog.text, 200.0, 12.0).len() as f32;
+            let text_x = if dialog.npc_texture.is_some() { self.x + 100.0 } else { self.x + 20.0 };
+            (text_x, self.y + 30.0 + lines * 16.0 + 4.0)
+        };
+        // Rects start a line above each option's baseline
+        (0..self.selection_options.len())
+            .map(|i| Rect::new(x, first_y + i as f32 * OPTION_HEIGHT - 12.0, 320.0, OPTION_HEIGHT))
+            .collect()
+    }
+
+    /// Options of a selection dialog, with the hovered/selected one highlighted
+    fn draw_options(&self) {
+        for (i, (rect, option)) in self.option_rects().iter().zip(&self.selection_options).enumerate() {
+            if i == self.selected_index {
+                draw_rectangle(rect.x - 2.0, rect.y, rect.w, rect.h, Color::from_rgba(200, 220, 255, 255));
+            }
+            let color = Color::from_rgba(30, 60, 200, 255);
+            let baseline = rect.y + 12.0;
+            if let Some(font) = &self.font {
+                draw_text_ex(option, rect.x, baseline, TextParams {
+                    font: Some(font), font_size: 12, color, ..Default::default()
+                });
+            } else {
+                draw_text(option, rect.x, baseline, 12.0, color);
+            }
+        }
+    }
+
     /// Get and clear last response
     pub fn take_response(&mut self) -> DialogResponse {
         let resp = self.last_response;
@@ -310,6 +420,41 @@ impl NpcDialogSystem {
 
         let (mx, my) = mouse_position();
 
+        // Selection list: hover or Up/Down picks an option, clicking one answers with it
+        if self.dialog_type == DialogType::Selection && !self.selection_options.is_empty() {
+            let count = self.selection_options.len();
+            if is_key_pressed(KeyCode::Down) {
+                self.selected_index = (self.selected_index + 1) % count;
+            }
+            if is_key_pressed(KeyCode::Up) {
+                self.selected_index = (self.selected_index + count - 1) % count;
+            }
+            if let Some(index) = self.option_rects().iter().position(|rect| rect.contains(vec2(mx, my))) {
+                self.selected_index = index;
+                if is_mouse_button_released(MouseButton::Left) {
+                    self.close_dialog();
+                    self.last_response = DialogResponse::Selection(index);
+                    return;
+                }
+            }
+        }
+
+        // Style preview: Left/Right or the arrows cycle through the candidates
+        if self.dialog_type == DialogType::Style && !self.style_options.is_empty() {
+            self.style_timer += get_frame_time();
+            let count = self.style_options.len();
+            let [prev, next] = self.style_arrow_rects();
+            let clicked = |rect: Rect| is_mouse_button_pressed(MouseButton::Left) && rect.contains(vec2(mx, my));
+            if is_key_pressed(KeyCode::Left) || clicked(prev) {
+                self.show_style_option((self.style_index + count - 1) % count);
+                return;
+            }
+            if is_key_pressed(KeyCode::Right) || clicked(next) {
+                self.show_style_option((self.style_index + 1) % count);
+                return;
+            }
+        }
+
         // Reset hover state
         self.btn_hovered = -1;
         self.btn_pressed = -1;
@@ -408,7 +553,7 @@ impl NpcDialogSystem {
                 return;
             }
 
-            self.last_response = match (self.dialog_type, self.btn_hovered) {
+            let response = match (self.dialog_type, self.btn_hovered) {
                 (DialogType::Ok, 0) => DialogResponse::Ok,
                 (DialogType::Next, 0) => DialogResponse::Next,
                 (DialogType::NextPrev, 0) => DialogResponse::Next,
@@ -419,29 +564,33 @@ impl NpcDialogSystem {
                 (DialogType::AcceptDecline, 1) => DialogResponse::Decline,
                 (DialogType::Selection, 0) => DialogResponse::Selection(self.selected_index),
                 (DialogType::GetText, 0) | (DialogType::GetNumber, 0) => DialogResponse::Ok,
-                (DialogType::Style, 0) => DialogResponse::Style(0),
-                (DialogType::Style, 1) => DialogResponse::None,
+                (DialogType::Style, 0) => self.chosen_style(),
+                (DialogType::Style, 1) => DialogResponse::No,
                 _ => DialogResponse::None,
             };
 
             // Don't close for Next/NextPrev dialogs - let script handle it
+            // (closing clears the response, so it is set afterwards)
             if !matches!(self.dialog_type, DialogType::Next | DialogType::NextPrev) {
                 self.close_dialog();
             }
+            self.last_response = response;
             return;
         }
 
         // Keyboard shortcuts
         if is_key_pressed(KeyCode::Enter) {
-            self.last_response = match self.dialog_type {
+            let response = match self.dialog_type {
                 DialogType::Ok | DialogType::GetText | DialogType::GetNumber => DialogResponse::Ok,
                 DialogType::Next | DialogType::NextPrev => DialogResponse::Next,
                 DialogType::Selection => DialogResponse::Selection(self.selected_index),
+                DialogType::Style => self.chosen_style(),
                 _ => DialogResponse::None,
             };
             if !matches!(self.dialog_type, DialogType::Next | DialogType::NextPrev) {
                 self.close_dialog();
             }
+            self.last_response = response;
             return;
         }
 
@@ -516,7 +665,10 @@ impl NpcDialogSystem {
 
             // Draw NPC speaker at center_pos with centered drawing (C++ line 92)
             // DrawArgument(center_pos, true) means center the sprite
-            if let Some(npc_tex) = &dialog.npc_texture {
+            // A style dialog shows the player's avatar there instead
+            if self.dialog_type == DialogType::Style {
+                self.draw_style_preview();
+            } else if let Some(npc_tex) = &dialog.npc_texture {
                 let npc_x = center_x - npc_tex.width() / 2.0;
                 let npc_y = speaker_pos_y - npc_tex.height() / 2.0;
                 draw_texture(npc_tex, npc_x, npc_y, WHITE);
@@ -565,6 +717,9 @@ impl NpcDialogSystem {
                 }
                 ty += line_height;
             }
+            if self.dialog_type == DialogType::Selection {
+                self.draw_options();
+            }
 
             // Draw buttons based on dialog type (from C++ lines 410-444)
             // y_cord = height + 48
@@ -616,7 +771,10 @@ impl NpcDialogSystem {
             draw_rectangle(self.x, self.y, width, height, Color::from_rgba(245, 235, 210, 255));
             draw_rectangle_lines(self.x, self.y, width, height, 2.0, Color::from_rgba(139, 90, 43, 255));
 
-            let text_x = if let Some(npc_tex) = &dialog.npc_texture {
+            let text_x = if self.dialog_type == DialogType::Style {
+                self.draw_style_preview();
+                self.x + 100.0
+            } else if let Some(npc_tex) = &dialog.npc_texture {
                 draw_texture(npc_tex, self.x + 20.0, self.y + 20.0, WHITE);
                 self.x + 100.0
             } else {
@@ -629,6 +787,9 @@ impl NpcDialogSystem {
                 draw_text(line, text_x, ty, 12.0, BLACK);
                 ty += 16.0;
             }
+            if self.dialog_type == DialogType::Selection {
+                self.draw_options();
+            }
 
             let btn_y = self.y + height - 30.0;
             let btn_color = |hovered: bool, pressed: bool| {
@@ -1,20 +1,36 @@
+use macroquad::prelude::*;
+use serde::Deserialize;
+use crate::character::CharacterData;
+use crate::dialog_script::{self, DialogScript, PageKind};
+use crate::job::Job;
 use crate::npc_dialog::{DialogType, DialogResponse};
-use std::collections::HashMap;
+use crate::quest_info::{self, QuestMark};
+use crate::script_runtime::ScriptRuntime;
+use crate::shop_table;
+use std::sync::Arc;
 
-/// NPC script state machine
+/// Branch jumps followed in a row before a script is taken to be looping
+const MAX_BRANCH_HOPS: usize = 32;
+
+/// NPC script state machine, running the NPC's Rhai script (see `script_runtime`) or else its
+/// dialog script (see `dialog_script`). Portal and map entry scripts run here too, since they
+/// can open dialogs.
 pub struct NpcScriptEngine {
     active_npc: Option<i32>,  // NPC ID currently talking
     script_state: ScriptState,
-    state_data: HashMap<String, String>,  // Script-specific data storage
+    script: Option<Arc<DialogScript>>,  // Script of the active NPC
+    runtime: ScriptRuntime,
+    quest_offers: Vec<(i32, QuestMark)>,  // Quests the active NPC offered, in selection order
 }
 
 #[derive(Clone, Debug)]
 pub enum ScriptState {
     Idle,
-    Waiting,              // Waiting for player response
-    DialogPage(usize),    // Multi-page dialog, current page index
-    SelectionWait,        // Waiting for selection
-    StyleWait,            // Waiting for style selection
+    Page(String),                  // Showing a script page, waiting for the player's answer
+    Selection(Vec<Option<String>>),  // Waiting for a selection; page each listed option goes to
+    Running,              // A Rhai script is waiting for the player's answer
+    QuestSelection,       // Waiting for the player to pick a quest (or the NPC's own talk)
+    QuestPage { quest_id: i32, mark: QuestMark, page: usize },  // Showing a quest's Say.img lines
 }
 
 /// Commands returned by script engine
@@ -38,12 +54,22 @@ pub enum NpcScriptCommand {
     GiveMeso(i32),
     GiveExp(i32),
     TakeItem(i32, i32),
-    Warp(i32),  // map_id
+    SetStyle(StyleType, i32),  // New hair, face or skin, picked in a style dialog
+    ChangeJob(Job),      // Job advancement, checked against its requirements
+    Warp(i32, Option<String>),  // map_id, portal to appear at (else the spawn point)
+    StartQuest(i32),     // quest_id, accepted by the player
+    CompleteQuest(i32),  // quest_id, handed in to the NPC
+    Message(String),     // Shown in the chat log
+    OpenShop(i32),       // npc_id whose shop (shops.json) opens
+    OpenStorage,         // The account's storage (trunk)
+    Sequence(Vec<NpcScriptCommand>),  // Run in order (a page's actions, then what it shows)
     Close,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum StyleType {
+    #[default]
     Hair,
     Face,
     Skin,
@@ -54,59 +80,152 @@ impl NpcScriptEngine {
         Self {
             active_npc: None,
             script_state: ScriptState::Idle,
-            state_data: HashMap::new(),
+            script: None,
+            runtime: ScriptRuntime::new(),
+            quest_offers: Vec::new(),
         }
     }
 
-    /// Start NPC interaction
-    pub fn start_npc(&mut self, npc_id: i32) -> NpcScriptCommand {
+    /// Start NPC interaction. If the NPC can start or complete quests for the player, the
+    /// player picks one first.
+    pub fn start_npc(&mut self, npc_id: i32, character: &CharacterData) -> NpcScriptCommand {
         self.active_npc = Some(npc_id);
-        self.script_state = ScriptState::Waiting;
-        self.state_data.clear();
-
-        // Route to appropriate script based on NPC ID
-        match npc_id {
-            1012100 => self.script_henesys_chief_start(),
-            9000000 => self.script_test_npc_start(),
-            9000001 => self.script_selection_test(),
-            _ => self.script_default(),
+        self.quest_offers = quest_info::npc_quests(npc_id, character);
+
+        if !self.quest_offers.is_empty() {
+            self.script_state = ScriptState::QuestSelection;
+            let mut options: Vec<String> = self
+                .quest_offers
+                .iter()
+                .map(|&(quest_id, mark)| {
+                    let name = quest_info::quest(quest_id).map_or_else(|| quest_id.to_string(), |quest| quest.name);
+                    match mark {
+                        QuestMark::Available => name,
+                        QuestMark::Completable => format!("{} (Complete)", name),
+                    }
+                })
+                .collect();
+            options.push("Just talk".to_string());
+            return NpcScriptCommand::ShowSelection {
+                text: "What would you like to talk about?".to_string(),
+                options,
+            };
+        }
+        self.start_script(npc_id, character)
+    }
+
+    /// Run an NPC's own script: its Rhai script if it has one, else its dialog script. A
+    /// shopkeeper without a script of its own opens its shop.
+    fn start_script(&mut self, npc_id: i32, character: &CharacterData) -> NpcScriptCommand {
+        if let Some(cmd) = self.runtime.start_npc(npc_id, character) {
+            return self.script_ran(cmd);
+        }
+        if !dialog_script::has_script(npc_id) && shop_table::has_shop(npc_id) {
+            self.end_dialog();
+            return NpcScriptCommand::OpenShop(npc_id);
+        }
+        let script = dialog_script::script_for(npc_id);
+        let start = script.start.clone();
+        self.script = Some(script);
+        self.enter_page(start, character)
+    }
+
+    /// Run a script portal's script. `None` if it has none.
+    pub fn start_portal_script(&mut self, script: &str, character: &CharacterData) -> Option<NpcScriptCommand> {
+        self.end_dialog();
+        let cmd = self.runtime.start_portal(script, character)?;
+        Some(self.script_ran(cmd))
+    }
+
+    /// Run the script of a reactor that broke. `None` if it has none.
+    pub fn start_reactor_script(&mut self, reactor_id: i32, character: &CharacterData) -> Option<NpcScriptCommand> {
+        self.end_dialog();
+        let cmd = self.runtime.start_reactor(reactor_id, character)?;
+        Some(self.script_ran(cmd))
+    }
+
+    /// Run a map's `onUserEnter` (or `onFirstUserEnter`) script. `None` if it has none.
+    pub fn start_map_script(&mut self, script: &str, first_enter: bool, character: &CharacterData) -> Option<NpcScriptCommand> {
+        self.end_dialog();
+        let cmd = self.runtime.start_map_enter(script, first_enter, character)?;
+        Some(self.script_ran(cmd))
+    }
+
+    /// Wait for the player if the Rhai script opened a dialog, else end the conversation
+    fn script_ran(&mut self, cmd: NpcScriptCommand) -> NpcScriptCommand {
+        if self.runtime.is_running() {
+            self.script_state = ScriptState::Running;
+        } else {
+            self.end_dialog();
         }
+        cmd
     }
 
     /// Handle player response and advance script
-    pub fn handle_response(&mut self, response: DialogResponse) -> NpcScriptCommand {
+    pub fn handle_response(&mut self, response: DialogResponse, character: &CharacterData) -> NpcScriptCommand {
+        if let ScriptState::Running = self.script_state {
+            let cmd = self.runtime.resume(response, character);
+            return self.script_ran(cmd);
+        }
         let npc_id = match self.active_npc {
             Some(id) => id,
             None => return NpcScriptCommand::None,
         };
 
-        match (&self.script_state, response) {
-            (ScriptState::Waiting, DialogResponse::Ok) => {
-                // Simple OK - end dialog
-                self.end_dialog()
+        match (self.script_state.clone(), response) {
+            (ScriptState::QuestSelection, DialogResponse::Selection(idx)) => {
+                match self.quest_offers.get(idx).copied() {
+                    Some((quest_id, mark)) => self.quest_page(quest_id, mark, 0),
+                    None => self.start_script(npc_id, character),
+                }
+            }
+            (ScriptState::QuestPage { quest_id, mark, page }, DialogResponse::Next) => {
+                self.quest_page(quest_id, mark, page + 1)
             }
-            (ScriptState::Waiting, DialogResponse::Next) => {
-                // Advance to next page
-                self.script_state = ScriptState::DialogPage(1);
-                self.continue_script(npc_id, 1)
+            (ScriptState::QuestPage { quest_id, mark: QuestMark::Available, .. }, DialogResponse::Accept) => {
+                self.end_dialog();
+                NpcScriptCommand::StartQuest(quest_id)
             }
-            (ScriptState::DialogPage(page), DialogResponse::Next) => {
-                // Continue multi-page dialog
-                let next_page = page + 1;
-                self.script_state = ScriptState::DialogPage(next_page);
-                self.continue_script(npc_id, next_page)
+            (ScriptState::QuestPage { quest_id, mark: QuestMark::Completable, .. }, DialogResponse::Ok) => {
+                self.end_dialog();
+                NpcScriptCommand::CompleteQuest(quest_id)
             }
-            (ScriptState::Waiting, DialogResponse::Yes) => {
-                // Handle Yes response
-                self.handle_yes(npc_id)
+            (ScriptState::QuestPage { .. }, DialogResponse::Decline) => self.end_dialog(),
+            (ScriptState::Page(name), DialogResponse::Style(style_id)) => {
+                let Some(page) = self.script.as_ref().and_then(|script| script.pages.get(&name)) else {
+                    return self.end_dialog();
+                };
+                if !page.styles.contains(&style_id) {
+                    return self.end_dialog();
+                }
+                let set_style = NpcScriptCommand::SetStyle(page.style, style_id);
+                let then = match page.next.clone() {
+                    Some(target) => self.enter_page(target, character),
+                    None => self.end_dialog(),
+                };
+                NpcScriptCommand::Sequence(vec![set_style, then])
             }
-            (ScriptState::Waiting, DialogResponse::No) => {
-                // Handle No response
-                self.end_dialog()
+            (ScriptState::Page(name), response) => {
+                let Some(page) = self.script.as_ref().and_then(|script| script.pages.get(&name)) else {
+                    return self.end_dialog();
+                };
+                let target = match response {
+                    DialogResponse::Ok | DialogResponse::Next | DialogResponse::Yes | DialogResponse::Accept => {
+                        page.next.clone()
+                    }
+                    DialogResponse::No | DialogResponse::Decline => page.no.clone(),
+                    _ => return NpcScriptCommand::None,
+                };
+                match target {
+                    Some(target) => self.enter_page(target, character),
+                    None => self.end_dialog(),
+                }
             }
-            (ScriptState::SelectionWait, DialogResponse::Selection(idx)) => {
-                // Handle selection
-                self.handle_selection(npc_id, idx)
+            (ScriptState::Selection(targets), DialogResponse::Selection(idx)) => {
+                match targets.get(idx).cloned().flatten() {
+                    Some(target) => self.enter_page(target, character),
+                    None => self.end_dialog(),
+                }
             }
             _ => NpcScriptCommand::None,
         }
@@ -115,121 +234,90 @@ impl NpcScriptEngine {
     fn end_dialog(&mut self) -> NpcScriptCommand {
         self.active_npc = None;
         self.script_state = ScriptState::Idle;
-        self.state_data.clear();
+        self.script = None;
+        self.quest_offers.clear();
         NpcScriptCommand::Close
     }
 
-    fn continue_script(&self, npc_id: i32, page: usize) -> NpcScriptCommand {
-        // Based on current NPC and state, return next command
-        match npc_id {
-            1012100 => self.script_henesys_chief_page(page),
-            9000000 => self.script_test_npc_page(page),
-            _ => NpcScriptCommand::Close,
-        }
-    }
+    /// Go to a page of the active script: follow its branches, run its actions and show its
+    /// text. A page without text (or a missing page) ends the conversation after its actions.
+    fn enter_page(&mut self, mut name: String, character: &CharacterData) -> NpcScriptCommand {
+        let Some(script) = self.script.clone() else { return self.end_dialog() };
 
-    fn handle_yes(&mut self, npc_id: i32) -> NpcScriptCommand {
-        match npc_id {
-            1012100 => {
-                // Henesys Chief - Give tour
-                NpcScriptCommand::ShowDialog {
-                    text: "Great! Let me give you a tour of Henesys. This is the town square where everyone gathers. To the north, you'll find shops and the job advancement center.".to_string(),
-                    dialog_type: DialogType::Ok,
+        let mut hops = 0;
+        let page = loop {
+            let Some(page) = script.pages.get(&name) else {
+                warn!("NPC script page \"{}\" not found", name);
+                return self.end_dialog();
+            };
+            match page.branches.iter().find(|branch| branch.condition.holds(character)) {
+                Some(branch) if hops < MAX_BRANCH_HOPS => {
+                    name = branch.goto.clone();
+                    hops += 1;
                 }
-            }
-            _ => self.end_dialog(),
-        }
-    }
-
-    fn handle_selection(&mut self, npc_id: i32, idx: usize) -> NpcScriptCommand {
-        match npc_id {
-            9000001 => {
-                // Selection test NPC
-                match idx {
-                    0 => NpcScriptCommand::ShowDialog {
-                        text: "You selected Option 1! That's a great choice.".to_string(),
-                        dialog_type: DialogType::Ok,
-                    },
-                    1 => NpcScriptCommand::ShowDialog {
-                        text: "You selected Option 2! Interesting decision.".to_string(),
-                        dialog_type: DialogType::Ok,
-                    },
-                    2 => NpcScriptCommand::ShowDialog {
-                        text: "You selected Option 3! The bold choice!".to_string(),
-                        dialog_type: DialogType::Ok,
-                    },
-                    _ => self.end_dialog(),
+                Some(_) => {
+                    warn!("NPC script branches loop at page \"{}\"", name);
+                    return self.end_dialog();
                 }
+                None => break page,
             }
-            _ => self.end_dialog(),
-        }
-    }
-
-    // Example scripts
-
-    fn script_default(&self) -> NpcScriptCommand {
-        NpcScriptCommand::ShowDialog {
-            text: "Hello! How can I help you today?".to_string(),
-            dialog_type: DialogType::Ok,
-        }
-    }
-
-    fn script_henesys_chief_start(&self) -> NpcScriptCommand {
-        NpcScriptCommand::ShowDialog {
-            text: "Welcome to Henesys! Are you new here?".to_string(),
-            dialog_type: DialogType::YesNo,
-        }
-    }
+        };
 
-    fn script_henesys_chief_page(&self, page: usize) -> NpcScriptCommand {
-        match page {
-            1 => NpcScriptCommand::ShowDialog {
-                text: "I'm the chief of Henesys village. We're a peaceful community of merchants and warriors.".to_string(),
-                dialog_type: DialogType::Next,
-            },
-            2 => NpcScriptCommand::ShowDialog {
-                text: "If you ever need help, feel free to ask any of the NPCs around town. Good luck on your journey!".to_string(),
-                dialog_type: DialogType::Ok,
-            },
-            _ => NpcScriptCommand::Close,
-        }
-    }
+        let mut commands: Vec<NpcScriptCommand> = page.actions.iter().map(|action| action.command()).collect();
+        let shown = if page.text.is_empty() {
+            self.end_dialog()
+        } else if page.kind == PageKind::Selection {
+            let options: Vec<_> = page.options.iter().filter(|option| option.condition.holds(character)).collect();
+            self.script_state = ScriptState::Selection(options.iter().map(|option| option.goto.clone()).collect());
+            NpcScriptCommand::ShowSelection {
+                text: page.text.clone(),
+                options: options.iter().map(|option| option.text.clone()).collect(),
+            }
+        } else if page.kind == PageKind::Style {
+            self.script_state = ScriptState::Page(name);
+            NpcScriptCommand::ShowStyle {
+                text: page.text.clone(),
+                style_type: page.style,
+                available_styles: page.styles.clone(),
+            }
+        } else {
+            self.script_state = ScriptState::Page(name);
+            NpcScriptCommand::ShowDialog {
+                text: page.text.clone(),
+                dialog_type: page.kind.dialog_type(),
+            }
+        };
 
-    fn script_test_npc_start(&self) -> NpcScriptCommand {
-        NpcScriptCommand::ShowDialog {
-            text: "Hi! I'm a test NPC. Let me tell you a multi-page story...".to_string(),
-            dialog_type: DialogType::Next,
+        if commands.is_empty() {
+            return shown;
         }
+        commands.push(shown);
+        NpcScriptCommand::Sequence(commands)
     }
 
-    fn script_test_npc_page(&self, page: usize) -> NpcScriptCommand {
-        match page {
-            1 => NpcScriptCommand::ShowDialog {
-                text: "Once upon a time, in the land of MapleStory, there lived a brave adventurer...".to_string(),
-                dialog_type: DialogType::Next,
-            },
-            2 => NpcScriptCommand::ShowDialog {
-                text: "This adventurer traveled far and wide, battling monsters and making friends.".to_string(),
-                dialog_type: DialogType::Next,
-            },
-            3 => NpcScriptCommand::ShowDialog {
-                text: "And they all lived happily ever after! The end.".to_string(),
-                dialog_type: DialogType::Ok,
-            },
-            _ => NpcScriptCommand::Close,
+    /// Show one of the lines an NPC says when offering or taking a quest. The last line asks
+    /// to accept the quest, or hands it in with OK.
+    fn quest_page(&mut self, quest_id: i32, mark: QuestMark, page: usize) -> NpcScriptCommand {
+        let Some(quest) = quest_info::quest(quest_id) else { return self.end_dialog() };
+        let mut lines = match mark {
+            QuestMark::Available => quest.start_say,
+            QuestMark::Completable => quest.end_say,
+        };
+        if lines.is_empty() {
+            lines.push(match mark {
+                QuestMark::Available => format!("Will you help me with \"{}\"?", quest.name),
+                QuestMark::Completable => format!("You've done everything for \"{}\". Here is your reward.", quest.name),
+            });
         }
-    }
+        let Some(text) = lines.get(page).cloned() else { return self.end_dialog() };
 
-    fn script_selection_test(&mut self) -> NpcScriptCommand {
-        self.script_state = ScriptState::SelectionWait;
-        NpcScriptCommand::ShowSelection {
-            text: "Hello! Please choose one of the following options:".to_string(),
-            options: vec![
-                "Option 1 - Get a free potion".to_string(),
-                "Option 2 - Warp to another map".to_string(),
-                "Option 3 - Learn more about NPCs".to_string(),
-            ],
-        }
+        self.script_state = ScriptState::QuestPage { quest_id, mark, page };
+        let dialog_type = match mark {
+            _ if page + 1 < lines.len() => DialogType::Next,
+            QuestMark::Available => DialogType::AcceptDecline,
+            QuestMark::Completable => DialogType::Ok,
+        };
+        NpcScriptCommand::ShowDialog { text, dialog_type }
     }
 }
 
@@ -0,0 +1,509 @@
+use macroquad::prelude::*;
+use std::collections::{HashMap, HashSet};
+use wz_reader::{WzNodeArc, WzNodeCast};
+
+use crate::inventory::{Inventory, InventoryTab, ItemSlot};
+use crate::item_info;
+use crate::shop_table::{self, ShopItem};
+use crate::ui_windows::format_meso;
+use crate::wz_cache::load_wz_image;
+
+const UIWINDOW2_PATH: &str = "UI/UIWindow2.img";
+
+/// Height of one row in the shop and inventory lists
+const ROW_HEIGHT: f32 = 36.0;
+/// Rows shown at once in each list (the rest scroll with the mouse wheel)
+const ROWS: usize = 6;
+/// Seconds between two clicks on the same row to count as a double-click
+const DOUBLE_CLICK_TIME: f64 = 0.5;
+/// Where the shop's list (left) and the player's list (right) start, relative to the window
+const BUY_LIST_X: f32 = 10.0;
+const SELL_LIST_X: f32 = 242.0;
+const LIST_Y: f32 = 88.0;
+const LIST_WIDTH: f32 = 213.0;
+
+/// A trade the player asked for in the shop window, carried out by gameplay
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShopEvent {
+    /// Buy `quantity` of the shop's item at `index` (throwing stars/bullets: one full set, whatever `quantity` is)
+    Buy { index: usize, quantity: u32 },
+    /// Sell `quantity` of the stack in an inventory slot
+    Sell { tab: InventoryTab, slot: usize, quantity: u32 },
+    /// Fill a stack of throwing stars/bullets back up
+    Recharge { tab: InventoryTab, slot: usize },
+}
+
+/// Button of the shop and storage windows (`Shop/Bt*`, `Trunk/Bt*`), drawn as a labelled box
+/// if its textures are missing
+#[derive(Default)]
+pub struct WindowButton {
+    normal: Option<Texture2D>,
+    mouse_over: Option<Texture2D>,
+    pressed: Option<Texture2D>,
+    label: &'static str,
+    // Position relative to the window
+    x: f32,
+    y: f32,
+}
+
+impl WindowButton {
+    pub fn new(label: &'static str, x: f32, y: f32) -> Self {
+        Self { label, x, y, ..Default::default() }
+    }
+
+    pub async fn load(&mut self, root: &WzNodeArc, base_path: &str) {
+        self.normal = load_texture(root, &format!("{}/normal/0", base_path)).await.map(|(tex, _)| tex);
+        self.mouse_over = load_texture(root, &format!("{}/mouseOver/0", base_path)).await.map(|(tex, _)| tex);
+        self.pressed = load_texture(root, &format!("{}/pressed/0", base_path)).await.map(|(tex, _)| tex);
+    }
+
+    fn rect(&self, window_x: f32, window_y: f32) -> Rect {
+        let (w, h) = self.normal.as_ref().map_or((48.0, 18.0), |tex| (tex.width(), tex.height()));
+        Rect::new(window_x + self.x, window_y + self.y, w, h)
+    }
+
+    pub fn clicked(&self, window_x: f32, window_y: f32) -> bool {
+        let (mx, my) = mouse_position();
+        is_mouse_button_released(MouseButton::Left) && self.rect(window_x, window_y).contains(vec2(mx, my))
+    }
+
+    pub fn draw(&self, window_x: f32, window_y: f32) {
+        let rect = self.rect(window_x, window_y);
+        let (mx, my) = mouse_position();
+        let hovered = rect.contains(vec2(mx, my));
+        let pressed = hovered && is_mouse_button_down(MouseButton::Left);
+        let texture = if pressed && self.pressed.is_some() {
+            &self.pressed
+        } else if hovered && self.mouse_over.is_some() {
+            &self.mouse_over
+        } else {
+            &self.normal
+        };
+        if let Some(tex) = texture {
+            draw_texture(tex, rect.x, rect.y, WHITE);
+            return;
+        }
+
+        let color = if pressed {
+            Color::from_rgba(180, 140, 80, 255)
+        } else if hovered {
+            Color::from_rgba(220, 180, 120, 255)
+        } else {
+            Color::from_rgba(200, 160, 100, 255)
+        };
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
+        draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 1.0, Color::from_rgba(139, 90, 43, 255));
+        let width = measure_text(self.label, None, 12, 1.0).width;
+        draw_text(self.label, rect.x + (rect.w - width) / 2.0, rect.y + 13.0, 12.0, BLACK);
+    }
+}
+
+/// NPC shop (UIWindow2.img `Shop`): the shop's items on the left, the player's inventory on the
+/// right. Double-click or select and press Buy/Sell; hold Shift to buy a full stack or sell a
+/// whole one.
+pub struct NpcShop {
+    visible: bool,
+    loaded: bool,
+    // backgrnd, backgrnd2, backgrnd3 in z-order, with their origins
+    backgrounds: Vec<(Texture2D, Vec2)>,
+    bt_buy: WindowButton,
+    bt_exit: WindowButton,
+    bt_sell: WindowButton,
+    bt_recharge: WindowButton,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    dragging: bool,
+    drag_offset_x: f32,
+    drag_offset_y: f32,
+    npc_id: i32,
+    npc_name: String,
+    // Inventory tab shown in the sell list
+    selected_tab: usize,
+    // Index into the shop's items / inventory slot of the selected row
+    selected_buy: Option<usize>,
+    selected_sell: Option<usize>,
+    buy_scroll: usize,
+    sell_scroll: usize,
+    // Item icons by item id, and every item whose info, name and icon were loaded (or tried)
+    icons: HashMap<i32, Texture2D>,
+    loaded_items: HashSet<i32>,
+    // Row clicked last (true for the buy list) and when, for double-clicks
+    last_click: Option<(bool, usize)>,
+    last_click_time: f64,
+}
+
+impl NpcShop {
+    const TABS: [&'static str; 5] = ["Equip", "Use", "Etc", "Setup", "Cash"];
+
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            loaded: false,
+            backgrounds: Vec::new(),
+            bt_buy: WindowButton::new("Buy", 128.0, 40.0),
+            bt_exit: WindowButton::new("Exit", 180.0, 40.0),
+            bt_sell: WindowButton::new("Sell", 360.0, 40.0),
+            bt_recharge: WindowButton::new("Recharge", 412.0, 40.0),
+            x: 150.0,
+            y: 100.0,
+            width: 465.0,
+            height: 328.0,
+            dragging: false,
+            drag_offset_x: 0.0,
+            drag_offset_y: 0.0,
+            npc_id: 0,
+            npc_name: String::new(),
+            selected_tab: 1,
+            selected_buy: None,
+            selected_sell: None,
+            buy_scroll: 0,
+            sell_scroll: 0,
+            icons: HashMap::new(),
+            loaded_items: HashSet::new(),
+            last_click: None,
+            last_click_time: 0.0,
+        }
+    }
+
+    pub async fn load_assets(&mut self) {
+        let root_node = match load_wz_image(UIWINDOW2_PATH).await {
+            Ok(node) => node,
+            Err(e) => {
+                error!("Failed to load shop window assets: {}", e);
+                return;
+            }
+        };
+
+        for name in ["backgrnd", "backgrnd2", "backgrnd3"] {
+            if let Some(bg) = load_texture(&root_node, &format!("Shop/{}", name)).await {
+                self.backgrounds.push(bg);
+            }
+        }
+        self.bt_buy.load(&root_node, "Shop/BtBuy").await;
+        self.bt_exit.load(&root_node, "Shop/BtExit").await;
+        self.bt_sell.load(&root_node, "Shop/BtSell").await;
+        self.bt_recharge.load(&root_node, "Shop/BtRecharge").await;
+
+        if let Some((bg, _)) = self.backgrounds.first() {
+            self.width = bg.width();
+            self.height = bg.height();
+        }
+        self.loaded = !self.backgrounds.is_empty();
+        info!("Shop window loaded");
+    }
+
+    /// Open an NPC's shop
+    pub fn open(&mut self, npc_id: i32, npc_name: &str) {
+        self.visible = true;
+        self.npc_id = npc_id;
+        self.npc_name = npc_name.to_string();
+        self.selected_buy = None;
+        self.selected_sell = None;
+        self.buy_scroll = 0;
+        self.sell_scroll = 0;
+        self.last_click = None;
+        self.x = (screen_width() - self.width) / 2.0;
+        self.y = (screen_height() - self.height) / 2.0;
+    }
+
+    pub fn close(&mut self) {
+        self.visible = false;
+        self.dragging = false;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// NPC whose shop is open
+    pub fn npc_id(&self) -> i32 {
+        self.npc_id
+    }
+
+    /// Items on sale, with what is left of their stock
+    fn items(&self) -> Vec<ShopItem> {
+        shop_table::shop_items(self.npc_id).unwrap_or_default()
+    }
+
+    fn current_tab(&self) -> InventoryTab {
+        InventoryTab::from_index(self.selected_tab).unwrap_or(InventoryTab::Use)
+    }
+
+    /// Filled slots of the inventory tab shown in the sell list
+    fn sell_rows(&self, inventory: &Inventory) -> Vec<(usize, ItemSlot)> {
+        inventory
+            .slots(self.current_tab())
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, item)| item.map(|item| (slot, item)))
+            .collect()
+    }
+
+    /// True if items on sale or in the inventory still need their info, name or icon
+    pub fn needs_items(&self, inventory: &Inventory) -> bool {
+        self.visible
+            && self.items().iter().map(|item| item.item_id).chain(inventory.item_ids())
+                .any(|item_id| !self.loaded_items.contains(&item_id))
+    }
+
+    /// Load the info (prices, stack sizes), names and icons of the items listed in the window
+    pub async fn load_items(&mut self, inventory: &Inventory) {
+        let missing: Vec<i32> = self.items().iter().map(|item| item.item_id).chain(inventory.item_ids())
+            .filter(|item_id| !self.loaded_items.contains(item_id))
+            .collect();
+        for item_id in missing {
+            item_info::load_item_info(item_id).await;
+            item_info::load_item_name(item_id).await;
+            if let Some(texture) = item_info::load_item_icon(item_id).await {
+                self.icons.insert(item_id, texture);
+            }
+            self.loaded_items.insert(item_id);
+        }
+    }
+
+    fn tab_rect(&self, index: usize) -> Rect {
+        Rect::new(self.x + SELL_LIST_X + index as f32 * 43.0, self.y + 68.0, 41.0, 16.0)
+    }
+
+    fn row_rect(&self, buy: bool, row: usize) -> Rect {
+        let x = if buy { BUY_LIST_X } else { SELL_LIST_X };
+        Rect::new(self.x + x, self.y + LIST_Y + row as f32 * ROW_HEIGHT, LIST_WIDTH, ROW_HEIGHT)
+    }
+
+    /// Record a click on a row; true if it completes a double-click
+    fn double_clicked(&mut self, row: (bool, usize)) -> bool {
+        let now = get_time();
+        if self.last_click == Some(row) && now - self.last_click_time < DOUBLE_CLICK_TIME {
+            self.last_click = None;
+            return true;
+        }
+        self.last_click = Some(row);
+        self.last_click_time = now;
+        false
+    }
+
+    fn buy_event(&self, items: &[ShopItem], bulk: bool) -> Option<ShopEvent> {
+        let index = self.selected_buy?;
+        let item = items.get(index)?;
+        let mut quantity = if bulk && !item_info::is_rechargeable(item.item_id) {
+            item_info::slot_max(item.item_id)
+        } else {
+            1
+        };
+        if let Some(stock) = item.stock {
+            quantity = quantity.min(stock.max(1));
+        }
+        Some(ShopEvent::Buy { index, quantity })
+    }
+
+    fn sell_event(&self, inventory: &Inventory, bulk: bool) -> Option<ShopEvent> {
+        let slot = self.selected_sell?;
+        let item = inventory.get(self.current_tab(), slot)?;
+        // Throwing stars and bullets are only sold as a whole stack
+        let quantity = if bulk || item_info::is_rechargeable(item.item_id) { item.quantity } else { 1 };
+        Some(ShopEvent::Sell { tab: self.current_tab(), slot, quantity })
+    }
+
+    /// Handle buttons, tabs, picking rows, scrolling and dragging
+    pub fn update(&mut self, inventory: &Inventory) -> Option<ShopEvent> {
+        if !self.visible {
+            return None;
+        }
+
+        let (mouse_x, mouse_y) = mouse_position();
+        let mouse = vec2(mouse_x, mouse_y);
+        let bulk = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        let items = self.items();
+        let rows = self.sell_rows(inventory);
+        if self.selected_sell.is_some_and(|slot| inventory.get(self.current_tab(), slot).is_none()) {
+            self.selected_sell = None;
+        }
+
+        if Rect::new(self.x, self.y, self.width, self.height).contains(mouse) {
+            let (_, wheel_y) = mouse_wheel();
+            let (scroll, count) = if mouse_x < self.x + SELL_LIST_X {
+                (&mut self.buy_scroll, items.len())
+            } else {
+                (&mut self.sell_scroll, rows.len())
+            };
+            if wheel_y > 0.0 {
+                *scroll = scroll.saturating_sub(1);
+            } else if wheel_y < 0.0 {
+                *scroll = (*scroll + 1).min(count.saturating_sub(ROWS));
+            }
+        }
+
+        if self.bt_exit.clicked(self.x, self.y) {
+            self.close();
+            return None;
+        }
+        if self.bt_buy.clicked(self.x, self.y) {
+            return self.buy_event(&items, bulk);
+        }
+        if self.bt_sell.clicked(self.x, self.y) {
+            return self.sell_event(inventory, bulk);
+        }
+        if self.bt_recharge.clicked(self.x, self.y) {
+            return self.selected_sell.map(|slot| ShopEvent::Recharge { tab: self.current_tab(), slot });
+        }
+
+        if is_mouse_button_pressed(MouseButton::Left) {
+            if let Some(index) = (0..Self::TABS.len()).find(|&index| self.tab_rect(index).contains(mouse)) {
+                self.selected_tab = index;
+                self.selected_sell = None;
+                self.sell_scroll = 0;
+                return None;
+            }
+            if let Some(row) = (0..ROWS).find(|&row| self.row_rect(true, row).contains(mouse)) {
+                let index = self.buy_scroll + row;
+                if index < items.len() {
+                    self.selected_buy = Some(index);
+                    if self.double_clicked((true, index)) {
+                        return self.buy_event(&items, bulk);
+                    }
+                }
+                return None;
+            }
+            if let Some(row) = (0..ROWS).find(|&row| self.row_rect(false, row).contains(mouse)) {
+                if let Some(&(slot, _)) = rows.get(self.sell_scroll + row) {
+                    self.selected_sell = Some(slot);
+                    if self.double_clicked((false, slot)) {
+                        return self.sell_event(inventory, bulk);
+                    }
+                }
+                return None;
+            }
+            if mouse_y >= self.y && mouse_y <= self.y + 20.0 &&
+               mouse_x >= self.x && mouse_x <= self.x + self.width {
+                self.dragging = true;
+                self.drag_offset_x = mouse_x - self.x;
+                self.drag_offset_y = mouse_y - self.y;
+            }
+        }
+        if is_mouse_button_down(MouseButton::Left) && self.dragging {
+            self.x = mouse_x - self.drag_offset_x;
+            self.y = mouse_y - self.drag_offset_y;
+        } else {
+            self.dragging = false;
+        }
+
+        None
+    }
+
+    pub fn draw(&self, inventory: &Inventory, meso: u32) {
+        if !self.visible {
+            return;
+        }
+
+        if self.loaded {
+            for (bg, origin) in &self.backgrounds {
+                draw_texture(bg, self.x - origin.x, self.y - origin.y, WHITE);
+            }
+        } else {
+            draw_rectangle(self.x, self.y, self.width, self.height, Color::from_rgba(40, 40, 60, 240));
+            draw_rectangle_lines(self.x, self.y, self.width, self.height, 2.0, Color::from_rgba(100, 100, 140, 255));
+            draw_text("SHOP", self.x + 10.0, self.y + 16.0, 16.0, WHITE);
+        }
+        let text_color = if self.loaded { BLACK } else { WHITE };
+
+        draw_text(&self.npc_name, self.x + BUY_LIST_X, self.y + 54.0, 13.0, text_color);
+        draw_text(&format!("{} mesos", format_meso(meso)), self.x + SELL_LIST_X, self.y + 54.0, 13.0, text_color);
+        for button in [&self.bt_buy, &self.bt_exit, &self.bt_sell, &self.bt_recharge] {
+            button.draw(self.x, self.y);
+        }
+
+        for (index, label) in Self::TABS.iter().enumerate() {
+            let rect = self.tab_rect(index);
+            let color = if index == self.selected_tab {
+                Color::from_rgba(250, 200, 90, 255)
+            } else {
+                Color::from_rgba(160, 160, 160, 255)
+            };
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
+            draw_text(label, rect.x + 3.0, rect.y + 12.0, 11.0, BLACK);
+        }
+
+        for (row, (index, item)) in self.items().iter().enumerate().skip(self.buy_scroll).take(ROWS).enumerate() {
+            let rect = self.row_rect(true, row);
+            let price = if item_info::is_rechargeable(item.item_id) {
+                format!("{} mesos a set of {}", format_meso(item.price), item_info::slot_max(item.item_id))
+            } else {
+                format!("{} mesos", format_meso(item.price))
+            };
+            let detail = match item.stock {
+                Some(0) => "Sold out".to_string(),
+                Some(stock) => format!("{} ({} left)", price, stock),
+                None => price,
+            };
+            let selected = self.selected_buy == Some(index);
+            draw_item_row(rect, self.icons.get(&item.item_id), item.item_id, &detail, selected, text_color);
+        }
+
+        for (row, &(slot, item)) in self.sell_rows(inventory).iter().skip(self.sell_scroll).take(ROWS).enumerate() {
+            let rect = self.row_rect(false, row);
+            let price = item_info::sell_price(item.item_id).unwrap_or(0);
+            let detail = if item_info::is_rechargeable(item.item_id) {
+                format!("{} / {}  {} mesos", item.quantity, item_info::slot_max(item.item_id), format_meso(price))
+            } else if item_info::is_equip(item.item_id) {
+                format!("{} mesos", format_meso(price))
+            } else {
+                format!("x{}  {} mesos each", item.quantity, format_meso(price))
+            };
+            let selected = self.selected_sell == Some(slot);
+            draw_item_row(rect, self.icons.get(&item.item_id), item.item_id, &detail, selected, text_color);
+        }
+    }
+}
+
+impl Default for NpcShop {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One item in a list: icon, name and a line of details
+pub fn draw_item_row(rect: Rect, icon: Option<&Texture2D>, item_id: i32, detail: &str, selected: bool, text_color: Color) {
+    if selected {
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, Color::from_rgba(120, 170, 250, 120));
+    }
+    match icon {
+        Some(texture) => draw_texture(texture, rect.x + 2.0, rect.y + 2.0, WHITE),
+        None => draw_rectangle(rect.x + 6.0, rect.y + 6.0, 24.0, 24.0, Color::from_rgba(120, 120, 120, 180)),
+    }
+    let name = item_info::item_name(item_id)
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| format!("Item {}", item_id));
+    draw_text(&fit_text(&name, rect.w - 42.0, 12.0), rect.x + 40.0, rect.y + 15.0, 12.0, text_color);
+    draw_text(&fit_text(detail, rect.w - 42.0, 11.0), rect.x + 40.0, rect.y + 29.0, 11.0, text_color);
+}
+
+/// Cut text down to `max_width` at `font_size`, ending it with ".." if anything was cut
+pub fn fit_text(text: &str, max_width: f32, font_size: f32) -> String {
+    if measure_text(text, None, font_size as u16, 1.0).width <= max_width {
+        return text.to_string();
+    }
+    let mut fitted: String = text.to_string();
+    while !fitted.is_empty() && measure_text(&format!("{}..", fitted), None, font_size as u16, 1.0).width > max_width {
+        fitted.pop();
+    }
+    format!("{}..", fitted)
+}
+
+/// Load a UI texture with its origin
+pub async fn load_texture(root: &WzNodeArc, path: &str) -> Option<(Texture2D, Vec2)> {
+    let node = root.read().unwrap().at_path(path)?;
+    node.write().unwrap().parse(&node).ok()?;
+    let node_read = node.read().unwrap();
+    let png = node_read.try_as_png()?;
+    let img = png.extract_png().ok()?;
+    let rgba = img.to_rgba8();
+    let texture = Texture2D::from_rgba8(rgba.width() as u16, rgba.height() as u16, &rgba.into_raw());
+    let origin = node_read
+        .children
+        .get("origin")
+        .and_then(|origin| origin.read().unwrap().try_as_vector2d().map(|vec| vec2(vec.0 as f32, vec.1 as f32)))
+        .unwrap_or(Vec2::ZERO);
+    Some((texture, origin))
+}
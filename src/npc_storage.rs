@@ -0,0 +1,364 @@
+use macroquad::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+use crate::inventory::{Inventory, InventoryTab, ItemSlot};
+use crate::item_info;
+use crate::npc_shop::{draw_item_row, load_texture, WindowButton};
+use crate::trunk::{self, Trunk};
+use crate::ui_windows::format_meso;
+use crate::wz_cache::load_wz_image;
+
+const UIWINDOW2_PATH: &str = "UI/UIWindow2.img";
+
+/// Height of one row in the storage and inventory lists
+const ROW_HEIGHT: f32 = 36.0;
+/// Rows shown at once in each list (the rest scroll with the mouse wheel)
+const ROWS: usize = 6;
+/// Seconds between two clicks on the same row to count as a double-click
+const DOUBLE_CLICK_TIME: f64 = 0.5;
+/// Where the storage's list (left) and the player's list (right) start, relative to the window
+const TRUNK_LIST_X: f32 = 10.0;
+const INVENTORY_LIST_X: f32 = 242.0;
+const LIST_Y: f32 = 88.0;
+const LIST_WIDTH: f32 = 213.0;
+
+/// A move the player asked for in the storage window, carried out by gameplay
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StorageEvent {
+    /// Take the stored stack at `index` out
+    Withdraw(usize),
+    /// Store the whole stack in an inventory slot
+    Deposit { tab: InventoryTab, slot: usize },
+    /// Buy more storage slots
+    Expand,
+}
+
+/// Storage keeper's window (UIWindow2.img `Trunk`): the account's stored items on the left, the
+/// player's inventory on the right. Double-click or select and press Get/Put to move a stack.
+pub struct NpcStorage {
+    visible: bool,
+    loaded: bool,
+    // backgrnd, backgrnd2, backgrnd3 in z-order, with their origins
+    backgrounds: Vec<(Texture2D, Vec2)>,
+    bt_get: WindowButton,
+    bt_exit: WindowButton,
+    bt_put: WindowButton,
+    bt_expand: WindowButton,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    dragging: bool,
+    drag_offset_x: f32,
+    drag_offset_y: f32,
+    npc_name: String,
+    // Inventory tab shown in the inventory list
+    selected_tab: usize,
+    // Index into the stored items / inventory slot of the selected row
+    selected_stored: Option<usize>,
+    selected_slot: Option<usize>,
+    trunk_scroll: usize,
+    inventory_scroll: usize,
+    // Item icons by item id, and every item whose info, name and icon were loaded (or tried)
+    icons: HashMap<i32, Texture2D>,
+    loaded_items: HashSet<i32>,
+    // Row clicked last (true for the storage list) and when, for double-clicks
+    last_click: Option<(bool, usize)>,
+    last_click_time: f64,
+}
+
+impl NpcStorage {
+    const TABS: [&'static str; 5] = ["Equip", "Use", "Etc", "Setup", "Cash"];
+
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            loaded: false,
+            backgrounds: Vec::new(),
+            bt_get: WindowButton::new("Get", 128.0, 40.0),
+            bt_exit: WindowButton::new("Exit", 180.0, 40.0),
+            bt_put: WindowButton::new("Put", 360.0, 40.0),
+            bt_expand: WindowButton::new("Expand", 412.0, 40.0),
+            x: 150.0,
+            y: 100.0,
+            width: 465.0,
+            height: 328.0,
+            dragging: false,
+            drag_offset_x: 0.0,
+            drag_offset_y: 0.0,
+            npc_name: String::new(),
+            selected_tab: 0,
+            selected_stored: None,
+            selected_slot: None,
+            trunk_scroll: 0,
+            inventory_scroll: 0,
+            icons: HashMap::new(),
+            loaded_items: HashSet::new(),
+            last_click: None,
+            last_click_time: 0.0,
+        }
+    }
+
+    pub async fn load_assets(&mut self) {
+        let root_node = match load_wz_image(UIWINDOW2_PATH).await {
+            Ok(node) => node,
+            Err(e) => {
+                error!("Failed to load storage window assets: {}", e);
+                return;
+            }
+        };
+
+        for name in ["backgrnd", "backgrnd2", "backgrnd3"] {
+            if let Some(bg) = load_texture(&root_node, &format!("Trunk/{}", name)).await {
+                self.backgrounds.push(bg);
+            }
+        }
+        self.bt_get.load(&root_node, "Trunk/BtGet").await;
+        self.bt_exit.load(&root_node, "Trunk/BtExit").await;
+        self.bt_put.load(&root_node, "Trunk/BtPut").await;
+        // The expand button has no texture of its own (slots were a Cash Shop item) and is drawn as a labelled box
+
+        if let Some((bg, _)) = self.backgrounds.first() {
+            self.width = bg.width();
+            self.height = bg.height();
+        }
+        self.loaded = !self.backgrounds.is_empty();
+        info!("Storage window loaded");
+    }
+
+    /// Open the storage window at a storage keeper
+    pub fn open(&mut self, npc_name: &str) {
+        self.visible = true;
+        self.npc_name = npc_name.to_string();
+        self.selected_stored = None;
+        self.selected_slot = None;
+        self.trunk_scroll = 0;
+        self.inventory_scroll = 0;
+        self.last_click = None;
+        self.x = (screen_width() - self.width) / 2.0;
+        self.y = (screen_height() - self.height) / 2.0;
+    }
+
+    pub fn close(&mut self) {
+        self.visible = false;
+        self.dragging = false;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    fn current_tab(&self) -> InventoryTab {
+        InventoryTab::from_index(self.selected_tab).unwrap_or(InventoryTab::Equip)
+    }
+
+    /// Filled slots of the inventory tab shown in the inventory list
+    fn inventory_rows(&self, inventory: &Inventory) -> Vec<(usize, ItemSlot)> {
+        inventory
+            .slots(self.current_tab())
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, item)| item.map(|item| (slot, item)))
+            .collect()
+    }
+
+    /// True if stored or carried items still need their info, name or icon
+    pub fn needs_items(&self, inventory: &Inventory, trunk: &Trunk) -> bool {
+        self.visible
+            && trunk.items().iter().map(|item| item.item_id).chain(inventory.item_ids())
+                .any(|item_id| !self.loaded_items.contains(&item_id))
+    }
+
+    /// Load the info (stack sizes), names and icons of the items listed in the window
+    pub async fn load_items(&mut self, inventory: &Inventory, trunk: &Trunk) {
+        let missing: Vec<i32> = trunk.items().iter().map(|item| item.item_id).chain(inventory.item_ids())
+            .filter(|item_id| !self.loaded_items.contains(item_id))
+            .collect();
+        for item_id in missing {
+            item_info::load_item_info(item_id).await;
+            item_info::load_item_name(item_id).await;
+            if let Some(texture) = item_info::load_item_icon(item_id).await {
+                self.icons.insert(item_id, texture);
+            }
+            self.loaded_items.insert(item_id);
+        }
+    }
+
+    fn tab_rect(&self, index: usize) -> Rect {
+        Rect::new(self.x + INVENTORY_LIST_X + index as f32 * 43.0, self.y + 68.0, 41.0, 16.0)
+    }
+
+    fn row_rect(&self, stored: bool, row: usize) -> Rect {
+        let x = if stored { TRUNK_LIST_X } else { INVENTORY_LIST_X };
+        Rect::new(self.x + x, self.y + LIST_Y + row as f32 * ROW_HEIGHT, LIST_WIDTH, ROW_HEIGHT)
+    }
+
+    /// Record a click on a row; true if it completes a double-click
+    fn double_clicked(&mut self, row: (bool, usize)) -> bool {
+        let now = get_time();
+        if self.last_click == Some(row) && now - self.last_click_time < DOUBLE_CLICK_TIME {
+            self.last_click = None;
+            return true;
+        }
+        self.last_click = Some(row);
+        self.last_click_time = now;
+        false
+    }
+
+    fn deposit_event(&self) -> Option<StorageEvent> {
+        self.selected_slot.map(|slot| StorageEvent::Deposit { tab: self.current_tab(), slot })
+    }
+
+    /// Handle buttons, tabs, picking rows, scrolling and dragging
+    pub fn update(&mut self, inventory: &Inventory, trunk: &Trunk) -> Option<StorageEvent> {
+        if !self.visible {
+            return None;
+        }
+
+        let (mouse_x, mouse_y) = mouse_position();
+        let mouse = vec2(mouse_x, mouse_y);
+        let stored = trunk.items().len();
+        let rows = self.inventory_rows(inventory);
+        if self.selected_stored.is_some_and(|index| index >= stored) {
+            self.selected_stored = None;
+        }
+        if self.selected_slot.is_some_and(|slot| inventory.get(self.current_tab(), slot).is_none()) {
+            self.selected_slot = None;
+        }
+
+        if Rect::new(self.x, self.y, self.width, self.height).contains(mouse) {
+            let (_, wheel_y) = mouse_wheel();
+            let (scroll, count) = if mouse_x < self.x + INVENTORY_LIST_X {
+                (&mut self.trunk_scroll, stored)
+            } else {
+                (&mut self.inventory_scroll, rows.len())
+            };
+            if wheel_y > 0.0 {
+                *scroll = scroll.saturating_sub(1);
+            } else if wheel_y < 0.0 {
+                *scroll = (*scroll + 1).min(count.saturating_sub(ROWS));
+            }
+        }
+
+        if self.bt_exit.clicked(self.x, self.y) {
+            self.close();
+            return None;
+        }
+        if self.bt_get.clicked(self.x, self.y) {
+            return self.selected_stored.map(StorageEvent::Withdraw);
+        }
+        if self.bt_put.clicked(self.x, self.y) {
+            return self.deposit_event();
+        }
+        if self.bt_expand.clicked(self.x, self.y) {
+            return Some(StorageEvent::Expand);
+        }
+
+        if is_mouse_button_pressed(MouseButton::Left) {
+            if let Some(index) = (0..Self::TABS.len()).find(|&index| self.tab_rect(index).contains(mouse)) {
+                self.selected_tab = index;
+                self.selected_slot = None;
+                self.inventory_scroll = 0;
+                return None;
+            }
+            if let Some(row) = (0..ROWS).find(|&row| self.row_rect(true, row).contains(mouse)) {
+                let index = self.trunk_scroll + row;
+                if index < stored {
+                    self.selected_stored = Some(index);
+                    if self.double_clicked((true, index)) {
+                        return Some(StorageEvent::Withdraw(index));
+                    }
+                }
+                return None;
+            }
+            if let Some(row) = (0..ROWS).find(|&row| self.row_rect(false, row).contains(mouse)) {
+                if let Some(&(slot, _)) = rows.get(self.inventory_scroll + row) {
+                    self.selected_slot = Some(slot);
+                    if self.double_clicked((false, slot)) {
+                        return self.deposit_event();
+                    }
+                }
+                return None;
+            }
+            if mouse_y >= self.y && mouse_y <= self.y + 20.0 &&
+               mouse_x >= self.x && mouse_x <= self.x + self.width {
+                self.dragging = true;
+                self.drag_offset_x = mouse_x - self.x;
+                self.drag_offset_y = mouse_y - self.y;
+            }
+        }
+        if is_mouse_button_down(MouseButton::Left) && self.dragging {
+            self.x = mouse_x - self.drag_offset_x;
+            self.y = mouse_y - self.drag_offset_y;
+        } else {
+            self.dragging = false;
+        }
+
+        None
+    }
+
+    pub fn draw(&self, inventory: &Inventory, trunk: &Trunk, meso: u32) {
+        if !self.visible {
+            return;
+        }
+
+        if self.loaded {
+            for (bg, origin) in &self.backgrounds {
+                draw_texture(bg, self.x - origin.x, self.y - origin.y, WHITE);
+            }
+        } else {
+            draw_rectangle(self.x, self.y, self.width, self.height, Color::from_rgba(40, 40, 60, 240));
+            draw_rectangle_lines(self.x, self.y, self.width, self.height, 2.0, Color::from_rgba(100, 100, 140, 255));
+            draw_text("STORAGE", self.x + 10.0, self.y + 16.0, 16.0, WHITE);
+        }
+        let text_color = if self.loaded { BLACK } else { WHITE };
+
+        let header = format!("{}  {} / {} slots", self.npc_name, trunk.items().len(), trunk.slots);
+        draw_text(&header, self.x + TRUNK_LIST_X, self.y + 54.0, 13.0, text_color);
+        draw_text(&format!("{} mesos", format_meso(meso)), self.x + INVENTORY_LIST_X, self.y + 54.0, 13.0, text_color);
+        for button in [&self.bt_get, &self.bt_exit, &self.bt_put, &self.bt_expand] {
+            button.draw(self.x, self.y);
+        }
+
+        for (index, label) in Self::TABS.iter().enumerate() {
+            let rect = self.tab_rect(index);
+            let color = if index == self.selected_tab {
+                Color::from_rgba(250, 200, 90, 255)
+            } else {
+                Color::from_rgba(160, 160, 160, 255)
+            };
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
+            draw_text(label, rect.x + 3.0, rect.y + 12.0, 11.0, BLACK);
+        }
+
+        for (row, (index, item)) in trunk.items().iter().enumerate().skip(self.trunk_scroll).take(ROWS).enumerate() {
+            let rect = self.row_rect(true, row);
+            let detail = if item_info::is_equip(item.item_id) { String::new() } else { format!("x{}", item.quantity) };
+            let selected = self.selected_stored == Some(index);
+            draw_item_row(rect, self.icons.get(&item.item_id), item.item_id, &detail, selected, text_color);
+        }
+
+        for (row, &(slot, item)) in self.inventory_rows(inventory).iter().skip(self.inventory_scroll).take(ROWS).enumerate() {
+            let rect = self.row_rect(false, row);
+            let detail = if item_info::is_equip(item.item_id) { String::new() } else { format!("x{}", item.quantity) };
+            let selected = self.selected_slot == Some(slot);
+            draw_item_row(rect, self.icons.get(&item.item_id), item.item_id, &detail, selected, text_color);
+        }
+
+        let fees = format!(
+            "Put: {} mesos   Get: {} mesos   {} more slots: {} mesos",
+            format_meso(trunk::DEPOSIT_FEE),
+            format_meso(trunk::WITHDRAW_FEE),
+            trunk::EXPANSION_SLOTS,
+            format_meso(trunk::EXPANSION_COST)
+        );
+        let list_bottom = LIST_Y + ROWS as f32 * ROW_HEIGHT;
+        draw_text(&fees, self.x + TRUNK_LIST_X, self.y + list_bottom + 14.0, 11.0, text_color);
+    }
+}
+
+impl Default for NpcStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
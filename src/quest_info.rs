@@ -0,0 +1,284 @@
+use macroquad::prelude::*;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use wz_reader::{WzNodeArc, WzObjectType};
+
+use crate::character::CharacterData;
+use crate::quest_log::QuestState;
+use crate::wz_cache::load_wz_image;
+
+const QUEST_INFO_PATH: &str = "Quest/QuestInfo.img";
+const QUEST_CHECK_PATH: &str = "Quest/Check.img";
+const QUEST_ACT_PATH: &str = "Quest/Act.img";
+const QUEST_SAY_PATH: &str = "Quest/Say.img";
+
+/// Requirements to start (`Check.img/<id>/0`) or complete (`Check.img/<id>/1`) a quest
+#[derive(Debug, Clone, Default)]
+pub struct QuestCheck {
+    /// NPC the player has to talk to
+    pub npc: Option<i32>,
+    pub level_min: u32,
+    /// 0 when there is no upper limit
+    pub level_max: u32,
+    /// Jobs allowed to take the quest (empty = any)
+    pub jobs: Vec<usize>,
+    /// Other quests and the state they have to be in
+    pub quests: Vec<(i32, QuestState)>,
+    /// Items (id, count) the player has to carry. Only checked: the end act takes them.
+    pub items: Vec<(i32, u32)>,
+    /// Mobs (id, count) the player has to kill after starting the quest
+    pub mobs: Vec<(i32, u32)>,
+    /// Meso the player has to carry to complete the quest
+    pub meso: u32,
+}
+
+/// What happens when a quest starts (`Act.img/<id>/0`) or is completed (`Act.img/<id>/1`)
+#[derive(Debug, Clone, Default)]
+pub struct QuestAct {
+    pub exp: i32,
+    /// Meso given (negative takes it)
+    pub meso: i32,
+    /// Items given (negative counts take them). Random rewards (`prop`) are left out.
+    pub items: Vec<(i32, i32)>,
+}
+
+/// A quest from Quest.wz
+#[derive(Debug, Clone, Default)]
+pub struct QuestInfo {
+    pub id: i32,
+    pub name: String,
+    /// Summary shown in the quest log while the quest is in progress (`QuestInfo.img/<id>/1`)
+    pub summary: String,
+    pub start: QuestCheck,
+    pub end: QuestCheck,
+    pub start_act: QuestAct,
+    pub end_act: QuestAct,
+    /// NPC lines when offering the quest (`Say.img/<id>/0`)
+    pub start_say: Vec<String>,
+    /// NPC lines when the quest is handed in (`Say.img/<id>/1`)
+    pub end_say: Vec<String>,
+}
+
+impl QuestInfo {
+    /// The character may take the quest now
+    pub fn can_start(&self, character: &CharacterData) -> bool {
+        let check = &self.start;
+        character.quests.state(self.id) == QuestState::NotStarted
+            && character.level >= check.level_min
+            && (check.level_max == 0 || character.level <= check.level_max)
+            && (check.jobs.is_empty() || check.jobs.contains(&character.job.id()))
+            && check.quests.iter().all(|&(id, state)| character.quests.state(id) == state)
+            && check.items.iter().all(|&(id, count)| character.inventory.has(id, count))
+    }
+
+    /// The quest is in progress and everything it asks for is done
+    pub fn can_complete(&self, character: &CharacterData) -> bool {
+        let Some(progress) = character.quests.progress(self.id) else { return false };
+        let check = &self.end;
+        check.mobs.iter().all(|&(id, count)| progress.mob_kills.get(&id).copied().unwrap_or(0) >= count)
+            && check.items.iter().all(|&(id, count)| character.inventory.has(id, count))
+            && character.meso >= check.meso
+    }
+}
+
+/// What an NPC has to do with a character's quests, shown over its head
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuestMark {
+    /// The NPC gives out a quest the character can take
+    Available,
+    /// The NPC takes a finished quest
+    Completable,
+}
+
+static QUESTS: Mutex<Option<HashMap<i32, QuestInfo>>> = Mutex::new(None);
+
+/// Load (and cache) every quest in Quest.wz
+pub async fn load_quests() {
+    if QUESTS.lock().unwrap().is_some() {
+        return;
+    }
+    let info_root = match load_wz_image(QUEST_INFO_PATH).await {
+        Ok(node) => node,
+        Err(e) => {
+            warn!("Failed to load quests: {}", e);
+            QUESTS.lock().unwrap().get_or_insert_with(HashMap::new);
+            return;
+        }
+    };
+    let check_root = load_wz_image(QUEST_CHECK_PATH).await.ok();
+    let act_root = load_wz_image(QUEST_ACT_PATH).await.ok();
+    let say_root = load_wz_image(QUEST_SAY_PATH).await.ok();
+
+    let mut quests = HashMap::new();
+    for (name, node) in child_nodes(&info_root, "") {
+        let Ok(id) = name.parse::<i32>() else { continue };
+        let part = |root: &Option<WzNodeArc>, index: &str| {
+            root.as_ref().and_then(|root| root.read().unwrap().at_path_parsed(&format!("{}/{}", name, index)).ok())
+        };
+        let quest = QuestInfo {
+            id,
+            name: get_string(&node, "name").unwrap_or_else(|| format!("Quest {}", id)),
+            summary: get_string(&node, "1").map(|text| clean_text(&text)).unwrap_or_default(),
+            start: part(&check_root, "0").map(|node| parse_check(&node)).unwrap_or_default(),
+            end: part(&check_root, "1").map(|node| parse_check(&node)).unwrap_or_default(),
+            start_act: part(&act_root, "0").map(|node| parse_act(&node)).unwrap_or_default(),
+            end_act: part(&act_root, "1").map(|node| parse_act(&node)).unwrap_or_default(),
+            start_say: part(&say_root, "0").map(|node| parse_say(&node)).unwrap_or_default(),
+            end_say: part(&say_root, "1").map(|node| parse_say(&node)).unwrap_or_default(),
+        };
+        quests.insert(id, quest);
+    }
+    info!("Loaded {} quests", quests.len());
+    *QUESTS.lock().unwrap() = Some(quests);
+}
+
+/// A loaded quest
+pub fn quest(quest_id: i32) -> Option<QuestInfo> {
+    QUESTS.lock().unwrap().as_ref()?.get(&quest_id).cloned()
+}
+
+/// Quests an NPC can start or complete for the character right now, sorted by id
+pub fn npc_quests(npc_id: i32, character: &CharacterData) -> Vec<(i32, QuestMark)> {
+    let guard = QUESTS.lock().unwrap();
+    let Some(quests) = guard.as_ref() else { return Vec::new() };
+    let mut found: Vec<(i32, QuestMark)> = quests
+        .values()
+        .filter_map(|quest| {
+            if quest.end.npc == Some(npc_id) && quest.can_complete(character) {
+                Some((quest.id, QuestMark::Completable))
+            } else if quest.start.npc == Some(npc_id) && quest.can_start(character) {
+                Some((quest.id, QuestMark::Available))
+            } else {
+                None
+            }
+        })
+        .collect();
+    found.sort_unstable_by_key(|&(id, _)| id);
+    found
+}
+
+/// Quests that need kills of a mob, with how many
+pub fn quests_needing_mob(mob_id: i32) -> Vec<(i32, u32)> {
+    let guard = QUESTS.lock().unwrap();
+    let Some(quests) = guard.as_ref() else { return Vec::new() };
+    quests
+        .values()
+        .filter_map(|quest| {
+            let &(_, count) = quest.end.mobs.iter().find(|&&(id, _)| id == mob_id)?;
+            Some((quest.id, count))
+        })
+        .collect()
+}
+
+fn parse_check(node: &WzNodeArc) -> QuestCheck {
+    let get = |key: &str| get_string(node, key).and_then(|v| v.parse::<i32>().ok());
+    QuestCheck {
+        npc: get("npc"),
+        level_min: get("lvmin").unwrap_or(0).max(0) as u32,
+        level_max: get("lvmax").unwrap_or(0).max(0) as u32,
+        jobs: child_nodes(node, "job")
+            .iter()
+            .filter_map(|(name, _)| get_string(node, &format!("job/{}", name))?.parse().ok())
+            .collect(),
+        quests: id_list(node, "quest", "state").into_iter().map(|(id, state)| (id, QuestState::from_wz(state))).collect(),
+        items: id_list(node, "item", "count").into_iter().map(|(id, count)| (id, count.max(0) as u32)).collect(),
+        mobs: id_list(node, "mob", "count").into_iter().map(|(id, count)| (id, count.max(0) as u32)).collect(),
+        meso: get("endmeso").unwrap_or(0).max(0) as u32,
+    }
+}
+
+fn parse_act(node: &WzNodeArc) -> QuestAct {
+    let get = |key: &str| get_string(node, key).and_then(|v| v.parse::<i32>().ok()).unwrap_or(0);
+    let items = child_nodes(node, "item")
+        .into_iter()
+        .filter(|(_, item)| get_string(item, "prop").is_none())
+        .filter_map(|(_, item)| {
+            let id = get_string(&item, "id")?.parse().ok()?;
+            let count = get_string(&item, "count").and_then(|v| v.parse().ok()).unwrap_or(1);
+            Some((id, count))
+        })
+        .collect();
+    QuestAct { exp: get("exp"), meso: get("money"), items }
+}
+
+/// Numbered lines of a `Say.img` part in order (`yes`/`no`/`stop` branches are skipped)
+fn parse_say(node: &WzNodeArc) -> Vec<String> {
+    let mut lines: Vec<(u32, String)> = child_nodes(node, "")
+        .into_iter()
+        .filter_map(|(name, _)| Some((name.parse().ok()?, get_string(node, &name)?)))
+        .collect();
+    lines.sort_by_key(|(index, _)| *index);
+    lines.into_iter().map(|(_, text)| clean_text(&text)).collect()
+}
+
+/// `<list>/<n>/id` entries with another value of the same entry
+fn id_list(node: &WzNodeArc, list: &str, value: &str) -> Vec<(i32, i32)> {
+    child_nodes(node, list)
+        .into_iter()
+        .filter_map(|(_, entry)| {
+            let id = get_string(&entry, "id")?.parse().ok()?;
+            let value = get_string(&entry, value).and_then(|v| v.parse().ok()).unwrap_or(0);
+            Some((id, value))
+        })
+        .collect()
+}
+
+/// Strip the client's text codes from quest text: colors and styles (`#b`, `#k`, `#e`, ...)
+/// and selection markers go away, references like `#t2000000#` (item) or `#o100100#` (mob) keep their id, and the
+/// player's name (`#h #`) becomes "you".
+pub fn clean_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '#' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('h') => {
+                while chars.next().is_some_and(|c| c != '#') {}
+                out.push_str("you");
+            }
+            // Start of a selection entry (`#L0#`)
+            Some('L') => while chars.next().is_some_and(|c| c != '#') {},
+            Some('t' | 'z' | 'o' | 'p' | 'm' | 'i' | 'v' | 'c' | 'q' | 's' | 'W') => {
+                // Reference or list entry: keep what's between the code and the closing '#'
+                while let Some(c) = chars.next_if(|&c| c != '#') {
+                    out.push(c);
+                }
+                chars.next();
+            }
+            _ => {}
+        }
+    }
+    out.replace("\\r\\n", "\n").replace("\r\n", "\n")
+}
+
+/// Names and nodes of the children of `path` below `node` (`""` for the node itself)
+fn child_nodes(node: &WzNodeArc, path: &str) -> Vec<(String, WzNodeArc)> {
+    let parent = if path.is_empty() {
+        node.clone()
+    } else {
+        let Ok(parent) = node.read().unwrap().at_path_parsed(path) else { return Vec::new() };
+        parent
+    };
+    let parent_read = parent.read().unwrap();
+    parent_read
+        .children
+        .iter()
+        .map(|(name, child)| (name.to_string(), child.clone()))
+        .collect()
+}
+
+/// Read a number or string property as a string
+fn get_string(node: &WzNodeArc, path: &str) -> Option<String> {
+    let prop = node.read().unwrap().at_path_parsed(path).ok()?;
+    let prop_read = prop.read().unwrap();
+    match &prop_read.object_type {
+        WzObjectType::Value(wz_reader::property::WzValue::Short(val)) => Some(val.to_string()),
+        WzObjectType::Value(wz_reader::property::WzValue::Int(val)) => Some(val.to_string()),
+        WzObjectType::Value(wz_reader::property::WzValue::Long(val)) => Some(val.to_string()),
+        WzObjectType::Value(wz_reader::property::WzValue::String(val)) => val.get_string().ok(),
+        _ => None,
+    }
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Where a character is with a quest (the numbers Quest.wz `Check.img` uses in `quest/<n>/state`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestState {
+    NotStarted,
+    Started,
+    Completed,
+}
+
+impl QuestState {
+    pub fn from_wz(state: i32) -> Self {
+        match state {
+            1 => QuestState::Started,
+            2 => QuestState::Completed,
+            _ => QuestState::NotStarted,
+        }
+    }
+}
+
+/// Counters of a quest in progress. Items aren't counted here: they are checked against
+/// the inventory, so dropping or using them takes them off the count.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuestProgress {
+    /// Mob id -> mobs killed since the quest started
+    pub mob_kills: BTreeMap<i32, u32>,
+}
+
+/// Quests a character has started and completed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuestLog {
+    started: BTreeMap<i32, QuestProgress>,
+    completed: BTreeSet<i32>,
+}
+
+impl QuestLog {
+    pub fn state(&self, quest_id: i32) -> QuestState {
+        if self.completed.contains(&quest_id) {
+            QuestState::Completed
+        } else if self.started.contains_key(&quest_id) {
+            QuestState::Started
+        } else {
+            QuestState::NotStarted
+        }
+    }
+
+    pub fn progress(&self, quest_id: i32) -> Option<&QuestProgress> {
+        self.started.get(&quest_id)
+    }
+
+    /// Quests in progress, by id
+    pub fn started(&self) -> impl Iterator<Item = (i32, &QuestProgress)> {
+        self.started.iter().map(|(&id, progress)| (id, progress))
+    }
+
+    /// Completed quests, by id
+    pub fn completed(&self) -> impl Iterator<Item = i32> + '_ {
+        self.completed.iter().copied()
+    }
+
+    /// Start a quest with fresh counters. Fails if it was started or completed already.
+    pub fn start(&mut self, quest_id: i32) -> Result<(), String> {
+        if self.state(quest_id) != QuestState::NotStarted {
+            return Err(format!("Quest {} was already started", quest_id));
+        }
+        self.started.insert(quest_id, QuestProgress::default());
+        Ok(())
+    }
+
+    /// Move a started quest to the completed ones
+    pub fn complete(&mut self, quest_id: i32) -> Result<(), String> {
+        if self.started.remove(&quest_id).is_none() {
+            return Err(format!("Quest {} is not in progress", quest_id));
+        }
+        self.completed.insert(quest_id);
+        Ok(())
+    }
+
+    /// Count a kill for the started quests in `wanted` (quest id, kills needed) that still need
+    /// this mob. Returns each counted quest with its new kill count.
+    pub fn record_kill(&mut self, mob_id: i32, wanted: &[(i32, u32)]) -> Vec<(i32, u32)> {
+        let mut counted = Vec::new();
+        for &(quest_id, needed) in wanted {
+            let Some(progress) = self.started.get_mut(&quest_id) else { continue };
+            let kills = progress.mob_kills.entry(mob_id).or_insert(0);
+            if *kills < needed {
+                *kills += 1;
+                counted.push((quest_id, *kills));
+            }
+        }
+        counted
+    }
+}
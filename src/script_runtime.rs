@@ -0,0 +1,422 @@
+use macroquad::prelude::*;
+use rhai::{Array, CallFnOptions, Dynamic, Engine, Map, Scope, AST, INT};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::character::CharacterData;
+use crate::job::Job;
+use crate::npc_dialog::{DialogResponse, DialogType};
+use crate::npc_script::{NpcScriptCommand, StyleType};
+use crate::quest_info::clean_text;
+use crate::quest_log::QuestState;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::config::config;
+#[cfg(target_arch = "wasm32")]
+use crate::assets::AssetManager;
+#[cfg(target_arch = "wasm32")]
+use std::collections::HashMap;
+#[cfg(target_arch = "wasm32")]
+use std::sync::Mutex;
+
+/// Operations a single call into a script may take before it is stopped
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// Dialog types passed to `action` (the numbers the client uses)
+const DIALOG_SAY: INT = 0;
+const DIALOG_YES_NO: INT = 1;
+const DIALOG_SIMPLE: INT = 4;
+const DIALOG_STYLE: INT = 7;
+const DIALOG_ACCEPT_DECLINE: INT = 12;
+
+/// What the scripts of the current call did. The API functions registered on the engine
+/// write into this; the runtime reads it back once the call returns.
+#[derive(Default)]
+struct ScriptContext {
+    /// The player as they were when the script was called
+    character: Option<CharacterData>,
+    commands: Vec<NpcScriptCommand>,
+    /// Dialog the script sent last, with its type number
+    dialog: Option<(NpcScriptCommand, INT)>,
+    /// Numbers (`#L<n>#`) of the options of the last `sendSimple` in the order shown,
+    /// or the style ids of the last `sendStyle`
+    options: Vec<INT>,
+    disposed: bool,
+}
+
+/// A script waiting for the player to answer a dialog
+struct Conversation {
+    name: String,
+    ast: AST,
+    scope: Scope<'static>,
+    /// Object map scripts keep their state in between calls (`this.status`)
+    state: Dynamic,
+    dialog_type: INT,
+    options: Vec<INT>,
+}
+
+/// Runs Rhai scripts for NPCs (`npc/<id>.rhai`), portals (`portal/<script>.rhai`) and map entry
+/// (`map/onUserEnter/<script>.rhai`, `map/onFirstUserEnter/<script>.rhai`), with the classic
+/// conversation API: a script starts in `start()` (`enter()` for portals), shows a dialog with
+/// `sendNext`, `sendYesNo`, `sendSimple`, ... and is resumed in `action(mode, kind, selection)`
+/// once the player answers. It ends when it calls `dispose()` (a dialog sent before that is
+/// still shown) or returns without sending a dialog.
+///
+/// Scripts only see the functions registered here: no files, no network, and a limit on
+/// operations, call depth and data sizes.
+pub struct ScriptRuntime {
+    engine: Engine,
+    context: Rc<RefCell<ScriptContext>>,
+    conversation: Option<Conversation>,
+}
+
+impl ScriptRuntime {
+    pub fn new() -> Self {
+        let context = Rc::new(RefCell::new(ScriptContext::default()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_call_levels(32);
+        engine.set_max_expr_depths(64, 32);
+        engine.set_max_string_size(16 * 1024);
+        engine.set_max_array_size(1024);
+        engine.set_max_map_size(1024);
+        engine.disable_symbol("eval");
+        engine.on_print(|text| info!("Script: {}", text));
+        register_api(&mut engine, &context);
+        Self { engine, context, conversation: None }
+    }
+
+    /// A script is waiting for a dialog answer
+    pub fn is_running(&self) -> bool {
+        self.conversation.is_some()
+    }
+
+    /// Start the script of an NPC. `None` if the NPC has no script or it doesn't compile.
+    pub fn start_npc(&mut self, npc_id: i32, character: &CharacterData) -> Option<NpcScriptCommand> {
+        self.start(&format!("npc/{}.rhai", npc_id), "start", character)
+    }
+
+    /// Run the script of a script portal (`enter()`)
+    pub fn start_portal(&mut self, script: &str, character: &CharacterData) -> Option<NpcScriptCommand> {
+        self.start(&portal_script_path(script), "enter", character)
+    }
+
+    /// Run the script of a reactor that broke (`act()`)
+    pub fn start_reactor(&mut self, reactor_id: i32, character: &CharacterData) -> Option<NpcScriptCommand> {
+        self.start(&reactor_script_path(reactor_id), "act", character)
+    }
+
+    /// Run a map's `onUserEnter` or `onFirstUserEnter` script
+    pub fn start_map_enter(&mut self, script: &str, first_enter: bool, character: &CharacterData) -> Option<NpcScriptCommand> {
+        let folder = if first_enter { "onFirstUserEnter" } else { "onUserEnter" };
+        self.start(&format!("map/{}/{}.rhai", folder, script), "start", character)
+    }
+
+    fn start(&mut self, path: &str, function: &str, character: &CharacterData) -> Option<NpcScriptCommand> {
+        self.conversation = None;
+        let source = read_source(path)?;
+        let ast = match self.engine.compile(&source) {
+            Ok(ast) => ast,
+            Err(e) => {
+                warn!("Script {} does not compile: {}", path, e);
+                return None;
+            }
+        };
+        let mut conversation = Conversation {
+            name: path.to_string(),
+            ast,
+            scope: Scope::new(),
+            state: Dynamic::from(Map::new()),
+            dialog_type: DIALOG_SAY,
+            options: Vec::new(),
+        };
+        // Top-level statements run once, before the first call
+        if let Err(e) = self.engine.run_ast_with_scope(&mut conversation.scope, &conversation.ast) {
+            warn!("Script {} failed: {}", path, e);
+            return Some(NpcScriptCommand::Close);
+        }
+        Some(self.call(conversation, function, (), character))
+    }
+
+    /// Pass the player's answer to the waiting script (`action(mode, kind, selection)`)
+    pub fn resume(&mut self, response: DialogResponse, character: &CharacterData) -> NpcScriptCommand {
+        let Some(conversation) = self.conversation.take() else { return NpcScriptCommand::None };
+        let (mode, selection): (INT, INT) = match response {
+            DialogResponse::Ok | DialogResponse::Next | DialogResponse::Yes | DialogResponse::Accept => (1, -1),
+            DialogResponse::Prev | DialogResponse::No | DialogResponse::Decline => (0, -1),
+            DialogResponse::Selection(index) => (1, conversation.options.get(index).copied().unwrap_or(-1)),
+            // Styles are passed by their index in the array given to sendStyle
+            DialogResponse::Style(style_id) => {
+                let index = conversation.options.iter().position(|&id| id == style_id as INT);
+                (1, index.map_or(-1, |index| index as INT))
+            }
+            _ => {
+                self.conversation = Some(conversation);
+                return NpcScriptCommand::None;
+            }
+        };
+        if !conversation.ast.iter_functions().any(|function| function.name == "action") {
+            return NpcScriptCommand::Close;
+        }
+        let dialog_type = conversation.dialog_type;
+        self.call(conversation, "action", (mode, dialog_type, selection), character)
+    }
+
+    /// Call a script function and turn what it did into commands. The conversation is kept
+    /// if the script is now waiting on a dialog.
+    fn call(&mut self, mut conversation: Conversation, function: &str, args: impl rhai::FuncArgs, character: &CharacterData) -> NpcScriptCommand {
+        *self.context.borrow_mut() = ScriptContext { character: Some(character.clone()), ..Default::default() };
+
+        let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(&mut conversation.state);
+        let result = self.engine.call_fn_with_options::<Dynamic>(
+            options,
+            &mut conversation.scope,
+            &conversation.ast,
+            function,
+            args,
+        );
+        let context = std::mem::take(&mut *self.context.borrow_mut());
+        let failed = match result {
+            Ok(_) => false,
+            Err(e) => {
+                warn!("Script {} failed in {}(): {}", conversation.name, function, e);
+                true
+            }
+        };
+
+        let mut commands = context.commands;
+        match context.dialog {
+            Some((dialog, dialog_type)) if !failed => {
+                // Dialogs go last: the other commands close whatever dialog is open
+                commands.push(dialog);
+                if !context.disposed {
+                    conversation.dialog_type = dialog_type;
+                    conversation.options = context.options;
+                    self.conversation = Some(conversation);
+                }
+            }
+            _ => commands.push(NpcScriptCommand::Close),
+        }
+        NpcScriptCommand::Sequence(commands)
+    }
+}
+
+impl Default for ScriptRuntime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Register the functions scripts can call
+fn register_api(engine: &mut Engine, context: &Rc<RefCell<ScriptContext>>) {
+    let dialog = |dialog_type: DialogType, number: INT| {
+        let context = context.clone();
+        move |text: &str| {
+            let command = NpcScriptCommand::ShowDialog { text: clean_text(text), dialog_type };
+            context.borrow_mut().dialog = Some((command, number));
+        }
+    };
+    engine.register_fn("sendOk", dialog(DialogType::Ok, DIALOG_SAY));
+    engine.register_fn("sendNext", dialog(DialogType::Next, DIALOG_SAY));
+    engine.register_fn("sendNextPrev", dialog(DialogType::NextPrev, DIALOG_SAY));
+    engine.register_fn("sendYesNo", dialog(DialogType::YesNo, DIALOG_YES_NO));
+    engine.register_fn("sendAcceptDecline", dialog(DialogType::AcceptDecline, DIALOG_ACCEPT_DECLINE));
+
+    let ctx = context.clone();
+    engine.register_fn("sendSimple", move |text: &str| {
+        let (prompt, options) = parse_simple(text);
+        let mut context = ctx.borrow_mut();
+        context.options = options.iter().map(|&(number, _)| number).collect();
+        let command = NpcScriptCommand::ShowSelection {
+            text: prompt,
+            options: options.into_iter().map(|(_, option)| option).collect(),
+        };
+        context.dialog = Some((command, DIALOG_SIMPLE));
+    });
+
+    // sendStyle(text, [ids]) previews each style on the player; the kind (hair, face or skin)
+    // follows from the ids
+    let ctx = context.clone();
+    engine.register_fn("sendStyle", move |text: &str, styles: Array| {
+        let styles: Vec<INT> = styles.iter().filter_map(|style| style.as_int().ok()).collect();
+        let Some(&first) = styles.first() else { return };
+        let command = NpcScriptCommand::ShowStyle {
+            text: clean_text(text),
+            style_type: style_type_of(first),
+            available_styles: styles.iter().map(|&id| id as i32).collect(),
+        };
+        let mut context = ctx.borrow_mut();
+        context.options = styles;
+        context.dialog = Some((command, DIALOG_STYLE));
+    });
+
+    let ctx = context.clone();
+    engine.register_fn("dispose", move || ctx.borrow_mut().disposed = true);
+
+    let command = |make: fn(i32) -> NpcScriptCommand| {
+        let context = context.clone();
+        move |value: INT| context.borrow_mut().commands.push(make(value as i32))
+    };
+    engine.register_fn("gainMeso", command(NpcScriptCommand::GiveMeso));
+    engine.register_fn("gainExp", command(NpcScriptCommand::GiveExp));
+    engine.register_fn("startQuest", command(NpcScriptCommand::StartQuest));
+    engine.register_fn("completeQuest", command(NpcScriptCommand::CompleteQuest));
+    engine.register_fn("openShop", command(NpcScriptCommand::OpenShop));
+    let ctx = context.clone();
+    engine.register_fn("openStorage", move || ctx.borrow_mut().commands.push(NpcScriptCommand::OpenStorage));
+    engine.register_fn("changeJob", command(|id| match Job::from_id(id as usize) {
+        Some(job) => NpcScriptCommand::ChangeJob(job),
+        None => {
+            warn!("Script: Unknown job {}", id);
+            NpcScriptCommand::None
+        }
+    }));
+    engine.register_fn("setHair", command(|id| NpcScriptCommand::SetStyle(StyleType::Hair, id)));
+    engine.register_fn("setFace", command(|id| NpcScriptCommand::SetStyle(StyleType::Face, id)));
+    engine.register_fn("setSkin", command(|id| NpcScriptCommand::SetStyle(StyleType::Skin, id)));
+
+    // warp(map) appears at the spawn point, warp(map, portal) at a named portal
+    let ctx = context.clone();
+    engine.register_fn("warp", move |map_id: INT| {
+        ctx.borrow_mut().commands.push(NpcScriptCommand::Warp(map_id as i32, None));
+    });
+    let ctx = context.clone();
+    engine.register_fn("warp", move |map_id: INT, portal: &str| {
+        ctx.borrow_mut().commands.push(NpcScriptCommand::Warp(map_id as i32, Some(portal.to_string())));
+    });
+
+    let ctx = context.clone();
+    engine.register_fn("gainItem", move |item_id: INT, quantity: INT| {
+        let command = if quantity < 0 {
+            NpcScriptCommand::TakeItem(item_id as i32, -quantity as i32)
+        } else {
+            NpcScriptCommand::GiveItem(item_id as i32, quantity as i32)
+        };
+        ctx.borrow_mut().commands.push(command);
+    });
+    let ctx = context.clone();
+    engine.register_fn("playerMessage", move |text: &str| {
+        ctx.borrow_mut().commands.push(NpcScriptCommand::Message(clean_text(text)));
+    });
+
+    // Queries, answered from the player as they were when the script was called
+    let query = |read: fn(&CharacterData) -> INT| {
+        let context = context.clone();
+        move || context.borrow().character.as_ref().map_or(0, read)
+    };
+    engine.register_fn("getLevel", query(|character| character.level as INT));
+    engine.register_fn("getJob", query(|character| character.job.id() as INT));
+    engine.register_fn("getMeso", query(|character| character.meso as INT));
+
+    let ctx = context.clone();
+    engine.register_fn("getName", move || {
+        ctx.borrow().character.as_ref().map_or_else(String::new, |character| character.name.clone())
+    });
+    let ctx = context.clone();
+    engine.register_fn("getJobName", move || {
+        ctx.borrow().character.as_ref().map_or_else(String::new, |character| character.job.name().to_string())
+    });
+    let ctx = context.clone();
+    engine.register_fn("canAdvance", move |job_id: INT| {
+        let context = ctx.borrow();
+        Job::from_id(job_id as usize)
+            .zip(context.character.as_ref())
+            .is_some_and(|(job, character)| character.check_advancement(job).is_ok())
+    });
+    let ctx = context.clone();
+    engine.register_fn("itemQuantity", move |item_id: INT| {
+        ctx.borrow().character.as_ref().map_or(0, |character| character.inventory.count(item_id as i32) as INT)
+    });
+    let ctx = context.clone();
+    engine.register_fn("haveItem", move |item_id: INT, quantity: INT| {
+        let quantity = quantity.max(0) as u32;
+        ctx.borrow().character.as_ref().is_some_and(|character| character.inventory.has(item_id as i32, quantity))
+    });
+    let ctx = context.clone();
+    engine.register_fn("getQuestStatus", move |quest_id: INT| {
+        let state = ctx.borrow().character.as_ref().map(|character| character.quests.state(quest_id as i32));
+        match state {
+            Some(QuestState::Started) => 1 as INT,
+            Some(QuestState::Completed) => 2,
+            _ => 0,
+        }
+    });
+}
+
+/// What a style id changes: skins are numbered from 0, faces from 20000 and hair from 30000
+fn style_type_of(style_id: INT) -> StyleType {
+    match style_id {
+        0..=99 => StyleType::Skin,
+        20000..=29999 => StyleType::Face,
+        _ => StyleType::Hair,
+    }
+}
+
+/// Split `sendSimple` text into the prompt and its `#L<n>#text#l` options
+fn parse_simple(text: &str) -> (String, Vec<(INT, String)>) {
+    let mut parts = text.split("#L");
+    let prompt = clean_text(parts.next().unwrap_or_default().trim_end());
+    let options = parts
+        .filter_map(|part| {
+            let (number, rest) = part.split_once('#')?;
+            let option = rest.split("#l").next().unwrap_or_default();
+            Some((number.trim().parse().ok()?, clean_text(option.trim())))
+        })
+        .collect();
+    (prompt, options)
+}
+
+pub fn portal_script_path(script: &str) -> String {
+    format!("portal/{}.rhai", script)
+}
+
+pub fn reactor_script_path(reactor_id: i32) -> String {
+    format!("reactor/{}.rhai", reactor_id)
+}
+
+/// Whether a script file exists
+#[cfg(not(target_arch = "wasm32"))]
+pub fn has_script(path: &str) -> bool {
+    std::path::Path::new(&config().script_dir).join(path).is_file()
+}
+
+/// Source of a script, read from `script_dir` each time so edits apply the next time the
+/// script starts
+#[cfg(not(target_arch = "wasm32"))]
+fn read_source(path: &str) -> Option<String> {
+    std::fs::read_to_string(std::path::Path::new(&config().script_dir).join(path)).ok()
+}
+
+/// Scripts fetched by `preload_scripts`, by path
+#[cfg(target_arch = "wasm32")]
+static SOURCES: Mutex<Option<HashMap<String, String>>> = Mutex::new(None);
+
+/// Whether a script was preloaded
+#[cfg(target_arch = "wasm32")]
+pub fn has_script(path: &str) -> bool {
+    SOURCES.lock().unwrap().as_ref().is_some_and(|sources| sources.contains_key(path))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_source(path: &str) -> Option<String> {
+    SOURCES.lock().unwrap().as_ref()?.get(path).cloned()
+}
+
+/// Fetch scripts from the asset source (`scripts/<path>`) so they can run without waiting.
+/// Scripts are read from disk when they start on native, so this only matters on web.
+pub async fn preload_scripts(paths: &[String]) {
+    #[cfg(target_arch = "wasm32")]
+    for path in paths {
+        if has_script(path) {
+            continue;
+        }
+        let Ok(bytes) = AssetManager::fetch_and_cache(&format!("scripts/{}", path)).await else { continue };
+        SOURCES
+            .lock()
+            .unwrap()
+            .get_or_insert_with(HashMap::new)
+            .insert(path.clone(), String::from_utf8_lossy(&bytes).into_owned());
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = paths;
+}
@@ -0,0 +1,100 @@
+use macroquad::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::assets::AssetManager;
+
+/// Shops bundled with the game
+const DEFAULT_SHOPS: &str = include_str!("../data/shops.json");
+
+/// A `shops.json` next to the game data replaces the bundled shops
+const SHOP_TABLE_PATH: &str = "shops.json";
+
+/// Something a shop sells
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct ShopItem {
+    pub item_id: i32,
+    /// Meso for one item, or for a full set of throwing stars/bullets
+    pub price: u32,
+    /// How many are left to buy this session (unlimited if not set)
+    #[serde(default)]
+    pub stock: Option<u32>,
+    /// Meso per star/bullet when recharging. The shop recharges this item only if it is set.
+    #[serde(default)]
+    pub unit_price: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct Shop {
+    items: Vec<ShopItem>,
+}
+
+/// Shops by NPC id, from JSON: `{ "<npc id>": { "items": [{ "item_id": ..., "price": ... }] } }`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+struct ShopTable {
+    shops: HashMap<String, Shop>,
+}
+
+impl ShopTable {
+    /// Look up an NPC's shop; ids match with or without leading zeros
+    fn shop_mut(&mut self, npc_id: i32) -> Option<&mut Shop> {
+        self.shops.iter_mut().find(|(id, _)| id.parse::<i32>().ok() == Some(npc_id)).map(|(_, shop)| shop)
+    }
+}
+
+static SHOP_TABLE: Mutex<Option<ShopTable>> = Mutex::new(None);
+
+/// Load the shops: `shops.json` from the asset source if there is one, the bundled shops otherwise
+pub async fn load_shops() {
+    if SHOP_TABLE.lock().unwrap().is_some() {
+        return;
+    }
+
+    let custom = match AssetManager::fetch_and_cache(SHOP_TABLE_PATH).await {
+        Ok(bytes) => match serde_json::from_slice::<ShopTable>(&bytes) {
+            Ok(table) => Some(table),
+            Err(e) => {
+                warn!("Ignoring {}: {}", SHOP_TABLE_PATH, e);
+                None
+            }
+        },
+        Err(_) => None,
+    };
+    let table = match custom {
+        Some(table) => {
+            info!("Loaded shops from {} ({} shops)", SHOP_TABLE_PATH, table.shops.len());
+            table
+        }
+        None => serde_json::from_str(DEFAULT_SHOPS).expect("bundled shops are valid JSON"),
+    };
+    *SHOP_TABLE.lock().unwrap() = Some(table);
+}
+
+/// Items an NPC sells, with what is left of their stock. `None` if the NPC has no shop.
+pub fn shop_items(npc_id: i32) -> Option<Vec<ShopItem>> {
+    let mut guard = SHOP_TABLE.lock().unwrap();
+    guard.as_mut()?.shop_mut(npc_id).map(|shop| shop.items.clone())
+}
+
+pub fn has_shop(npc_id: i32) -> bool {
+    shop_items(npc_id).is_some()
+}
+
+/// Take bought items out of a shop's stock. Fails without changing anything if there aren't enough.
+pub fn take_stock(npc_id: i32, index: usize, quantity: u32) -> Result<(), String> {
+    let mut guard = SHOP_TABLE.lock().unwrap();
+    let item = guard
+        .as_mut()
+        .and_then(|table| table.shop_mut(npc_id))
+        .and_then(|shop| shop.items.get_mut(index))
+        .ok_or_else(|| format!("Shop {} has no item {}", npc_id, index))?;
+    if let Some(stock) = item.stock.as_mut() {
+        if *stock < quantity {
+            return Err("That item is sold out.".to_string());
+        }
+        *stock -= quantity;
+    }
+    Ok(())
+}
@@ -0,0 +1,358 @@
+use macroquad::prelude::*;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use wz_reader::{WzNodeArc, WzNodeCast, WzObjectType};
+
+use crate::wz_cache::load_wz_image;
+
+const SKILL_STRING_PATH: &str = "String/Skill.img";
+
+/// Values of one skill level, from `level/<n>` or the `common` formulas evaluated at that level
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SkillLevel {
+    pub mp_con: i32,
+    pub hp_con: i32,
+    /// Damage in percent of a basic attack
+    pub damage: i32,
+    /// Most mobs hit by one cast
+    pub mob_count: i32,
+    /// Hits on each mob
+    pub attack_count: i32,
+    /// Buff duration (seconds)
+    pub time: i32,
+    /// Cooldown (seconds)
+    pub cooltime: i32,
+    pub pad: i32,
+    pub pdd: i32,
+    /// Magic attack: spell attack for attack spells, a bonus for buffs
+    pub mad: i32,
+    pub mdd: i32,
+    pub acc: i32,
+    pub eva: i32,
+    pub speed: i32,
+    pub jump: i32,
+    /// Damage each second of the poison a skill leaves on mobs
+    pub dot: i32,
+    /// Poison duration (seconds, `time` is used if missing)
+    pub dot_time: i32,
+    /// Skill-specific value (e.g. how much Slow slows)
+    pub x: i32,
+    /// Chance in percent for a skill's debuff to land (100 if missing)
+    pub prop: i32,
+    /// Area hit, relative to the caster's feet when facing left (lt = top left, rb = bottom right)
+    pub range: Option<Rect>,
+}
+
+impl SkillLevel {
+    /// Buffs last a while instead of hitting anything (attacks can have a `time` for their debuff)
+    pub fn is_buff(&self) -> bool {
+        self.time > 0 && self.damage == 0 && self.dot == 0
+    }
+
+    /// Hits mobs, with weapon damage, as a spell or with poison
+    pub fn is_attack(&self) -> bool {
+        !self.is_buff() && (self.damage > 0 || self.mad > 0 || self.dot > 0)
+    }
+}
+
+/// A skill from Skill.wz `<book>.img/skill/<id>`
+#[derive(Debug, Clone, Default)]
+pub struct SkillInfo {
+    pub id: i32,
+    pub name: String,
+    /// Index 0 is level 1
+    pub levels: Vec<SkillLevel>,
+    /// Skills (and their levels) that have to be learned first
+    pub required: Vec<(i32, u32)>,
+    /// Character stance played when casting (`action/0`)
+    pub action: Option<String>,
+    /// Not shown in the skill window
+    pub invisible: bool,
+}
+
+impl SkillInfo {
+    pub fn max_level(&self) -> u32 {
+        self.levels.len() as u32
+    }
+
+    /// Values at a level (None for level 0 or past the max)
+    pub fn level(&self, level: u32) -> Option<&SkillLevel> {
+        self.levels.get((level as usize).checked_sub(1)?)
+    }
+
+    /// Skill book (job id) a skill belongs to
+    pub fn book(skill_id: i32) -> usize {
+        (skill_id / 10000) as usize
+    }
+
+    /// WZ image and node path of a skill
+    pub fn wz_path(skill_id: i32) -> (String, String) {
+        (
+            format!("Skill/{:03}.img", Self::book(skill_id)),
+            format!("skill/{:07}", skill_id),
+        )
+    }
+}
+
+static SKILL_INFO: Mutex<Option<HashMap<i32, SkillInfo>>> = Mutex::new(None);
+
+/// Skill books a job can use: its own and every earlier job on its branch
+/// (e.g. Fighter 110 uses 100 and 110, Hero 112 uses 100, 110, 111 and 112)
+pub fn skill_books(job: usize) -> Vec<usize> {
+    if job == 0 {
+        return vec![0];
+    }
+    let mut books = vec![job / 100 * 100];
+    if job % 100 != 0 {
+        books.extend(job / 10 * 10..=job);
+    }
+    books
+}
+
+/// Load (and cache) every skill in a skill book. Returns the ids of the skills shown
+/// in the skill window, sorted.
+pub async fn load_skill_book(book: usize) -> Vec<i32> {
+    let path = format!("Skill/{:03}.img", book);
+    let root = match load_wz_image(&path).await {
+        Ok(node) => node,
+        Err(e) => {
+            warn!("Failed to load skill book {}: {}", book, e);
+            return Vec::new();
+        }
+    };
+    let names = load_wz_image(SKILL_STRING_PATH).await.ok();
+
+    let skills_node = match root.read().unwrap().at_path_parsed("skill") {
+        Ok(node) => node,
+        Err(_) => return Vec::new(),
+    };
+    let children: Vec<(String, WzNodeArc)> = skills_node
+        .read()
+        .unwrap()
+        .children
+        .iter()
+        .map(|(name, node)| (name.to_string(), node.clone()))
+        .collect();
+
+    let mut ids = Vec::new();
+    for (name, node) in children {
+        let Ok(id) = name.parse::<i32>() else { continue };
+        let mut skill = parse_skill(id, &node);
+        skill.name = names
+            .as_ref()
+            .and_then(|names| get_string(names, &format!("{:07}/name", id)))
+            .unwrap_or_else(|| id.to_string());
+        if !skill.invisible {
+            ids.push(id);
+        }
+        SKILL_INFO.lock().unwrap().get_or_insert_with(HashMap::new).insert(id, skill);
+    }
+    ids.sort_unstable();
+    info!("Loaded skill book {} ({} skills)", book, ids.len());
+    ids
+}
+
+/// A loaded skill, if its book has been loaded with `load_skill_book`
+pub fn skill_info(skill_id: i32) -> Option<SkillInfo> {
+    SKILL_INFO.lock().unwrap().as_ref()?.get(&skill_id).cloned()
+}
+
+fn parse_skill(id: i32, node: &WzNodeArc) -> SkillInfo {
+    let common = node.read().unwrap().at_path_parsed("common").ok();
+    let levels = if let Some(common) = common {
+        // Newer data: one set of formulas in x (the skill level)
+        let max_level = get_string(&common, "maxLevel").and_then(|s| s.parse::<u32>().ok()).unwrap_or(0);
+        (1..=max_level).map(|level| parse_level(&common, level as f64)).collect()
+    } else {
+        let mut levels: Vec<(u32, SkillLevel)> = child_nodes(node, "level")
+            .into_iter()
+            .filter_map(|(name, level_node)| Some((name.parse().ok()?, parse_level(&level_node, 0.0))))
+            .collect();
+        levels.sort_by_key(|(level, _)| *level);
+        levels.into_iter().map(|(_, level)| level).collect()
+    };
+
+    let required: Vec<(i32, u32)> = child_nodes(node, "req")
+        .into_iter()
+        .filter_map(|(name, _)| {
+            let level = get_string(node, &format!("req/{}", name))?.parse().ok()?;
+            Some((name.parse().ok()?, level))
+        })
+        .collect();
+
+    SkillInfo {
+        id,
+        name: String::new(),
+        levels,
+        required,
+        action: get_string(node, "action/0"),
+        invisible: get_string(node, "invisible").is_some_and(|v| v != "0"),
+    }
+}
+
+fn parse_level(node: &WzNodeArc, x: f64) -> SkillLevel {
+    let get = |key: &str| {
+        get_string(node, key)
+            .and_then(|formula| eval_formula(&formula, x))
+            .map_or(0, |v| v as i32)
+    };
+    let range = get_vector(node, "lt")
+        .zip(get_vector(node, "rb"))
+        .map(|(lt, rb)| Rect::new(lt.x, lt.y, rb.x - lt.x, rb.y - lt.y));
+
+    SkillLevel {
+        mp_con: get("mpCon"),
+        hp_con: get("hpCon"),
+        damage: get("damage"),
+        mob_count: get("mobCount").max(1),
+        attack_count: get("attackCount").max(1),
+        time: get("time"),
+        cooltime: get("cooltime"),
+        pad: get("pad"),
+        pdd: get("pdd"),
+        mad: get("mad"),
+        mdd: get("mdd"),
+        acc: get("acc"),
+        eva: get("eva"),
+        speed: get("speed"),
+        jump: get("jump"),
+        dot: get("dot"),
+        dot_time: get("dotTime"),
+        x: get("x"),
+        prop: get_string(node, "prop").map_or(100, |_| get("prop")),
+        range,
+    }
+}
+
+/// Evaluate a `common` formula such as "10+2*x", "u(x/2)" or "-5+d(x/3)" at skill level `x`.
+/// `u` rounds up and `d` rounds down; plain numbers are formulas too.
+pub fn eval_formula(formula: &str, x: f64) -> Option<f64> {
+    let mut parser = FormulaParser { chars: formula.chars().filter(|c| !c.is_whitespace()).collect(), pos: 0, x };
+    let value = parser.expr()?;
+    (parser.pos == parser.chars.len()).then_some(value)
+}
+
+struct FormulaParser {
+    chars: Vec<char>,
+    pos: usize,
+    x: f64,
+}
+
+impl FormulaParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expr(&mut self) -> Option<f64> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Option<f64> {
+        let mut value = self.factor()?;
+        loop {
+            if self.eat('*') {
+                value *= self.factor()?;
+            } else if self.eat('/') {
+                value /= self.factor()?;
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    fn factor(&mut self) -> Option<f64> {
+        match self.peek()? {
+            '-' => {
+                self.pos += 1;
+                Some(-self.factor()?)
+            }
+            '(' => {
+                self.pos += 1;
+                let value = self.expr()?;
+                self.eat(')').then_some(value)
+            }
+            'x' => {
+                self.pos += 1;
+                Some(self.x)
+            }
+            'u' | 'd' => {
+                let round_up = self.peek() == Some('u');
+                self.pos += 1;
+                let value = self.factor()?;
+                Some(if round_up { value.ceil() } else { value.floor() })
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+                    self.pos += 1;
+                }
+                self.chars[start..self.pos].iter().collect::<String>().parse().ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Names and nodes of the children of `path` below `node`
+fn child_nodes(node: &WzNodeArc, path: &str) -> Vec<(String, WzNodeArc)> {
+    let Ok(parent) = node.read().unwrap().at_path_parsed(path) else { return Vec::new() };
+    let parent_read = parent.read().unwrap();
+    parent_read
+        .children
+        .iter()
+        .map(|(name, child)| (name.to_string(), child.clone()))
+        .collect()
+}
+
+/// Read a number or string property as a string
+fn get_string(node: &WzNodeArc, path: &str) -> Option<String> {
+    let prop = node.read().unwrap().at_path_parsed(path).ok()?;
+    let prop_read = prop.read().unwrap();
+    match &prop_read.object_type {
+        WzObjectType::Value(wz_reader::property::WzValue::Short(val)) => Some(val.to_string()),
+        WzObjectType::Value(wz_reader::property::WzValue::Int(val)) => Some(val.to_string()),
+        WzObjectType::Value(wz_reader::property::WzValue::Long(val)) => Some(val.to_string()),
+        WzObjectType::Value(wz_reader::property::WzValue::Float(val)) => Some(val.to_string()),
+        WzObjectType::Value(wz_reader::property::WzValue::Double(val)) => Some(val.to_string()),
+        WzObjectType::Value(wz_reader::property::WzValue::String(val)) => val.get_string().ok(),
+        _ => None,
+    }
+}
+
+fn get_vector(node: &WzNodeArc, path: &str) -> Option<Vec2> {
+    let prop = node.read().unwrap().at_path_parsed(path).ok()?;
+    let prop_read = prop.read().unwrap();
+    prop_read.try_as_vector2d().map(|v| vec2(v.0 as f32, v.1 as f32))
+}
+
+/// Load the skill window icon (`icon`) of a skill
+pub async fn load_skill_icon(skill_id: i32) -> Option<Texture2D> {
+    let (img_path, node_path) = SkillInfo::wz_path(skill_id);
+    let root = load_wz_image(&img_path).await.ok()?;
+    let icon = root.read().unwrap().at_path_parsed(&format!("{}/icon", node_path)).ok()?;
+    icon.write().unwrap().parse(&icon).ok()?;
+
+    let icon_read = icon.read().unwrap();
+    let png = icon_read.try_as_png()?;
+    let img = png.extract_png().ok()?;
+    let rgba = img.to_rgba8();
+    Some(Texture2D::from_rgba8(rgba.width() as u16, rgba.height() as u16, &rgba.into_raw()))
+}
@@ -1,11 +1,8 @@
 use macroquad::prelude::*;
-use crate::assets::AssetManager;
-use std::sync::Arc;
-use wz_reader::version::guess_iv_from_wz_img;
-use wz_reader::{WzImage, WzNode, WzNodeArc, WzReader, WzNodeCast};
+use crate::wz_cache::load_wz_image;
+use wz_reader::{WzNodeArc, WzNodeCast};
 
-const UIWINDOW2_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/UI/UIWindow2.img";
-const UIWINDOW2_CACHE: &str = "/01/UI/UIWindow2.img";
+const UIWINDOW2_PATH: &str = "UI/UIWindow2.img";
 
 struct Tex { texture: Texture2D, origin: Vec2 }
 
@@ -23,15 +20,7 @@ async fn load_tex(root: &WzNodeArc, path: &str) -> Option<Tex> {
 }
 
 async fn load_wz() -> Option<WzNodeArc> {
-    let bytes = AssetManager::fetch_and_cache(UIWINDOW2_URL, UIWINDOW2_CACHE).await.ok()?;
-    let iv = guess_iv_from_wz_img(&bytes)?;
-    let len = bytes.len();
-    let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(iv));
-    let name: wz_reader::WzNodeName = UIWINDOW2_CACHE.to_string().into();
-    let img = WzImage::new(&name, 0, len, &reader);
-    let root: WzNodeArc = WzNode::new(&UIWINDOW2_CACHE.into(), img, None).into();
-    root.write().unwrap().parse(&root).ok()?;
-    Some(root)
+    load_wz_image(UIWINDOW2_PATH).await.ok()
 }
 
 /// Channel selection window
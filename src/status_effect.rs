@@ -0,0 +1,173 @@
+use crate::item_info::EquipStats;
+
+/// Seconds between damage ticks of effects like poison
+const TICK_INTERVAL: f32 = 1.0;
+
+/// What applied an effect. Two effects with the same source are the same effect for stacking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EffectSource {
+    /// A skill, by skill id
+    Skill(i32),
+    /// A consumable, by item id
+    Item(i32),
+}
+
+/// What happens when an effect is applied while the same source's effect is still running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stacking {
+    /// The new effect takes over (values and duration)
+    Replace,
+    /// The running effect keeps its values and only gets its duration reset
+    Refresh,
+    /// Adds a stack, up to the given count. Values scale with the stacks and the duration resets.
+    Stack(u32),
+}
+
+/// What an effect does while it runs (negative stat bonuses are debuffs)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StatModifiers {
+    /// Added to the stats like an equip bonus
+    pub bonus: EquipStats,
+    /// Movement slowed by this percent (100 stops it)
+    pub slow: i32,
+    /// Can't move or act
+    pub stun: bool,
+    /// HP lost every tick (poison)
+    pub damage_per_tick: i32,
+}
+
+/// A temporary effect on the player or a mob
+#[derive(Debug, Clone)]
+pub struct StatusEffect {
+    pub source: EffectSource,
+    /// Skill level of the source (1 for items)
+    pub level: u32,
+    /// Seconds the effect lasts
+    pub duration: f32,
+    /// Seconds left
+    pub remaining: f32,
+    pub stacking: Stacking,
+    pub stacks: u32,
+    pub modifiers: StatModifiers,
+    tick_timer: f32,
+}
+
+impl StatusEffect {
+    pub fn new(source: EffectSource, level: u32, duration: f32, modifiers: StatModifiers) -> Self {
+        Self {
+            source,
+            level,
+            duration,
+            remaining: duration,
+            stacking: Stacking::Replace,
+            stacks: 1,
+            modifiers,
+            tick_timer: 0.0,
+        }
+    }
+
+    pub fn with_stacking(mut self, stacking: Stacking) -> Self {
+        self.stacking = stacking;
+        self
+    }
+
+    pub fn is_stun(&self) -> bool {
+        self.modifiers.stun
+    }
+}
+
+/// Result of counting down a set of effects
+#[derive(Debug, Default)]
+pub struct EffectTick {
+    /// Sources of effects that ran out
+    pub expired: Vec<EffectSource>,
+    /// Damage dealt by damage-over-time effects
+    pub damage: i32,
+}
+
+/// The effects running on one player or mob
+#[derive(Debug, Clone, Default)]
+pub struct StatusEffects {
+    effects: Vec<StatusEffect>,
+}
+
+impl StatusEffects {
+    pub fn new() -> Self {
+        Self { effects: Vec::new() }
+    }
+
+    /// Start an effect, following its stacking rule if the same source's effect is running
+    pub fn apply(&mut self, effect: StatusEffect) {
+        let Some(running) = self.effects.iter_mut().find(|e| e.source == effect.source) else {
+            self.effects.push(effect);
+            return;
+        };
+        match effect.stacking {
+            Stacking::Replace => *running = effect,
+            Stacking::Refresh => {
+                running.duration = effect.duration;
+                running.remaining = effect.duration;
+            }
+            Stacking::Stack(max) => {
+                running.stacks = (running.stacks + 1).min(max.max(1));
+                running.level = effect.level;
+                running.modifiers = effect.modifiers;
+                running.duration = effect.duration;
+                running.remaining = effect.duration;
+            }
+        }
+    }
+
+    /// Count down every effect and tick damage-over-time
+    pub fn update(&mut self, dt: f32) -> EffectTick {
+        let mut tick = EffectTick::default();
+        for effect in &mut self.effects {
+            effect.remaining -= dt;
+            if effect.modifiers.damage_per_tick > 0 {
+                effect.tick_timer += dt;
+                while effect.tick_timer >= TICK_INTERVAL {
+                    effect.tick_timer -= TICK_INTERVAL;
+                    tick.damage += effect.modifiers.damage_per_tick * effect.stacks as i32;
+                }
+            }
+            if effect.remaining <= 0.0 {
+                tick.expired.push(effect.source);
+            }
+        }
+        self.effects.retain(|effect| effect.remaining > 0.0);
+        tick
+    }
+
+    pub fn clear(&mut self) {
+        self.effects.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StatusEffect> {
+        self.effects.iter()
+    }
+
+    /// Combined stat bonuses of every effect
+    pub fn bonus(&self) -> EquipStats {
+        let mut total = EquipStats::default();
+        for effect in &self.effects {
+            for _ in 0..effect.stacks {
+                total.add_bonuses(&effect.modifiers.bonus);
+            }
+        }
+        total
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        self.effects.iter().any(StatusEffect::is_stun)
+    }
+
+    /// Factor to scale movement speed by (1.0 when nothing slows, 0.0 when fully stopped)
+    pub fn speed_multiplier(&self) -> f32 {
+        let slow: i32 = self.effects.iter().map(|e| e.modifiers.slow * e.stacks as i32).sum();
+        1.0 - slow.clamp(0, 100) as f32 / 100.0
+    }
+
+    pub fn is_poisoned(&self) -> bool {
+        self.effects.iter().any(|e| e.modifiers.damage_per_tick > 0)
+    }
+}
@@ -0,0 +1,341 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::config::config;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+#[cfg(target_arch = "wasm32")]
+extern "C" {
+    fn idb_save(name_ptr: *const u8, name_len: u32, data_ptr: *const u8, data_len: u32);
+    fn idb_delete(name_ptr: *const u8, name_len: u32);
+}
+
+/// A key/value store for save data (characters, settings, ...).
+///
+/// Keys are short identifiers such as `character_list` or `character_TestHero`;
+/// values are serialized JSON documents.
+pub trait StorageBackend: Send + Sync {
+    /// Short human readable description used in logs
+    fn describe(&self) -> String;
+
+    /// Read the value stored under `key`, or None if there is none
+    fn load(&self, key: &str) -> Result<Option<Vec<u8>>, String>;
+
+    /// Create or replace the value stored under `key`
+    fn store(&self, key: &str, data: &[u8]) -> Result<(), String>;
+
+    /// Delete the value stored under `key` (no error if it does not exist)
+    fn remove(&self, key: &str) -> Result<(), String>;
+}
+
+static STORAGE: RwLock<Option<Arc<dyn StorageBackend>>> = RwLock::new(None);
+
+/// Install the storage backend used for the rest of the session
+pub fn set_storage(backend: Arc<dyn StorageBackend>) {
+    info!("Save storage: {}", backend.describe());
+    *STORAGE.write().unwrap() = Some(backend);
+}
+
+/// Get the active storage backend.
+/// Falls back to an in-memory store if `init` has not run yet.
+pub fn storage() -> Arc<dyn StorageBackend> {
+    if let Some(backend) = STORAGE.read().unwrap().as_ref() {
+        return backend.clone();
+    }
+
+    warn!("Storage used before init, falling back to memory storage");
+    let backend: Arc<dyn StorageBackend> = Arc::new(MemoryStorage::new());
+    set_storage(backend.clone());
+    backend
+}
+
+/// Pick and open the storage backend from the game config:
+/// memory if `use_memory_storage` is set, otherwise files on native and IndexedDB on web
+pub async fn init() {
+    let backend: Arc<dyn StorageBackend> = if config().use_memory_storage {
+        Arc::new(MemoryStorage::new())
+    } else {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            Arc::new(FileStorage::new(&config().save_dir))
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            Arc::new(IndexedDbStorage::open().await)
+        }
+    };
+    set_storage(backend);
+}
+
+/// Envelope every saved document is wrapped in, so old saves can be migrated
+#[derive(Serialize, Deserialize)]
+struct Versioned<T> {
+    version: u32,
+    data: T,
+}
+
+/// Serialize `value` wrapped with its schema version
+pub fn encode_versioned<T: Serialize>(version: u32, value: &T) -> Result<Vec<u8>, String> {
+    serde_json::to_vec(&Versioned { version, data: value })
+        .map_err(|e| format!("Failed to serialize save data: {}", e))
+}
+
+/// Parse a saved document, returning its schema version and raw JSON data.
+/// Documents written before versioning was added are reported as version 0.
+pub fn decode_versioned(bytes: &[u8]) -> Result<(u32, Value), String> {
+    let value: Value = serde_json::from_slice(bytes).map_err(|e| format!("Corrupt save data: {}", e))?;
+
+    let is_envelope = value.get("version").map_or(false, Value::is_u64) && value.get("data").is_some();
+    if !is_envelope {
+        return Ok((0, value));
+    }
+
+    let envelope: Versioned<Value> =
+        serde_json::from_value(value).map_err(|e| format!("Corrupt save data: {}", e))?;
+    Ok((envelope.version, envelope.data))
+}
+
+/// Keeps everything in memory; nothing survives a restart (for testing)
+pub struct MemoryStorage {
+    entries: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl StorageBackend for MemoryStorage {
+    fn describe(&self) -> String {
+        "memory".to_string()
+    }
+
+    fn load(&self, key: &str) -> Result<Option<Vec<u8>>, String> {
+        Ok(self.entries.lock().unwrap().get(key).cloned())
+    }
+
+    fn store(&self, key: &str, data: &[u8]) -> Result<(), String> {
+        self.entries.lock().unwrap().insert(key.to_string(), data.to_vec());
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> Result<(), String> {
+        self.entries.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+/// Stores each key as `<dir>/<key>.json` on disk
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileStorage {
+    dir: PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileStorage {
+    pub fn new(dir: &str) -> Self {
+        Self { dir: PathBuf::from(dir) }
+    }
+
+    fn path_for(&self, key: &str) -> PathBuf {
+        // Keys contain character names; keep them to safe file name characters
+        let file_name: String = key
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+            .collect();
+        self.dir.join(format!("{}.json", file_name))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl StorageBackend for FileStorage {
+    fn describe(&self) -> String {
+        format!("files ({})", self.dir.display())
+    }
+
+    fn load(&self, key: &str) -> Result<Option<Vec<u8>>, String> {
+        let path = self.path_for(key);
+        match std::fs::read(&path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    fn store(&self, key: &str, data: &[u8]) -> Result<(), String> {
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create {}: {}", self.dir.display(), e))?;
+
+        // Write to a temporary file first so a crash never leaves a half-written save
+        let path = self.path_for(key);
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, data).map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
+        std::fs::rename(&tmp_path, &path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    fn remove(&self, key: &str) -> Result<(), String> {
+        let path = self.path_for(key);
+        match std::fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!("Failed to delete {}: {}", path.display(), e)),
+        }
+    }
+}
+
+/// Stores saves in the browser's IndexedDB (the same database as the asset cache).
+///
+/// IndexedDB is asynchronous, so every save is read into memory when the store is
+/// opened and writes are sent to IndexedDB in the background. An index of all keys
+/// is kept under `save/__index` so the store can be reloaded.
+#[cfg(target_arch = "wasm32")]
+pub struct IndexedDbStorage {
+    entries: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl IndexedDbStorage {
+    const PREFIX: &'static str = "save/";
+    const INDEX_KEY: &'static str = "__index";
+
+    pub async fn open() -> Self {
+        let mut entries = HashMap::new();
+
+        let keys: Vec<String> = match load_file(&Self::idb_url(Self::INDEX_KEY)).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
+            Err(_) => Vec::new(),
+        };
+
+        for key in keys {
+            match load_file(&Self::idb_url(&key)).await {
+                Ok(bytes) => {
+                    entries.insert(key, bytes);
+                }
+                Err(e) => warn!("Failed to load save '{}' from IndexedDB: {:?}", key, e),
+            }
+        }
+
+        info!("Loaded {} saved entries from IndexedDB", entries.len());
+        Self {
+            entries: Mutex::new(entries),
+        }
+    }
+
+    fn idb_url(key: &str) -> String {
+        format!("idb://{}{}", Self::PREFIX, key)
+    }
+
+    fn idb_put(key: &str, data: &[u8]) {
+        let name = format!("{}{}", Self::PREFIX, key);
+        unsafe {
+            idb_save(name.as_ptr(), name.len() as u32, data.as_ptr(), data.len() as u32);
+        }
+    }
+
+    fn save_index(entries: &HashMap<String, Vec<u8>>) {
+        let keys: Vec<&String> = entries.keys().collect();
+        if let Ok(json) = serde_json::to_vec(&keys) {
+            Self::idb_put(Self::INDEX_KEY, &json);
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl StorageBackend for IndexedDbStorage {
+    fn describe(&self) -> String {
+        "IndexedDB".to_string()
+    }
+
+    fn load(&self, key: &str) -> Result<Option<Vec<u8>>, String> {
+        Ok(self.entries.lock().unwrap().get(key).cloned())
+    }
+
+    fn store(&self, key: &str, data: &[u8]) -> Result<(), String> {
+        let mut entries = self.entries.lock().unwrap();
+        let is_new = entries.insert(key.to_string(), data.to_vec()).is_none();
+        Self::idb_put(key, data);
+        if is_new {
+            Self::save_index(&entries);
+        }
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> Result<(), String> {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.remove(key).is_some() {
+            let name = format!("{}{}", Self::PREFIX, key);
+            unsafe {
+                idb_delete(name.as_ptr(), name.len() as u32);
+            }
+            Self::save_index(&entries);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn decode_unversioned_document_as_version_0() {
+        let bytes = serde_json::to_vec(&json!({ "name": "Hero", "level": 10 })).unwrap();
+        let (version, data) = decode_versioned(&bytes).unwrap();
+        assert_eq!(version, 0);
+        assert_eq!(data, json!({ "name": "Hero", "level": 10 }));
+    }
+
+    #[test]
+    fn decode_document_with_version_field_but_no_data_as_version_0() {
+        let bytes = serde_json::to_vec(&json!({ "version": 2, "name": "Hero" })).unwrap();
+        let (version, data) = decode_versioned(&bytes).unwrap();
+        assert_eq!(version, 0);
+        assert_eq!(data, json!({ "version": 2, "name": "Hero" }));
+    }
+
+    #[test]
+    fn versioned_envelope_round_trips() {
+        let bytes = encode_versioned(3, &json!({ "slots": 8 })).unwrap();
+        let (version, data) = decode_versioned(&bytes).unwrap();
+        assert_eq!(version, 3);
+        assert_eq!(data, json!({ "slots": 8 }));
+    }
+
+    #[test]
+    fn decode_rejects_corrupt_data() {
+        assert!(decode_versioned(b"{ not json").is_err());
+    }
+
+    #[test]
+    fn memory_storage_stores_and_removes() {
+        let storage = MemoryStorage::new();
+        assert_eq!(storage.load("character_Hero").unwrap(), None);
+        storage.store("character_Hero", b"{}").unwrap();
+        assert_eq!(storage.load("character_Hero").unwrap(), Some(b"{}".to_vec()));
+        storage.remove("character_Hero").unwrap();
+        assert_eq!(storage.load("character_Hero").unwrap(), None);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn path_for_keeps_safe_keys() {
+        let storage = FileStorage::new("saves");
+        assert_eq!(storage.path_for("character_Hero-2"), PathBuf::from("saves").join("character_Hero-2.json"));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn path_for_stays_inside_save_dir() {
+        let storage = FileStorage::new("saves");
+        assert_eq!(storage.path_for("../character/x.y"), PathBuf::from("saves").join("___character_x_y.json"));
+    }
+}
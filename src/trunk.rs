@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::inventory::ItemSlot;
+use crate::storage::{decode_versioned, encode_versioned, storage};
+
+/// Storage key of the trunk, shared by every character
+const TRUNK_KEY: &str = "account_trunk";
+
+/// Schema version written with every trunk save
+const TRUNK_SCHEMA_VERSION: u32 = 1;
+
+/// Slots a new trunk has, how many one expansion adds and the most it can have
+pub const STARTING_SLOTS: usize = 4;
+pub const EXPANSION_SLOTS: usize = 4;
+pub const MAX_SLOTS: usize = 48;
+
+/// Meso charged by the storage keeper
+pub const DEPOSIT_FEE: u32 = 100;
+pub const WITHDRAW_FEE: u32 = 100;
+pub const EXPANSION_COST: u32 = 100_000;
+
+/// Items left with storage keepers (UIWindow2.img `Trunk`). Every character of the account
+/// sees the same trunk; each stored stack takes one slot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Trunk {
+    pub slots: usize,
+    items: Vec<ItemSlot>,
+}
+
+impl Default for Trunk {
+    fn default() -> Self {
+        Self {
+            slots: STARTING_SLOTS,
+            items: Vec::new(),
+        }
+    }
+}
+
+impl Trunk {
+    /// Load the trunk from storage (an empty one if nothing was saved yet)
+    pub fn load() -> Result<Self, String> {
+        let Some(bytes) = storage().load(TRUNK_KEY)? else {
+            return Ok(Trunk::default());
+        };
+        let (version, data) = decode_versioned(&bytes)?;
+        let data = migrate_trunk(version, data)?;
+        serde_json::from_value::<Trunk>(data).map_err(|e| format!("Invalid trunk data: {}", e))
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let bytes = encode_versioned(TRUNK_SCHEMA_VERSION, self)
+            .map_err(|e| format!("Failed to serialize trunk: {}", e))?;
+        storage().store(TRUNK_KEY, &bytes)
+    }
+
+    /// Stored stacks, in the order they were put in
+    pub fn items(&self) -> &[ItemSlot] {
+        &self.items
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.items.len() >= self.slots
+    }
+
+    /// Store a stack in a free slot
+    pub fn deposit(&mut self, item: ItemSlot) -> Result<(), String> {
+        if self.is_full() {
+            return Err("Your storage is full.".to_string());
+        }
+        self.items.push(item);
+        Ok(())
+    }
+
+    /// Take a stored stack out
+    pub fn withdraw(&mut self, index: usize) -> Option<ItemSlot> {
+        (index < self.items.len()).then(|| self.items.remove(index))
+    }
+
+    /// Add `EXPANSION_SLOTS` slots, up to `MAX_SLOTS`
+    pub fn expand(&mut self) -> Result<(), String> {
+        if self.slots >= MAX_SLOTS {
+            return Err("Your storage cannot be expanded any further.".to_string());
+        }
+        self.slots = (self.slots + EXPANSION_SLOTS).min(MAX_SLOTS);
+        Ok(())
+    }
+}
+
+/// Upgrade a saved trunk from `version` to `TRUNK_SCHEMA_VERSION`
+fn migrate_trunk(version: u32, data: Value) -> Result<Value, String> {
+    if version > TRUNK_SCHEMA_VERSION {
+        return Err(format!(
+            "Trunk was written by a newer version (schema {}, this build supports {})",
+            version, TRUNK_SCHEMA_VERSION
+        ));
+    }
+    Ok(data)
+}
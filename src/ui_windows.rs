@@ -1,12 +1,18 @@
 use macroquad::prelude::*;
-use crate::assets::AssetManager;
-use std::sync::Arc;
+use crate::wz_cache::load_wz_image;
+use crate::inventory::{Inventory, InventoryTab};
+use crate::equipment::{EquipSlot, Equipment};
+use crate::item_info;
+use crate::character::{CharacterData, DerivedStats};
+use crate::job::Job;
+use crate::leveling::ApStat;
+use crate::skill_info;
+use crate::quest_info;
+use crate::game_world::skills::{SKILL_HOTKEYS, SKILL_HOTKEY_LABELS};
 use std::collections::HashMap;
-use wz_reader::version::guess_iv_from_wz_img;
-use wz_reader::{WzImage, WzNode, WzNodeArc, WzReader, WzNodeCast};
+use wz_reader::{WzNodeArc, WzNodeCast};
 
-const UIWINDOW2_URL: &str = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/UI/UIWindow2.img";
-const UIWINDOW2_CACHE: &str = "/01/UI/UIWindow2.img";
+const UIWINDOW2_PATH: &str = "UI/UIWindow2.img";
 
 #[derive(Clone)]
 struct TextureWithOrigin {
@@ -14,11 +20,22 @@ struct TextureWithOrigin {
     origin: Vec2,
 }
 
-/// Item data for inventory display
-#[derive(Clone)]
-struct ItemIcon {
-    texture: Texture2D,
-    item_id: String,
+// Inventory slot grid layout
+const INVENTORY_COLS: usize = 4;
+const INVENTORY_ROWS: usize = 6;
+const INVENTORY_SLOT_SIZE: f32 = 32.0;
+const INVENTORY_SLOT_GAP: f32 = 4.0;
+
+/// Seconds between two clicks on the same slot to count as a double-click
+const DOUBLE_CLICK_TIME: f64 = 0.5;
+
+/// Something the player did in the inventory window that gameplay has to act on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InventoryEvent {
+    /// Items were rearranged by drag-and-drop
+    Moved,
+    /// An item was double-clicked (equip, use, ...)
+    DoubleClicked(InventoryTab, usize),
 }
 
 #[derive(Clone)]
@@ -37,9 +54,13 @@ pub struct InventoryWindow {
     dragging: bool,
     drag_offset_x: f32,
     drag_offset_y: f32,
-    // Item icons
-    items: Vec<ItemIcon>,
-    items_loaded: bool,
+    // Item icons by item id
+    icons: HashMap<i32, Texture2D>,
+    // Slot whose item is being dragged with the mouse
+    drag_slot: Option<usize>,
+    // Double-click tracking
+    last_click_slot: Option<usize>,
+    last_click_time: f64,
 }
 
 impl InventoryWindow {
@@ -58,8 +79,10 @@ impl InventoryWindow {
             dragging: false,
             drag_offset_x: 0.0,
             drag_offset_y: 0.0,
-            items: Vec::new(),
-            items_loaded: false,
+            icons: HashMap::new(),
+            drag_slot: None,
+            last_click_slot: None,
+            last_click_time: 0.0,
         }
     }
 
@@ -79,73 +102,29 @@ impl InventoryWindow {
                 self.loaded = false;
             }
         }
-        
-        // Load some item icons
-        self.load_items().await;
     }
 
-    async fn load_items(&mut self) {
-        // Try to load items from 0501.img
-        let url = "https://scribbles-public.s3.us-east-1.amazonaws.com/tutorial/01/Item/Cash/0501.img";
-        let cache = "/01/Item/Cash/0501.img";
-        
-        if let Ok(bytes) = AssetManager::fetch_and_cache(url, cache).await {
-            if let Some(wz_iv) = guess_iv_from_wz_img(&bytes) {
-                let byte_len = bytes.len();
-                let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-                let cache_name_ref: wz_reader::WzNodeName = cache.to_string().into();
-                let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-                let root_node: WzNodeArc = WzNode::new(&cache.to_string().into(), wz_image, None).into();
-
-                if root_node.write().unwrap().parse(&root_node).is_ok() {
-                    // Get first few item IDs
-                    let item_ids: Vec<String> = {
-                        let root_read = root_node.read().unwrap();
-                        root_read.children.keys()
-                            .filter(|k| k.as_str().starts_with("0501"))
-                            .take(8)
-                            .map(|k| k.to_string())
-                            .collect()
-                    };
-
-                    for item_id in item_ids {
-                        let icon_path = format!("{}/info/icon", item_id);
-                        if let Some(tex) = Self::load_item_icon(&root_node, &icon_path).await {
-                            self.items.push(ItemIcon { texture: tex, item_id });
-                        }
-                    }
-                    self.items_loaded = true;
-                    info!("Loaded {} item icons", self.items.len());
+    /// Load icons for any items in the inventory that don't have one yet
+    pub async fn load_icons(&mut self, inventory: &Inventory) {
+        let missing: Vec<i32> = inventory
+            .item_ids()
+            .into_iter()
+            .filter(|id| !self.icons.contains_key(id))
+            .collect();
+
+        for item_id in missing {
+            match item_info::load_item_icon(item_id).await {
+                Some(texture) => {
+                    self.icons.insert(item_id, texture);
                 }
+                None => warn!("No icon for item {}", item_id),
             }
         }
     }
 
-    async fn load_item_icon(root: &WzNodeArc, path: &str) -> Option<Texture2D> {
-        let node = root.read().unwrap().at_path(path)?.clone();
-        node.write().unwrap().parse(&node).ok()?;
-        let node_read = node.read().unwrap();
-        let png = node_read.try_as_png()?;
-        let img = png.extract_png().ok()?;
-        let rgba = img.to_rgba8();
-        Some(Texture2D::from_rgba8(rgba.width() as u16, rgba.height() as u16, &rgba.into_raw()))
-    }
-
     async fn load_from_wz() -> Result<InventoryWindowData, String> {
-        let bytes = AssetManager::fetch_and_cache(UIWINDOW2_URL, UIWINDOW2_CACHE).await
-            .map_err(|e| format!("Failed to fetch UIWindow2.img: {}", e))?;
-
-        let wz_iv = guess_iv_from_wz_img(&bytes)
-            .ok_or_else(|| "Unable to guess WZ version from UIWindow2.img".to_string())?;
-
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        let cache_name_ref: wz_reader::WzNodeName = UIWINDOW2_CACHE.to_string().into();
-        let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-        let root_node: WzNodeArc = WzNode::new(&UIWINDOW2_CACHE.to_string().into(), wz_image, None).into();
-
-        root_node.write().unwrap().parse(&root_node)
-            .map_err(|e| format!("Failed to parse UIWindow2.img: {:?}", e))?;
+        let root_node = load_wz_image(UIWINDOW2_PATH).await
+            .map_err(|e| format!("Failed to load UIWindow2.img: {}", e))?;
 
         let mut data = InventoryWindowData::default();
 
@@ -198,12 +177,65 @@ impl InventoryWindow {
         Ok(TextureWithOrigin { texture, origin })
     }
 
-    pub fn update(&mut self) {
+    /// Top-left corner of a slot in the item grid
+    fn slot_position(&self, slot: usize) -> (f32, f32) {
+        let col = slot % INVENTORY_COLS;
+        let row = slot / INVENTORY_COLS;
+        (
+            self.x + 12.0 + col as f32 * (INVENTORY_SLOT_SIZE + INVENTORY_SLOT_GAP),
+            self.y + 52.0 + row as f32 * (INVENTORY_SLOT_SIZE + INVENTORY_SLOT_GAP),
+        )
+    }
+
+    /// Slot under the given screen position, if any
+    fn slot_at(&self, mouse_x: f32, mouse_y: f32) -> Option<usize> {
+        (0..INVENTORY_COLS * INVENTORY_ROWS).find(|&slot| {
+            let (sx, sy) = self.slot_position(slot);
+            mouse_x >= sx && mouse_x < sx + INVENTORY_SLOT_SIZE && mouse_y >= sy && mouse_y < sy + INVENTORY_SLOT_SIZE
+        })
+    }
+
+    fn current_tab(&self) -> InventoryTab {
+        InventoryTab::from_index(self.selected_tab).unwrap_or(InventoryTab::Equip)
+    }
+
+    /// Handle tab clicks, window dragging and item drag-and-drop
+    pub fn update(&mut self, inventory: &mut Inventory) -> Option<InventoryEvent> {
         if !self.visible || !self.loaded {
-            return;
+            self.drag_slot = None;
+            return None;
         }
 
         let (mouse_x, mouse_y) = mouse_position();
+        let mut event = None;
+
+        // Item drag-and-drop: pick up on press, drop on release
+        if is_mouse_button_pressed(MouseButton::Left) {
+            if let Some(slot) = self.slot_at(mouse_x, mouse_y) {
+                if inventory.get(self.current_tab(), slot).is_some() {
+                    let now = get_time();
+                    if self.last_click_slot == Some(slot) && now - self.last_click_time < DOUBLE_CLICK_TIME {
+                        self.last_click_slot = None;
+                        return Some(InventoryEvent::DoubleClicked(self.current_tab(), slot));
+                    }
+                    self.last_click_slot = Some(slot);
+                    self.last_click_time = now;
+                    self.drag_slot = Some(slot);
+                }
+            }
+        }
+        if is_mouse_button_released(MouseButton::Left) {
+            if let Some(from) = self.drag_slot.take() {
+                if let Some(to) = self.slot_at(mouse_x, mouse_y) {
+                    if from != to {
+                        match inventory.move_item(self.current_tab(), from, to) {
+                            Ok(()) => event = Some(InventoryEvent::Moved),
+                            Err(e) => warn!("Failed to move item: {}", e),
+                        }
+                    }
+                }
+            }
+        }
 
         // Handle tab clicks - simple fixed positions
         if is_mouse_button_pressed(MouseButton::Left) {
@@ -220,6 +252,7 @@ impl InventoryWindow {
                     let tab_x = tab_start_x + (i as f32 * tab_width);
                     if mouse_x >= tab_x && mouse_x <= tab_x + tab_width {
                         self.selected_tab = i;
+                        self.drag_slot = None;
                         info!("Selected inventory tab {}", i);
                         break;
                     }
@@ -243,10 +276,13 @@ impl InventoryWindow {
         } else {
             self.dragging = false;
         }
+
+        event
     }
 
     pub fn toggle(&mut self) {
         self.visible = !self.visible;
+        self.drag_slot = None;
         if self.visible {
             // Center window when shown
             self.x = (screen_width() - 500.0) / 2.0;
@@ -254,7 +290,7 @@ impl InventoryWindow {
         }
     }
 
-    pub fn draw(&self) {
+    pub fn draw(&self, inventory: &Inventory, meso: u32) {
         if !self.visible || !self.loaded {
             return;
         }
@@ -282,28 +318,57 @@ impl InventoryWindow {
             }
         }
 
-        // Draw item icons in a grid (4 columns, starting after some padding)
-        let slot_size = 32.0;
-        let slot_gap = 4.0;
-        let start_x = self.x + 12.0;
-        let start_y = self.y + 52.0;
-        let cols = 4;
+        // Draw items of the selected tab in the slot grid
+        let tab = self.current_tab();
+        for (slot, item) in inventory.slots(tab).iter().enumerate().take(INVENTORY_COLS * INVENTORY_ROWS) {
+            let Some(item) = item else { continue };
+            // The dragged item is drawn at the cursor instead
+            if self.drag_slot == Some(slot) {
+                continue;
+            }
+            let (ix, iy) = self.slot_position(slot);
+            self.draw_item(item.item_id, item.quantity, ix, iy);
+        }
 
-        for (i, item) in self.items.iter().enumerate() {
-            let col = i % cols;
-            let row = i / cols;
-            let ix = start_x + col as f32 * (slot_size + slot_gap);
-            let iy = start_y + row as f32 * (slot_size + slot_gap);
-            draw_texture(&item.texture, ix, iy, WHITE);
+        if let Some(item) = self.drag_slot.and_then(|slot| inventory.get(tab, slot)) {
+            let (mouse_x, mouse_y) = mouse_position();
+            self.draw_item(item.item_id, item.quantity, mouse_x - 16.0, mouse_y - 16.0);
         }
 
         // Draw currency at bottom of window
         let currency_y = self.y + 200.0;
         draw_text("Mesos:", self.x + 10.0, currency_y, 12.0, WHITE);
-        draw_text("1,234,567", self.x + 60.0, currency_y, 12.0, YELLOW);
+        draw_text(&format_meso(meso), self.x + 60.0, currency_y, 12.0, YELLOW);
+    }
+
+    /// Draw an item icon with its stack size in the bottom-left corner
+    fn draw_item(&self, item_id: i32, quantity: u32, x: f32, y: f32) {
+        match self.icons.get(&item_id) {
+            Some(texture) => draw_texture(texture, x, y, WHITE),
+            None => draw_rectangle(x + 4.0, y + 4.0, 24.0, 24.0, Color::from_rgba(120, 120, 120, 180)),
+        }
+
+        if !item_info::is_equip(item_id) {
+            let text = quantity.to_string();
+            draw_text(&text, x + 1.0, y + INVENTORY_SLOT_SIZE, 12.0, BLACK);
+            draw_text(&text, x, y + INVENTORY_SLOT_SIZE - 1.0, 12.0, WHITE);
+        }
     }
 }
 
+/// Meso amount with thousands separators ("1,234,567")
+pub fn format_meso(meso: u32) -> String {
+    let digits = meso.to_string();
+    let mut text = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            text.push(',');
+        }
+        text.push(c);
+    }
+    text
+}
+
 #[derive(Clone)]
 pub struct EquipWindow {
     pub visible: bool,
@@ -318,6 +383,30 @@ pub struct EquipWindow {
     dragging: bool,
     drag_offset_x: f32,
     drag_offset_y: f32,
+    // Item icons by item id
+    icons: HashMap<i32, Texture2D>,
+    // Double-click tracking
+    last_click_slot: Option<EquipSlot>,
+    last_click_time: f64,
+}
+
+/// Top-left of each equip slot icon, relative to the window
+fn equip_slot_position(slot: EquipSlot) -> (f32, f32) {
+    match slot {
+        EquipSlot::Cap => (43.0, 25.0),
+        EquipSlot::FaceAccessory => (43.0, 58.0),
+        EquipSlot::EyeAccessory => (43.0, 91.0),
+        EquipSlot::Earrings => (109.0, 91.0),
+        EquipSlot::Pendant => (76.0, 91.0),
+        EquipSlot::Cape => (10.0, 124.0),
+        EquipSlot::Top => (43.0, 124.0),
+        EquipSlot::Weapon => (109.0, 124.0),
+        EquipSlot::Shield => (142.0, 124.0),
+        EquipSlot::Gloves => (10.0, 157.0),
+        EquipSlot::Bottom => (43.0, 157.0),
+        EquipSlot::Ring => (109.0, 157.0),
+        EquipSlot::Shoes => (76.0, 190.0),
+    }
 }
 
 impl EquipWindow {
@@ -333,9 +422,36 @@ impl EquipWindow {
             dragging: false,
             drag_offset_x: 0.0,
             drag_offset_y: 0.0,
+            icons: HashMap::new(),
+            last_click_slot: None,
+            last_click_time: 0.0,
+        }
+    }
+
+    /// Load icons for any equipped items that don't have one yet
+    pub async fn load_icons(&mut self, equipment: &Equipment) {
+        for item_id in equipment.item_ids() {
+            if self.icons.contains_key(&item_id) {
+                continue;
+            }
+            match item_info::load_item_icon(item_id).await {
+                Some(texture) => {
+                    self.icons.insert(item_id, texture);
+                }
+                None => warn!("No icon for item {}", item_id),
+            }
         }
     }
 
+    /// Equip slot under the given screen position, if any
+    fn slot_at(&self, mouse_x: f32, mouse_y: f32) -> Option<EquipSlot> {
+        EquipSlot::ALL.into_iter().find(|&slot| {
+            let (sx, sy) = equip_slot_position(slot);
+            let (sx, sy) = (self.x + sx, self.y + sy);
+            mouse_x >= sx && mouse_x < sx + INVENTORY_SLOT_SIZE && mouse_y >= sy && mouse_y < sy + INVENTORY_SLOT_SIZE
+        })
+    }
+
     pub async fn load_assets(&mut self) {
         match Self::load_from_wz().await {
             Ok(data) => {
@@ -353,20 +469,8 @@ impl EquipWindow {
     }
 
     async fn load_from_wz() -> Result<EquipWindowData, String> {
-        let bytes = AssetManager::fetch_and_cache(UIWINDOW2_URL, UIWINDOW2_CACHE).await
-            .map_err(|e| format!("Failed to fetch UIWindow2.img: {}", e))?;
-
-        let wz_iv = guess_iv_from_wz_img(&bytes)
-            .ok_or_else(|| "Unable to guess WZ version from UIWindow2.img".to_string())?;
-
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        let cache_name_ref: wz_reader::WzNodeName = UIWINDOW2_CACHE.to_string().into();
-        let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-        let root_node: WzNodeArc = WzNode::new(&UIWINDOW2_CACHE.to_string().into(), wz_image, None).into();
-
-        root_node.write().unwrap().parse(&root_node)
-            .map_err(|e| format!("Failed to parse UIWindow2.img: {:?}", e))?;
+        let root_node = load_wz_image(UIWINDOW2_PATH).await
+            .map_err(|e| format!("Failed to load UIWindow2.img: {}", e))?;
 
         let mut data = EquipWindowData::default();
 
@@ -413,13 +517,26 @@ impl EquipWindow {
         Ok(TextureWithOrigin { texture, origin })
     }
 
-    pub fn update(&mut self) {
+    /// Handle window dragging. Returns the slot to take off when an equipped item is double-clicked.
+    pub fn update(&mut self, equipment: &Equipment) -> Option<EquipSlot> {
         if !self.visible || !self.loaded {
-            return;
+            return None;
         }
 
         let (mouse_x, mouse_y) = mouse_position();
 
+        if is_mouse_button_pressed(MouseButton::Left) {
+            if let Some(slot) = self.slot_at(mouse_x, mouse_y).filter(|&slot| equipment.get(slot).is_some()) {
+                let now = get_time();
+                if self.last_click_slot == Some(slot) && now - self.last_click_time < DOUBLE_CLICK_TIME {
+                    self.last_click_slot = None;
+                    return Some(slot);
+                }
+                self.last_click_slot = Some(slot);
+                self.last_click_time = now;
+            }
+        }
+
         // Handle dragging
         if is_mouse_button_pressed(MouseButton::Left) {
             // Check if clicking on title bar area (top 30 pixels)
@@ -437,6 +554,8 @@ impl EquipWindow {
         } else {
             self.dragging = false;
         }
+
+        None
     }
 
     pub fn toggle(&mut self) {
@@ -448,7 +567,7 @@ impl EquipWindow {
         }
     }
 
-    pub fn draw(&self) {
+    pub fn draw(&self, equipment: &Equipment) {
         if !self.visible || !self.loaded {
             return;
         }
@@ -463,6 +582,16 @@ impl EquipWindow {
         if let Some(bg3) = &self.backgrnd3 {
             draw_texture(&bg3.texture, self.x - bg3.origin.x, self.y - bg3.origin.y, WHITE);
         }
+
+        // Draw equipped items in their slots
+        for slot in EquipSlot::ALL {
+            let Some(item_id) = equipment.get(slot) else { continue };
+            let (sx, sy) = equip_slot_position(slot);
+            match self.icons.get(&item_id) {
+                Some(texture) => draw_texture(texture, self.x + sx, self.y + sy, WHITE),
+                None => draw_rectangle(self.x + sx + 4.0, self.y + sy + 4.0, 24.0, 24.0, Color::from_rgba(120, 120, 120, 180)),
+            }
+        }
     }
 }
 
@@ -501,20 +630,10 @@ impl UserInfoWindow {
     }
 
     pub async fn load_assets(&mut self) {
-        let bytes = match AssetManager::fetch_and_cache(UIWINDOW2_URL, UIWINDOW2_CACHE).await {
-            Ok(b) => b,
+        let root_node = match load_wz_image(UIWINDOW2_PATH).await {
+            Ok(node) => node,
             Err(_) => return,
         };
-        let wz_iv = match guess_iv_from_wz_img(&bytes) {
-            Some(iv) => iv,
-            None => return,
-        };
-        let byte_len = bytes.len();
-        let reader = Arc::new(WzReader::from_buff(&bytes).with_iv(wz_iv));
-        let cache_name_ref: wz_reader::WzNodeName = UIWINDOW2_CACHE.to_string().into();
-        let wz_image = WzImage::new(&cache_name_ref, 0, byte_len, &reader);
-        let root_node: WzNodeArc = WzNode::new(&UIWINDOW2_CACHE.to_string().into(), wz_image, None).into();
-        if root_node.write().unwrap().parse(&root_node).is_err() { return; }
 
         self.backgrnd = Self::load_texture(&root_node, "UserInfo/character/backgrnd").await.ok();
         self.backgrnd2 = Self::load_texture(&root_node, "UserInfo/character/backgrnd2").await.ok();
@@ -575,7 +694,7 @@ impl UserInfoWindow {
         self.y = (screen_height() - self.height) / 2.0;
     }
 
-    pub fn draw(&self, name: &str, level: u32) {
+    pub fn draw(&self, name: &str, level: u32, job: Job) {
         if !self.visible { return; }
         
         // Draw WZ backgrounds in z-order
@@ -599,8 +718,718 @@ impl UserInfoWindow {
         // Values positioned to right side of labels
         draw_text(name, self.x + 75.0, self.y + 35.0, 11.0, BLACK);
         draw_text(&format!("{}", level), self.x + 75.0, self.y + 52.0, 11.0, BLACK);
-        draw_text("Beginner", self.x + 75.0, self.y + 69.0, 11.0, BLACK);
+        draw_text(job.name(), self.x + 75.0, self.y + 69.0, 11.0, BLACK);
+    }
+}
+
+/// Height of one row in the stat window
+const STAT_ROW_HEIGHT: f32 = 16.0;
+/// Size of the AP up buttons
+const AP_BUTTON_SIZE: f32 = 12.0;
+
+/// Stat window (MenuAction::Stat): base stats, AP spending and derived stats
+pub struct StatWindow {
+    pub visible: bool,
+    loaded: bool,
+    backgrnd: Option<TextureWithOrigin>,
+    backgrnd2: Option<TextureWithOrigin>,
+    backgrnd3: Option<TextureWithOrigin>,
+    ap_up: Option<TextureWithOrigin>,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    dragging: bool,
+    drag_offset_x: f32,
+    drag_offset_y: f32,
+}
+
+impl StatWindow {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            loaded: false,
+            backgrnd: None,
+            backgrnd2: None,
+            backgrnd3: None,
+            ap_up: None,
+            x: 150.0,
+            y: 100.0,
+            width: 212.0,
+            height: 360.0,
+            dragging: false,
+            drag_offset_x: 0.0,
+            drag_offset_y: 0.0,
+        }
+    }
+
+    pub async fn load_assets(&mut self) {
+        let root_node = match load_wz_image(UIWINDOW2_PATH).await {
+            Ok(node) => node,
+            Err(_) => return,
+        };
+
+        self.backgrnd = UserInfoWindow::load_texture(&root_node, "Stat/main/backgrnd").await.ok();
+        self.backgrnd2 = UserInfoWindow::load_texture(&root_node, "Stat/main/backgrnd2").await.ok();
+        self.backgrnd3 = UserInfoWindow::load_texture(&root_node, "Stat/main/backgrnd3").await.ok();
+        self.ap_up = UserInfoWindow::load_texture(&root_node, "Stat/main/BtApUp/normal/0").await.ok();
+
+        if let Some(ref bg) = self.backgrnd {
+            self.width = bg.texture.width();
+            self.height = self.height.max(bg.texture.height());
+        }
+        self.loaded = self.backgrnd.is_some();
+        info!("Stat window loaded");
+    }
+
+    /// Screen rect of the AP up button next to a stat's row
+    fn ap_button_rect(&self, stat: ApStat) -> Rect {
+        let row = Self::ap_row(stat);
+        Rect::new(
+            self.x + self.width - AP_BUTTON_SIZE - 12.0,
+            self.y + 30.0 + row as f32 * STAT_ROW_HEIGHT - AP_BUTTON_SIZE + 2.0,
+            AP_BUTTON_SIZE,
+            AP_BUTTON_SIZE,
+        )
+    }
+
+    /// Row a stat is listed on
+    fn ap_row(stat: ApStat) -> usize {
+        match stat {
+            ApStat::Hp => 3,
+            ApStat::Mp => 4,
+            ApStat::Str => 7,
+            ApStat::Dex => 8,
+            ApStat::Int => 9,
+            ApStat::Luk => 10,
+        }
+    }
+
+    /// Handle dragging. Returns the stat to put a point into when an AP button is clicked.
+    pub fn update(&mut self, character: &CharacterData) -> Option<ApStat> {
+        if !self.visible { return None; }
+
+        let (mouse_x, mouse_y) = mouse_position();
+
+        if is_mouse_button_pressed(MouseButton::Left) {
+            if character.ap > 0 {
+                let mouse = vec2(mouse_x, mouse_y);
+                if let Some(stat) = ApStat::ALL.into_iter().find(|&stat| self.ap_button_rect(stat).contains(mouse)) {
+                    return Some(stat);
+                }
+            }
+            if mouse_y >= self.y && mouse_y <= self.y + 25.0 &&
+               mouse_x >= self.x && mouse_x <= self.x + self.width {
+                self.dragging = true;
+                self.drag_offset_x = mouse_x - self.x;
+                self.drag_offset_y = mouse_y - self.y;
+            }
+        }
+        if is_mouse_button_down(MouseButton::Left) && self.dragging {
+            self.x = mouse_x - self.drag_offset_x;
+            self.y = mouse_y - self.drag_offset_y;
+        } else {
+            self.dragging = false;
+        }
+
+        None
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        if self.visible {
+            self.x = (screen_width() - self.width) / 2.0;
+            self.y = (screen_height() - self.height) / 2.0;
+        }
+    }
+
+    pub fn draw(&self, character: &CharacterData, stats: &DerivedStats) {
+        if !self.visible { return; }
+
+        if self.loaded {
+            for bg in [&self.backgrnd, &self.backgrnd2, &self.backgrnd3].into_iter().flatten() {
+                draw_texture(&bg.texture, self.x - bg.origin.x, self.y - bg.origin.y, WHITE);
+            }
+        } else {
+            draw_rectangle(self.x, self.y, self.width, self.height, Color::from_rgba(40, 40, 60, 240));
+            draw_rectangle_lines(self.x, self.y, self.width, self.height, 2.0, Color::from_rgba(100, 100, 140, 255));
+            draw_text("STAT", self.x + 10.0, self.y + 18.0, 16.0, WHITE);
+        }
+        let text_color = if self.loaded { BLACK } else { WHITE };
+
+        let with_bonus = |base: u32, total: u32| {
+            if total == base { format!("{}", base) } else { format!("{} ({}+{})", total, base, total - base) }
+        };
+        let exp_needed = character.exp_to_next_level();
+        let exp = if exp_needed > 0 {
+            format!("{} ({:.2}%)", character.exp, character.exp as f64 * 100.0 / exp_needed as f64)
+        } else {
+            "-".to_string()
+        };
+
+        let rows: [(&str, String); 20] = [
+            ("NAME", character.name.clone()),
+            ("LEVEL", character.level.to_string()),
+            ("EXP", exp),
+            ("HP", format!("{} / {}", character.hp, stats.max_hp)),
+            ("MP", format!("{} / {}", character.mp, stats.max_mp)),
+            ("", String::new()),
+            ("AP", character.ap.to_string()),
+            ("STR", with_bonus(character.str, stats.str)),
+            ("DEX", with_bonus(character.dex, stats.dex)),
+            ("INT", with_bonus(character.int, stats.int)),
+            ("LUK", with_bonus(character.luk, stats.luk)),
+            ("", String::new()),
+            ("ATT", format!("{} ~ {}", stats.damage.0, stats.damage.1)),
+            ("W.DEF", stats.defense.to_string()),
+            ("M.ATT", stats.magic_attack.to_string()),
+            ("M.DEF", stats.magic_defense.to_string()),
+            ("ACC", stats.accuracy.to_string()),
+            ("AVOID", stats.avoid.to_string()),
+            ("SPEED", format!("{}%", stats.speed)),
+            ("JUMP", format!("{}%", stats.jump)),
+        ];
+        for (row, (label, value)) in rows.iter().enumerate() {
+            let y = self.y + 30.0 + row as f32 * STAT_ROW_HEIGHT;
+            draw_text(label, self.x + 12.0, y, 12.0, text_color);
+            draw_text(value, self.x + 70.0, y, 12.0, text_color);
+        }
+
+        if character.ap > 0 {
+            for stat in ApStat::ALL {
+                let rect = self.ap_button_rect(stat);
+                match &self.ap_up {
+                    Some(button) => draw_texture(&button.texture, rect.x - button.origin.x, rect.y - button.origin.y, WHITE),
+                    None => {
+                        draw_rectangle(rect.x, rect.y, rect.w, rect.h, Color::from_rgba(220, 180, 60, 255));
+                        draw_text("+", rect.x + 3.0, rect.y + rect.h - 2.0, 14.0, BLACK);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Height of one row in the skill window
+const SKILL_ROW_HEIGHT: f32 = 40.0;
+/// Skill rows shown at once (the rest scroll with the mouse wheel)
+const SKILL_ROWS: usize = 4;
+/// Size of the SP up buttons
+const SP_BUTTON_SIZE: f32 = 12.0;
+
+/// Something the player did in the skill window that gameplay has to act on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SkillEvent {
+    /// The SP up button of a skill was clicked
+    LevelUp(i32),
+    /// A skill was double-clicked
+    Use(i32),
+    /// A skill key was pressed while hovering a skill (skill id, index into `SKILL_HOTKEYS`)
+    Bind(i32, usize),
+}
+
+/// Skill window (MenuAction::Skill): one tab per skill book of the character's job
+pub struct SkillWindow {
+    pub visible: bool,
+    loaded: bool,
+    backgrnd: Option<TextureWithOrigin>,
+    backgrnd2: Option<TextureWithOrigin>,
+    backgrnd3: Option<TextureWithOrigin>,
+    sp_up: Option<TextureWithOrigin>,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    dragging: bool,
+    drag_offset_x: f32,
+    drag_offset_y: f32,
+    // Skill ids of each book, in tab order
+    books: Vec<(usize, Vec<i32>)>,
+    // Job the books were loaded for
+    books_job: Option<usize>,
+    selected_book: usize,
+    scroll: usize,
+    // Skill icons by skill id
+    icons: HashMap<i32, Texture2D>,
+    // Double-click tracking
+    last_click_skill: Option<i32>,
+    last_click_time: f64,
+}
+
+impl SkillWindow {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            loaded: false,
+            backgrnd: None,
+            backgrnd2: None,
+            backgrnd3: None,
+            sp_up: None,
+            x: 150.0,
+            y: 100.0,
+            width: 200.0,
+            height: 250.0,
+            dragging: false,
+            drag_offset_x: 0.0,
+            drag_offset_y: 0.0,
+            books: Vec::new(),
+            books_job: None,
+            selected_book: 0,
+            scroll: 0,
+            icons: HashMap::new(),
+            last_click_skill: None,
+            last_click_time: 0.0,
+        }
+    }
+
+    pub async fn load_assets(&mut self) {
+        let root_node = match load_wz_image(UIWINDOW2_PATH).await {
+            Ok(node) => node,
+            Err(_) => return,
+        };
+
+        self.backgrnd = UserInfoWindow::load_texture(&root_node, "Skill/main/backgrnd").await.ok();
+        self.backgrnd2 = UserInfoWindow::load_texture(&root_node, "Skill/main/backgrnd2").await.ok();
+        self.backgrnd3 = UserInfoWindow::load_texture(&root_node, "Skill/main/backgrnd3").await.ok();
+        self.sp_up = UserInfoWindow::load_texture(&root_node, "Skill/main/BtSpUp/normal/0").await.ok();
+
+        if let Some(ref bg) = self.backgrnd {
+            self.width = bg.texture.width();
+            self.height = self.height.max(bg.texture.height());
+        }
+        self.loaded = self.backgrnd.is_some();
+        info!("Skill window loaded");
+    }
+
+    /// True when the skill books shown don't belong to `job` yet
+    pub fn needs_books(&self, job: usize) -> bool {
+        self.books_job != Some(job)
+    }
+
+    /// Load the skill books of a job and their icons
+    pub async fn load_books(&mut self, job: usize) {
+        self.books.clear();
+        for book in skill_info::skill_books(job) {
+            let ids = skill_info::load_skill_book(book).await;
+            for &skill_id in &ids {
+                if self.icons.contains_key(&skill_id) {
+                    continue;
+                }
+                match skill_info::load_skill_icon(skill_id).await {
+                    Some(texture) => {
+                        self.icons.insert(skill_id, texture);
+                    }
+                    None => warn!("No icon for skill {}", skill_id),
+                }
+            }
+            self.books.push((book, ids));
+        }
+        self.books_job = Some(job);
+        self.selected_book = self.books.len().saturating_sub(1);
+        self.scroll = 0;
+    }
+
+    fn current_skills(&self) -> &[i32] {
+        self.books.get(self.selected_book).map_or(&[][..], |(_, ids)| ids.as_slice())
+    }
+
+    /// Tab label of a skill book: "Beginner" or the job advancement it comes with
+    fn book_label(book: usize) -> &'static str {
+        if book == 0 {
+            return "Beginner";
+        }
+        match (book % 100, book % 10) {
+            (0, _) => "1st",
+            (_, 0) => "2nd",
+            (_, 1) => "3rd",
+            _ => "4th",
+        }
+    }
+
+    fn tab_rect(&self, index: usize) -> Rect {
+        Rect::new(self.x + 10.0 + index as f32 * 45.0, self.y + 28.0, 42.0, 16.0)
+    }
+
+    fn row_rect(&self, row: usize) -> Rect {
+        Rect::new(self.x + 8.0, self.y + 52.0 + row as f32 * SKILL_ROW_HEIGHT, self.width - 16.0, SKILL_ROW_HEIGHT)
+    }
+
+    fn sp_button_rect(&self, row: usize) -> Rect {
+        let row_rect = self.row_rect(row);
+        Rect::new(
+            row_rect.right() - SP_BUTTON_SIZE - 4.0,
+            row_rect.bottom() - SP_BUTTON_SIZE - 6.0,
+            SP_BUTTON_SIZE,
+            SP_BUTTON_SIZE,
+        )
+    }
+
+    /// Skill shown on a visible row
+    fn skill_on_row(&self, row: usize) -> Option<i32> {
+        if row >= SKILL_ROWS {
+            return None;
+        }
+        self.current_skills().get(self.scroll + row).copied()
+    }
+
+    /// Handle tabs, scrolling, dragging, SP buttons, double-clicks and key binding
+    pub fn update(&mut self, character: &CharacterData) -> Option<SkillEvent> {
+        if !self.visible { return None; }
+
+        let (mouse_x, mouse_y) = mouse_position();
+        let mouse = vec2(mouse_x, mouse_y);
+        let window = Rect::new(self.x, self.y, self.width, self.height);
+        let hovered_row = (0..SKILL_ROWS).find(|&row| self.row_rect(row).contains(mouse));
+
+        if window.contains(mouse) {
+            let (_, wheel_y) = mouse_wheel();
+            let max_scroll = self.current_skills().len().saturating_sub(SKILL_ROWS);
+            if wheel_y > 0.0 {
+                self.scroll = self.scroll.saturating_sub(1);
+            } else if wheel_y < 0.0 {
+                self.scroll = (self.scroll + 1).min(max_scroll);
+            }
+        }
+
+        // Pressing a skill key over a skill binds it to that key
+        if let Some(skill_id) = hovered_row.and_then(|row| self.skill_on_row(row)) {
+            if character.skill_level(skill_id) > 0 {
+                if let Some(slot) = SKILL_HOTKEYS.iter().position(|&key| is_key_pressed(key)) {
+                    return Some(SkillEvent::Bind(skill_id, slot));
+                }
+            }
+        }
+
+        if is_mouse_button_pressed(MouseButton::Left) {
+            if let Some(index) = (0..self.books.len()).find(|&index| self.tab_rect(index).contains(mouse)) {
+                self.selected_book = index;
+                self.scroll = 0;
+                return None;
+            }
+            if let Some(row) = hovered_row {
+                if let Some(skill_id) = self.skill_on_row(row) {
+                    if character.sp > 0 && self.sp_button_rect(row).contains(mouse) {
+                        return Some(SkillEvent::LevelUp(skill_id));
+                    }
+                    let now = get_time();
+                    if self.last_click_skill == Some(skill_id) && now - self.last_click_time < DOUBLE_CLICK_TIME {
+                        self.last_click_skill = None;
+                        return Some(SkillEvent::Use(skill_id));
+                    }
+                    self.last_click_skill = Some(skill_id);
+                    self.last_click_time = now;
+                }
+            }
+            if mouse_y >= self.y && mouse_y <= self.y + 25.0 &&
+               mouse_x >= self.x && mouse_x <= self.x + self.width {
+                self.dragging = true;
+                self.drag_offset_x = mouse_x - self.x;
+                self.drag_offset_y = mouse_y - self.y;
+            }
+        }
+        if is_mouse_button_down(MouseButton::Left) && self.dragging {
+            self.x = mouse_x - self.drag_offset_x;
+            self.y = mouse_y - self.drag_offset_y;
+        } else {
+            self.dragging = false;
+        }
+
+        None
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        if self.visible {
+            self.x = (screen_width() - self.width) / 2.0;
+            self.y = (screen_height() - self.height) / 2.0;
+        }
+    }
+
+    pub fn draw(&self, character: &CharacterData) {
+        if !self.visible { return; }
+
+        if self.loaded {
+            for bg in [&self.backgrnd, &self.backgrnd2, &self.backgrnd3].into_iter().flatten() {
+                draw_texture(&bg.texture, self.x - bg.origin.x, self.y - bg.origin.y, WHITE);
+            }
+        } else {
+            draw_rectangle(self.x, self.y, self.width, self.height, Color::from_rgba(40, 40, 60, 240));
+            draw_rectangle_lines(self.x, self.y, self.width, self.height, 2.0, Color::from_rgba(100, 100, 140, 255));
+            draw_text("SKILL", self.x + 10.0, self.y + 18.0, 16.0, WHITE);
+        }
+        let text_color = if self.loaded { BLACK } else { WHITE };
+
+        for (index, (book, _)) in self.books.iter().enumerate() {
+            let rect = self.tab_rect(index);
+            let color = if index == self.selected_book {
+                Color::from_rgba(250, 200, 90, 255)
+            } else {
+                Color::from_rgba(160, 160, 160, 255)
+            };
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
+            draw_text(Self::book_label(*book), rect.x + 3.0, rect.y + 12.0, 11.0, BLACK);
+        }
+
+        for row in 0..SKILL_ROWS {
+            let Some(skill_id) = self.skill_on_row(row) else { break };
+            let Some(skill) = skill_info::skill_info(skill_id) else { continue };
+            let rect = self.row_rect(row);
+            let level = character.skill_level(skill_id);
+
+            if let Some(icon) = self.icons.get(&skill_id) {
+                let color = if level > 0 { WHITE } else { Color::from_rgba(255, 255, 255, 110) };
+                draw_texture(icon, rect.x + 2.0, rect.y + (rect.h - icon.height()) / 2.0, color);
+            }
+            draw_text(&skill.name, rect.x + 40.0, rect.y + 15.0, 12.0, text_color);
+            draw_text(&format!("Lv. {} / {}", level, skill.max_level()), rect.x + 40.0, rect.y + 31.0, 11.0, text_color);
+
+            if let Some(slot) = character.skill_keys.iter().position(|&key| key == Some(skill_id)) {
+                draw_text(SKILL_HOTKEY_LABELS[slot], rect.x + 100.0, rect.y + 31.0, 11.0, BLUE);
+            }
+
+            if character.sp > 0 && level < skill.max_level() {
+                let button = self.sp_button_rect(row);
+                match &self.sp_up {
+                    Some(tex) => draw_texture(&tex.texture, button.x - tex.origin.x, button.y - tex.origin.y, WHITE),
+                    None => {
+                        draw_rectangle(button.x, button.y, button.w, button.h, Color::from_rgba(220, 180, 60, 255));
+                        draw_text("+", button.x + 3.0, button.y + button.h - 2.0, 14.0, BLACK);
+                    }
+                }
+            }
+        }
+
+        draw_text(&format!("SP {}", character.sp), self.x + self.width - 50.0, self.y + self.height - 12.0, 12.0, text_color);
+    }
+}
+
+/// Height of one row in the quest list
+const QUEST_ROW_HEIGHT: f32 = 18.0;
+/// Quest rows shown at once (the rest scroll with the mouse wheel)
+const QUEST_ROWS: usize = 8;
+
+/// Quest log (MenuAction::Quest): quests in progress with their counters, and completed ones
+pub struct QuestWindow {
+    pub visible: bool,
+    loaded: bool,
+    backgrnd: Option<TextureWithOrigin>,
+    backgrnd2: Option<TextureWithOrigin>,
+    backgrnd3: Option<TextureWithOrigin>,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    dragging: bool,
+    drag_offset_x: f32,
+    drag_offset_y: f32,
+    // 0 = in progress, 1 = completed
+    selected_tab: usize,
+    scroll: usize,
+    selected_quest: Option<i32>,
+}
+
+impl QuestWindow {
+    const TABS: [&'static str; 2] = ["In Progress", "Completed"];
+
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            loaded: false,
+            backgrnd: None,
+            backgrnd2: None,
+            backgrnd3: None,
+            x: 150.0,
+            y: 100.0,
+            width: 240.0,
+            height: 380.0,
+            dragging: false,
+            drag_offset_x: 0.0,
+            drag_offset_y: 0.0,
+            selected_tab: 0,
+            scroll: 0,
+            selected_quest: None,
+        }
+    }
+
+    pub async fn load_assets(&mut self) {
+        let root_node = match load_wz_image(UIWINDOW2_PATH).await {
+            Ok(node) => node,
+            Err(_) => return,
+        };
+
+        self.backgrnd = UserInfoWindow::load_texture(&root_node, "Quest/list/backgrnd").await.ok();
+        self.backgrnd2 = UserInfoWindow::load_texture(&root_node, "Quest/list/backgrnd2").await.ok();
+        self.backgrnd3 = UserInfoWindow::load_texture(&root_node, "Quest/list/backgrnd3").await.ok();
+
+        if let Some(ref bg) = self.backgrnd {
+            self.width = bg.texture.width();
+            self.height = self.height.max(bg.texture.height());
+        }
+        self.loaded = self.backgrnd.is_some();
+        info!("Quest window loaded");
+    }
+
+    /// Quest ids listed on the current tab
+    fn current_quests(&self, character: &CharacterData) -> Vec<i32> {
+        match self.selected_tab {
+            0 => character.quests.started().map(|(id, _)| id).collect(),
+            _ => character.quests.completed().collect(),
+        }
+    }
+
+    fn tab_rect(&self, index: usize) -> Rect {
+        Rect::new(self.x + 10.0 + index as f32 * 75.0, self.y + 28.0, 72.0, 16.0)
+    }
+
+    fn row_rect(&self, row: usize) -> Rect {
+        Rect::new(self.x + 10.0, self.y + 50.0 + row as f32 * QUEST_ROW_HEIGHT, self.width - 20.0, QUEST_ROW_HEIGHT)
+    }
+
+    /// Handle tabs, scrolling, picking a quest and dragging
+    pub fn update(&mut self, character: &CharacterData) {
+        if !self.visible { return; }
+
+        let (mouse_x, mouse_y) = mouse_position();
+        let mouse = vec2(mouse_x, mouse_y);
+        let quests = self.current_quests(character);
+
+        if Rect::new(self.x, self.y, self.width, self.height).contains(mouse) {
+            let (_, wheel_y) = mouse_wheel();
+            if wheel_y > 0.0 {
+                self.scroll = self.scroll.saturating_sub(1);
+            } else if wheel_y < 0.0 {
+                self.scroll = (self.scroll + 1).min(quests.len().saturating_sub(QUEST_ROWS));
+            }
+        }
+
+        if is_mouse_button_pressed(MouseButton::Left) {
+            if let Some(index) = (0..Self::TABS.len()).find(|&index| self.tab_rect(index).contains(mouse)) {
+                self.selected_tab = index;
+                self.scroll = 0;
+                self.selected_quest = None;
+                return;
+            }
+            if let Some(row) = (0..QUEST_ROWS).find(|&row| self.row_rect(row).contains(mouse)) {
+                if let Some(&quest_id) = quests.get(self.scroll + row) {
+                    self.selected_quest = Some(quest_id);
+                }
+            }
+            if mouse_y >= self.y && mouse_y <= self.y + 25.0 &&
+               mouse_x >= self.x && mouse_x <= self.x + self.width {
+                self.dragging = true;
+                self.drag_offset_x = mouse_x - self.x;
+                self.drag_offset_y = mouse_y - self.y;
+            }
+        }
+        if is_mouse_button_down(MouseButton::Left) && self.dragging {
+            self.x = mouse_x - self.drag_offset_x;
+            self.y = mouse_y - self.drag_offset_y;
+        } else {
+            self.dragging = false;
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        if self.visible {
+            self.x = (screen_width() - self.width) / 2.0;
+            self.y = (screen_height() - self.height) / 2.0;
+        }
+    }
+
+    pub fn draw(&self, character: &CharacterData) {
+        if !self.visible { return; }
+
+        if self.loaded {
+            for bg in [&self.backgrnd, &self.backgrnd2, &self.backgrnd3].into_iter().flatten() {
+                draw_texture(&bg.texture, self.x - bg.origin.x, self.y - bg.origin.y, WHITE);
+            }
+        } else {
+            draw_rectangle(self.x, self.y, self.width, self.height, Color::from_rgba(40, 40, 60, 240));
+            draw_rectangle_lines(self.x, self.y, self.width, self.height, 2.0, Color::from_rgba(100, 100, 140, 255));
+            draw_text("QUEST", self.x + 10.0, self.y + 18.0, 16.0, WHITE);
+        }
+        let text_color = if self.loaded { BLACK } else { WHITE };
+
+        for (index, label) in Self::TABS.iter().enumerate() {
+            let rect = self.tab_rect(index);
+            let color = if index == self.selected_tab {
+                Color::from_rgba(250, 200, 90, 255)
+            } else {
+                Color::from_rgba(160, 160, 160, 255)
+            };
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
+            draw_text(label, rect.x + 3.0, rect.y + 12.0, 11.0, BLACK);
+        }
+
+        let quests = self.current_quests(character);
+        if quests.is_empty() {
+            draw_text("No quests.", self.x + 14.0, self.y + 64.0, 12.0, text_color);
+        }
+        for row in 0..QUEST_ROWS {
+            let Some(&quest_id) = quests.get(self.scroll + row) else { break };
+            let rect = self.row_rect(row);
+            if self.selected_quest == Some(quest_id) {
+                draw_rectangle(rect.x, rect.y, rect.w, rect.h, Color::from_rgba(120, 170, 250, 120));
+            }
+            let name = quest_info::quest(quest_id).map_or_else(|| format!("Quest {}", quest_id), |quest| quest.name);
+            draw_text(&name, rect.x + 4.0, rect.y + 13.0, 12.0, text_color);
+        }
+
+        if let Some(quest_id) = self.selected_quest {
+            self.draw_details(quest_id, character, text_color);
+        }
+    }
+
+    /// Summary and counters of the selected quest, under the list
+    fn draw_details(&self, quest_id: i32, character: &CharacterData, text_color: Color) {
+        let Some(quest) = quest_info::quest(quest_id) else { return };
+        let left = self.x + 14.0;
+        let mut y = self.row_rect(QUEST_ROWS).y + 16.0;
+        draw_line(self.x + 10.0, y - 12.0, self.x + self.width - 10.0, y - 12.0, 1.0, GRAY);
+
+        for line in wrap_words(&quest.summary, self.width - 28.0, 11.0) {
+            draw_text(&line, left, y, 11.0, text_color);
+            y += 13.0;
+        }
+
+        let Some(progress) = character.quests.progress(quest_id) else { return };
+        y += 4.0;
+        for &(mob_id, count) in &quest.end.mobs {
+            let kills = progress.mob_kills.get(&mob_id).copied().unwrap_or(0);
+            let color = if kills >= count { DARKGREEN } else { text_color };
+            draw_text(&format!("Mob {}: {} / {}", mob_id, kills, count), left, y, 11.0, color);
+            y += 13.0;
+        }
+        for &(item_id, count) in &quest.end.items {
+            let held = character.inventory.count(item_id);
+            let color = if held >= count { DARKGREEN } else { text_color };
+            draw_text(&format!("Item {}: {} / {}", item_id, held.min(count), count), left, y, 11.0, color);
+            y += 13.0;
+        }
+        if quest.end.meso > 0 {
+            let color = if character.meso >= quest.end.meso { DARKGREEN } else { text_color };
+            draw_text(&format!("Mesos: {}", format_meso(quest.end.meso)), left, y, 11.0, color);
+        }
+    }
+}
+
+/// Break text into lines no wider than `max_width` at `font_size`
+fn wrap_words(text: &str, max_width: f32, font_size: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+            if !line.is_empty() && measure_text(&candidate, None, font_size as u16, 1.0).width > max_width {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            } else {
+                line = candidate;
+            }
+        }
+        lines.push(line);
     }
+    lines
 }
 
 #[derive(Default)]
@@ -0,0 +1,226 @@
+use macroquad::prelude::*;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use wz_reader::version::guess_iv_from_wz_img;
+use wz_reader::{WzImage, WzNode, WzNodeArc, WzReader};
+
+use crate::assets::AssetManager;
+use crate::config::config;
+
+/// Process-wide cache of parsed WZ images, keyed by logical WZ path (e.g. "UI/UIWindow2.img").
+///
+/// Every loader goes through `load_wz_image`, so an image such as UIWindow2.img is fetched,
+/// IV-guessed and parsed once and the same `WzNodeArc` is handed to every window that needs it.
+/// Entries are evicted least-recently-used first once the byte budget is exceeded; entries that
+/// are still referenced outside the cache are never evicted.
+struct WzCache {
+    entries: HashMap<String, CacheEntry>,
+    budget_bytes: usize,
+    used_bytes: usize,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+struct CacheEntry {
+    node: WzNodeArc,
+    size: usize,
+    last_used: u64,
+}
+
+/// Snapshot of the cache counters, for logging and the debug overlay
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WzCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub entries: usize,
+    pub used_bytes: usize,
+    pub budget_bytes: usize,
+}
+
+impl WzCacheStats {
+    pub fn hit_rate(&self) -> f32 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f32 / total as f32
+        }
+    }
+}
+
+static WZ_CACHE: Mutex<Option<WzCache>> = Mutex::new(None);
+
+impl WzCache {
+    fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            budget_bytes: config().wz_cache_budget_mb * 1024 * 1024,
+            used_bytes: 0,
+            tick: 0,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    fn get(&mut self, path: &str) -> Option<WzNodeArc> {
+        self.tick += 1;
+        match self.entries.get_mut(path) {
+            Some(entry) => {
+                entry.last_used = self.tick;
+                self.hits += 1;
+                Some(entry.node.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, path: &str, node: WzNodeArc, size: usize) -> WzNodeArc {
+        // Another load of the same image may have finished first; keep the existing node
+        // so every caller shares one tree
+        if let Some(entry) = self.entries.get(path) {
+            return entry.node.clone();
+        }
+
+        self.tick += 1;
+        self.used_bytes += size;
+        self.entries.insert(
+            path.to_string(),
+            CacheEntry {
+                node: node.clone(),
+                size,
+                last_used: self.tick,
+            },
+        );
+        self.evict_to_budget();
+        node
+    }
+
+    /// Drop least-recently-used entries that nobody else holds until we are under budget
+    fn evict_to_budget(&mut self) {
+        while self.used_bytes > self.budget_bytes {
+            let victim = self
+                .entries
+                .iter()
+                .filter(|(_, entry)| Arc::strong_count(&entry.node) == 1)
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(path, _)| path.clone());
+
+            let Some(path) = victim else {
+                // Everything left is in use
+                break;
+            };
+
+            if let Some(entry) = self.entries.remove(&path) {
+                self.used_bytes -= entry.size;
+                self.evictions += 1;
+                if config().verbose_asset_loading {
+                    info!("WZ cache evicted {} ({} bytes)", path, entry.size);
+                }
+            }
+        }
+    }
+
+    fn stats(&self) -> WzCacheStats {
+        WzCacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+            entries: self.entries.len(),
+            used_bytes: self.used_bytes,
+            budget_bytes: self.budget_bytes,
+        }
+    }
+}
+
+fn with_cache<R>(f: impl FnOnce(&mut WzCache) -> R) -> R {
+    let mut guard = WZ_CACHE.lock().unwrap();
+    f(guard.get_or_insert_with(WzCache::new))
+}
+
+/// Parse raw .img bytes into a root node (no caching)
+pub fn parse_wz_image(path: &str, bytes: &[u8]) -> Result<WzNodeArc, String> {
+    let wz_iv = guess_iv_from_wz_img(bytes)
+        .ok_or_else(|| format!("Unable to guess version from {}", path))?;
+
+    let reader = Arc::new(WzReader::from_buff(bytes).with_iv(wz_iv));
+    let name: wz_reader::WzNodeName = path.to_string().into();
+    let wz_image = WzImage::new(&name, 0, bytes.len(), &reader);
+    let root_node: WzNodeArc = WzNode::new(&name, wz_image, None).into();
+
+    root_node
+        .write()
+        .unwrap()
+        .parse(&root_node)
+        .map_err(|e| format!("Failed to parse {}: {:?}", path, e))?;
+
+    Ok(root_node)
+}
+
+/// Get the parsed root node of a WZ image, fetching and parsing it on a cache miss
+pub async fn load_wz_image(path: &str) -> Result<WzNodeArc, String> {
+    if let Some(node) = with_cache(|cache| cache.get(path)) {
+        return Ok(node);
+    }
+
+    let bytes = AssetManager::fetch_and_cache(path).await?;
+    insert_wz_image(path, &bytes)
+}
+
+/// Load several WZ images, fetching all cache misses in parallel.
+/// Returns results in the same order as `paths`.
+pub async fn load_wz_images(paths: Vec<String>) -> Vec<Result<WzNodeArc, String>> {
+    let cached: Vec<Option<WzNodeArc>> = with_cache(|cache| paths.iter().map(|path| cache.get(path)).collect());
+
+    let missing: Vec<String> = paths
+        .iter()
+        .zip(&cached)
+        .filter(|(_, node)| node.is_none())
+        .map(|(path, _)| path.clone())
+        .collect();
+    let mut fetched = AssetManager::fetch_and_cache_batch(missing).await.into_iter();
+
+    paths
+        .iter()
+        .zip(cached)
+        .map(|(path, node)| match node {
+            Some(node) => Ok(node),
+            None => fetched
+                .next()
+                .unwrap_or_else(|| Err(format!("No fetch result for {}", path)))
+                .and_then(|bytes| insert_wz_image(path, &bytes)),
+        })
+        .collect()
+}
+
+/// Parse bytes that were fetched elsewhere and add them to the cache
+pub fn insert_wz_image(path: &str, bytes: &[u8]) -> Result<WzNodeArc, String> {
+    let node = parse_wz_image(path, bytes)?;
+    Ok(with_cache(|cache| cache.insert(path, node, bytes.len())))
+}
+
+/// Current cache counters
+pub fn stats() -> WzCacheStats {
+    with_cache(|cache| cache.stats())
+}
+
+/// Log the cache counters
+pub fn log_stats() {
+    let stats = stats();
+    info!(
+        "WZ cache: {} entries, {:.1}/{:.0} MB, {} hits / {} misses ({:.0}% hit rate), {} evictions",
+        stats.entries,
+        stats.used_bytes as f32 / (1024.0 * 1024.0),
+        stats.budget_bytes as f32 / (1024.0 * 1024.0),
+        stats.hits,
+        stats.misses,
+        stats.hit_rate() * 100.0,
+        stats.evictions
+    );
+}